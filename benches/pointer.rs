@@ -0,0 +1,47 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_json::{json, Value};
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/pointer.rs"]
+mod pointer;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/verbose.rs"]
+mod verbose;
+
+/// Stands in for the one of the binary, it only names types in `--verbose` messages.
+fn type_name(_: &Value) -> &'static str {
+    "value"
+}
+
+/// 1000 siblings at every level of a 10 deep path.
+fn document() -> Value {
+    let mut value = json!("leaf");
+    for depth in (0..10).rev() {
+        let mut map = serde_json::Map::new();
+        for i in 0..1000 {
+            map.insert(format!("key{}", i), json!(i));
+        }
+        map.insert(format!("level{}", depth), value);
+        value = Value::Object(map);
+    }
+    value
+}
+
+fn resolve(c: &mut Criterion) {
+    let mut value = document();
+    let path: Vec<String> = (0..10).map(|depth| format!("/level{}", depth)).collect();
+    let plain = path.concat();
+    let escaped = plain.replace("level9", "level~09");
+
+    let mut group = c.benchmark_group("resolve a 10 deep pointer");
+    group.bench_function("plain", |b| {
+        b.iter(|| pointer::pointer_mut(black_box(&mut value), black_box(&plain)).is_some())
+    });
+    group.bench_function("escaped", |b| {
+        b.iter(|| pointer::pointer_mut(black_box(&mut value), black_box(&escaped)).is_some())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, resolve);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_json::Value;
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/pointer.rs"]
+mod pointer;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/stream.rs"]
+mod stream;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/verbose.rs"]
+mod verbose;
+
+/// Stands in for the one of the binary, it only names types in `--verbose` messages.
+fn type_name(_: &Value) -> &'static str {
+    "value"
+}
+
+fn document(amount: usize) -> Vec<u8> {
+    let items: Vec<_> = (0..amount)
+        .map(|i| serde_json::json!({"id": i, "name": format!("item {}", i), "tags": ["a", "b"]}))
+        .collect();
+    serde_json::to_vec(&serde_json::json!({ "items": items, "last": true })).unwrap()
+}
+
+fn get(c: &mut Criterion) {
+    let data = document(100_000);
+    let mut group = c.benchmark_group("get /items/99999/name");
+
+    group.bench_function("parse", |b| {
+        b.iter(|| {
+            let value: Value = serde_json::from_slice(black_box(&data)).unwrap();
+            value.pointer("/items/99999/name").cloned()
+        })
+    });
+    group.bench_function("stream", |b| {
+        b.iter(|| stream::get(black_box(data.as_slice()), "/items/99999/name").unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, get);
+criterion_main!(benches);
@@ -0,0 +1,82 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::{exit, string_value, value_from_str, value_printer, variable_or_object, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "apply",
+    description = "Replace the value at the pointer with the output of a command the value is piped to",
+    note = "The command is given after -- and run without a shell, so its arguments are never expanded. A string is written to its stdin without quotes, anything else as compact json.
+The output of the command is parsed as json, or with --string-result taken as a string without the single line break at its end. When the command fails nothing is printed and it exits with the exit code of the command.",
+    example = r#"input                      arguments                                   output
+{{"name": "web"}}            /name --string-result -- tr a-z A-Z         {{"name":"WEB"}}
+{{"list": [1, 2]}}           /list -- cat                                {{"list":[1,2]}}"#
+)]
+pub struct SubCommandApply {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional)]
+    command: Vec<String>,
+    #[argh(switch)]
+    /// take the output of the command as a string instead of parsing it as json
+    string_result: bool,
+}
+
+pub fn do_apply(args: SubCommandApply, pretty: bool) -> Result<String, exit::Error> {
+    let (program, arguments) = args
+        .command
+        .split_first()
+        .ok_or_else(|| exit::usage(String::from("missing the command, give it after --")))?;
+    let mut value = variable_or_object(&args.variable)?;
+    let pointer = args.pointer.as_str();
+    let target = value
+        .pointer_mut(pointer)
+        .ok_or_else(|| format!("{} does not exist", pointer))?;
+    let input = match &*target {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+
+    let mut child = Command::new(program)
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| exit::io(format!("unable to run {}: {}", program, e)))?;
+    // written from another thread, so a command that prints while it reads can not block on a full pipe
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child
+        .wait_with_output()
+        .map_err(|e| exit::io(format!("unable to run {}: {}", program, e)))?;
+    match writer.join() {
+        // a command does not have to read its input
+        Ok(Err(e)) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+            return Err(exit::io(format!("unable to write to {}: {}", program, e)))
+        }
+        _ => (),
+    }
+
+    if !output.status.success() {
+        let message = format!("{} failed with {}", program, output.status);
+        return Err(match output.status.code() {
+            Some(code) => exit::command(code, message),
+            None => message.into(),
+        });
+    }
+    let text = String::from_utf8(output.stdout)
+        .map_err(|_| exit::parse(format!("the output of {} is not utf-8", program)))?;
+    *target = match args.string_result {
+        true => string_value(text, false),
+        false => value_from_str(&text)
+            .map_err(|e| exit::parse(format!("the output of {} is {}", program, e)))?,
+    };
+    Ok(value_printer(pretty, &value))
+}
@@ -0,0 +1,379 @@
+use argh::FromArgs;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::escape::{escape, Style};
+use crate::input::is_identifier;
+use crate::{exit, pointer, type_name, value_printer, variable_or_value, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "to-bash-array",
+    description = "Print a declare statement that creates a bash array from the array or object at the pointer",
+    note = "An array becomes an indexed array (declare -a) and an object an associative array (declare -A) with the keys as subscripts. Strings are used as they are, arrays and objects as compact json, null as an empty string and numbers and booleans as their json.
+Every key and value is in single quotes, so `eval \"$(bash_map to-bash-array \"$DOC\" /hosts HOSTS)\"` creates the array with the exact strings, quotes, newlines and `$` included. Bash strings can not hold NUL characters, so those fail, as do empty keys.",
+    example = r#"input                                   arguments           output
+{{"hosts": ["a", "it's"]}}                /hosts HOSTS        declare -a HOSTS=('a' 'it'\''s')
+{{"env": {{"A": "$x", "B": [1]}}}}          /env ENV            declare -A ENV=(['A']='$x' ['B']='[1]')"#
+)]
+pub struct SubCommandToBashArray {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional)]
+    name: String,
+}
+
+pub fn do_to_bash_array(args: SubCommandToBashArray) -> Result<String, exit::Error> {
+    if !is_identifier(&args.name) {
+        return Err(exit::usage(format!(
+            "{:?} is not a valid bash variable name",
+            args.name
+        )));
+    }
+    let value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_str();
+
+    match value.pointer(pointer) {
+        Some(Value::Array(list)) => {
+            let items = list
+                .iter()
+                .map(|item| quote(&text(item)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("declare -a {}=({})", args.name, items.join(" ")))
+        }
+        Some(Value::Object(map)) => {
+            let items = map
+                .iter()
+                .map(|(key, item)| match key.is_empty() {
+                    true => Err(String::from("bash arrays can not have an empty key")),
+                    false => Ok(format!("[{}]={}", quote(key)?, quote(&text(item))?)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("declare -A {}=({})", args.name, items.join(" ")))
+        }
+        Some(other) => Err(format!(
+            "{} is of type {}, not array or object",
+            pointer,
+            type_name(other)
+        )
+        .into()),
+        None => Err(format!("{} does not exist", pointer).into()),
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "export",
+    description = "Print a NAME='value' assignment for every key of the object",
+    note = "Strings are used as they are and everything else as compact json. Names are the keys uppercased, with every character that can not be in a variable name replaced by _, and a _ in front of a leading digit. With --prefix the names start with the prefix and the separator.
+With --flatten every leaf of nested arrays and objects gets its own assignment, named after all the keys and indices on the way joined by --separator. Empty arrays and objects are leaves. Two keys that give the same name fail.
+Every value is in single quotes, so `eval \"$(bash_map export \"$DOC\")\"` sets the variables with the exact strings.
+--escape-style picks the statement for another shell instead of escaping the output again: `set -x NAME 'value'` for fish, `export NAME='value'` for zsh, `$env:NAME = \"value\"` for powershell and NAME=\"value\" for bash-double.",
+    example = r#"input                                       arguments                              output
+{{"port": 80, "tags": ["a"]}}                                                        PORT='80' and TAGS='["a"]' on their own line
+{{"server": {{"port": 80}}, "hosts": ["a"]}}    --flatten --separator __ --prefix CFG  CFG__SERVER__PORT='80' and CFG__HOSTS__0='a' on their own line"#
+)]
+pub struct SubCommandExport {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(switch)]
+    /// assign every leaf of nested arrays and objects instead of only the top-level keys
+    flatten: bool,
+    #[argh(option, default = "String::from(\"_\")")]
+    /// text between the parts of a name (default _)
+    separator: String,
+    #[argh(option)]
+    /// start every name with this
+    prefix: Option<String>,
+}
+
+pub fn do_export(args: SubCommandExport, style: Option<Style>) -> Result<String, exit::Error> {
+    if let Some(prefix) = args.prefix.as_ref().filter(|x| !is_identifier(x)) {
+        return Err(exit::usage(format!(
+            "{:?} is not a valid bash variable name",
+            prefix
+        )));
+    }
+    if !args
+        .separator
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(exit::usage(format!(
+            "{:?} can not be in a bash variable name",
+            args.separator
+        )));
+    }
+    let value = variable_or_value(&args.variable)?;
+    if !value.is_object() && !value.is_array() {
+        return Err(format!(
+            "the map is of type {}, not array or object",
+            type_name(&value)
+        )
+        .into());
+    }
+
+    let mut found = Vec::new();
+    assignments(&value, &mut Vec::new(), args.flatten, &mut found);
+    let mut names = HashMap::new();
+    let mut lines = Vec::new();
+    for (path, leaf) in found {
+        let parts = args
+            .prefix
+            .iter()
+            .cloned()
+            .chain(path.iter().map(|key| sanitize(key)));
+        let mut name = parts.collect::<Vec<_>>().join(&args.separator);
+        if !is_identifier(&name) {
+            name.insert(0, '_');
+        }
+        let pointer: String = path
+            .iter()
+            .map(|key| format!("/{}", pointer::escape(key)))
+            .collect();
+        if let Some(other) = names.insert(name.clone(), pointer.clone()) {
+            return Err(format!("{} and {} are both exported as {}", other, pointer, name).into());
+        }
+
+        let text = match leaf {
+            Value::String(text) => text.clone(),
+            other => value_printer(false, other),
+        };
+        lines.push(statement(&name, &text, style)?);
+    }
+    Ok(lines.join("\n"))
+}
+
+/// The statement setting the environment variable in the shell of the style,
+/// a bash assignment unless the style is for another shell.
+fn statement(name: &str, text: &str, style: Option<Style>) -> Result<String, String> {
+    let quoted = quote(text)?;
+    Ok(match style {
+        Some(Style::Fish) => format!("set -x {} {}", name, escape(text, Style::Fish)),
+        Some(Style::Zsh) => format!("export {}={}", name, escape(text, Style::Zsh)),
+        Some(Style::Powershell) => format!("$env:{} = {}", name, escape(text, Style::Powershell)),
+        Some(Style::BashDouble) => format!("{}={}", name, escape(text, Style::BashDouble)),
+        Some(Style::Rust | Style::BashSingle) | None => format!("{}={}", name, quoted),
+    })
+}
+
+/// The keys on the way to every value that gets an assignment, in document order.
+fn assignments<'a>(
+    value: &'a Value,
+    path: &mut Vec<String>,
+    flatten: bool,
+    found: &mut Vec<(Vec<String>, &'a Value)>,
+) {
+    let nested = flatten || path.is_empty();
+    match value {
+        Value::Object(map) if nested && !map.is_empty() => {
+            for (key, item) in map {
+                path.push(key.clone());
+                assignments(item, path, flatten, found);
+                path.pop();
+            }
+        }
+        Value::Array(list) if nested && !list.is_empty() => {
+            for (index, item) in list.iter().enumerate() {
+                path.push(index.to_string());
+                assignments(item, path, flatten, found);
+                path.pop();
+            }
+        }
+        leaf => found.push((path.clone(), leaf)),
+    }
+}
+
+/// The key uppercased, with the characters that can not be in a variable name replaced by `_`.
+fn sanitize(key: &str) -> String {
+    key.chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect()
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "shell-quote",
+    description = "Print the value at the pointer quoted for use in a shell command",
+    note = "A string is quoted as it is, anything else as its compact json. bash, sh and zsh use single quotes, where a quote becomes '\\''. fish also uses single quotes, with a backslash before \\ and '. powershell uses double quotes, with a backtick before $ and backticks and double quotes doubled.
+No shell can pass a NUL character in an argument, so those fail.",
+    example = r#"input                    arguments                       output
+{{"msg": "it's $5"}}       /msg                            'it'\''s $5'
+{{"msg": "it's $5"}}       /msg --shell fish               'it\'s $5'
+{{"msg": "it's $5"}}       /msg --shell powershell         "it's `$5"
+{{"ids": [1, 2]}}          /ids                            '[1,2]'"#
+)]
+pub struct SubCommandShellQuote {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(option, default = "Shell::Bash")]
+    /// the shell to quote for: bash (default), sh, zsh, fish or powershell
+    shell: Shell,
+}
+
+/// The shells `shell-quote` quotes for.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Shell {
+    /// bash and other POSIX shells
+    #[default]
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl std::str::FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" | "sh" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            "powershell" | "pwsh" => Ok(Shell::Powershell),
+            _ => Err(format!(
+                "unknown shell {:?}, use bash, sh, zsh, fish or powershell",
+                s
+            )),
+        }
+    }
+}
+
+pub fn do_shell_quote(args: SubCommandShellQuote) -> Result<String, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_str();
+    match value.pointer(pointer) {
+        Some(found) => Ok(shell_quote(found, args.shell)?),
+        None => Err(format!("{} does not exist", pointer).into()),
+    }
+}
+
+/// The string, or the compact json of anything else, quoted as one argument for the shell.
+fn shell_quote(value: &Value, shell: Shell) -> Result<String, String> {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    if text.contains('\0') {
+        return Err(format!(
+            "{:?} contains a NUL character, a shell can not hold it",
+            text
+        ));
+    }
+    let style = match shell {
+        Shell::Bash => Style::BashSingle,
+        Shell::Zsh => Style::Zsh,
+        Shell::Fish => Style::Fish,
+        Shell::Powershell => Style::Powershell,
+    };
+    Ok(escape(&text, style))
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// The text in single quotes, where only the quote itself needs escaping.
+fn quote(text: &str) -> Result<String, String> {
+    if text.contains('\0') {
+        return Err(format!(
+            "{:?} contains a NUL character, bash can not hold it",
+            text
+        ));
+    }
+    Ok(format!("'{}'", text.replace('\'', r"'\''")))
+}
+
+#[cfg(test)]
+mod bash_test {
+    use super::{quote, sanitize, shell_quote, statement, Shell};
+    use crate::escape::Style;
+    use serde_json::json;
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("plain").unwrap(), "'plain'");
+        assert_eq!(quote("").unwrap(), "''");
+        assert_eq!(quote("it's").unwrap(), r"'it'\''s'");
+        assert_eq!(
+            quote("$HOME `x` \"y\"\n\\").unwrap(),
+            "'$HOME `x` \"y\"\n\\'"
+        );
+        assert!(quote("a\0b").is_err());
+    }
+
+    #[test]
+    fn statements() {
+        let text = "it's $HOME\n* \\ \"x\" \u{2713}";
+        assert_eq!(
+            statement("A", text, None).unwrap(),
+            "A='it'\\''s $HOME\n* \\ \"x\" \u{2713}'"
+        );
+        assert_eq!(
+            statement("A", text, Some(Style::Fish)).unwrap(),
+            "set -x A 'it\\'s $HOME\n* \\\\ \"x\" \u{2713}'"
+        );
+        assert_eq!(
+            statement("A", text, Some(Style::Zsh)).unwrap(),
+            "export A='it'\\''s $HOME\n* \\ \"x\" \u{2713}'"
+        );
+        assert_eq!(
+            statement("A", text, Some(Style::Powershell)).unwrap(),
+            "$env:A = \"it's `$HOME\n* \\ \"\"x\"\" \u{2713}\""
+        );
+        assert_eq!(
+            statement("A", text, Some(Style::BashDouble)).unwrap(),
+            "A=\"it's \\$HOME\n* \\\\ \\\"x\\\" \u{2713}\""
+        );
+        assert!(statement("A", "a\0b", Some(Style::Fish)).is_err());
+    }
+
+    #[test]
+    fn sanitized_names() {
+        assert_eq!(sanitize("port"), "PORT");
+        assert_eq!(sanitize("log-level.x"), "LOG_LEVEL_X");
+        assert_eq!(sanitize("caf\u{e9} 1"), "CAF__1");
+    }
+
+    #[test]
+    fn shell_quoting() {
+        let text = json!("$HOME `id` $(id) !! it's\na\\b \"x\" ✓ \u{1f600}");
+        assert_eq!(
+            shell_quote(&text, Shell::Bash).unwrap(),
+            "'$HOME `id` $(id) !! it'\\''s\na\\b \"x\" ✓ \u{1f600}'"
+        );
+        assert_eq!(
+            shell_quote(&text, Shell::Zsh).unwrap(),
+            "'$HOME `id` $(id) !! it'\\''s\na\\b \"x\" ✓ \u{1f600}'"
+        );
+        assert_eq!(
+            shell_quote(&text, Shell::Fish).unwrap(),
+            "'$HOME `id` $(id) !! it\\'s\na\\\\b \"x\" ✓ \u{1f600}'"
+        );
+        assert_eq!(
+            shell_quote(&text, Shell::Powershell).unwrap(),
+            "\"`$HOME ``id`` `$(id) !! it's\na\\b \"\"x\"\" ✓ \u{1f600}\""
+        );
+        assert_eq!(shell_quote(&json!(""), Shell::Bash).unwrap(), "''");
+        assert_eq!(
+            shell_quote(&json!({"a": [1, "it's"]}), Shell::Bash).unwrap(),
+            r#"'{"a":[1,"it'\''s"]}'"#
+        );
+        assert_eq!(shell_quote(&json!(null), Shell::Fish).unwrap(), "'null'");
+        assert!(shell_quote(&json!("a\0b"), Shell::Bash).is_err());
+    }
+}
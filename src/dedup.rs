@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::pointer::escape;
+
+/// Checks the json text for objects containing the same key more than once,
+/// which serde_json would otherwise silently resolve by keeping the last one.
+///
+/// The error names the key and the json pointer of the duplicate.
+/// Like `depth::from_str` there is no recursion limit, the depth should be checked first.
+pub fn check(text: &str) -> Result<(), String> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    deserializer.disable_recursion_limit();
+    Check {
+        path: String::new(),
+    }
+    .deserialize(serde_stacker::Deserializer::new(&mut deserializer))
+    .and_then(|_| deserializer.end())
+    .map_err(|e| e.to_string())
+}
+
+struct Check {
+    path: String,
+}
+
+impl<'de> DeserializeSeed<'de> for Check {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Check {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any json value")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut seen = HashSet::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            let path = format!("{}/{}", self.path, escape(&key));
+            if !seen.insert(key.clone()) {
+                return Err(de::Error::custom(format!(
+                    "duplicate key {:?} at {}",
+                    key, path
+                )));
+            }
+            map.next_value_seed(Check { path })?;
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut index = 0;
+        while seq
+            .next_element_seed(Check {
+                path: format!("{}/{}", self.path, index),
+            })?
+            .is_some()
+        {
+            index += 1;
+        }
+        Ok(())
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod dedup_test {
+    use super::check;
+
+    #[test]
+    fn no_duplicates() {
+        assert!(check(r#"{"a": 1, "b": {"a": 1}, "c": [{"a": 1}, {"a": 2}]}"#).is_ok());
+        assert!(check("[1, 2, 1]").is_ok());
+        assert!(check("1").is_ok());
+    }
+
+    #[test]
+    fn top_level_duplicate() {
+        let error = check(r#"{"a": 1, "a": 2}"#).unwrap_err();
+        assert!(error.starts_with(r#"duplicate key "a" at /a"#), "{}", error);
+    }
+
+    #[test]
+    fn nested_duplicate_in_array() {
+        let error =
+            check(r#"{"items": [{"id": 1}, {"id": 2, "name": "x", "id": 3}]}"#).unwrap_err();
+        assert!(
+            error.starts_with(r#"duplicate key "id" at /items/1/id"#),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn escaped_pointer() {
+        let error = check(r#"{"a/b": {"c~d": 1, "c~d": 1}}"#).unwrap_err();
+        assert!(
+            error.starts_with(r#"duplicate key "c~d" at /a~1b/c~0d"#),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert!(check(r#"{"a": "#).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+use argh::FromArgs;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::pointer::escape;
+use crate::{exit, variable_or_value, Pointer};
+
+/// Default for `--max-depth`, the nesting serde_json allows on its own.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "depth",
+    description = "Print how deeply the map, or the part at the pointer, is nested",
+    note = "Every level of values counts, so a value that is not an array or object is 0, {{}} and [] are 1 and {{\"a\": [1]}} is 3. With --max it fails instead when the depth is above the maximum.
+--max-depth does not apply to depth, so maps nested as deep as they come can be measured.",
+    example = r#"input                          arguments          output
+{{"a": [1]}}                     ""                 3
+{{"a": [1]}}                     /a                 2
+[[[[]]]]                       "" --max 3         error: depth 4 is above the maximum of 3"#
+)]
+pub struct SubCommandDepth {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(option)]
+    /// fail when the depth is above this
+    max: Option<usize>,
+}
+
+pub fn do_depth(args: SubCommandDepth) -> Result<String, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_ref().map_or("", Pointer::as_str);
+    let target = value
+        .pointer(pointer)
+        .ok_or_else(|| format!("{} does not exist", pointer))?;
+
+    let depth = depth(target);
+    match args.max {
+        Some(max) if depth > max => {
+            Err(format!("depth {} is above the maximum of {}", depth, max).into())
+        }
+        _ => Ok(depth.to_string()),
+    }
+}
+
+/// The number of levels in the value, 0 for values that are not arrays or objects.
+///
+/// Walks with a stack of its own instead of recursing, so any depth fits.
+pub fn depth(value: &Value) -> usize {
+    if !matches!(value, Value::Array(_) | Value::Object(_)) {
+        return 0;
+    }
+
+    let mut deepest = 0;
+    let mut stack = vec![(value, 1)];
+    while let Some((value, level)) = stack.pop() {
+        deepest = deepest.max(level);
+        match value {
+            Value::Object(map) => stack.extend(map.values().map(|x| (x, level + 1))),
+            Value::Array(list) => stack.extend(list.iter().map(|x| (x, level + 1))),
+            _ => (),
+        }
+    }
+    deepest
+}
+
+enum Frame {
+    Object {
+        key: Option<String>,
+        key_start: usize,
+    },
+    Array {
+        index: usize,
+    },
+}
+
+/// Checks that the text nests at most `max_depth` arrays and objects.
+///
+/// The text is scanned without recursing, so even hostile inputs can not exhaust the stack.
+/// Strings and comments are skipped, which makes this work for jsonc and json5 too,
+/// anything else that is not valid is left for the parser to report.
+pub fn check_text(text: &str, max_depth: usize) -> Result<(), String> {
+    let bytes = text.as_bytes();
+    let mut frames = Vec::new();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            quote @ b'"' | quote @ b'\'' => {
+                index += 1;
+                while index < bytes.len() && bytes[index] != quote {
+                    if bytes[index] == b'\\' {
+                        index += 1;
+                    }
+                    index += 1;
+                }
+            }
+            b'/' if bytes.get(index + 1) == Some(&b'/') => {
+                while index < bytes.len() && bytes[index] != b'\n' {
+                    index += 1;
+                }
+            }
+            b'/' if bytes.get(index + 1) == Some(&b'*') => {
+                index = text[index + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| index + 2 + end + 1);
+            }
+            open @ b'{' | open @ b'[' => {
+                if frames.len() >= max_depth {
+                    return Err(format!(
+                        "maximum depth {} exceeded at {}",
+                        max_depth,
+                        pointer(&frames)
+                    ));
+                }
+                frames.push(match open {
+                    b'{' => Frame::Object {
+                        key: None,
+                        key_start: index + 1,
+                    },
+                    _ => Frame::Array { index: 0 },
+                });
+            }
+            b'}' | b']' => {
+                frames.pop();
+            }
+            b',' => match frames.last_mut() {
+                Some(Frame::Object { key, key_start }) => {
+                    *key = None;
+                    *key_start = index + 1;
+                }
+                Some(Frame::Array { index }) => *index += 1,
+                None => (),
+            },
+            b':' => {
+                if let Some(Frame::Object { key, key_start }) = frames.last_mut() {
+                    *key = Some(unquote(text[*key_start..index].trim()));
+                }
+            }
+            _ => (),
+        }
+        index += 1;
+    }
+    Ok(())
+}
+
+fn unquote(key: &str) -> String {
+    let value = match key.as_bytes().first() {
+        Some(b'"') => serde_json::from_str(key).ok(),
+        Some(b'\'') => crate::json5::parse(key, false).ok(),
+        _ => None,
+    };
+    match value {
+        Some(Value::String(key)) => key,
+        _ => key.to_string(),
+    }
+}
+
+fn pointer(frames: &[Frame]) -> String {
+    frames
+        .iter()
+        .map(|frame| match frame {
+            Frame::Object { key, .. } => format!("/{}", escape(key.as_deref().unwrap_or_default())),
+            Frame::Array { index } => format!("/{}", index),
+        })
+        .collect()
+}
+
+/// Checks that the value nests at most `max_depth` arrays and objects,
+/// for values the crate puts together itself, like the result of `set`.
+pub fn check(value: &Value, max_depth: usize) -> Result<(), String> {
+    let mut stack = vec![(value, 0, String::new())];
+
+    while let Some((value, depth, path)) = stack.pop() {
+        let children: Vec<(String, &Value)> = match value {
+            Value::Object(map) => map
+                .iter()
+                .map(|(key, value)| (escape(key).into_owned(), value))
+                .collect(),
+            Value::Array(list) => list
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (index.to_string(), value))
+                .collect(),
+            _ => continue,
+        };
+        if depth >= max_depth {
+            return Err(format!("maximum depth {} exceeded at {}", max_depth, path));
+        }
+        for (token, child) in children {
+            stack.push((child, depth + 1, format!("{}/{}", path, token)));
+        }
+    }
+    Ok(())
+}
+
+/// Parses json text without serde_json's own recursion limit, growing the stack when needed.
+/// The depth should be checked with `check_text` first.
+pub fn from_str(text: &str) -> Result<Value, serde_json::Error> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    deserializer.disable_recursion_limit();
+    let value = Value::deserialize(serde_stacker::Deserializer::new(&mut deserializer))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod depth_test {
+    use super::{check, check_text, depth, from_str};
+    use serde_json::json;
+
+    fn nested(depth: usize) -> String {
+        format!("{}1{}", r#"{"a": ["#.repeat(depth), "]}".repeat(depth))
+    }
+
+    #[test]
+    fn text_depth() {
+        assert!(check_text("1", 0).is_ok());
+        assert!(check_text("[]", 1).is_ok());
+        assert!(check_text("[[]]", 1).is_err());
+        assert!(check_text(r#"{"a": "[[[[", "b": [1]}"#, 2).is_ok());
+        assert!(check_text("{a: '{{{{', /* [[[[ */ b: [1] // {{\n}", 2).is_ok());
+        assert_eq!(
+            check_text(r#"{"x": 1, "a/b": [0, {"c": {}}]}"#, 3).unwrap_err(),
+            "maximum depth 3 exceeded at /a~1b/1/c"
+        );
+        assert_eq!(
+            check_text("{x: 1, 'q': [[]]}", 2).unwrap_err(),
+            "maximum depth 2 exceeded at /q/0"
+        );
+    }
+
+    #[test]
+    fn value_depth() {
+        let value = json!({"a": [{"b": {}}], "c": 1});
+        assert!(check(&value, 4).is_ok());
+        assert_eq!(
+            check(&value, 3).unwrap_err(),
+            "maximum depth 3 exceeded at /a/0/b"
+        );
+    }
+
+    #[test]
+    fn deep_documents() {
+        let text = nested(1000);
+        assert!(check_text(&text, 128).is_err());
+        assert!(check_text(&text, 2000).is_ok());
+
+        let value = from_str(&text).unwrap();
+        assert!(check(&value, 2000).is_ok());
+        assert!(check(&value, 1999).is_err());
+    }
+
+    #[test]
+    fn levels() {
+        assert_eq!(depth(&json!(1)), 0);
+        assert_eq!(depth(&json!("[[")), 0);
+        assert_eq!(depth(&json!({})), 1);
+        assert_eq!(depth(&json!([])), 1);
+        assert_eq!(depth(&json!({"a": [1]})), 3);
+        assert_eq!(depth(&json!([1, [[]], {"b": {"c": null}}])), 4);
+
+        for levels in [1, 2, 17, 100] {
+            assert_eq!(depth(&from_str(&nested(levels)).unwrap()), 2 * levels + 1);
+        }
+    }
+
+    #[test]
+    fn ten_thousand_levels() {
+        let text = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+        let value = from_str(&text).unwrap();
+        assert_eq!(depth(&value), 10_000);
+    }
+}
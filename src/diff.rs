@@ -0,0 +1,507 @@
+use argh::FromArgs;
+use serde_json::{Map, Value};
+
+use crate::number::values_equal;
+use crate::pointer::escape;
+use crate::{exit, input, output, value_printer, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "diff",
+    description = "Print the differences between two maps as a json patch or a unified diff",
+    note = "--format json-patch (default) prints the RFC 6902 operations that turn the first map into the second. Numbers are compared by value, array items by index.
+With --invertible removed and replaced values are added as \"_prev\", so patch --reverse can undo the patch.
+--format unified prints a diff of the two maps written with --pretty and sorted keys, with -U lines of context (default 3), so key order makes no difference. It applies with patch(1) to the output of `bash_map --pretty --sort-keys get MAP \"\"`. Nothing is printed when there is no difference.
+The names in the --- and +++ lines are the paths of @path maps, the names of environment variables, or old and new.",
+    example = r#"first                   second                      arguments              output
+{{"a": 1, "b": [1, 2]}}   {{"a": 2, "b": [1]}}          --format json-patch    [{{"op":"replace","path":"/a","value":2}},{{"op":"remove","path":"/b/1"}}]
+{{"a": 1}}                {{"a": 1, "c": true}}         --format unified       a unified diff with the line +  "c": true"#
+)]
+pub struct SubCommandDiff {
+    #[argh(positional)]
+    pub first: String,
+    #[argh(positional)]
+    pub second: String,
+    #[argh(option, default = "Format::JsonPatch")]
+    /// what to print: json-patch (default) or unified
+    format: Format,
+    #[argh(switch)]
+    /// record the removed and replaced values in "_prev", so patch --reverse can undo the patch
+    invertible: bool,
+    #[argh(option, short = 'U', default = "3")]
+    /// the number of unchanged lines around the changes of --format unified (default 3)
+    context: usize,
+}
+
+/// What `diff` prints.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Format {
+    #[default]
+    JsonPatch,
+    Unified,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json-patch" => Ok(Format::JsonPatch),
+            "unified" => Ok(Format::Unified),
+            _ => Err(format!(
+                "unknown diff format {:?}, use json-patch or unified",
+                s
+            )),
+        }
+    }
+}
+
+pub fn do_diff(args: SubCommandDiff, pretty: bool) -> Result<Option<String>, exit::Error> {
+    let old = variable_or_value(&args.first)?;
+    let new = variable_or_value(&args.second)?;
+    if args.invertible && args.format != Format::JsonPatch {
+        return Err(exit::usage(String::from(
+            "--invertible only applies to --format json-patch",
+        )));
+    }
+    match args.format {
+        Format::JsonPatch => Ok(Some(value_printer(
+            pretty,
+            &Value::Array(patch(&old, &new, args.invertible)),
+        ))),
+        Format::Unified => {
+            let lines = unified(
+                (&label(&args.first, "old"), &pretty_sorted(old)),
+                (&label(&args.second, "new"), &pretty_sorted(new)),
+                args.context,
+            );
+            Ok(lines.map(|lines| lines.join("\n")))
+        }
+    }
+}
+
+/// The RFC 6902 operations that turn `old` into `new`. Items past the end of the
+/// shorter array are removed from the last one back, so the indices stay right.
+/// When `invertible` removed and replaced values are kept in `"_prev"`.
+pub fn patch(old: &Value, new: &Value, invertible: bool) -> Vec<Value> {
+    let mut operations = Vec::new();
+    collect_operations(&mut operations, String::new(), old, new);
+    operations
+        .into_iter()
+        .map(|(op, path, value, prev)| {
+            let mut map = Map::new();
+            map.insert(String::from("op"), Value::from(op));
+            map.insert(String::from("path"), Value::String(path));
+            if let Some(value) = value {
+                map.insert(String::from("value"), value.clone());
+            }
+            if let Some(prev) = prev.filter(|_| invertible) {
+                map.insert(String::from("_prev"), prev.clone());
+            }
+            Value::Object(map)
+        })
+        .collect()
+}
+
+/// An operation with its path, value and the value it removes or replaces.
+type Operation<'a> = (&'static str, String, Option<&'a Value>, Option<&'a Value>);
+
+fn collect_operations<'a>(
+    operations: &mut Vec<Operation<'a>>,
+    pointer: String,
+    old: &'a Value,
+    new: &'a Value,
+) {
+    let path = |token: &str| format!("{}/{}", pointer, escape(token));
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, item) in old {
+                match new.get(key) {
+                    Some(other) => collect_operations(operations, path(key), item, other),
+                    None => operations.push(("remove", path(key), None, Some(item))),
+                }
+            }
+            for (key, item) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                operations.push(("add", path(key), Some(item), None));
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (index, (item, other)) in old.iter().zip(new).enumerate() {
+                collect_operations(operations, path(&index.to_string()), item, other);
+            }
+            for (index, item) in new.iter().enumerate().skip(old.len()) {
+                operations.push(("add", path(&index.to_string()), Some(item), None));
+            }
+            for index in (new.len()..old.len()).rev() {
+                operations.push(("remove", path(&index.to_string()), None, Some(&old[index])));
+            }
+        }
+        (old, new) if !values_equal(old, new) => {
+            operations.push(("replace", pointer, Some(new), Some(old)))
+        }
+        _ => (),
+    }
+}
+
+/// The name of a map in the header of a unified diff.
+fn label(input: &str, fallback: &str) -> String {
+    match input::Source::of(input) {
+        input::Source::File(path) => path.display().to_string(),
+        input::Source::Env(name) => name.to_string(),
+        input::Source::Literal(_) => fallback.to_string(),
+    }
+}
+
+/// The value like `--pretty --sort-keys` prints it.
+fn pretty_sorted(mut value: Value) -> String {
+    output::sort_keys(&mut value, false);
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// The lines of a unified diff between the texts with their names, `None` when they are the same.
+fn unified(old: (&str, &str), new: (&str, &str), context: usize) -> Option<Vec<String>> {
+    let old_lines: Vec<&str> = old.1.lines().collect();
+    let new_lines: Vec<&str> = new.1.lines().collect();
+
+    // every edit with the index of the old and the new line it is at
+    let mut positions = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for edit in edits(&old_lines, &new_lines) {
+        positions.push((edit, i, j));
+        match edit {
+            Edit::Equal => {
+                i += 1;
+                j += 1;
+            }
+            Edit::Delete => i += 1,
+            Edit::Insert => j += 1,
+        }
+    }
+    let changed: Vec<usize> = (0..positions.len())
+        .filter(|x| positions[*x].0 != Edit::Equal)
+        .collect();
+    if changed.is_empty() {
+        return None;
+    }
+
+    let mut lines = vec![format!("--- {}", old.0), format!("+++ {}", new.0)];
+    let mut next = 0;
+    while next < changed.len() {
+        // changes with at most twice the context between them share a hunk
+        let mut last = next;
+        while last + 1 < changed.len() && changed[last + 1] - changed[last] <= 2 * context + 1 {
+            last += 1;
+        }
+        let start = changed[next].saturating_sub(context);
+        let end = (changed[last] + context + 1).min(positions.len());
+        let hunk = &positions[start..end];
+
+        let old_count = hunk.iter().filter(|x| x.0 != Edit::Insert).count();
+        let new_count = hunk.iter().filter(|x| x.0 != Edit::Delete).count();
+        // an empty range is written as the line before it
+        let old_start = hunk[0].1 + usize::from(old_count > 0);
+        let new_start = hunk[0].2 + usize::from(new_count > 0);
+        lines.push(format!(
+            "@@ -{} +{} @@",
+            range(old_start, old_count),
+            range(new_start, new_count)
+        ));
+        for (edit, i, j) in hunk {
+            lines.push(match edit {
+                Edit::Equal => format!(" {}", old_lines[*i]),
+                Edit::Delete => format!("-{}", old_lines[*i]),
+                Edit::Insert => format!("+{}", new_lines[*j]),
+            });
+        }
+        next = last + 1;
+    }
+    Some(lines)
+}
+
+/// A range in a hunk header, the count is left out when it is 1.
+fn range(start: usize, count: usize) -> String {
+    match count {
+        1 => start.to_string(),
+        _ => format!("{},{}", start, count),
+    }
+}
+
+/// The shortest edit script from `old` to `new`, with Myers' algorithm.
+fn edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    let index = |k: isize| (k + max + 1) as usize;
+    let mut furthest = vec![0isize; 2 * max as usize + 3];
+    let mut trace = Vec::new();
+
+    'search: for d in 0..=max {
+        trace.push(furthest.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = match k == -d || (k != d && furthest[index(k - 1)] < furthest[index(k + 1)])
+            {
+                true => furthest[index(k + 1)],
+                false => furthest[index(k - 1)] + 1,
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut script = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, furthest) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let previous = match k == -d || (k != d && furthest[index(k - 1)] < furthest[index(k + 1)])
+        {
+            true => k + 1,
+            false => k - 1,
+        };
+        let previous_x = furthest[index(previous)];
+        let previous_y = previous_x - previous;
+        while x > previous_x && y > previous_y {
+            script.push(Edit::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            script.push(match x == previous_x {
+                true => Edit::Insert,
+                false => Edit::Delete,
+            });
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    script.reverse();
+    script
+}
+
+/// A location where two documents differ, `None` on the side that does not have it.
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub pointer: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// The locations where `new` differs from `old` in document order. Objects and arrays
+/// are compared key by key and index by index, anything else as a whole.
+pub fn changes(old: &Value, new: &Value) -> Vec<Change> {
+    let mut list = Vec::new();
+    collect(&mut list, String::new(), Some(old), Some(new));
+    list
+}
+
+fn collect(list: &mut Vec<Change>, pointer: String, old: Option<&Value>, new: Option<&Value>) {
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let added = new.keys().filter(|key| !old.contains_key(*key));
+            for key in old.keys().chain(added) {
+                let token = escape(key);
+                collect(
+                    list,
+                    format!("{}/{}", pointer, token),
+                    old.get(key),
+                    new.get(key),
+                );
+            }
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            for index in 0..old.len().max(new.len()) {
+                collect(
+                    list,
+                    format!("{}/{}", pointer, index),
+                    old.get(index),
+                    new.get(index),
+                );
+            }
+        }
+        (old, new) if old != new => list.push(Change {
+            pointer,
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+        _ => (),
+    }
+}
+
+/// What `--dry-run` prints, a `pointer: old -> new` line for every change, with
+/// `(missing)` for a side without the location. Fails without a message when nothing changes.
+pub fn dry_run(old: &Value, new: &Value) -> Result<String, String> {
+    let side = |value: &Option<Value>| match value {
+        Some(value) => value_printer(false, value),
+        None => String::from("(missing)"),
+    };
+    let lines: Vec<String> = changes(old, new)
+        .iter()
+        .map(|change| {
+            format!(
+                "{}: {} -> {}",
+                change.pointer,
+                side(&change.old),
+                side(&change.new)
+            )
+        })
+        .collect();
+
+    match lines.is_empty() {
+        true => Err(String::new()),
+        false => Ok(lines.join("\n")),
+    }
+}
+
+#[cfg(test)]
+mod diff_test {
+    use super::{changes, dry_run, edits, patch, unified, Change, Edit};
+    use serde_json::json;
+
+    #[test]
+    fn nested_changes() {
+        assert_eq!(
+            changes(
+                &json!({"server": {"port": 8080, "host": "a"}, "list": [1, 2], "old": true}),
+                &json!({"server": {"port": 9090, "host": "a"}, "list": [1], "new/key": null})
+            ),
+            vec![
+                Change {
+                    pointer: String::from("/server/port"),
+                    old: Some(json!(8080)),
+                    new: Some(json!(9090)),
+                },
+                Change {
+                    pointer: String::from("/list/1"),
+                    old: Some(json!(2)),
+                    new: None,
+                },
+                Change {
+                    pointer: String::from("/old"),
+                    old: Some(json!(true)),
+                    new: None,
+                },
+                Change {
+                    pointer: String::from("/new~1key"),
+                    old: None,
+                    new: Some(json!(null)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn replaced_container() {
+        assert_eq!(
+            changes(&json!({"a": [1]}), &json!({"a": {"0": 1}})),
+            vec![Change {
+                pointer: String::from("/a"),
+                old: Some(json!([1])),
+                new: Some(json!({"0": 1})),
+            }]
+        );
+        assert_eq!(changes(&json!(1), &json!(2))[0].pointer, "");
+    }
+
+    #[test]
+    fn dry_run_lines() {
+        assert_eq!(
+            dry_run(&json!({"a": 1}), &json!({"a": 2, "b": [3]})).unwrap(),
+            "/a: 1 -> 2\n/b: (missing) -> [3]"
+        );
+        assert_eq!(
+            dry_run(&json!({"a": 1}), &json!({"a": 1})),
+            Err(String::new())
+        );
+    }
+
+    #[test]
+    fn patch_operations() {
+        assert_eq!(
+            patch(
+                &json!({"a": 1, "b": [1, 2, 3], "c": {"d": 1.0}, "e/f": 0}),
+                &json!({"a": 2, "b": [1], "c": {"d": 1, "g": null}}),
+                false
+            ),
+            vec![
+                json!({"op": "replace", "path": "/a", "value": 2}),
+                json!({"op": "remove", "path": "/b/2"}),
+                json!({"op": "remove", "path": "/b/1"}),
+                json!({"op": "add", "path": "/c/g", "value": null}),
+                json!({"op": "remove", "path": "/e~1f"}),
+            ]
+        );
+        assert_eq!(
+            patch(&json!([1]), &json!([1, 2]), false),
+            vec![json!({"op": "add", "path": "/1", "value": 2})]
+        );
+        assert!(patch(&json!({"a": [1]}), &json!({"a": [1]}), false).is_empty());
+        assert_eq!(
+            patch(
+                &json!({"a": 1, "b": [1, 2]}),
+                &json!({"a": 2, "b": [1]}),
+                true
+            ),
+            vec![
+                json!({"op": "replace", "path": "/a", "value": 2, "_prev": 1}),
+                json!({"op": "remove", "path": "/b/1", "_prev": 2}),
+            ]
+        );
+    }
+
+    #[test]
+    fn shortest_edits() {
+        use Edit::*;
+
+        assert_eq!(
+            edits(&["a", "b", "c"], &["a", "x", "c", "d"]),
+            vec![Equal, Delete, Insert, Equal, Insert]
+        );
+        assert_eq!(edits(&[], &["a"]), vec![Insert]);
+        assert_eq!(edits(&["a"], &[]), vec![Delete]);
+        assert!(edits(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn unified_hunks() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11";
+        assert_eq!(
+            unified(("a", old), ("b", new), 1).unwrap(),
+            [
+                "--- a",
+                "+++ b",
+                "@@ -2,3 +2,3 @@",
+                " 2",
+                "-3",
+                "+three",
+                " 4",
+                "@@ -10 +10,2 @@",
+                " 10",
+                "+11",
+            ]
+        );
+        assert_eq!(
+            unified(("a", old), ("b", new), 4).unwrap()[2],
+            "@@ -1,10 +1,11 @@"
+        );
+        assert_eq!(
+            unified(("a", ""), ("b", "x"), 3).unwrap(),
+            ["--- a", "+++ b", "@@ -0,0 +1 @@", "+x"]
+        );
+        assert_eq!(unified(("a", old), ("b", old), 3), None);
+    }
+}
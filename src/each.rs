@@ -0,0 +1,57 @@
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::{exit, output, type_name, variable_or_value, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "each",
+    description = "Print every item of the array at the pointer as compact json on its own line",
+    note = "Meant for `bash_map each \"$DOC\" /items | while IFS= read -r item; do ... done`. An empty array prints nothing, anything other than an array fails.
+With a field pointer the value at that pointer in every item is printed instead, strings without quotes. Items without the field are skipped, or print null with --null-missing.
+Strings can contain newlines, use --print0 with `while IFS= read -r -d '' item` to iterate them safely.",
+    example = r#"input                                        arguments                      output
+{{"items": [{{"name": "a"}}, 2]}}                /items                         {{"name":"a"}} and 2 on their own line
+{{"items": [{{"name": "a"}}, {{"name": "b"}}]}}    /items /name                   a and b on their own line
+{{"items": [{{"name": "a"}}, {{}}]}}               /items /name                   a
+{{"items": [{{"name": "a"}}, {{}}]}}               /items /name --null-missing    a and null on their own line"#
+)]
+pub struct SubCommandEach {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional)]
+    field: Option<Pointer>,
+    #[argh(switch)]
+    /// print null for items without the field instead of skipping them
+    null_missing: bool,
+}
+
+/// The lines to print, None for an empty array.
+pub fn do_each(args: SubCommandEach, pretty: bool) -> Result<Option<String>, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_str();
+    let list = match value.pointer(pointer) {
+        Some(Value::Array(list)) => list,
+        Some(other) => {
+            return Err(format!("{} is of type {}, not array", pointer, type_name(other)).into())
+        }
+        None => return Err(format!("{} does not exist", pointer).into()),
+    };
+
+    let field = match &args.field {
+        Some(field) => field.as_str(),
+        None => return Ok(output::lines(list, pretty, false)),
+    };
+    let items: Vec<Value> = list
+        .iter()
+        .filter_map(|item| match item.pointer(field) {
+            Some(found) => Some(found.clone()),
+            None if args.null_missing => Some(Value::Null),
+            None => None,
+        })
+        .collect();
+    Ok(output::lines(&items, pretty, true))
+}
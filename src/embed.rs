@@ -0,0 +1,237 @@
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::{exit, input, type_name, value_printer, variable_or_value, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "stringify",
+    description = "Replace the value at the pointer by a string holding its json",
+    note = "The json in the string is compact and keeps the key order and numbers as they are written, so parse-embedded gives back the same value. With --pretty-inner it is indented instead.
+A value that is a string already is left as it is, or fails with --strict.",
+    example = r#"input                                  arguments            output
+{{"policy": {{"Version": "2012"}}}}        /policy              {{"policy":"{{\"Version\":\"2012\"}}"}}
+{{"a": [1, 2]}}                          /a --pretty-inner    {{"a":"[\n  1,\n  2\n]"}}"#
+)]
+pub struct SubCommandStringify {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(switch)]
+    /// indent the json in the string
+    pretty_inner: bool,
+    #[argh(switch)]
+    /// fail when the value is a string already
+    strict: bool,
+}
+
+pub fn do_stringify(args: SubCommandStringify, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_str();
+    let target = value
+        .pointer_mut(pointer)
+        .ok_or_else(|| format!("{} does not exist", pointer))?;
+
+    if target.is_string() {
+        if input::strict(args.strict) {
+            return Err(format!("{} is a string already", pointer).into());
+        }
+    } else {
+        let text = match args.pretty_inner {
+            true => serde_json::to_string_pretty(&*target),
+            false => serde_json::to_string(&*target),
+        };
+        *target = Value::String(text.map_err(|e| e.to_string())?);
+    }
+    Ok(value_printer(pretty, &value))
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "parse-embedded",
+    description = "Replace the string at the pointer by the json it holds",
+    note = "The string has to be valid json, otherwise it fails with 3 and shows where the json went wrong.
+With --recursive every string under the pointer, or in the whole map when no pointer is given, that holds a json object or array is replaced, and the strings in those are expanded as well. Strings holding other json, like \"1\" or \"true\", are left as they are.",
+    example = r#"input                                   arguments      output
+{{"policy": "{{\"Version\":\"2012\"}}"}}    /policy        {{"policy":{{"Version":"2012"}}}}
+{{"logs": [{{"body": "[1]"}}, "2"]}}        --recursive    {{"logs":[{{"body":[1]}},"2"]}}"#
+)]
+pub struct SubCommandParseEmbedded {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(switch)]
+    /// expand every string that holds a json object or array
+    recursive: bool,
+}
+
+pub fn do_parse_embedded(
+    args: SubCommandParseEmbedded,
+    pretty: bool,
+) -> Result<String, exit::Error> {
+    if args.pointer.is_none() && !args.recursive {
+        return Err(exit::usage(String::from(
+            "give the pointer of the string, or --recursive",
+        )));
+    }
+    let mut value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_ref().map_or("", Pointer::as_str);
+    let target = value
+        .pointer_mut(pointer)
+        .ok_or_else(|| format!("{} does not exist", pointer))?;
+
+    if args.recursive {
+        expand(target);
+    } else {
+        let text = match &*target {
+            Value::String(text) => text,
+            other => {
+                return Err(
+                    format!("{} is of type {}, not string", pointer, type_name(other)).into(),
+                )
+            }
+        };
+        *target = serde_json::from_str(text).map_err(|e| {
+            exit::parse(format!(
+                "the string at {} is {}",
+                pointer,
+                input::value_error(text, &e)
+            ))
+        })?;
+    }
+    Ok(value_printer(pretty, &value))
+}
+
+/// Replaces the strings that hold an object or array by their value, at any depth.
+fn expand(value: &mut Value) {
+    if let Value::String(text) = value {
+        match serde_json::from_str(text) {
+            Ok(parsed @ (Value::Object(_) | Value::Array(_))) => *value = parsed,
+            _ => return,
+        }
+    }
+    match value {
+        Value::Object(map) => map.values_mut().for_each(expand),
+        Value::Array(list) => list.iter_mut().for_each(expand),
+        _ => (),
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "fix-encoding",
+    description = "Replace strings that hold json encoded once or more by the value they encode",
+    note = "A string is fixed when it is, after decoding it as often as it was encoded, a json object or array. With --aggressive strings holding any json are fixed, so \"1\" becomes 1 and \"true\" true. Only strings that are valid json as a whole count, \"true story\" is left as it is.
+The values that come out are fixed as well, at any depth. How many times a string was decoded is counted and the total is printed to stderr. Given a pointer only the part under it is fixed.",
+    example = r#"input                                   arguments       output
+"{{\"a\":\"[1]\"}}"                                       {{"a":[1]}}
+{{"a": "\"{{\\\"b\\\":1}}\"", "n": "1"}}                    {{"a":{{"b":1}},"n":"1"}}
+{{"a": "\"{{\\\"b\\\":1}}\"", "n": "1"}}    --aggressive    {{"a":{{"b":1}},"n":1}}"#
+)]
+pub struct SubCommandFixEncoding {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(switch)]
+    /// also fix strings that hold json other than an object or array
+    aggressive: bool,
+}
+
+pub fn do_fix_encoding(args: SubCommandFixEncoding, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_ref().map_or("", Pointer::as_str);
+    let target = value
+        .pointer_mut(pointer)
+        .ok_or_else(|| format!("{} does not exist", pointer))?;
+
+    let fixes = fix(target, args.aggressive);
+    eprintln!("{} fixed", fixes);
+    Ok(value_printer(pretty, &value))
+}
+
+/// Decodes the strings that hold encoded json, at any depth, and counts how many times strings were decoded.
+fn fix(value: &mut Value, aggressive: bool) -> usize {
+    let mut fixes = 0;
+    if let Value::String(text) = value {
+        if let Some((decoded, times)) = decode(text, aggressive) {
+            *value = decoded;
+            fixes += times;
+        }
+    }
+    match value {
+        Value::Object(map) => fixes + map.values_mut().map(|x| fix(x, aggressive)).sum::<usize>(),
+        Value::Array(list) => fixes + list.iter_mut().map(|x| fix(x, aggressive)).sum::<usize>(),
+        _ => fixes,
+    }
+}
+
+/// The value the text encodes with how many times it was encoded, decoding for as long as
+/// that gives a string. `None` when it is not json, or without `aggressive` not an object or array.
+fn decode(text: &str, aggressive: bool) -> Option<(Value, usize)> {
+    let mut decoded: Value = serde_json::from_str(text).ok()?;
+    let mut times = 1;
+    while let Value::String(inner) = &decoded {
+        match serde_json::from_str(inner) {
+            Ok(value) => decoded = value,
+            Err(_) => break,
+        }
+        times += 1;
+    }
+    match decoded {
+        Value::Object(_) | Value::Array(_) => Some((decoded, times)),
+        _ if aggressive => Some((decoded, times)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod embed_test {
+    use super::{decode, expand, fix};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn expand_objects_and_arrays_only() {
+        let mut value = json!({
+            "a": "{\"b\": \"[1, \\\"2\\\"]\"}",
+            "n": "1",
+            "t": "true story",
+            "s": "\"x\"",
+            "l": ["[]", "{"]
+        });
+        expand(&mut value);
+        assert_eq!(
+            value,
+            json!({"a": {"b": [1, "2"]}, "n": "1", "t": "true story", "s": "\"x\"", "l": [[], "{"]})
+        );
+    }
+
+    #[test]
+    fn decode_layers() {
+        let once = json!({"a": 1}).to_string();
+        let twice = Value::String(once.clone()).to_string();
+        assert_eq!(decode(&once, false), Some((json!({"a": 1}), 1)));
+        assert_eq!(decode(&twice, false), Some((json!({"a": 1}), 2)));
+        assert_eq!(decode("\"\\\"x\\\"\"", false), None);
+        assert_eq!(decode("\"\\\"x\\\"\"", true), Some((json!("x"), 2)));
+        assert_eq!(decode("true story", true), None);
+        assert_eq!(decode("12", false), None);
+        assert_eq!(decode("12", true), Some((json!(12), 1)));
+    }
+
+    #[test]
+    fn fix_nested() {
+        let inner = Value::String(json!({"b": "[1]"}).to_string()).to_string();
+        let mut value = json!({"a": inner, "n": "1", "l": ["{}", "true story"]});
+        assert_eq!(fix(&mut value, false), 4);
+        assert_eq!(
+            value,
+            json!({"a": {"b": [1]}, "n": "1", "l": [{}, "true story"]})
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use argh::FromArgs;
+use regex::Regex;
+use serde_json::{from_str, Map, Value};
+use std::ffi::OsStr;
+
+use crate::{exit, value_printer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "env",
+    description = "Print the environment variables as a json object of strings",
+    note = "The names are sorted unless --unsorted is given. With --parse values that are json are added as json, so a variable holding [1,2] gives an array and one holding 80 a number.
+Names and values that are not valid utf-8 have their invalid bytes replaced by U+FFFD, with a warning on stderr.",
+    example = r#"arguments                  output
+--filter '^HOME$'          {{"HOME":"/home/user"}}
+--filter '^PORT$' --parse  {{"PORT":8080}}"#
+)]
+pub struct SubCommandEnv {
+    #[argh(switch)]
+    /// add values that are json as json instead of strings
+    parse: bool,
+    #[argh(option)]
+    /// only add the variables with names that match this regular expression
+    filter: Option<String>,
+    #[argh(switch)]
+    /// keep the order of the environment instead of sorting the names
+    unsorted: bool,
+}
+
+pub fn do_env(args: SubCommandEnv, pretty: bool) -> Result<String, exit::Error> {
+    let filter = match &args.filter {
+        Some(pattern) => {
+            Some(Regex::new(pattern).map_err(|e| exit::usage(format!("invalid filter: {}", e)))?)
+        }
+        None => None,
+    };
+
+    let mut variables: Vec<(String, String)> = std::env::vars_os()
+        .map(|(name, value)| {
+            let name = lossy(&name, || format!("the name {}", name.to_string_lossy()));
+            (name, value)
+        })
+        .filter(|(name, _)| filter.as_ref().is_none_or(|x| x.is_match(name)))
+        .map(|(name, value)| {
+            let value = lossy(&value, || format!("the value of {}", name));
+            (name, value)
+        })
+        .collect();
+    if !args.unsorted {
+        variables.sort();
+    }
+
+    let map: Map<String, Value> = variables
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match args.parse {
+                true => from_str(&value).unwrap_or(Value::String(value)),
+                false => Value::String(value),
+            };
+            (name, value)
+        })
+        .collect();
+    Ok(value_printer(pretty, &Value::Object(map)))
+}
+
+/// The text with invalid utf-8 replaced, with a warning naming `what` when there was any.
+fn lossy(text: &OsStr, what: impl FnOnce() -> String) -> String {
+    if text.to_str().is_none() {
+        eprintln!(
+            "warning: {} is not valid utf-8, invalid bytes are replaced",
+            what()
+        );
+    }
+    text.to_string_lossy().into_owned()
+}
@@ -0,0 +1,140 @@
+/// How `--escaped` quotes the output.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Style {
+    /// a Rust string literal, close to a bash double quoted string but not the same
+    #[default]
+    Rust,
+    /// a bash single quoted string, nothing in it is expanded
+    BashSingle,
+    /// a bash double quoted string with `$`, backticks, `"` and `\` escaped and `!` kept out of it
+    BashDouble,
+    /// a PowerShell double quoted string with `$` and backticks escaped by a backtick and `"` doubled
+    Powershell,
+    /// a fish single quoted string, where `\` and `'` are escaped by a backslash
+    Fish,
+    /// a zsh single quoted string, quoted like `BashSingle`
+    Zsh,
+}
+
+impl std::str::FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rust" => Ok(Style::Rust),
+            "bash-single" => Ok(Style::BashSingle),
+            "bash-double" => Ok(Style::BashDouble),
+            "powershell" => Ok(Style::Powershell),
+            "fish" => Ok(Style::Fish),
+            "zsh" => Ok(Style::Zsh),
+            _ => Err(format!(
+                "unknown escape style {:?}, use rust, bash-single, bash-double, powershell, fish or zsh",
+                s
+            )),
+        }
+    }
+}
+
+/// The text quoted in the style.
+pub fn escape(text: &str, style: Style) -> String {
+    match style {
+        Style::Rust => format!("{:?}", text),
+        // a quote can not be escaped inside single quotes, so it ends the quoted part,
+        // adds an escaped quote and starts a new quoted part: '\''
+        Style::BashSingle | Style::Zsh => format!("'{}'", text.replace('\'', r"'\''")),
+        Style::BashDouble => {
+            let mut escaped = String::from("\"");
+            for c in text.chars() {
+                match c {
+                    '$' | '`' | '"' | '\\' => {
+                        escaped.push('\\');
+                        escaped.push(c);
+                    }
+                    // a backslash does not stop history expansion inside double quotes
+                    // in an interactive shell, so the `!` is single quoted instead
+                    '!' => escaped.push_str("\"'!'\""),
+                    c => escaped.push(c),
+                }
+            }
+            escaped.push('"');
+            escaped
+        }
+        Style::Powershell => {
+            let mut escaped = String::from("\"");
+            for c in text.chars() {
+                match c {
+                    '$' | '`' => {
+                        escaped.push('`');
+                        escaped.push(c);
+                    }
+                    // PowerShell also ends strings at typographic double quotes
+                    '"' | '\u{201c}' | '\u{201d}' | '\u{201e}' => {
+                        escaped.push(c);
+                        escaped.push(c);
+                    }
+                    c => escaped.push(c),
+                }
+            }
+            escaped.push('"');
+            escaped
+        }
+        Style::Fish => format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'")),
+    }
+}
+
+#[cfg(test)]
+mod escape_test {
+    use super::{escape, Style};
+
+    #[test]
+    fn bash_single() {
+        assert_eq!(escape("it's $HOME", Style::BashSingle), r"'it'\''s $HOME'");
+        assert_eq!(escape("", Style::BashSingle), "''");
+    }
+
+    #[test]
+    fn bash_double() {
+        assert_eq!(
+            escape(r#"{"a": "$HOME `x` \n"}"#, Style::BashDouble),
+            r#""{\"a\": \"\$HOME \`x\` \\n\"}""#
+        );
+        assert_eq!(escape("hi!", Style::BashDouble), r#""hi"'!'"""#);
+    }
+
+    #[test]
+    fn powershell() {
+        assert_eq!(
+            escape(r#"{"a": "$env:HOME `n"}"#, Style::Powershell),
+            r#""{""a"": ""`$env:HOME ``n""}""#
+        );
+        assert_eq!(
+            escape("{\n  \"a\": \"\u{201c}x\u{201d}\"\n}", Style::Powershell),
+            "\"{\n  \"\"a\"\": \"\"\u{201c}\u{201c}x\u{201d}\u{201d}\"\"\n}\""
+        );
+        assert_eq!(escape("", Style::Powershell), r#""""#);
+    }
+
+    #[test]
+    fn fish() {
+        assert_eq!(
+            escape("it's a \\ *.txt\n$HOME (ls) ✓", Style::Fish),
+            "'it\\'s a \\\\ *.txt\n$HOME (ls) ✓'"
+        );
+        assert_eq!(escape(r"\'", Style::Fish), r"'\\\''");
+        assert_eq!(escape("", Style::Fish), "''");
+    }
+
+    #[test]
+    fn zsh() {
+        assert_eq!(
+            escape("it's a \\ *.txt\n$HOME !! ✓", Style::Zsh),
+            "'it'\\''s a \\ *.txt\n$HOME !! ✓'"
+        );
+        assert_eq!(escape("''", Style::Zsh), r"''\'''\'''");
+    }
+
+    #[test]
+    fn rust() {
+        assert_eq!(escape("a\"\n", Style::Rust), r#""a\"\n""#);
+    }
+}
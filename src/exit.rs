@@ -0,0 +1,65 @@
+use std::fmt;
+
+/// Exit code of a check that came out negative, like `compare` finding a difference or
+/// `index-of` finding nothing, and of every error not in one of the classes below.
+pub const FAILURE: i32 = 1;
+
+/// Exit code of arguments that can not be used, like options that can not be combined
+/// or a pointer that does not start with `/`.
+pub const USAGE: i32 = 2;
+
+/// Exit code of an input that could not be parsed.
+pub const PARSE: i32 = 3;
+
+/// Exit code of a file, directory or stream that could not be read or written.
+pub const IO: i32 = 4;
+
+/// An error with the exit code the run ends with when it fails with it.
+/// Plain `String` errors convert to one with `FAILURE`.
+#[derive(Clone, PartialEq)]
+pub struct Error {
+    pub code: i32,
+    pub message: String,
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error {
+            code: FAILURE,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Like the message, so errors print the same as the `String` errors they replace.
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.message, f)
+    }
+}
+
+/// An error in the arguments, the run exits with `USAGE` when it fails with it.
+pub fn usage(message: String) -> Error {
+    command(USAGE, message)
+}
+
+/// An input that could not be parsed, the run exits with `PARSE` when it fails with it.
+pub fn parse(message: String) -> Error {
+    command(PARSE, message)
+}
+
+/// An io error, the run exits with `IO` when it fails with it.
+pub fn io(message: String) -> Error {
+    command(IO, message)
+}
+
+/// A command that failed, the run exits with the exit code of the command.
+pub fn command(code: i32, message: String) -> Error {
+    Error { code, message }
+}
@@ -0,0 +1,334 @@
+use std::fs::{self, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::exit;
+
+/// Settings from the command line for the files that are written.
+#[derive(Debug, Default)]
+pub struct Options {
+    /// the suffix of the copy of the previous version kept by `--backup`
+    pub backup: Option<String>,
+    /// replace a symlink itself instead of the file it points to
+    pub no_follow_symlinks: bool,
+}
+
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+/// Sets the file options for the rest of the process, only the first call has effect.
+pub fn configure(options: Options) {
+    let _ = OPTIONS.set(options);
+}
+
+pub fn options() -> &'static Options {
+    OPTIONS.get_or_init(Options::default)
+}
+
+/// Writes `contents` followed by a newline to `path` by writing a temporary file next to it
+/// and renaming it over the target, so readers never see a half written map.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), exit::Error> {
+    replace(path, &[contents.as_bytes(), b"\n"])
+}
+
+/// Like `write_atomic`, but writes `contents` exactly as given.
+pub fn write_atomic_exact(path: &Path, contents: &str) -> Result<(), exit::Error> {
+    replace(path, &[contents.as_bytes()])
+}
+
+fn replace(path: &Path, parts: &[&[u8]]) -> Result<(), exit::Error> {
+    let options = options();
+    replace_with(
+        path,
+        options.backup.as_deref(),
+        !options.no_follow_symlinks,
+        |file| parts.iter().try_for_each(|part| file.write_all(part)),
+    )
+    .map_err(|e| exit::io(format!("unable to write {}: {}", path.display(), e)))
+}
+
+/// Replaces the file at `path`, or the file its symlinks point to when `follow_symlinks`,
+/// with what `write` writes. The previous version is copied to the path with the
+/// `backup` suffix first. Nothing is left behind when writing fails.
+fn replace_with(
+    path: &Path,
+    backup: Option<&str>,
+    follow_symlinks: bool,
+    write: impl FnOnce(&mut fs::File) -> io::Result<()>,
+) -> io::Result<()> {
+    let target = match follow_symlinks {
+        true => resolve_symlinks(path)?,
+        false => path.to_path_buf(),
+    };
+    let permissions = match fs::metadata(&target) {
+        Ok(metadata) => Some(metadata.permissions()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    if let (Some(suffix), Some(permissions)) = (backup, &permissions) {
+        let previous = fs::read(&target)?;
+        let mut backup_path = target.clone().into_os_string();
+        backup_path.push(suffix);
+        write_renamed(Path::new(&backup_path), Some(permissions), |file| {
+            file.write_all(&previous)
+        })?;
+    }
+    write_renamed(&target, permissions.as_ref(), write)
+}
+
+/// Writes a new temporary file next to `path` and renames it over `path`.
+fn write_renamed(
+    path: &Path,
+    permissions: Option<&fs::Permissions>,
+    write: impl FnOnce(&mut fs::File) -> io::Result<()>,
+) -> io::Result<()> {
+    let (temp_path, mut file) = create_temp(path)?;
+    let result = write(&mut file)
+        .and_then(|_| match permissions {
+            Some(permissions) => file.set_permissions(permissions.clone()),
+            None => Ok(()),
+        })
+        .and_then(|_| file.sync_all())
+        .and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+    sync_parent(path);
+    Ok(())
+}
+
+/// A new file named `.<name>.<pid>.<n>.tmp` next to `path`, with `n` counting up
+/// until the name is not taken.
+fn create_temp(path: &Path) -> io::Result<(PathBuf, fs::File)> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "the path is not a file path")
+    })?;
+    loop {
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(file_name);
+        temp_name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = path.with_file_name(temp_name);
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// The path with its symlinks followed, the target does not have to exist.
+fn resolve_symlinks(path: &Path) -> io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    // the limit of Linux, so a loop of links is an error instead of a hang
+    for _ in 0..40 {
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&path)?;
+                path = match path.parent() {
+                    Some(parent) => parent.join(link),
+                    None => link,
+                };
+            }
+            _ => return Ok(path),
+        }
+    }
+    Err(io::Error::other(format!(
+        "too many levels of symbolic links at {}",
+        path.display()
+    )))
+}
+
+/// Flushes the rename to disk, where directories can be synced.
+fn sync_parent(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = match parent.as_os_str().is_empty() {
+            true => Path::new("."),
+            false => parent,
+        };
+        if let Ok(directory) = fs::File::open(parent) {
+            let _ = directory.sync_all();
+        }
+    }
+}
+
+/// Exit code used when the lock on a file could not be acquired within `--lock-timeout`,
+/// matching `EX_TEMPFAIL` from sysexits.h.
+pub const LOCK_TIMEOUT_EXIT_CODE: i32 = 75;
+
+/// Advisory lock guarding a map file, released when dropped.
+///
+/// The lock is taken on a `<file>.lock` file next to the map instead of the map itself,
+/// because `write_atomic` replaces the map with a new file on every write.
+pub struct FileLock {
+    _file: fs::File,
+}
+
+pub enum LockError {
+    Timeout,
+    Io(String),
+}
+
+/// Path of the lock file belonging to the map file at `path`.
+pub fn lock_path(path: &Path) -> PathBuf {
+    let mut lock_name = path.file_name().unwrap_or_default().to_os_string();
+    lock_name.push(".lock");
+    path.with_file_name(lock_name)
+}
+
+pub fn lock(
+    path: &Path,
+    exclusive: bool,
+    timeout: Option<Duration>,
+) -> Result<FileLock, LockError> {
+    let lock_path = lock_path(path);
+
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| LockError::Io(format!("unable to open {}: {}", lock_path.display(), e)))?;
+
+    let io_error =
+        |e: std::io::Error| LockError::Io(format!("unable to lock {}: {}", lock_path.display(), e));
+
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None if exclusive => {
+            return file
+                .lock()
+                .map(|_| FileLock { _file: file })
+                .map_err(io_error)
+        }
+        None => {
+            return file
+                .lock_shared()
+                .map(|_| FileLock { _file: file })
+                .map_err(io_error)
+        }
+    };
+
+    let start = Instant::now();
+    loop {
+        let attempt = if exclusive {
+            file.try_lock()
+        } else {
+            file.try_lock_shared()
+        };
+
+        match attempt {
+            Ok(()) => return Ok(FileLock { _file: file }),
+            Err(TryLockError::WouldBlock) if start.elapsed() < timeout => {
+                std::thread::sleep(Duration::from_millis(10))
+            }
+            Err(TryLockError::WouldBlock) => return Err(LockError::Timeout),
+            Err(TryLockError::Error(e)) => return Err(io_error(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod file_test {
+    use super::replace_with;
+    use std::fs;
+    use std::io::{self, Write};
+    use std::path::Path;
+
+    fn names(directory: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(directory)
+            .unwrap()
+            .map(|x| x.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn no_temp_files_left() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map.json");
+
+        replace_with(&path, None, true, |file| file.write_all(b"{}")).unwrap();
+        replace_with(&path, None, true, |file| file.write_all(b"[]")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[]");
+        assert_eq!(names(dir.path()), ["map.json"]);
+
+        let failed = replace_with(&path, Some("~"), true, |file| {
+            file.write_all(b"[1, ")?;
+            Err(io::Error::other("serialization failed"))
+        });
+        assert_eq!(failed.unwrap_err().to_string(), "serialization failed");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[]");
+        // the backup is written before the map
+        assert_eq!(names(dir.path()), ["map.json", "map.json~"]);
+    }
+
+    #[test]
+    fn backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map.json");
+
+        replace_with(&path, Some(".bak"), true, |file| file.write_all(b"1")).unwrap();
+        assert_eq!(names(dir.path()), ["map.json"]);
+        replace_with(&path, Some(".bak"), true, |file| file.write_all(b"2")).unwrap();
+        replace_with(&path, Some(".bak"), true, |file| file.write_all(b"3")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "3");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("map.json.bak")).unwrap(),
+            "2"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_kept() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map.json");
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        replace_with(&path, Some("~"), true, |file| file.write_all(b"[]")).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o640);
+        assert_eq!(mode(&dir.path().join("map.json~")), 0o640);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target.json");
+        let link = dir.path().join("link.json");
+        fs::write(&target, "{}").unwrap();
+        std::os::unix::fs::symlink("target.json", &link).unwrap();
+
+        replace_with(&link, None, true, |file| file.write_all(b"1")).unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "1");
+
+        replace_with(&link, None, false, |file| file.write_all(b"2")).unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_file());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "1");
+        assert_eq!(fs::read_to_string(&link).unwrap(), "2");
+    }
+}
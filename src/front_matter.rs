@@ -0,0 +1,325 @@
+use std::path::Path;
+
+use argh::FromArgs;
+use serde_json::{Map, Number, Value};
+
+use crate::{exit, file, pointer_mut, value_from_str, value_printer, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "front-matter",
+    description = "Get or set items in the front matter of a Markdown file",
+    note = "The front matter is the block at the top of the file between `---` fences (yaml, or json when it starts with `{{`) or `+++` fences (toml)"
+)]
+pub struct SubCommandFrontMatter {
+    #[argh(subcommand)]
+    command: FrontMatterCommand,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(subcommand)]
+enum FrontMatterCommand {
+    Get(SubCommandFrontMatterGet),
+    Set(SubCommandFrontMatterSet),
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+/// Get item from the front matter with given json pointer
+#[argh(subcommand, name = "get")]
+struct SubCommandFrontMatterGet {
+    #[argh(positional)]
+    file: String,
+    #[argh(positional)]
+    pointer: Pointer,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+/// Set the value at the given pointer in the front matter and print the document
+#[argh(subcommand, name = "set")]
+struct SubCommandFrontMatterSet {
+    #[argh(switch)]
+    /// write the document back to the file instead of printing it
+    in_place: bool,
+    #[argh(positional)]
+    file: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional, from_str_fn(value_from_str))]
+    value: Value,
+}
+
+/// Runs the front matter subcommand, returning the output to print.
+pub fn do_front_matter(
+    args: SubCommandFrontMatter,
+    pretty: bool,
+) -> Result<Option<String>, exit::Error> {
+    match args.command {
+        FrontMatterCommand::Get(args) => do_get(args, pretty).map(Some),
+        FrontMatterCommand::Set(args) => do_set(args),
+    }
+}
+
+fn do_get(args: SubCommandFrontMatterGet, pretty: bool) -> Result<String, exit::Error> {
+    let path = file_path(&args.file);
+    let text = read(path)?;
+    let document =
+        Document::split(&text).ok_or_else(|| format!("{} has no front matter", path.display()))?;
+    let value = document.format.parse(document.matter)?;
+
+    match value.pointer(args.pointer.as_str()) {
+        Some(val) => Ok(value_printer(pretty, val)),
+        None => Ok(String::new()),
+    }
+}
+
+fn do_set(args: SubCommandFrontMatterSet) -> Result<Option<String>, exit::Error> {
+    let path = file_path(&args.file);
+    let text = read(path)?;
+
+    let output = match Document::split(&text) {
+        Some(document) => {
+            let mut value = document.format.parse(document.matter)?;
+            if let Some(val) = pointer_mut(&mut value, args.pointer.as_str()) {
+                *val = args.value;
+            }
+            let matter = document.format.print(&value)?;
+            format!(
+                "{}{}{}{}",
+                document.open, matter, document.close, document.body
+            )
+        }
+        None => {
+            let mut value = Value::Object(Map::new());
+            if let Some(val) = pointer_mut(&mut value, args.pointer.as_str()) {
+                *val = args.value;
+            }
+            format!("---\n{}---\n{}", Format::Yaml.print(&value)?, text)
+        }
+    };
+
+    if args.in_place {
+        file::write_atomic_exact(path, &output)?;
+        return Ok(None);
+    }
+    // the output is printed with a newline
+    Ok(Some(
+        output.strip_suffix('\n').unwrap_or(&output).to_string(),
+    ))
+}
+
+/// The file argument, with or without the `@` used for map files.
+fn file_path(file: &str) -> &Path {
+    Path::new(file.strip_prefix('@').unwrap_or(file))
+}
+
+fn read(path: &Path) -> Result<String, exit::Error> {
+    std::fs::read_to_string(path)
+        .map_err(|e| exit::io(format!("unable to read {}: {}", path.display(), e)))
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Format {
+    Yaml,
+    Toml,
+    Json,
+}
+
+/// A Markdown file split around its front matter, the parts joined together are the file.
+#[derive(Debug, PartialEq)]
+struct Document<'a> {
+    format: Format,
+    /// opening fence line, including the line ending
+    open: &'a str,
+    matter: &'a str,
+    /// closing fence line, including the line ending when there is one
+    close: &'a str,
+    body: &'a str,
+}
+
+impl<'a> Document<'a> {
+    fn split(text: &'a str) -> Option<Document<'a>> {
+        let first_line = text.split_inclusive('\n').next()?;
+        let fence = first_line.trim_end();
+        let open = &text[..first_line.len()];
+        let rest = &text[open.len()..];
+
+        let mut format = match fence {
+            "---" => Format::Yaml,
+            "+++" => Format::Toml,
+            _ => return None,
+        };
+
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == fence {
+                let matter = &rest[..offset];
+                if format == Format::Yaml && matter.trim_start().starts_with('{') {
+                    format = Format::Json;
+                }
+                return Some(Document {
+                    format,
+                    open,
+                    matter,
+                    close: &rest[offset..offset + line.len()],
+                    body: &rest[offset + line.len()..],
+                });
+            }
+            offset += line.len();
+        }
+        None
+    }
+}
+
+impl Format {
+    fn parse(self, text: &str) -> Result<Value, exit::Error> {
+        let value = match self {
+            Format::Yaml => serde_yaml::from_str::<Option<Value>>(text)
+                .map_err(|e| exit::parse(format!("invalid yaml front matter: {}", e)))?
+                .unwrap_or(Value::Null),
+            Format::Toml => {
+                from_toml(toml::Value::Table(toml::from_str(text).map_err(|e| {
+                    exit::parse(format!("invalid toml front matter: {}", e))
+                })?))
+            }
+            Format::Json => serde_json::from_str(text)
+                .map_err(|e| exit::parse(format!("invalid json front matter: {}", e)))?,
+        };
+
+        match value {
+            Value::Null => Ok(Value::Object(Map::new())),
+            value @ Value::Object(_) => Ok(value),
+            _ => Err(String::from("the front matter is not a map").into()),
+        }
+    }
+
+    /// The front matter text, ending in a newline.
+    fn print(self, value: &Value) -> Result<String, String> {
+        match self {
+            Format::Yaml => serde_yaml::to_string(&to_yaml(value)?).map_err(|e| e.to_string()),
+            Format::Toml => toml::to_string(&to_toml(value)?).map_err(|e| e.to_string()),
+            Format::Json => serde_json::to_string_pretty(value)
+                .map(|x| x + "\n")
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+// serde_json's `arbitrary_precision` numbers do not serialize as numbers in other formats,
+// so values are converted by hand.
+
+fn to_yaml(value: &Value) -> Result<serde_yaml::Value, String> {
+    Ok(match value {
+        Value::Null => serde_yaml::Value::Null,
+        Value::Bool(b) => serde_yaml::Value::Bool(*b),
+        Value::Number(number) => {
+            serde_yaml::Value::Number(match (number.as_i64(), number.as_u64()) {
+                (Some(x), _) => x.into(),
+                (_, Some(x)) => x.into(),
+                _ => float(number, "yaml")?.into(),
+            })
+        }
+        Value::String(string) => serde_yaml::Value::String(string.clone()),
+        Value::Array(list) => {
+            serde_yaml::Value::Sequence(list.iter().map(to_yaml).collect::<Result<_, _>>()?)
+        }
+        Value::Object(map) => serde_yaml::Value::Mapping(
+            map.iter()
+                .map(|(key, value)| Ok((serde_yaml::Value::String(key.clone()), to_yaml(value)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+    })
+}
+
+/// The number as a float, integers that do not fit in 64 bits fail instead of losing digits.
+fn float(number: &Number, format: &str) -> Result<f64, String> {
+    let integer = !number.as_str().contains(['.', 'e', 'E']);
+    if integer && !crate::json5::is_non_finite(number) {
+        return Err(format!(
+            "{} does not fit in a 64 bit integer, {} can not hold it",
+            number, format
+        ));
+    }
+    Ok(number.as_f64().unwrap_or(f64::NAN))
+}
+
+fn to_toml(value: &Value) -> Result<toml::Value, String> {
+    Ok(match value {
+        Value::Null => return Err(String::from("null can not be written as toml")),
+        Value::Bool(b) => toml::Value::Boolean(*b),
+        Value::Number(number) => match number.as_i64() {
+            Some(x) => toml::Value::Integer(x),
+            None => toml::Value::Float(float(number, "toml")?),
+        },
+        Value::String(string) => toml::Value::String(string.clone()),
+        Value::Array(list) => {
+            toml::Value::Array(list.iter().map(to_toml).collect::<Result<_, _>>()?)
+        }
+        Value::Object(map) => toml::Value::Table(
+            map.iter()
+                .map(|(key, value)| Ok((key.clone(), to_toml(value)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+    })
+}
+
+fn from_toml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Integer(x) => Value::Number(x.into()),
+        toml::Value::Float(x) => Number::from_f64(x).map_or(Value::Null, Value::Number),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(list) => Value::Array(list.into_iter().map(from_toml).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, from_toml(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod front_matter_test {
+    use super::{Document, Format};
+    use serde_json::json;
+
+    #[test]
+    fn split() {
+        let text = "---\ntitle: a\n---\nbody\n---\nmore\n";
+        let document = Document::split(text).unwrap();
+        assert_eq!(document.format, Format::Yaml);
+        assert_eq!(document.matter, "title: a\n");
+        assert_eq!(document.body, "body\n---\nmore\n");
+
+        let text = "+++\r\ntitle = 'a'\r\n+++\r\n";
+        let document = Document::split(text).unwrap();
+        assert_eq!(document.format, Format::Toml);
+        assert_eq!(document.close, "+++\r\n");
+        assert_eq!(document.body, "");
+
+        assert_eq!(
+            Document::split("---\n{\"a\": 1}\n---").unwrap().format,
+            Format::Json
+        );
+        assert_eq!(Document::split("# title\n---\n"), None);
+        assert_eq!(Document::split("---\nunterminated: true\n"), None);
+    }
+
+    #[test]
+    fn formats_round_trip() {
+        let value = json!({"title": "post", "draft": false, "tags": ["a", "b"], "weight": 1.5});
+        for format in [Format::Yaml, Format::Toml, Format::Json] {
+            let text = format.print(&value).unwrap();
+            assert_eq!(format.parse(&text).unwrap(), value, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn empty_and_invalid() {
+        assert_eq!(Format::Yaml.parse("").unwrap(), json!({}));
+        assert!(Format::Yaml.parse("- a\n").is_err());
+        assert!(Format::Toml.print(&json!({"a": null})).is_err());
+    }
+}
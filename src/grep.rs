@@ -0,0 +1,138 @@
+use argh::FromArgs;
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+
+use crate::pointer::escape;
+use crate::{exit, value_printer, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "grep",
+    description = "Print the pointer of every string in the map that matches the regular expression",
+    note = "Pointers are printed one per line in the order of the map, and can be given to get and delete as they are. Exits with 1 without printing anything when nothing matches.
+With --with-values the pointer is followed by a tab and the value as json. With --keys an object key that matches prints the pointer of its value.",
+    example = r#"input                                 arguments                 output
+{{"a": "ok", "b": ["error: x", "fail"]}}  'error|fail'              /b/0 and /b/1 on their own line
+{{"a/b": "Failed"}}                     fail --ignore-case        /a~1b"#
+)]
+pub struct SubCommandGrep {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pattern: String,
+    #[argh(switch)]
+    /// print a tab and the matching value after each pointer
+    with_values: bool,
+    #[argh(switch)]
+    /// match object keys as well
+    keys: bool,
+    #[argh(switch)]
+    /// match without regard to case
+    ignore_case: bool,
+}
+
+pub fn do_grep(args: SubCommandGrep) -> Result<String, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    let regex = RegexBuilder::new(&args.pattern)
+        .case_insensitive(args.ignore_case)
+        .build()
+        .map_err(|e| exit::usage(format!("invalid pattern: {}", e)))?;
+
+    let mut matches = Vec::new();
+    search(&value, &regex, args.keys, &mut String::new(), &mut matches);
+    if matches.is_empty() {
+        return Err(String::new().into());
+    }
+
+    let lines: Vec<String> = matches
+        .into_iter()
+        .map(|(pointer, value)| match args.with_values {
+            true => format!("{}\t{}", pointer, value_printer(false, value)),
+            false => pointer,
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+/// Collects the pointers of the matches in `value` with their values, `path` is the pointer to `value`.
+fn search<'a>(
+    value: &'a Value,
+    regex: &Regex,
+    keys: bool,
+    path: &mut String,
+    matches: &mut Vec<(String, &'a Value)>,
+) {
+    match value {
+        Value::String(text) if regex.is_match(text) => matches.push((path.clone(), value)),
+        Value::Object(map) => {
+            for (key, item) in map {
+                let length = path.len();
+                path.push('/');
+                path.push_str(&escape(key));
+
+                let before = matches.len();
+                if keys && regex.is_match(key) {
+                    matches.push((path.clone(), item));
+                }
+                search(item, regex, keys, path, matches);
+                // the key and the string it holds both matching is one match
+                if matches.len() == before + 2 && matches[before].0 == matches[before + 1].0 {
+                    matches.pop();
+                }
+                path.truncate(length);
+            }
+        }
+        Value::Array(list) => {
+            for (index, item) in list.iter().enumerate() {
+                let length = path.len();
+                path.push_str(&format!("/{}", index));
+                search(item, regex, keys, path, matches);
+                path.truncate(length);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod grep_test {
+    use super::search;
+    use regex::Regex;
+    use serde_json::json;
+
+    fn pointers(value: serde_json::Value, pattern: &str, keys: bool) -> Vec<String> {
+        let mut matches = Vec::new();
+        search(
+            &value,
+            &Regex::new(pattern).unwrap(),
+            keys,
+            &mut String::new(),
+            &mut matches,
+        );
+        matches.into_iter().map(|(pointer, _)| pointer).collect()
+    }
+
+    #[test]
+    fn string_values() {
+        let value = json!({"a": "error", "b": [1, "ok", {"c": "failed"}], "error": 2});
+        assert_eq!(pointers(value, "error|fail", false), ["/a", "/b/2/c"]);
+        assert_eq!(pointers(json!("error"), "err", false), [""]);
+        assert!(pointers(json!({"a": 1, "b": null}), ".*", false).is_empty());
+    }
+
+    #[test]
+    fn keys() {
+        let value = json!({"error": 2, "errors": "error", "x": {"error_count": [1]}});
+        assert_eq!(
+            pointers(value, "^error", true),
+            ["/error", "/errors", "/x/error_count"]
+        );
+    }
+
+    #[test]
+    fn escaped_tokens() {
+        let value = json!({"a/b": {"c~d": "x"}, "": ["x"]});
+        assert_eq!(pointers(value, "x", false), ["/a~1b/c~0d", "//0"]);
+    }
+}
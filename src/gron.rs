@@ -0,0 +1,279 @@
+use argh::FromArgs;
+use serde_json::{Map, Value};
+
+use crate::{exit, input, output, value_printer, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "gron",
+    description = "Print the map as one javascript assignment per value, like gron",
+    note = "Every array and object is assigned as [] or {{}} before its items, so the lines build the whole map starting from `json = {{}};`. Keys that are not javascript identifiers, or are reserved words, use bracket syntax like json[\"a b\"]. The lines are in the order of the map.
+Filter the lines with grep and turn them back into json with ungron, like `bash_map ungron <(bash_map gron DOC | grep name)`.",
+    example = r#"input                          output
+{{"items": [{{"name": "x"}}]}}     json = {{}}; json.items = []; json.items[0] = {{}}; json.items[0].name = "x"; on their own line
+{{"a b": 1}}                     json = {{}}; json["a b"] = 1; on their own line"#
+)]
+pub struct SubCommandGron {
+    #[argh(positional)]
+    pub variable: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "ungron",
+    description = "Turn the assignments printed by gron back into json",
+    note = "Every line is read as `path = value;` and the value is set at the path, creating the arrays and objects on the way, so a filtered part of the gron output gives the matching part of the map. Array items in front of the ones that are set become null. Empty lines are skipped, anything else that is not an assignment fails with its line number.",
+    example = r#"input                                          output
+json = {{}};\njson.items[0].name = "x";          {{"items":[{{"name":"x"}}]}}
+json.items[1] = 2;                             {{"items":[null,2]}}"#
+)]
+pub struct SubCommandUngron {
+    #[argh(positional)]
+    /// the gron text, an environment variable or @path
+    pub variable: String,
+}
+
+/// A step in a gron path.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Key(String),
+    Index(usize),
+}
+
+const RESERVED: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+pub fn do_gron(args: SubCommandGron) -> Result<String, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    let mut lines = Vec::new();
+    gron(&value, String::from("json"), &mut lines);
+    Ok(lines.join(output::terminator()))
+}
+
+pub fn do_ungron(args: SubCommandUngron, pretty: bool) -> Result<String, exit::Error> {
+    let text = input::Source::of(&args.variable)
+        .text()?
+        .ok_or_else(|| input::not_set(&args.variable))?;
+
+    let mut value = Value::Null;
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (path, item) = parse_line(line.trim())
+            .map_err(|e| exit::parse(format!("line {}: {}", number + 1, e)))?;
+        assign(&mut value, &path, item);
+    }
+    Ok(value_printer(pretty, &value))
+}
+
+fn gron(value: &Value, path: String, lines: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            lines.push(format!("{} = {{}};", path));
+            for (key, item) in map {
+                gron(item, format!("{}{}", path, key_accessor(key)), lines);
+            }
+        }
+        Value::Array(list) => {
+            lines.push(format!("{} = [];", path));
+            for (index, item) in list.iter().enumerate() {
+                gron(item, format!("{}[{}]", path, index), lines);
+            }
+        }
+        scalar => lines.push(format!("{} = {};", path, scalar)),
+    }
+}
+
+/// `.key` for keys that are javascript identifiers, `["key"]` for the rest.
+fn key_accessor(key: &str) -> String {
+    let mut chars = key.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !RESERVED.contains(&key);
+
+    match identifier {
+        true => format!(".{}", key),
+        false => format!("[{}]", Value::String(key.to_string())),
+    }
+}
+
+/// Splits `json.a[0]["b c"] = value;` into the path after `json` and the value.
+fn parse_line(line: &str) -> Result<(Vec<Token>, Value), String> {
+    let mut rest = line
+        .strip_prefix("json")
+        .ok_or_else(|| String::from("expected the line to start with json"))?;
+    let mut path = Vec::new();
+
+    loop {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(after.len());
+            if end == 0 {
+                return Err(String::from("expected a key after ."));
+            }
+            path.push(Token::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix("[\"") {
+            // the key is a json string, it ends at the first quote that is not escaped
+            let mut escaped = false;
+            let end = after
+                .char_indices()
+                .find(|&(_, c)| {
+                    let end = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    end
+                })
+                .map(|(index, _)| index)
+                .ok_or_else(|| String::from("unterminated key"))?;
+            let key: String = serde_json::from_str(&rest[1..end + 3])
+                .map_err(|e| format!("invalid key: {}", e))?;
+            rest = after[end + 1..]
+                .strip_prefix(']')
+                .ok_or_else(|| String::from("expected ] after the key"))?;
+            path.push(Token::Key(key));
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let (index, after) = after
+                .split_once(']')
+                .ok_or_else(|| String::from("expected ] after the index"))?;
+            let index = crate::parse_index(index)
+                .ok_or_else(|| format!("invalid array index {:?}", index))?;
+            path.push(Token::Index(index));
+            rest = after;
+        } else {
+            break;
+        }
+    }
+
+    let value = rest
+        .trim_start()
+        .strip_prefix('=')
+        .and_then(|x| x.trim().strip_suffix(';'))
+        .ok_or_else(|| String::from("expected = value;"))?;
+    let value = serde_json::from_str(value).map_err(|e| format!("invalid value: {}", e))?;
+    Ok((path, value))
+}
+
+/// Sets the value at the path, empty arrays and objects keep what is already there.
+fn assign(target: &mut Value, path: &[Token], value: Value) {
+    let (token, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            let keep = match (&*target, &value) {
+                (Value::Object(_), Value::Object(map)) => map.is_empty(),
+                (Value::Array(_), Value::Array(list)) => list.is_empty(),
+                _ => false,
+            };
+            if !keep {
+                *target = value;
+            }
+            return;
+        }
+    };
+
+    let child = match token {
+        Token::Key(key) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            match target {
+                Value::Object(map) => map.entry(key.as_str()).or_insert(Value::Null),
+                _ => unreachable!(),
+            }
+        }
+        Token::Index(index) => {
+            if !target.is_array() {
+                *target = Value::Array(Vec::new());
+            }
+            match target {
+                Value::Array(list) => {
+                    if list.len() <= *index {
+                        list.resize(index + 1, Value::Null);
+                    }
+                    &mut list[*index]
+                }
+                _ => unreachable!(),
+            }
+        }
+    };
+    assign(child, rest, value)
+}
+
+#[cfg(test)]
+mod gron_test {
+    use super::{key_accessor, parse_line, Token};
+    use serde_json::json;
+
+    #[test]
+    fn accessors() {
+        assert_eq!(key_accessor("name"), ".name");
+        assert_eq!(key_accessor("_a$1"), "._a$1");
+        assert_eq!(key_accessor("a b"), r#"["a b"]"#);
+        assert_eq!(key_accessor("1a"), r#"["1a"]"#);
+        assert_eq!(key_accessor(""), r#"[""]"#);
+        assert_eq!(key_accessor("class"), r#"["class"]"#);
+        assert_eq!(key_accessor("say \"hi\"]"), r#"["say \"hi\"]"]"#);
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(parse_line("json = {};").unwrap(), (vec![], json!({})));
+        assert_eq!(
+            parse_line(r#"json.a[10]["b \"c\"]"].d = "x = y;";"#).unwrap(),
+            (
+                vec![
+                    Token::Key("a".into()),
+                    Token::Index(10),
+                    Token::Key("b \"c\"]".into()),
+                    Token::Key("d".into())
+                ],
+                json!("x = y;")
+            )
+        );
+        assert!(parse_line("data.a = 1;").is_err());
+        assert!(parse_line("json.a = 1").is_err());
+        assert!(parse_line("json[01] = 1;").is_err());
+        assert!(parse_line("json.a = nope;").is_err());
+    }
+}
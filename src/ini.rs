@@ -0,0 +1,329 @@
+use argh::FromArgs;
+use serde_json::{Map, Value};
+
+use crate::pointer::escape;
+use crate::{exit, input, type_name, value_printer, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "from-ini",
+    description = "Convert an INI file to json",
+    note = "Every [section] becomes an object, the keys before the first section are put at the top level, or in an object named by --globals. Sections that appear twice are merged and of keys that appear twice the last value is kept.
+Keys and values are separated by `=` or `:` and trimmed, values in matching double or single quotes lose the quotes. Values are strings, with --types unquoted true and false become booleans and unquoted json numbers become numbers.
+Lines starting with `;` or `#` are comments and left out, comments after a value are part of the value.",
+    example = r#"input                                         arguments       output
+name = demo\n[server]\nport = 8080           ""              {{"name":"demo","server":{{"port":"8080"}}}}
+name = demo\n[server]\nport = 8080           --types         {{"name":"demo","server":{{"port":8080}}}}"#
+)]
+pub struct SubCommandFromIni {
+    #[argh(positional)]
+    /// the INI text, an environment variable or @path
+    pub variable: String,
+    #[argh(switch)]
+    /// read true, false and numbers as booleans and numbers instead of strings
+    types: bool,
+    #[argh(option)]
+    /// put the keys before the first section in an object with this name
+    globals: Option<String>,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "to-ini",
+    description = "Convert the map to an INI file, the reverse of from-ini",
+    note = "Objects at the top level become sections, the other top level keys are written before the first section, like the keys of the object named by --globals. null is written as an empty value.
+Strings that from-ini would read differently, like \" x\" or \"8080\" with --types, are written in double quotes. Fails with the pointer of the first value INI can not hold: arrays, objects more than two levels deep and strings with line breaks.",
+    example = r#"input                                           arguments           output
+{{"name": "demo", "server": {{"port": 8080}}}}      ""                  name = demo, [server] and port = 8080 on their own line
+{{"server": {{"tls": {{"on": true}}}}}}                 ""                  error: /server/tls is more than two levels deep"#
+)]
+pub struct SubCommandToIni {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(option)]
+    /// write the keys of the object with this name before the first section
+    globals: Option<String>,
+}
+
+pub fn do_from_ini(args: SubCommandFromIni, pretty: bool) -> Result<String, exit::Error> {
+    let text = input::Source::of(&args.variable)
+        .text()?
+        .ok_or_else(|| input::not_set(&args.variable))?;
+    let value = parse(&text, args.types, args.globals.as_deref()).map_err(exit::parse)?;
+    Ok(value_printer(pretty, &value))
+}
+
+pub fn do_to_ini(args: SubCommandToIni) -> Result<String, exit::Error> {
+    Ok(to_string(
+        &variable_or_value(&args.variable)?,
+        args.globals.as_deref(),
+    )?)
+}
+
+/// Parses INI text to json, see the note of `from-ini` for how.
+fn parse(text: &str, types: bool, globals: Option<&str>) -> Result<Value, String> {
+    let mut root = Map::new();
+    let mut global = Map::new();
+    // the section the keys go to, `None` before the first section
+    let mut section: Option<String> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .ok_or_else(|| format!("expected `]` at line {}", number + 1))?
+                .trim();
+            root.entry(name)
+                .or_insert_with(|| Value::Object(Map::new()));
+            section = Some(name.to_string());
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once(['=', ':'])
+            .ok_or_else(|| format!("expected `key = value` at line {}", number + 1))?;
+        let key = key.trim().to_string();
+        let value = read_value(value.trim(), types);
+        match section.as_ref().and_then(|x| root.get_mut(x)) {
+            Some(Value::Object(map)) => map.insert(key, value),
+            _ => global.insert(key, value),
+        };
+    }
+
+    match globals {
+        Some(name) if root.contains_key(name) && !global.is_empty() => {
+            return Err(format!("{:?} is both the --globals and a section", name))
+        }
+        Some(name) if !global.is_empty() => {
+            let mut map = Map::new();
+            map.insert(name.to_string(), Value::Object(global));
+            map.extend(root);
+            return Ok(Value::Object(map));
+        }
+        Some(_) => (),
+        None => {
+            if let Some(key) = global.keys().find(|x| root.contains_key(*x)) {
+                return Err(format!("{:?} is both a key and a section", key));
+            }
+            global.extend(root);
+            return Ok(Value::Object(global));
+        }
+    }
+    Ok(Value::Object(root))
+}
+
+fn read_value(text: &str, types: bool) -> Value {
+    for quote in ['"', '\''] {
+        if text.len() >= 2 && text.starts_with(quote) && text.ends_with(quote) {
+            return Value::String(text[1..text.len() - 1].to_string());
+        }
+    }
+    if types {
+        match text {
+            "true" => return Value::Bool(true),
+            "false" => return Value::Bool(false),
+            _ => (),
+        }
+        // only plain numbers, serde_json would also read `[1]` or `null`
+        if text.starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+            if let Ok(number @ Value::Number(_)) = serde_json::from_str(text) {
+                return number;
+            }
+        }
+    }
+    Value::String(text.to_string())
+}
+
+/// The value as INI text, see the note of `to-ini` for how.
+fn to_string(value: &Value, globals: Option<&str>) -> Result<String, String> {
+    let map = match value {
+        Value::Object(map) => map,
+        other => {
+            return Err(format!(
+                "the map is of type {}, INI needs an object",
+                type_name(other)
+            ))
+        }
+    };
+
+    let mut global = Vec::new();
+    let mut sections = Vec::new();
+    for (key, item) in map {
+        let path = format!("/{}", escape(key));
+        match item {
+            Value::Object(section) if Some(key.as_str()) == globals => {
+                for (key, item) in section {
+                    global.push(line(key, item, &format!("{}/{}", path, escape(key)))?);
+                }
+            }
+            Value::Object(section) => {
+                check_key(key, &path)?;
+                let mut lines = vec![format!("[{}]", key)];
+                for (key, item) in section {
+                    lines.push(line(key, item, &format!("{}/{}", path, escape(key)))?);
+                }
+                sections.push(lines.join("\n"));
+            }
+            _ => global.push(line(key, item, &path)?),
+        }
+    }
+
+    if !global.is_empty() {
+        sections.insert(0, global.join("\n"));
+    }
+    Ok(sections.join("\n\n"))
+}
+
+/// The `key = value` line, `path` is the pointer to the value for errors.
+fn line(key: &str, value: &Value, path: &str) -> Result<String, String> {
+    check_key(key, path)?;
+    let text = match value {
+        Value::Null => String::new(),
+        Value::String(text) if text.contains(['\n', '\r']) => {
+            return Err(format!("{} has a line break, INI can not hold it", path))
+        }
+        Value::String(text) if text.trim() != text || read_value(text, true) != *value => {
+            format!("\"{}\"", text)
+        }
+        Value::String(text) => text.clone(),
+        Value::Object(_) => return Err(format!("{} is more than two levels deep", path)),
+        Value::Array(_) => return Err(format!("{} is of type array, INI has no arrays", path)),
+        other => other.to_string(),
+    };
+    match text.is_empty() {
+        true => Ok(format!("{} =", key)),
+        false => Ok(format!("{} = {}", key, text)),
+    }
+}
+
+fn check_key(key: &str, path: &str) -> Result<(), String> {
+    let invalid = key.trim() != key
+        || key.is_empty()
+        || key.starts_with(['[', ';', '#'])
+        || key.contains(['=', ':', ']', '\n', '\r']);
+    match invalid {
+        true => Err(format!(
+            "{:?} at {} can not be written as an INI key",
+            key, path
+        )),
+        false => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod ini_test {
+    use super::{parse, to_string};
+    use serde_json::json;
+
+    const FILE: &str = r#"; global settings
+name = demo app
+debug: false
+
+[server]
+host = 0.0.0.0
+port = 8080
+# the port is overridden below
+port = 8443
+motd = "  welcome  "
+
+[database]
+url = postgres://localhost/db?sslmode=disable
+ratio = -0.25
+empty =
+version = '2'
+
+[server]
+timeout = 30
+"#;
+
+    #[test]
+    fn strings() {
+        assert_eq!(
+            parse(FILE, false, None).unwrap(),
+            json!({
+                "name": "demo app",
+                "debug": "false",
+                "server": {"host": "0.0.0.0", "port": "8443", "motd": "  welcome  ", "timeout": "30"},
+                "database": {
+                    "url": "postgres://localhost/db?sslmode=disable",
+                    "ratio": "-0.25",
+                    "empty": "",
+                    "version": "2"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn types_and_globals() {
+        assert_eq!(
+            parse(FILE, true, Some("global")).unwrap(),
+            json!({
+                "global": {"name": "demo app", "debug": false},
+                "server": {"host": "0.0.0.0", "port": 8443, "motd": "  welcome  ", "timeout": 30},
+                "database": {
+                    "url": "postgres://localhost/db?sslmode=disable",
+                    "ratio": -0.25,
+                    "empty": "",
+                    "version": "2"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for types in [false, true] {
+            let value = parse(FILE, types, None).unwrap();
+            let text = to_string(&value, None).unwrap();
+            assert_eq!(parse(&text, types, None).unwrap(), value);
+        }
+        let value = parse(FILE, true, Some("global")).unwrap();
+        let text = to_string(&value, Some("global")).unwrap();
+        assert!(text.starts_with("name = demo app\ndebug = false\n\n[server]\n"));
+        assert_eq!(parse(&text, true, Some("global")).unwrap(), value);
+    }
+
+    #[test]
+    fn writing() {
+        let value = json!({"a": null, "s": {"n": 1, "t": "true", "q": "\"x\"", "p": " x"}});
+        assert_eq!(
+            to_string(&value, None).unwrap(),
+            "a =\n\n[s]\nn = 1\nt = \"true\"\nq = \"\"x\"\"\np = \" x\""
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            to_string(&json!({"a": {"b": {"c": 1}}}), None).unwrap_err(),
+            "/a/b is more than two levels deep"
+        );
+        assert_eq!(
+            to_string(&json!({"a": {"b": [1]}}), None).unwrap_err(),
+            "/a/b is of type array, INI has no arrays"
+        );
+        assert_eq!(
+            to_string(&json!({"a": {"x=y": 1}}), None).unwrap_err(),
+            r#""x=y" at /a/x=y can not be written as an INI key"#
+        );
+        assert_eq!(
+            parse("a = 1\n[b\n", false, None).unwrap_err(),
+            "expected `]` at line 2"
+        );
+        assert_eq!(
+            parse("[a]\njust text", false, None).unwrap_err(),
+            "expected `key = value` at line 2"
+        );
+        assert_eq!(
+            parse("a = 1\n[a]", false, None).unwrap_err(),
+            r#""a" is both a key and a section"#
+        );
+    }
+}
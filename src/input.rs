@@ -0,0 +1,560 @@
+use std::env::{var, var_os};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use serde_json::Value;
+
+use crate::{depth, exit, number, verbose};
+
+/// Settings from the command line that apply to every input that is read.
+#[derive(Debug)]
+pub struct Options {
+    pub dedup_check: bool,
+    pub format: Format,
+    pub max_depth: usize,
+    /// decode `\uXXXX` escapes left in object keys, see `decode_keys`
+    pub decode_unicode_keys: bool,
+    /// whether BASH_MAP_STRICT is set, see `strict`
+    pub strict: bool,
+    /// fail on environment variables that are not set or not valid json, see `strict_variables`
+    pub strict_env: bool,
+    /// do not read arguments that name an existing file from it, see `Source::of`
+    pub no_file_fallback: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            dedup_check: false,
+            format: Format::default(),
+            max_depth: depth::DEFAULT_MAX_DEPTH,
+            decode_unicode_keys: false,
+            strict: false,
+            strict_env: false,
+            no_file_fallback: false,
+        }
+    }
+}
+
+/// Syntax accepted for inputs, the output is json unless `--output-format` says otherwise.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Format {
+    #[default]
+    Json,
+    /// json with `//` and `/* */` comments and trailing commas
+    Jsonc,
+    /// see `json5::parse`
+    Json5,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "jsonc" => Ok(Format::Jsonc),
+            "json5" => Ok(Format::Json5),
+            _ => Err(format!(
+                "unknown input format {:?}, use json, jsonc or json5",
+                s
+            )),
+        }
+    }
+}
+
+/// Checks that the text nests no deeper than `--max-depth`, before it is parsed.
+pub fn check_depth(text: &str) -> Result<(), exit::Error> {
+    depth::check_text(text, options().max_depth).map_err(exit::parse)
+}
+
+/// Parses the text according to `--input-format`, `check_depth` has to be called first.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut value = match options().format {
+        Format::Json => parse_json(text),
+        Format::Jsonc => parse_json(&crate::jsonc::strip(text)),
+        Format::Json5 => crate::json5::parse(text, false),
+    }?;
+    if options().decode_unicode_keys {
+        decode_keys(&mut value);
+    }
+    Ok(value)
+}
+
+fn parse_json(text: &str) -> Result<Value, String> {
+    let mut value = depth::from_str(text).map_err(|e| e.to_string())?;
+    number::keep_written(text, &mut value);
+    Ok(value)
+}
+
+/// Decodes `\uXXXX` escapes that are still in the object keys after parsing, like the keys
+/// of json that was escaped twice, so a pointer with the characters themselves finds them.
+fn decode_keys(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let entries = std::mem::take(map);
+            for (key, mut item) in entries {
+                decode_keys(&mut item);
+                map.insert(decode_escapes(&key), item);
+            }
+        }
+        Value::Array(list) => list.iter_mut().for_each(decode_keys),
+        _ => (),
+    }
+}
+
+/// The text with `\uXXXX` escapes replaced by their characters. Surrogate pairs are combined,
+/// escapes that do not make a character, like a lone surrogate, are kept as they are.
+fn decode_escapes(text: &str) -> String {
+    let unit = |rest: &str| {
+        let digits = rest.strip_prefix("\\u")?.get(..4)?;
+        match digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            true => u16::from_str_radix(digits, 16).ok(),
+            false => None,
+        }
+    };
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("\\u") {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let first = unit(rest);
+        let second = unit(rest.get(6..).unwrap_or_default());
+        let (c, length) = match (first, second) {
+            (Some(high), Some(low)) if (0xd800..0xdc00).contains(&high) => {
+                match char::decode_utf16([high, low]).next() {
+                    Some(Ok(c)) => (Some(c), 12),
+                    _ => (None, 6),
+                }
+            }
+            (Some(unit), _) => (char::from_u32(unit as u32), 6),
+            (None, _) => (None, 2),
+        };
+        match c {
+            Some(c) => decoded.push(c),
+            None => decoded.push_str(&rest[..length]),
+        }
+        rest = &rest[length..];
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Checks the text for duplicate keys according to `--input-format`.
+pub fn check_duplicates(text: &str) -> Result<(), exit::Error> {
+    let checked = match options().format {
+        Format::Json => crate::dedup::check(text),
+        Format::Jsonc => crate::dedup::check(&crate::jsonc::strip(text)),
+        Format::Json5 => crate::json5::parse(text, true).map(|_| ()),
+    };
+    checked.map_err(exit::parse)
+}
+
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+/// Sets the input options for the rest of the process, only the first call has effect.
+pub fn configure(options: Options) {
+    let _ = OPTIONS.set(options);
+}
+
+pub fn options() -> &'static Options {
+    OPTIONS.get_or_init(Options::default)
+}
+
+/// Whether the environment variable BASH_MAP_STRICT asks for strict runs, `1` or `true`.
+pub fn strict_requested() -> bool {
+    var("BASH_MAP_STRICT").is_ok_and(|x| ["1", "true"].contains(&x.as_str()))
+}
+
+/// Whether a command given `flag` as its `--strict` runs strict: not finding something fails,
+/// as do inputs that would otherwise count as empty. The flag always applies,
+/// BASH_MAP_STRICT makes every command strict unless `--no-strict` is given.
+pub fn strict(flag: bool) -> bool {
+    flag || options().strict
+}
+
+/// The error of an environment variable that is not set where it has to be, an input
+/// that can not be parsed like everywhere else.
+pub fn not_set(name: &str) -> exit::Error {
+    exit::parse(format!("environment variable {} is not set", name))
+}
+
+/// Whether an environment variable that is not set or not valid json fails, with `--strict-env`
+/// or when strict, instead of counting as empty.
+pub fn strict_variables() -> bool {
+    options().strict_env || strict(false)
+}
+
+static SAW_BOM: AtomicBool = AtomicBool::new(false);
+
+/// The text without a leading byte order mark, as written by Windows tools like PowerShell's
+/// `Out-File`. Whether one was removed is remembered for `--keep-bom`.
+pub fn strip_bom(text: &str) -> &str {
+    match text.strip_prefix('\u{feff}') {
+        Some(rest) => {
+            SAW_BOM.store(true, Ordering::Relaxed);
+            rest
+        }
+        None => text,
+    }
+}
+
+/// Like `strip_bom`, for inputs that are read while parsing.
+pub fn skip_bom<R: std::io::BufRead>(reader: &mut R) -> std::io::Result<()> {
+    let bom = "\u{feff}".as_bytes();
+    if reader.fill_buf()?.starts_with(bom) {
+        SAW_BOM.store(true, Ordering::Relaxed);
+        reader.consume(bom.len());
+    }
+    Ok(())
+}
+
+/// Whether any input started with a byte order mark.
+pub fn saw_bom() -> bool {
+    SAW_BOM.load(Ordering::Relaxed)
+}
+
+/// Where the map or value argument of a subcommand is read from.
+///
+/// Every argument names exactly one source, so it is only read and parsed once:
+/// - `@path` and paths of pipes (like `<(...)` process substitution, `/dev/fd/63`) are files,
+/// - words shaped like a shell identifier (except `true`, `false` and `null`) are environment variables,
+/// - everything else is json text.
+///
+/// Unless `--no-file-fallback` is given, an environment variable that is not set and json text
+/// that is not valid json are the file at that path when there is one, so the order is
+/// json text, environment variable, file, and `{}` or an error when strict.
+#[derive(Debug, PartialEq)]
+pub enum Source<'a> {
+    Literal(&'a str),
+    Env(&'a str),
+    File(&'a Path),
+}
+
+impl<'a> Source<'a> {
+    pub fn of(input: &'a str) -> Source<'a> {
+        let source = if let Some(path) = input.strip_prefix('@') {
+            Source::File(Path::new(path))
+        } else if is_identifier(input) && !["true", "false", "null"].contains(&input) {
+            Source::Env(input)
+        } else if is_pipe(input) {
+            Source::File(Path::new(input))
+        } else {
+            Source::Literal(input)
+        };
+
+        let fallback = match source {
+            _ if options().no_file_fallback || !Path::new(input).is_file() => false,
+            Source::Env(name) => var_os(name).is_none(),
+            // only parsed when there is a file with the same name
+            Source::Literal(text) => parse(strip_bom(text)).is_err(),
+            Source::File(_) => false,
+        };
+        match fallback {
+            true => Source::File(Path::new(input)),
+            false => source,
+        }
+    }
+
+    /// The text of the source without byte order mark, `None` when the variable is not set.
+    pub fn text(&self) -> Result<Option<String>, exit::Error> {
+        verbose::log(1, || match self {
+            Source::Literal(_) => String::from("input is a json literal"),
+            Source::Env(name) => format!("input is the environment variable {}", name),
+            Source::File(path) => format!("input is the file {}", path.display()),
+        });
+        let text = match self {
+            Source::Literal(text) => Some(text.to_string()),
+            Source::Env(name) => {
+                let text = var(name).ok();
+                if text.is_none() {
+                    verbose::log(1, || format!("environment variable {} is not set", name));
+                }
+                text
+            }
+            Source::File(path) => Some(
+                std::fs::read_to_string(path)
+                    .map_err(|e| exit::io(format!("unable to read {}: {}", path.display(), e)))?,
+            ),
+        };
+        Ok(text.map(|mut text| {
+            let bom = text.len() - strip_bom(&text).len();
+            text.drain(..bom);
+            text
+        }))
+    }
+
+    /// Reads and parses the source, `None` when a literal is not valid json or the variable
+    /// is not set or not valid json. Files that can not be read or parsed are an error,
+    /// as are inputs nested deeper than `--max-depth` and duplicate keys under `--dedup-check`.
+    pub fn read(&self) -> Result<Option<Value>, exit::Error> {
+        let text = match (self.text()?, self) {
+            (Some(text), _) => text,
+            (None, Source::Env(name)) if strict_variables() => return Err(not_set(name)),
+            (None, _) => return Ok(None),
+        };
+        check_depth(&text)?;
+
+        let value = match (self, parse(&text)) {
+            (_, Ok(value)) => value,
+            (Source::File(path), Err(e)) => {
+                return Err(exit::parse(format!(
+                    "invalid json in {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+            (Source::Env(name), Err(e)) if strict_variables() => {
+                return Err(exit::parse(format!(
+                    "environment variable {} contains invalid json: {}",
+                    name, e
+                )))
+            }
+            (_, Err(e)) if strict(false) => {
+                return Err(exit::parse(format!("invalid json: {}", e)))
+            }
+            (_, Err(_)) => return Ok(None),
+        };
+
+        if options().dedup_check {
+            check_duplicates(&text)?;
+        }
+        Ok(Some(value))
+    }
+}
+
+/// Whether the text is a shell variable name.
+pub fn is_identifier(input: &str) -> bool {
+    let mut chars = input.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// How many bytes around the error of a long line `value_error` shows.
+const ERROR_CONTEXT: usize = 30;
+
+/// The error for a value argument that is not valid json: the message with the byte offset,
+/// the line with a caret under where it went wrong and a hint for the usual mistakes.
+pub fn value_error(text: &str, error: &serde_json::Error) -> String {
+    let line_number = error.line().max(1);
+    let line = text.split('\n').nth(line_number - 1).unwrap_or("");
+    // serde counts the column in bytes from 1, errors before the first byte have column 0
+    let column = floor_boundary(line, error.column().saturating_sub(1).min(line.len()));
+    let offset = text
+        .split_inclusive('\n')
+        .take(line_number - 1)
+        .map(str::len)
+        .sum::<usize>()
+        + column;
+
+    let start = floor_boundary(line, column.saturating_sub(ERROR_CONTEXT));
+    let end = ceil_boundary(line, (column + ERROR_CONTEXT).min(line.len()));
+    let prefix = if start > 0 { "..." } else { "" };
+    let suffix = if end < line.len() { "..." } else { "" };
+    let caret = prefix.len() + line[start..column].chars().count();
+
+    let mut message = format!(
+        "invalid json at byte {}: {}\n  {}{}{}\n  {}^",
+        offset,
+        error,
+        prefix,
+        &line[start..end],
+        suffix,
+        " ".repeat(caret)
+    );
+    if let Some(hint) = value_hint(text, error) {
+        message.push_str("\nhint: ");
+        message.push_str(&hint);
+    }
+    message
+}
+
+fn value_hint(text: &str, error: &serde_json::Error) -> Option<String> {
+    let text = text.trim();
+    // long values are not echoed in full, like in the line above the caret
+    let quoted = |x: &str| match x.chars().count() > 2 * ERROR_CONTEXT {
+        true => {
+            let start: String = x.chars().take(2 * ERROR_CONTEXT).collect();
+            let start = Value::String(start).to_string();
+            format!("{}...\"", &start[..start.len() - 1])
+        }
+        false => Value::String(x.to_string()).to_string(),
+    };
+
+    if let Some(inner) = text.strip_prefix('\'').and_then(|x| x.strip_suffix('\'')) {
+        Some(format!(
+            "json strings use double quotes, did you mean '{}'?",
+            quoted(inner)
+        ))
+    } else if text.starts_with(char::is_alphabetic) {
+        Some(format!(
+            "strings need double quotes, did you mean '{}'?",
+            quoted(text)
+        ))
+    } else if error.is_eof() {
+        Some(String::from(
+            "the value ends too early, check for a missing }, ] or \"",
+        ))
+    } else {
+        None
+    }
+}
+
+fn floor_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// Whether the input is read from stdin.
+pub fn reads_stdin(input: &str) -> bool {
+    matches!(
+        input.strip_prefix('@').unwrap_or(input),
+        "/dev/stdin" | "/dev/fd/0" | "/proc/self/fd/0"
+    )
+}
+
+/// Whether the input names a pipe, like stdin or a process substitution.
+#[cfg(unix)]
+pub fn is_pipe(input: &str) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    std::fs::metadata(input)
+        .map(|x| x.file_type().is_fifo() || x.file_type().is_char_device())
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub fn is_pipe(_input: &str) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod input_test {
+    use super::{decode_escapes, decode_keys, value_error, Source};
+    use std::path::Path;
+
+    #[test]
+    fn sources() {
+        assert_eq!(Source::of("MAP"), Source::Env("MAP"));
+        assert_eq!(Source::of("_map_2"), Source::Env("_map_2"));
+        assert_eq!(Source::of("true"), Source::Literal("true"));
+        assert_eq!(Source::of("null"), Source::Literal("null"));
+        assert_eq!(Source::of("1.5"), Source::Literal("1.5"));
+        assert_eq!(Source::of(r#"{"a": 1}"#), Source::Literal(r#"{"a": 1}"#));
+        assert_eq!(Source::of("2map"), Source::Literal("2map"));
+        assert_eq!(Source::of("@map.json"), Source::File(Path::new("map.json")));
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let text = Source::of("\u{feff}{\r\n  \"a\": \"b\"\r\n}")
+            .text()
+            .unwrap();
+        assert_eq!(text.as_deref(), Some("{\r\n  \"a\": \"b\"\r\n}"));
+        assert_eq!(
+            Source::of("\u{feff}[1]").read().unwrap(),
+            Some(serde_json::json!([1]))
+        );
+    }
+
+    #[test]
+    fn file_errors_are_reported() {
+        let dir = std::env::temp_dir().join(format!("bash_map_input_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("invalid.json");
+        std::fs::write(&path, "{not json").unwrap();
+
+        let invalid = format!("@{}", path.display());
+        assert!(Source::of(&invalid)
+            .read()
+            .unwrap_err()
+            .message
+            .starts_with("invalid json"));
+
+        let missing = format!("@{}", dir.join("missing.json").display());
+        assert!(Source::of(&missing)
+            .read()
+            .unwrap_err()
+            .message
+            .starts_with("unable to read"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn error(text: &str) -> String {
+        let error = serde_json::from_str::<serde_json::Value>(text).unwrap_err();
+        value_error(text, &error)
+    }
+
+    #[test]
+    fn value_errors() {
+        assert_eq!(
+            error("foo"),
+            "invalid json at byte 1: expected ident at line 1 column 2\n  foo\n   ^\nhint: strings need double quotes, did you mean '\"foo\"'?"
+        );
+        assert_eq!(
+            error("'it'"),
+            "invalid json at byte 0: expected value at line 1 column 1\n  'it'\n  ^\nhint: json strings use double quotes, did you mean '\"it\"'?"
+        );
+        assert_eq!(
+            error("{\"a\": [1,\n 2"),
+            "invalid json at byte 11: EOF while parsing a list at line 2 column 2\n   2\n   ^\nhint: the value ends too early, check for a missing }, ] or \""
+        );
+        assert_eq!(
+            error("[1, 2,]"),
+            "invalid json at byte 6: trailing comma at line 1 column 7\n  [1, 2,]\n        ^"
+        );
+    }
+
+    #[test]
+    fn long_values_are_cut() {
+        let text = format!("[{}x, {}]", "1, ".repeat(40), "ü, ".repeat(40));
+        let message = error(&text);
+        let lines: Vec<&str> = message.lines().collect();
+        assert!(lines[1].starts_with("  ...") && lines[1].ends_with("..."));
+        assert!(lines[1].len() < 80);
+        let caret = lines[2].find('^').unwrap();
+        assert_eq!(lines[1][caret..].chars().next(), Some('x'));
+
+        let message = error(&format!("foo {}", "x".repeat(1000)));
+        assert!(message.len() < 250);
+        assert!(message.ends_with(&format!("did you mean '\"foo {}...\"'?", "x".repeat(56))));
+    }
+
+    #[test]
+    fn decoded_escapes() {
+        assert_eq!(decode_escapes(r"caf\u00e9"), "caf\u{e9}");
+        assert_eq!(decode_escapes(r"\ud83d\ude00!"), "\u{1f600}!");
+        assert_eq!(decode_escapes(r"a\u0001b"), "a\u{1}b");
+        assert_eq!(decode_escapes(r"\ud83dA"), r"\ud83dA");
+        assert_eq!(
+            decode_escapes(r"\ude00 \u12 \uzzzz\"),
+            r"\ude00 \u12 \uzzzz\"
+        );
+    }
+
+    #[test]
+    fn decoded_keys() {
+        let mut value = serde_json::json!({"a\\u00e9": [{"\\ud83d\\ude00": 1}], "b": "\\u00e9"});
+        decode_keys(&mut value);
+        assert_eq!(
+            value,
+            serde_json::json!({"a\u{e9}": [{"\u{1f600}": 1}], "b": "\\u00e9"})
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use argh::FromArgs;
+use serde_json::{Number, Value};
+
+use crate::number::Decimal;
+use crate::{exit, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "canonicalize",
+    description = "Print the value in the JSON Canonicalization Scheme of RFC 8785",
+    note = "Keys are sorted by their UTF-16 code units, numbers are written like JavaScript writes doubles and there is no whitespace. Numbers that are not doubles, like 1e400 or json5's Infinity, fail. Other output options are ignored.",
+    example = r#"input                                        output
+{{"b": [4.50, 1E30, -0], "a": "\u20ac"}}       {{"a":"€","b":[4.5,1e+30,0]}}"#
+)]
+pub struct SubCommandCanonicalize {
+    #[argh(positional)]
+    pub variable: String,
+}
+
+pub fn do_canonicalize(args: SubCommandCanonicalize) -> Result<String, exit::Error> {
+    Ok(to_string(&variable_or_value(&args.variable)?)?)
+}
+
+/// The value as RFC 8785 canonical json.
+pub fn to_string(value: &Value) -> Result<String, String> {
+    let mut output = String::new();
+    write(&mut output, value)?;
+    Ok(output)
+}
+
+fn write(output: &mut String, value: &Value) -> Result<(), String> {
+    match value {
+        Value::Number(number) => output.push_str(&number_to_string(number)?),
+        // serde_json escapes strings the way JSON.stringify does
+        Value::Null | Value::Bool(_) | Value::String(_) => output.push_str(&value.to_string()),
+        Value::Array(list) => {
+            output.push('[');
+            for (index, item) in list.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write(output, item)?;
+            }
+            output.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries
+                .sort_by(|(first, _), (second, _)| first.encode_utf16().cmp(second.encode_utf16()));
+
+            output.push('{');
+            for (index, (key, item)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                output.push_str(&Value::String(key.clone()).to_string());
+                output.push(':');
+                write(output, item)?;
+            }
+            output.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn number_to_string(number: &Number) -> Result<String, String> {
+    match number.as_str().parse::<f64>() {
+        Ok(double) if double.is_finite() => Ok(ecmascript(double)),
+        _ => Err(format!("{} is not a double", number)),
+    }
+}
+
+/// The double the way JavaScript's `Number.prototype.toString` writes it.
+fn ecmascript(double: f64) -> String {
+    let text = Decimal::parse(&shortest(double))
+        .expect("a finite double")
+        .canonical();
+    match text.split_once('e') {
+        Some((mantissa, exponent)) if !exponent.starts_with('-') => {
+            format!("{}e+{}", mantissa, exponent)
+        }
+        _ => text,
+    }
+}
+
+/// The shortest digits that read back as the double, as `<digits>e<exponent>`.
+///
+/// Rust finds the same digits as JavaScript, except when the double is exactly halfway
+/// between the two closest candidates: JavaScript then takes the even one, Rust rounds up.
+fn shortest(double: f64) -> String {
+    let sign = if double < 0.0 { "-" } else { "" };
+    let (digits, exponent) = split_exponent(&format!("{:e}", double.abs()));
+    // with this many digits `{:e}` is exact
+    let (exact, exact_exponent) = split_exponent(&format!("{:.1100e}", double.abs()));
+
+    let halfway = exponent == exact_exponent
+        && exact.len() > digits.len()
+        && exact[digits.len()..].trim_end_matches('0') == "5";
+    let odd = digits.bytes().last().is_some_and(|x| (x - b'0') % 2 == 1);
+    if !(halfway && odd) {
+        return format!("{}{}e{}", sign, digits, exponent - digits.len() as i64 + 1);
+    }
+
+    let truncated = &exact[..digits.len()];
+    let even = match truncated == digits {
+        true => (digits.parse::<u64>().expect("at most 17 digits") + 1).to_string(),
+        false => truncated.to_string(),
+    };
+    // a carry (`...9` + 1) makes the number one digit longer at the same exponent
+    let exponent = exponent + (even.len() - digits.len()) as i64;
+    format!("{}{}e{}", sign, even, exponent - even.len() as i64 + 1)
+}
+
+/// The digits and exponent of `{:e}` text, `d.ddde<exponent>`.
+fn split_exponent(text: &str) -> (String, i64) {
+    let (mantissa, exponent) = text.split_once('e').expect("exponent notation");
+    let digits = mantissa.replace('.', "");
+    (digits, exponent.parse().expect("an exponent"))
+}
+
+#[cfg(test)]
+mod jcs_test {
+    use super::{ecmascript, to_string};
+    use serde_json::{from_str, Value};
+
+    fn canonical(text: &str) -> String {
+        to_string(&from_str::<Value>(text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn number_vectors() {
+        // RFC 8785 appendix B
+        let cases = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+            // halfway cases, checked with node
+            (0x4320000000000001, "2251799813685248.5"),
+            (0x4330000000000001, "4503599627370497"),
+        ];
+        for (bits, expected) in cases {
+            assert_eq!(ecmascript(f64::from_bits(bits)), expected, "{:x}", bits);
+        }
+    }
+
+    #[test]
+    fn rfc_example() {
+        // RFC 8785 section 3.2.2
+        assert_eq!(
+            canonical(
+                r#"{
+                    "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                    "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                    "literals": [null, true, false]
+                }"#
+            ),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn utf16_key_order() {
+        // RFC 8785 section 3.2.3
+        assert_eq!(
+            canonical(
+                r#"{
+                    "\u20ac": "Euro Sign",
+                    "\r": "Carriage Return",
+                    "\ufb33": "Hebrew Letter Dalet With Dagesh",
+                    "1": "One",
+                    "\ud83d\ude00": "Emoji: Grinning Face",
+                    "\u0080": "Control",
+                    "\u00f6": "Latin Small Letter O With Diaeresis"
+                }"#
+            ),
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\"ö\":\"Latin Small Letter O With Diaeresis\",\"€\":\"Euro Sign\",\"😀\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        );
+    }
+
+    #[test]
+    fn not_doubles() {
+        assert_eq!(
+            to_string(&from_str::<Value>("[1e400]").unwrap()).unwrap_err(),
+            "1e+400 is not a double"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::borrow::Cow;
+
+use argh::FromArgs;
+use jmespath::{Context, Rcvar, Variable};
+use serde_json::Value;
+
+use crate::{exit, input, value_printer, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "jp",
+    description = "Search the map with a JMESPath expression and print the result",
+    note = "Check https://jmespath.org/specification.html for the spec on JMESPath, the expressions are the ones of aws --query.
+Literals between backticks are json, so strings are written `\"running\"` or as the raw string 'running'. Like aws --query, a literal that is not valid json, like `running`, is taken as a string. Objects in the result have their keys sorted.
+An expression that can not be compiled fails with 2 and shows where it went wrong. With --strict a result that is null or an empty array fails with 1.",
+    example = r#"input                                          expression                        output
+{{"items": [{{"id": 1, "state": "running"}}]}}     "items[?state=='running'].id"     [1]
+{{"items": [1, 2, 3]}}                           'length(items)'                   3"#
+)]
+pub struct SubCommandJp {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    expression: String,
+    #[argh(switch)]
+    /// fail when the result is null or an empty array
+    strict: bool,
+}
+
+pub fn do_jp(args: SubCommandJp, pretty: bool) -> Result<String, exit::Error> {
+    let text = legacy_literals(&args.expression);
+    let expression =
+        jmespath::compile(&text).map_err(|e| exit::usage(format!("invalid expression: {}", e)))?;
+    let value = variable_or_value(&args.variable)?;
+    let mut context = Context::new(&text, &jmespath::DEFAULT_RUNTIME);
+    let found = jmespath::interpret(&to_variable(value), expression.as_ast(), &mut context)
+        .map_err(|e| e.to_string())?;
+    let found = to_value(&found);
+
+    let empty = match &found {
+        Value::Null => true,
+        Value::Array(list) => list.is_empty(),
+        _ => false,
+    };
+    if empty && input::strict(args.strict) {
+        return Err(format!("{} found nothing", args.expression).into());
+    }
+    Ok(value_printer(pretty, &found))
+}
+
+/// The expression with backtick literals that are not valid json, like `running`,
+/// written as json strings, the way aws --query still accepts them.
+fn legacy_literals(expression: &str) -> Cow<'_, str> {
+    let mut rewritten = String::new();
+    // the end of the text copied to `rewritten` so far
+    let mut copied = 0;
+    // the quote that is open and where its text starts
+    let mut open: Option<(char, usize)> = None;
+    let mut chars = expression.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match (open, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(('`', start)), '`') => {
+                let literal = expression[start..index].replace("\\`", "`");
+                if serde_json::from_str::<Value>(&literal).is_err() {
+                    let string = Value::String(literal.trim().to_string()).to_string();
+                    rewritten.push_str(&expression[copied..start]);
+                    rewritten.push_str(&string.replace('`', "\\`"));
+                    copied = index;
+                }
+                open = None;
+            }
+            (Some((quote, _)), _) if quote == c => open = None,
+            (None, '`' | '\'' | '"') => open = Some((c, index + 1)),
+            _ => (),
+        }
+    }
+    match copied {
+        0 => Cow::Borrowed(expression),
+        _ => {
+            rewritten.push_str(&expression[copied..]);
+            Cow::Owned(rewritten)
+        }
+    }
+}
+
+/// The value as a JMESPath variable. Converted here instead of by jmespath, as that
+/// goes through serde for nested values, where the numbers of `arbitrary_precision` become objects.
+fn to_variable(value: Value) -> Rcvar {
+    Rcvar::new(match value {
+        Value::Null => Variable::Null,
+        Value::Bool(boolean) => Variable::Bool(boolean),
+        Value::Number(number) => Variable::Number(number),
+        Value::String(text) => Variable::String(text),
+        Value::Array(list) => Variable::Array(list.into_iter().map(to_variable).collect()),
+        Value::Object(map) => Variable::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, to_variable(value)))
+                .collect(),
+        ),
+    })
+}
+
+fn to_value(variable: &Variable) -> Value {
+    match variable {
+        Variable::Null => Value::Null,
+        Variable::Bool(boolean) => Value::Bool(*boolean),
+        Variable::Number(number) => Value::Number(number.clone()),
+        Variable::String(text) => Value::String(text.clone()),
+        Variable::Array(list) => Value::Array(list.iter().map(|x| to_value(x)).collect()),
+        Variable::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), to_value(value)))
+                .collect(),
+        ),
+        // an expression reference like &name only exists as a function argument
+        Variable::Expref(_) => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod jp_test {
+    use super::legacy_literals;
+
+    #[test]
+    fn bare_literals_become_strings() {
+        assert_eq!(
+            legacy_literals("items[?state==`running`].id"),
+            r#"items[?state==`"running"`].id"#
+        );
+        assert_eq!(legacy_literals("a[?b==` x y `]"), r#"a[?b==`"x y"`]"#);
+        assert_eq!(legacy_literals(r"a[?b==`x\`y`]"), r#"a[?b==`"x\`y"`]"#);
+    }
+
+    #[test]
+    fn json_literals_and_strings_stay() {
+        for expression in [
+            "items[?size > `5`].id",
+            r#"a[?b==`"running"`]"#,
+            "a[?b=='`x`']",
+            r#""`x`".y"#,
+            "a[?b==`[1, {\"c\": null}]`]",
+        ] {
+            assert_eq!(legacy_literals(expression), expression);
+        }
+    }
+}
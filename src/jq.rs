@@ -0,0 +1,864 @@
+use std::cmp::Ordering;
+
+use argh::FromArgs;
+use serde_json::{Map, Value};
+
+use crate::{exit, number, output, type_name, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "jq",
+    description = "Run a small jq filter on the map and print every result on its own line",
+    note = "Supported are . and .key, .\"key\", .[index] with negative indices counting from the end, .[\"key\"], .[] over arrays and objects, the pipe |, the comma , for several results, parentheses, array construction [...], object construction {{a: .b, \"c\": .d, e, (.key): 1}}, json literals, the comparisons == != < <= > >=, and, or, and the functions select(f), not, length, keys and empty.
+Not supported are arithmetic, variables and `as`, if, reduce, foreach, try and the optional ?, the alternative //, recursive descent .., slices, string interpolation, def and every other builtin function.
+An expression that can not be parsed fails with 2 and a caret under the offending character, an expression that fails on the input, like .a on an array, fails with 1.",
+    example = r#"input                              expression                      output
+{{"a": {{"b": [1, 2]}}}}               .a.b[0]                         1
+{{"items": [{{"x": 1}}, {{"x": 2}}]}}    '.items[] | select(.x == 1)'    {{"x":1}}
+{{"a": 1, "b": 2}}                   '{{sum: [.a, .b]}}'               {{"sum":[1,2]}}"#
+)]
+pub struct SubCommandJq {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    expression: String,
+}
+
+pub fn do_jq(args: SubCommandJq, pretty: bool) -> Result<Option<String>, exit::Error> {
+    let filter = parse(&args.expression).map_err(exit::usage)?;
+    let value = variable_or_value(&args.variable)?;
+    Ok(output::lines(&filter.eval(&value)?, pretty, false))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, PartialEq)]
+enum Filter {
+    Identity,
+    Literal(Value),
+    /// the target indexed by every result of the index, both run on the same input
+    Index(Box<Filter>, Box<Filter>),
+    Iterate(Box<Filter>),
+    Pipe(Box<Filter>, Box<Filter>),
+    Comma(Box<Filter>, Box<Filter>),
+    Compare(Box<Filter>, Compare, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Array(Option<Box<Filter>>),
+    Object(Vec<(Filter, Filter)>),
+    Select(Box<Filter>),
+    Not,
+    Length,
+    Keys,
+    Empty,
+}
+
+impl Filter {
+    /// Every result of the filter on the input, in the order jq gives them.
+    fn eval(&self, input: &Value) -> Result<Vec<Value>, String> {
+        Ok(match self {
+            Filter::Identity => vec![input.clone()],
+            Filter::Literal(value) => vec![value.clone()],
+            Filter::Index(target, index) => {
+                let mut results = Vec::new();
+                for target in target.eval(input)? {
+                    for index in index.eval(input)? {
+                        results.push(index_value(&target, &index)?);
+                    }
+                }
+                results
+            }
+            Filter::Iterate(target) => {
+                let mut results = Vec::new();
+                for target in target.eval(input)? {
+                    match target {
+                        Value::Array(list) => results.extend(list),
+                        Value::Object(map) => results.extend(map.into_iter().map(|(_, x)| x)),
+                        other => return Err(format!("can not iterate over {}", type_name(&other))),
+                    }
+                }
+                results
+            }
+            Filter::Pipe(left, right) => {
+                let mut results = Vec::new();
+                for value in left.eval(input)? {
+                    results.extend(right.eval(&value)?);
+                }
+                results
+            }
+            Filter::Comma(left, right) => {
+                let mut results = left.eval(input)?;
+                results.extend(right.eval(input)?);
+                results
+            }
+            Filter::Compare(left, compare, right) => {
+                let mut results = Vec::new();
+                // like jq the right side is the outer loop
+                for right in right.eval(input)? {
+                    for left in left.eval(input)? {
+                        let ordering = order(&left, &right);
+                        results.push(Value::Bool(match compare {
+                            Compare::Eq => ordering == Ordering::Equal,
+                            Compare::Ne => ordering != Ordering::Equal,
+                            Compare::Lt => ordering == Ordering::Less,
+                            Compare::Le => ordering != Ordering::Greater,
+                            Compare::Gt => ordering == Ordering::Greater,
+                            Compare::Ge => ordering != Ordering::Less,
+                        }));
+                    }
+                }
+                results
+            }
+            Filter::And(left, right) | Filter::Or(left, right) => {
+                let is_and = matches!(self, Filter::And(..));
+                let mut results = Vec::new();
+                for left in left.eval(input)? {
+                    // the right side only runs when the left side does not decide
+                    if truthy(&left) != is_and {
+                        results.push(Value::Bool(!is_and));
+                        continue;
+                    }
+                    for right in right.eval(input)? {
+                        results.push(Value::Bool(truthy(&right)));
+                    }
+                }
+                results
+            }
+            Filter::Array(None) => vec![Value::Array(Vec::new())],
+            Filter::Array(Some(items)) => vec![Value::Array(items.eval(input)?)],
+            Filter::Object(entries) => {
+                let mut objects = vec![Map::new()];
+                for (key, value) in entries {
+                    let mut next = Vec::new();
+                    for object in &objects {
+                        for key in key.eval(input)? {
+                            let key = match key {
+                                Value::String(key) => key,
+                                other => {
+                                    return Err(format!(
+                                        "object keys must be strings, not {}",
+                                        type_name(&other)
+                                    ))
+                                }
+                            };
+                            for value in value.eval(input)? {
+                                let mut object = object.clone();
+                                object.insert(key.clone(), value);
+                                next.push(object);
+                            }
+                        }
+                    }
+                    objects = next;
+                }
+                objects.into_iter().map(Value::Object).collect()
+            }
+            Filter::Select(condition) => condition
+                .eval(input)?
+                .iter()
+                .filter(|x| truthy(x))
+                .map(|_| input.clone())
+                .collect(),
+            Filter::Not => vec![Value::Bool(!truthy(input))],
+            Filter::Length => vec![length(input)?],
+            Filter::Keys => vec![keys(input)?],
+            Filter::Empty => Vec::new(),
+        })
+    }
+}
+
+/// Only `false` and `null` are false.
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn index_value(target: &Value, index: &Value) -> Result<Value, String> {
+    match (target, index) {
+        (Value::Null, Value::String(_) | Value::Number(_)) => Ok(Value::Null),
+        (Value::Object(map), Value::String(key)) => {
+            Ok(map.get(key).cloned().unwrap_or(Value::Null))
+        }
+        (Value::Array(list), Value::Number(number)) => {
+            let index = number.as_f64().unwrap_or(0.0).floor();
+            let index = match index < 0.0 {
+                true => list.len() as f64 + index,
+                false => index,
+            };
+            Ok(match index < 0.0 {
+                true => Value::Null,
+                false => list.get(index as usize).cloned().unwrap_or(Value::Null),
+            })
+        }
+        (target, Value::String(key)) => Err(format!(
+            "can not index {} with {:?}",
+            type_name(target),
+            key
+        )),
+        (target, index) => Err(format!(
+            "can not index {} with {}",
+            type_name(target),
+            type_name(index)
+        )),
+    }
+}
+
+fn length(value: &Value) -> Result<Value, String> {
+    Ok(match value {
+        Value::Null => Value::from(0),
+        Value::Bool(_) => return Err(String::from("boolean has no length")),
+        Value::Number(number) => match number.as_i64() {
+            Some(number) => Value::from(number.unsigned_abs()),
+            None => Value::from(number.as_f64().unwrap_or(0.0).abs()),
+        },
+        Value::String(text) => Value::from(text.chars().count()),
+        Value::Array(list) => Value::from(list.len()),
+        Value::Object(map) => Value::from(map.len()),
+    })
+}
+
+fn keys(value: &Value) -> Result<Value, String> {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Ok(Value::from(
+                keys.into_iter().cloned().collect::<Vec<String>>(),
+            ))
+        }
+        Value::Array(list) => Ok(Value::from((0..list.len()).collect::<Vec<usize>>())),
+        other => Err(format!("{} has no keys", type_name(other))),
+    }
+}
+
+/// The order of jq: null, false, true, numbers, strings, arrays and objects,
+/// objects compare their sorted keys first and then the values of those keys.
+fn order(left: &Value, right: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => {
+            let left = left.as_f64().unwrap_or(f64::NAN);
+            let right = right.as_f64().unwrap_or(f64::NAN);
+            left.partial_cmp(&right).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(left), Value::String(right)) => left.cmp(right),
+        (Value::Array(left), Value::Array(right)) => left
+            .iter()
+            .zip(right)
+            .map(|(left, right)| order(left, right))
+            .find(|x| x.is_ne())
+            .unwrap_or_else(|| left.len().cmp(&right.len())),
+        (Value::Object(left), Value::Object(right)) => {
+            let mut left_keys: Vec<&String> = left.keys().collect();
+            let mut right_keys: Vec<&String> = right.keys().collect();
+            left_keys.sort();
+            right_keys.sort();
+            left_keys.cmp(&right_keys).then_with(|| {
+                left_keys
+                    .iter()
+                    .map(|key| order(&left[*key], &right[*key]))
+                    .find(|x| x.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        (left, right) => rank(left).cmp(&rank(right)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    Field(String),
+    Str(String),
+    Number(Value),
+    Ident(String),
+    Open(char),
+    Close(char),
+    Pipe,
+    Comma,
+    Colon,
+    Compare(Compare),
+}
+
+/// The tokens with the character position they start at.
+fn tokenize(expression: &str) -> Result<Vec<(Token, usize)>, (String, usize)> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    let is_ident_start = |c: char| c.is_ascii_alphabetic() || c == '_';
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    while index < chars.len() {
+        let start = index;
+        let c = chars[index];
+        let next = chars.get(index + 1).copied();
+        let token = match c {
+            c if c.is_whitespace() => {
+                index += 1;
+                continue;
+            }
+            '.' if next == Some('.') => {
+                return Err((String::from("recursive descent .. is not supported"), start))
+            }
+            '.' if next.is_some_and(is_ident_start) => {
+                index += 1;
+                while index < chars.len() && is_ident(chars[index]) {
+                    index += 1;
+                }
+                tokens.push((
+                    Token::Field(chars[start + 1..index].iter().collect()),
+                    start,
+                ));
+                continue;
+            }
+            '.' => Token::Dot,
+            '"' => {
+                index += 1;
+                while index < chars.len() && chars[index] != '"' {
+                    match (chars[index], chars.get(index + 1)) {
+                        ('\\', Some('(')) => {
+                            return Err((
+                                String::from("string interpolation is not supported"),
+                                index,
+                            ))
+                        }
+                        ('\\', Some(_)) => index += 2,
+                        _ => index += 1,
+                    }
+                }
+                if index >= chars.len() {
+                    return Err((String::from("unterminated string"), start));
+                }
+                index += 1;
+                let text: String = chars[start..index].iter().collect();
+                let text = serde_json::from_str(&text)
+                    .map_err(|e| (format!("invalid string: {}", e), start))?;
+                tokens.push((Token::Str(text), start));
+                continue;
+            }
+            c if c.is_ascii_digit() || (c == '-' && next.is_some_and(|x| x.is_ascii_digit())) => {
+                index += 1;
+                while index < chars.len()
+                    && (chars[index].is_ascii_digit()
+                        || matches!(chars[index], '.' | 'e' | 'E')
+                        || (matches!(chars[index], '+' | '-')
+                            && matches!(chars[index - 1], 'e' | 'E')))
+                {
+                    index += 1;
+                }
+                let text: String = chars[start..index].iter().collect();
+                let mut number = serde_json::from_str(&text)
+                    .map_err(|_| (format!("invalid number `{}`", text), start))?;
+                number::keep_written(&text, &mut number);
+                tokens.push((Token::Number(number), start));
+                continue;
+            }
+            c if is_ident_start(c) => {
+                while index < chars.len() && is_ident(chars[index]) {
+                    index += 1;
+                }
+                tokens.push((Token::Ident(chars[start..index].iter().collect()), start));
+                continue;
+            }
+            '(' | '[' | '{' => Token::Open(c),
+            ')' | ']' | '}' => Token::Close(c),
+            '|' => Token::Pipe,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            '=' if next == Some('=') => Token::Compare(Compare::Eq),
+            '!' if next == Some('=') => Token::Compare(Compare::Ne),
+            '<' if next == Some('=') => Token::Compare(Compare::Le),
+            '>' if next == Some('=') => Token::Compare(Compare::Ge),
+            '<' => Token::Compare(Compare::Lt),
+            '>' => Token::Compare(Compare::Gt),
+            '/' if next == Some('/') => {
+                return Err((
+                    String::from("the alternative operator // is not supported"),
+                    start,
+                ))
+            }
+            '+' | '-' | '*' | '/' | '%' => {
+                return Err((String::from("arithmetic is not supported"), start))
+            }
+            '$' => return Err((String::from("variables are not supported"), start)),
+            '?' => return Err((String::from("the optional ? is not supported"), start)),
+            '=' => return Err((String::from("assignment is not supported"), start)),
+            c => return Err((format!("unexpected character `{}`", c), start)),
+        };
+        index += match token {
+            Token::Compare(Compare::Lt | Compare::Gt) => 1,
+            Token::Compare(_) => 2,
+            _ => 1,
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+/// Parses the expression, the error shows the expression with a caret under where it went wrong.
+fn parse(expression: &str) -> Result<Filter, String> {
+    let result = tokenize(expression).and_then(|tokens| {
+        let mut parser = Parser {
+            tokens,
+            index: 0,
+            end: expression.chars().count(),
+        };
+        let filter = parser.pipe(true)?;
+        match parser.peek() {
+            None => Ok(filter),
+            Some(_) => Err(parser.unexpected()),
+        }
+    });
+    result.map_err(|(message, position)| {
+        format!(
+            "{} at character {}\n  {}\n  {}^",
+            message,
+            position + 1,
+            expression,
+            " ".repeat(position)
+        )
+    })
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    /// the position of the end of the expression, for errors after the last token
+    end: usize,
+}
+
+type Parsed = Result<Filter, (String, usize)>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.index).map_or(self.end, |(_, x)| *x)
+    }
+
+    fn unexpected(&self) -> (String, usize) {
+        let message = match self.peek() {
+            None => String::from("unexpected end of the expression"),
+            Some(Token::Open(c) | Token::Close(c)) => format!("unexpected `{}`", c),
+            Some(Token::Pipe) => String::from("unexpected `|`"),
+            Some(Token::Comma) => String::from("unexpected `,`"),
+            Some(Token::Colon) => String::from("unexpected `:`, slices are not supported"),
+            Some(Token::Dot) => String::from("unexpected `.`"),
+            Some(Token::Field(key)) => format!("unexpected `.{}`", key),
+            Some(Token::Str(_)) => String::from("unexpected string"),
+            Some(Token::Number(number)) => format!("unexpected number `{}`", number),
+            Some(Token::Ident(name)) => format!("unexpected `{}`", name),
+            Some(Token::Compare(_)) => String::from("unexpected comparison"),
+        };
+        (message, self.position())
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), (String, usize)> {
+        match self.eat(&token) {
+            true => Ok(()),
+            false => Err(self.unexpected()),
+        }
+    }
+
+    /// `a | b`, where the parts are `a, b` unless `comma` is false, as in object values.
+    fn pipe(&mut self, comma: bool) -> Parsed {
+        let left = match comma {
+            true => self.comma()?,
+            false => self.or()?,
+        };
+        match self.eat(&Token::Pipe) {
+            true => Ok(Filter::Pipe(Box::new(left), Box::new(self.pipe(comma)?))),
+            false => Ok(left),
+        }
+    }
+
+    fn comma(&mut self) -> Parsed {
+        let mut left = self.or()?;
+        while self.eat(&Token::Comma) {
+            left = Filter::Comma(Box::new(left), Box::new(self.or()?));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Parsed {
+        let mut left = self.and()?;
+        while self.eat(&Token::Ident(String::from("or"))) {
+            left = Filter::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Parsed {
+        let mut left = self.compare()?;
+        while self.eat(&Token::Ident(String::from("and"))) {
+            left = Filter::And(Box::new(left), Box::new(self.compare()?));
+        }
+        Ok(left)
+    }
+
+    fn compare(&mut self) -> Parsed {
+        let left = self.postfix()?;
+        match self.peek() {
+            Some(&Token::Compare(compare)) => {
+                self.index += 1;
+                let right = self.postfix()?;
+                Ok(Filter::Compare(Box::new(left), compare, Box::new(right)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    /// A term followed by any number of `.key`, `."key"`, `[index]` and `[]`.
+    fn postfix(&mut self) -> Parsed {
+        let mut filter = self.term()?;
+        loop {
+            filter = match self.peek().cloned() {
+                Some(Token::Field(key)) => {
+                    self.index += 1;
+                    Filter::Index(
+                        Box::new(filter),
+                        Box::new(Filter::Literal(Value::String(key))),
+                    )
+                }
+                Some(Token::Dot) => {
+                    self.index += 1;
+                    match self.peek().cloned() {
+                        Some(Token::Str(key)) => {
+                            self.index += 1;
+                            Filter::Index(
+                                Box::new(filter),
+                                Box::new(Filter::Literal(Value::String(key))),
+                            )
+                        }
+                        Some(Token::Open('[')) => self.bracket(filter)?,
+                        _ => return Err(self.unexpected()),
+                    }
+                }
+                Some(Token::Open('[')) => self.bracket(filter)?,
+                _ => return Ok(filter),
+            };
+        }
+    }
+
+    /// `[]` or `[index]` after the target.
+    fn bracket(&mut self, target: Filter) -> Parsed {
+        self.expect(Token::Open('['))?;
+        if self.eat(&Token::Close(']')) {
+            return Ok(Filter::Iterate(Box::new(target)));
+        }
+        let index = self.pipe(true)?;
+        self.expect(Token::Close(']'))?;
+        Ok(Filter::Index(Box::new(target), Box::new(index)))
+    }
+
+    fn term(&mut self) -> Parsed {
+        let position = self.position();
+        let token = match self.peek().cloned() {
+            Some(token) => token,
+            None => return Err(self.unexpected()),
+        };
+        self.index += 1;
+        Ok(match token {
+            Token::Dot => match self.peek().cloned() {
+                Some(Token::Str(key)) => {
+                    self.index += 1;
+                    Filter::Index(
+                        Box::new(Filter::Identity),
+                        Box::new(Filter::Literal(Value::String(key))),
+                    )
+                }
+                _ => Filter::Identity,
+            },
+            Token::Field(key) => Filter::Index(
+                Box::new(Filter::Identity),
+                Box::new(Filter::Literal(Value::String(key))),
+            ),
+            Token::Str(text) => Filter::Literal(Value::String(text)),
+            Token::Number(number) => Filter::Literal(number),
+            Token::Open('(') => {
+                let filter = self.pipe(true)?;
+                self.expect(Token::Close(')'))?;
+                filter
+            }
+            Token::Open('[') => match self.eat(&Token::Close(']')) {
+                true => Filter::Array(None),
+                false => {
+                    let items = self.pipe(true)?;
+                    self.expect(Token::Close(']'))?;
+                    Filter::Array(Some(Box::new(items)))
+                }
+            },
+            Token::Open('{') => self.object()?,
+            Token::Ident(name) => match name.as_str() {
+                "true" => Filter::Literal(Value::Bool(true)),
+                "false" => Filter::Literal(Value::Bool(false)),
+                "null" => Filter::Literal(Value::Null),
+                "not" => Filter::Not,
+                "length" => Filter::Length,
+                "keys" => Filter::Keys,
+                "empty" => Filter::Empty,
+                "select" => {
+                    self.expect(Token::Open('('))?;
+                    let condition = self.pipe(true)?;
+                    self.expect(Token::Close(')'))?;
+                    Filter::Select(Box::new(condition))
+                }
+                name => {
+                    return Err((
+                        format!("unknown or unsupported function `{}`", name),
+                        position,
+                    ))
+                }
+            },
+            _ => {
+                self.index -= 1;
+                return Err(self.unexpected());
+            }
+        })
+    }
+
+    /// The entries of `{...}` after the opening brace.
+    fn object(&mut self) -> Parsed {
+        let mut entries = Vec::new();
+        if self.eat(&Token::Close('}')) {
+            return Ok(Filter::Object(entries));
+        }
+        loop {
+            let key = match self.peek().cloned() {
+                Some(Token::Ident(key) | Token::Str(key)) => {
+                    self.index += 1;
+                    key
+                }
+                Some(Token::Open('(')) => {
+                    self.index += 1;
+                    let key = self.pipe(true)?;
+                    self.expect(Token::Close(')'))?;
+                    self.expect(Token::Colon)?;
+                    entries.push((key, self.pipe(false)?));
+                    if self.eat(&Token::Comma) {
+                        continue;
+                    }
+                    self.expect(Token::Close('}'))?;
+                    return Ok(Filter::Object(entries));
+                }
+                _ => return Err(self.unexpected()),
+            };
+            // `{a}` is short for `{a: .a}`
+            let value = match self.eat(&Token::Colon) {
+                true => self.pipe(false)?,
+                false => Filter::Index(
+                    Box::new(Filter::Identity),
+                    Box::new(Filter::Literal(Value::String(key.clone()))),
+                ),
+            };
+            entries.push((Filter::Literal(Value::String(key)), value));
+            if self.eat(&Token::Comma) {
+                continue;
+            }
+            self.expect(Token::Close('}'))?;
+            return Ok(Filter::Object(entries));
+        }
+    }
+}
+
+#[cfg(test)]
+mod jq_test {
+    use super::parse;
+    use serde_json::{json, Value};
+
+    fn run(expression: &str, input: Value) -> Result<String, String> {
+        let results = parse(expression)?.eval(&input)?;
+        let lines: Vec<String> = results.iter().map(Value::to_string).collect();
+        Ok(lines.join("\n"))
+    }
+
+    #[test]
+    fn corpus() {
+        let input = json!({
+            "a": {"b": [1, 2, 3], "c": "x"},
+            "items": [
+                {"name": "web", "state": "running", "port": 80},
+                {"name": "db", "state": "stopped", "port": 5432},
+                {"name": "cache", "state": "running", "port": 6379}
+            ],
+            "weird key": true,
+            "n": null
+        });
+        let cases = [
+            (".", r#"{"x":1}"#, json!({"x": 1})),
+            (".a.b[0]", "1", input.clone()),
+            (".a.b[-1]", "3", input.clone()),
+            (".a.b[10]", "null", input.clone()),
+            (".a.b.[1]", "2", input.clone()),
+            (".a[\"c\"]", r#""x""#, input.clone()),
+            (".\"weird key\"", "true", input.clone()),
+            (".a.\"c\"", r#""x""#, input.clone()),
+            (".missing.deeper", "null", input.clone()),
+            (".n[0]", "null", input.clone()),
+            (".a.b[]", "1\n2\n3", input.clone()),
+            (".a[]", "[1,2,3]\n\"x\"", input.clone()),
+            (".items[].name", "\"web\"\n\"db\"\n\"cache\"", input.clone()),
+            (".items[] | .port", "80\n5432\n6379", input.clone()),
+            (
+                ".items[] | select(.state == \"running\") | .name",
+                "\"web\"\n\"cache\"",
+                input.clone(),
+            ),
+            (
+                ".items[] | select(.port > 100 and .port < 6000) | .name",
+                "\"db\"",
+                input.clone(),
+            ),
+            (
+                ".items[] | select(.name == \"db\" or .port == 80) | .port",
+                "80\n5432",
+                input.clone(),
+            ),
+            ("[.items[] | .port]", "[80,5432,6379]", input.clone()),
+            (
+                "[.items[] | select(.port >= 6379)] | length",
+                "1",
+                input.clone(),
+            ),
+            (
+                "{name: .a.c, first: .a.b[0]}",
+                r#"{"name":"x","first":1}"#,
+                input.clone(),
+            ),
+            ("{\"a b\": 1, n}", r#"{"a b":1,"n":null}"#, input.clone()),
+            ("{(.a.c): 2}", r#"{"x":2}"#, input.clone()),
+            ("{a: (1, 2)}", "{\"a\":1}\n{\"a\":2}", input.clone()),
+            ("{a: .a.b | length}", r#"{"a":3}"#, input.clone()),
+            (".a.c, .a.b[0]", "\"x\"\n1", input.clone()),
+            ("[]", "[]", input.clone()),
+            ("{}", "{}", input.clone()),
+            (".a | keys", r#"["b","c"]"#, input.clone()),
+            (".a.b | keys", "[0,1,2]", input.clone()),
+            (".a.c | length", "1", input.clone()),
+            (".n | not", "true", input.clone()),
+            (".[] | empty", "", json!([1, 2])),
+            ("1 == 1.0", "true", json!(null)),
+            (
+                "null < false and false < true and true < 0 and 0 < \"\"",
+                "true",
+                json!(null),
+            ),
+            (
+                "\"a\" < [] and [] < {} and [1, 2] < [1, 3]",
+                "true",
+                json!(null),
+            ),
+            ("{a: 1} < {b: 0} and {a: 1} < {a: 2}", "true", json!(null)),
+            ("(1, 2) == (1, 2)", "true\nfalse\nfalse\ntrue", json!(null)),
+            (
+                "[-1.5e2, \"s\\n\", true, null]",
+                r#"[-1.5e2,"s\n",true,null]"#,
+                json!(null),
+            ),
+        ];
+        for (expression, expected, input) in cases {
+            assert_eq!(
+                run(expression, input).as_deref(),
+                Ok(expected),
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn length_of_numbers() {
+        assert_eq!(run("length", json!(-5.5)).unwrap(), "5.5");
+        assert_eq!(run("length", json!(-3)).unwrap(), "3");
+    }
+
+    #[test]
+    fn eval_errors() {
+        assert_eq!(
+            run(".a", json!([1])),
+            Err(String::from("can not index array with \"a\""))
+        );
+        assert_eq!(
+            run(".[0]", json!({"a": 1})),
+            Err(String::from("can not index object with number"))
+        );
+        assert_eq!(
+            run(".[]", json!(1)),
+            Err(String::from("can not iterate over number"))
+        );
+        assert_eq!(
+            run("{(.a): 1}", json!({"a": 1})),
+            Err(String::from("object keys must be strings, not number"))
+        );
+        assert_eq!(
+            run("true | length", json!(null)),
+            Err(String::from("boolean has no length"))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse(".a | foo").unwrap_err(),
+            "unknown or unsupported function `foo` at character 6\n  .a | foo\n       ^"
+        );
+        assert_eq!(
+            parse(".a + 1").unwrap_err(),
+            "arithmetic is not supported at character 4\n  .a + 1\n     ^"
+        );
+        assert_eq!(
+            parse(".items[0").unwrap_err(),
+            "unexpected end of the expression at character 9\n  .items[0\n          ^"
+        );
+        assert_eq!(
+            parse("{a: 1, b: 2)").unwrap_err(),
+            "unexpected `)` at character 12\n  {a: 1, b: 2)\n             ^"
+        );
+        assert!(parse("..").unwrap_err().starts_with("recursive descent"));
+        assert!(parse(".[1:2]")
+            .unwrap_err()
+            .contains("slices are not supported"));
+        assert!(parse("$x")
+            .unwrap_err()
+            .starts_with("variables are not supported"));
+        assert!(parse(".a // 1")
+            .unwrap_err()
+            .starts_with("the alternative operator"));
+        assert!(parse("\"\\(.a)\"")
+            .unwrap_err()
+            .starts_with("string interpolation"));
+        assert!(parse(".a?").unwrap_err().starts_with("the optional ?"));
+        assert_eq!(
+            parse(". a").unwrap_err(),
+            "unexpected `a` at character 3\n  . a\n    ^"
+        );
+    }
+}
@@ -0,0 +1,570 @@
+use serde_json::{Map, Number, Value};
+
+use crate::pointer::escape;
+
+/// Parses a JSON5 document: comments, trailing commas, unquoted keys, single quoted strings,
+/// hexadecimal numbers, `Infinity` and `NaN` on top of plain json.
+///
+/// Errors mention the line and column like serde_json's do. With `dedup_check` an object
+/// containing the same key twice is an error, like `dedup::check` for json.
+pub fn parse(text: &str, dedup_check: bool) -> Result<Value, String> {
+    let mut parser = Parser {
+        text,
+        index: 0,
+        dedup_check,
+        path: String::new(),
+    };
+    parser.skip_whitespace()?;
+    let value = parser.value()?;
+    parser.skip_whitespace()?;
+    if parser.index < text.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+/// Whether the number is `Infinity`, `-Infinity` or `NaN`, which only JSON5 can represent.
+pub fn is_non_finite(number: &Number) -> bool {
+    matches!(number.as_str(), "Infinity" | "-Infinity" | "NaN")
+}
+
+/// Replaces the numbers that are not valid json by `null`, like serde_json does for floats.
+pub fn replace_non_finite(value: &mut Value) {
+    match value {
+        Value::Number(number) if is_non_finite(number) => *value = Value::Null,
+        Value::Object(map) => map.values_mut().for_each(replace_non_finite),
+        Value::Array(list) => list.iter_mut().for_each(replace_non_finite),
+        _ => (),
+    }
+}
+
+fn non_finite(text: &str) -> Value {
+    // serde_json has no public way to build a number that is not valid json, with
+    // `arbitrary_precision` the number is its text so this is the same representation.
+    Value::Number(Number::from_string_unchecked(text.to_string()))
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    index: usize,
+    dedup_check: bool,
+    /// json pointer of the value being parsed, only kept for `dedup_check`
+    path: String,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.index..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, word: &str) -> bool {
+        if self.text[self.index..].starts_with(word) {
+            self.index += word.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.peek() {
+            Some(x) if x == c => {
+                self.next();
+                Ok(())
+            }
+            Some(_) => Err(self.error(&format!("expected `{}`", c))),
+            None => Err(self.error("EOF while parsing")),
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        let before = &self.text[..self.index];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+        format!("{} at line {} column {}", message, line, column)
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), String> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() || c == '\u{feff}' => {
+                    self.next();
+                }
+                Some('/') if self.eat("//") => while !matches!(self.next(), Some('\n') | None) {},
+                Some('/') if self.eat("/*") => match self.text[self.index..].find("*/") {
+                    Some(end) => self.index += end + 2,
+                    None => {
+                        self.index -= 2;
+                        return Err(self.error("unterminated comment"));
+                    }
+                },
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some(quote @ '"') | Some(quote @ '\'') => self.string(quote).map(Value::String),
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'I' | 'N') => {
+                self.number()
+            }
+            _ if self.eat("null") => Ok(Value::Null),
+            _ if self.eat("true") => Ok(Value::Bool(true)),
+            _ if self.eat("false") => Ok(Value::Bool(false)),
+            Some(_) => Err(self.error("expected value")),
+            None => Err(self.error("EOF while parsing a value")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut map = Map::new();
+        loop {
+            self.skip_whitespace()?;
+            if self.eat("}") {
+                return Ok(Value::Object(map));
+            }
+            let key_start = self.index;
+            let key = match self.peek() {
+                Some(quote @ '"') | Some(quote @ '\'') => self.string(quote)?,
+                _ => self.identifier()?,
+            };
+            let parent = self.path.len();
+            if self.dedup_check {
+                self.path.push('/');
+                self.path.push_str(&escape(&key));
+                if map.contains_key(&key) {
+                    self.index = key_start;
+                    return Err(self.error(&format!("duplicate key {:?} at {}", key, self.path)));
+                }
+            }
+            self.skip_whitespace()?;
+            self.expect(':')?;
+            self.skip_whitespace()?;
+            let value = self.value()?;
+            self.path.truncate(parent);
+            map.insert(key, value);
+            self.skip_whitespace()?;
+            if !self.eat(",") {
+                self.skip_whitespace()?;
+                self.expect('}')?;
+                return Ok(Value::Object(map));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut list = Vec::new();
+        loop {
+            self.skip_whitespace()?;
+            if self.eat("]") {
+                return Ok(Value::Array(list));
+            }
+            let parent = self.path.len();
+            if self.dedup_check {
+                self.path.push_str(&format!("/{}", list.len()));
+            }
+            list.push(self.value()?);
+            self.path.truncate(parent);
+            self.skip_whitespace()?;
+            if !self.eat(",") {
+                self.skip_whitespace()?;
+                self.expect(']')?;
+                return Ok(Value::Array(list));
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        let start = self.index;
+        while let Some(c) = self.peek() {
+            let valid = if self.index == start {
+                is_identifier_start(c)
+            } else {
+                is_identifier_part(c)
+            };
+            if !valid {
+                break;
+            }
+            self.next();
+        }
+        if self.index == start {
+            return Err(self.error("expected key"));
+        }
+        Ok(self.text[start..self.index].to_string())
+    }
+
+    fn string(&mut self, quote: char) -> Result<String, String> {
+        self.expect(quote)?;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error("EOF while parsing a string")),
+                Some(c) if c == quote => return Ok(string),
+                Some('\n') | Some('\r') => return Err(self.error("newline in string")),
+                Some('\\') => match self.next() {
+                    None => return Err(self.error("EOF while parsing a string")),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('v') => string.push('\u{b}'),
+                    Some('0') => string.push('\0'),
+                    Some('x') => {
+                        let code = self.hex(2)?;
+                        string.push(char::from_u32(code).expect("two hex digits are a char"));
+                    }
+                    Some('u') => string.push(self.unicode_escape()?),
+                    // an escaped line break continues the string on the next line
+                    Some('\r') => {
+                        self.eat("\n");
+                    }
+                    Some('\n') | Some('\u{2028}') | Some('\u{2029}') => (),
+                    Some(c) => string.push(c),
+                },
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn hex(&mut self, digits: usize) -> Result<u32, String> {
+        let text = self.text[self.index..].get(..digits).unwrap_or("");
+        match u32::from_str_radix(text, 16) {
+            Ok(code) if text.len() == digits && text.chars().all(|c| c.is_ascii_hexdigit()) => {
+                self.index += digits;
+                Ok(code)
+            }
+            _ => Err(self.error("invalid escape")),
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let first = self.hex(4)?;
+        let code = if (0xD800..0xDC00).contains(&first) && self.eat("\\u") {
+            let second = self.hex(4)?;
+            if !(0xDC00..0xE000).contains(&second) {
+                return Err(self.error("invalid unicode surrogate"));
+            }
+            0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00)
+        } else {
+            first
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode code point"))
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.index;
+        let negative = self.eat("-");
+        if !negative {
+            self.eat("+");
+        }
+
+        if self.eat("Infinity") {
+            return Ok(non_finite(if negative { "-Infinity" } else { "Infinity" }));
+        }
+        if self.eat("NaN") {
+            return Ok(non_finite("NaN"));
+        }
+
+        let sign = if negative { "-" } else { "" };
+        if self.eat("0x") || self.eat("0X") {
+            let digits_start = self.index;
+            while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                self.next();
+            }
+            let digits = &self.text[digits_start..self.index];
+            if digits.is_empty() {
+                return Err(self.error("invalid number"));
+            }
+            return self.json_number(start, format!("{}{}", sign, hex_to_decimal(digits)));
+        }
+
+        let digits_start = self.index;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-'))
+        {
+            if matches!(self.peek(), Some('+') | Some('-'))
+                && !matches!(
+                    self.text[..self.index].chars().last(),
+                    Some('e') | Some('E')
+                )
+            {
+                break;
+            }
+            self.next();
+        }
+        let mut digits = self.text[digits_start..self.index].to_string();
+        // json needs digits around the decimal point
+        if digits.starts_with('.') {
+            digits.insert(0, '0');
+        }
+        if let Some(index) = digits.find(".e").or_else(|| digits.find(".E")) {
+            digits.remove(index);
+        } else if digits.ends_with('.') {
+            digits.pop();
+        }
+        self.json_number(start, format!("{}{}", sign, digits))
+    }
+
+    fn json_number(&mut self, start: usize, text: String) -> Result<Value, String> {
+        text.parse::<Number>().map(Value::Number).map_err(|_| {
+            self.index = start;
+            self.error("invalid number")
+        })
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_identifier_part(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Converts hexadecimal digits to decimal digits without limiting the size of the number.
+fn hex_to_decimal(hex: &str) -> String {
+    // little endian decimal digits
+    let mut decimal = vec![0u32];
+    for digit in hex.chars().filter_map(|c| c.to_digit(16)) {
+        let mut carry = digit;
+        for place in decimal.iter_mut() {
+            let value = *place * 16 + carry;
+            *place = value % 10;
+            carry = value / 10;
+        }
+        while carry > 0 {
+            decimal.push(carry % 10);
+            carry /= 10;
+        }
+    }
+    while decimal.len() > 1 && decimal.last() == Some(&0) {
+        decimal.pop();
+    }
+    decimal
+        .iter()
+        .rev()
+        .map(|digit| char::from_digit(*digit, 10).expect("decimal digit"))
+        .collect()
+}
+
+/// Prints the value as JSON5: keys without quotes where they are identifiers, single quoted
+/// strings and, when pretty, a trailing comma after every element.
+pub fn to_string(value: &Value, pretty: bool) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, pretty.then_some(0));
+    out
+}
+
+fn write_value(out: &mut String, value: &Value, indent: Option<usize>) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(number) => out.push_str(&number.to_string()),
+        Value::String(string) => write_string(out, string),
+        Value::Array(list) => write_list(out, ('[', ']'), list, indent, |out, value, indent| {
+            write_value(out, value, indent)
+        }),
+        Value::Object(map) => {
+            write_list(out, ('{', '}'), map, indent, |out, (key, value), indent| {
+                if is_identifier(key) {
+                    out.push_str(key);
+                } else {
+                    write_string(out, key);
+                }
+                out.push(':');
+                if indent.is_some() {
+                    out.push(' ');
+                }
+                write_value(out, value, indent);
+            })
+        }
+    }
+}
+
+fn write_list<I, F>(
+    out: &mut String,
+    (open, close): (char, char),
+    items: I,
+    indent: Option<usize>,
+    mut write: F,
+) where
+    I: IntoIterator,
+    F: FnMut(&mut String, I::Item, Option<usize>),
+{
+    out.push(open);
+    let mut empty = true;
+    for (index, item) in items.into_iter().enumerate() {
+        empty = false;
+        match indent {
+            Some(level) => {
+                out.push('\n');
+                out.push_str(&"  ".repeat(level + 1));
+                write(out, item, Some(level + 1));
+                out.push(',');
+            }
+            None => {
+                if index > 0 {
+                    out.push(',');
+                }
+                write(out, item, None);
+            }
+        }
+    }
+    if let (Some(level), false) = (indent, empty) {
+        out.push('\n');
+        out.push_str(&"  ".repeat(level));
+    }
+    out.push(close);
+}
+
+fn write_string(out: &mut String, string: &str) {
+    out.push('\'');
+    for c in string.chars() {
+        match c {
+            '\'' => out.push_str("\\'"),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                out.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if is_identifier_start(c) => chars.all(is_identifier_part),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod json5_test {
+    use super::{replace_non_finite, to_string};
+    use serde_json::{json, Value};
+
+    fn parse(text: &str) -> Result<Value, String> {
+        super::parse(text, false)
+    }
+
+    #[test]
+    fn parses_json5() {
+        let value = parse(
+            r#"// config
+            {
+                name: 'bash_map',
+                "quoted key": "text",
+                'single': 'it\'s',
+                hex: 0xFF,
+                half: .5,
+                whole: 5.,
+                positive: +1,
+                list: [1, 2, /* three */ 3,],
+                line: 'a\
+b',
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            value,
+            json!({
+                "name": "bash_map",
+                "quoted key": "text",
+                "single": "it's",
+                "hex": 255,
+                "half": 0.5,
+                "whole": 5,
+                "positive": 1,
+                "list": [1, 2, 3],
+                "line": "ab"
+            })
+        );
+    }
+
+    #[test]
+    fn plain_json_is_json5() {
+        let text = r#"{"a": [1, -2.5e3, true, null, "é\n"], "b": {}}"#;
+        assert_eq!(
+            parse(text).unwrap(),
+            serde_json::from_str::<Value>(text).unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse("{\n  a: tru\n}").unwrap_err(),
+            "expected value at line 2 column 6"
+        );
+        assert!(parse("{a: 1} 2").is_err());
+        assert!(parse("[1,,]").is_err());
+        assert!(parse("{'a: 1}").is_err());
+        assert!(parse("0x").is_err());
+    }
+
+    #[test]
+    fn prints_json5() {
+        let value = json!({"name": "it's", "two words": [1, {}], "$ok_1": [], "1st": null});
+        assert_eq!(
+            to_string(&value, false),
+            r#"{name:'it\'s','two words':[1,{}],$ok_1:[],'1st':null}"#
+        );
+        assert_eq!(
+            to_string(&value, true),
+            "{\n  name: 'it\\'s',\n  'two words': [\n    1,\n    {},\n  ],\n  $ok_1: [],\n  '1st': null,\n}"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let text = "{a: [Infinity, -Infinity, NaN, 1.5, 0xa], 'b\\'\\n': 'x\"y', c: {d: null}}";
+        let value = parse(text).unwrap();
+
+        for pretty in [false, true] {
+            let printed = to_string(&value, pretty);
+            assert_eq!(to_string(&parse(&printed).unwrap(), pretty), printed);
+        }
+        assert_eq!(
+            to_string(&value, false),
+            r#"{a:[Infinity,-Infinity,NaN,1.5,10],'b\'\n':'x"y',c:{d:null}}"#
+        );
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        assert_eq!(parse("{a: 1, 'a': 2}").unwrap(), json!({"a": 2}));
+        assert_eq!(
+            super::parse("{list: [{}, {\n  a: 1,\n  \"a\": 2}]}", true).unwrap_err(),
+            r#"duplicate key "a" at /list/1/a at line 3 column 3"#
+        );
+        assert!(super::parse("{a: {a: 1}, b: [{a: 1}, {a: 1}]}", true).is_ok());
+    }
+
+    #[test]
+    fn non_finite_becomes_null() {
+        let mut value = parse("{a: [NaN, 1], b: -Infinity}").unwrap();
+        replace_non_finite(&mut value);
+        assert_eq!(value, json!({"a": [null, 1], "b": null}));
+    }
+}
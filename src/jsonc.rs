@@ -0,0 +1,145 @@
+use std::borrow::Cow;
+
+/// Turns JSON with comments (`//` and `/* */`) and trailing commas into plain json.
+///
+/// Comments and trailing commas are replaced by spaces (keeping newlines), so line and column
+/// numbers in parse errors still point at the original text. Strings are left untouched.
+pub fn strip(text: &str) -> Cow<'_, str> {
+    if !text.contains('/') && !text.contains(',') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut bytes = text.as_bytes().to_vec();
+    strip_comments(&mut bytes);
+    strip_trailing_commas(&mut bytes);
+
+    if bytes == text.as_bytes() {
+        Cow::Borrowed(text)
+    } else {
+        // only ascii bytes were replaced by spaces, so the text is still utf-8
+        Cow::Owned(String::from_utf8(bytes).expect("valid utf-8"))
+    }
+}
+
+fn strip_comments(bytes: &mut [u8]) {
+    let mut index = 0;
+    let mut in_string = false;
+
+    while index < bytes.len() {
+        match (in_string, bytes[index], bytes.get(index + 1)) {
+            (true, b'\\', _) => index += 1,
+            (true, b'"', _) | (false, b'"', _) => in_string = !in_string,
+            (false, b'/', Some(b'/')) => {
+                while index < bytes.len() && bytes[index] != b'\n' {
+                    bytes[index] = b' ';
+                    index += 1;
+                }
+                continue;
+            }
+            (false, b'/', Some(b'*')) => {
+                let end = match find(&bytes[index + 2..], b"*/") {
+                    Some(end) => index + 2 + end + 2,
+                    // leave unterminated comments for the parser to report
+                    None => return,
+                };
+                for byte in &mut bytes[index..end] {
+                    if !matches!(byte, b'\n' | b'\r') {
+                        *byte = b' ';
+                    }
+                }
+                index = end;
+                continue;
+            }
+            _ => (),
+        }
+        index += 1;
+    }
+}
+
+fn strip_trailing_commas(bytes: &mut [u8]) {
+    let mut index = 0;
+    let mut in_string = false;
+
+    while index < bytes.len() {
+        match (in_string, bytes[index]) {
+            (true, b'\\') => index += 1,
+            (_, b'"') => in_string = !in_string,
+            (false, b',') => {
+                let next = bytes[index + 1..].iter().find(|x| !x.is_ascii_whitespace());
+                if matches!(next, Some(b'}') | Some(b']')) {
+                    bytes[index] = b' ';
+                }
+            }
+            _ => (),
+        }
+        index += 1;
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod jsonc_test {
+    use super::strip;
+    use serde_json::{from_str, json, Value};
+
+    fn parse(text: &str) -> Value {
+        from_str(&strip(text)).unwrap()
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            parse(
+                r#"{
+                    // line comment
+                    "a": 1, /* block
+                    comment */ "b": [1, 2 /* inline */]
+                }"#
+            ),
+            json!({"a": 1, "b": [1, 2]})
+        );
+    }
+
+    #[test]
+    fn trailing_commas() {
+        assert_eq!(
+            parse(r#"{"a": [1, 2, ], "b": {"c": 1,}, }"#),
+            json!({"a": [1, 2], "b": {"c": 1}})
+        );
+        assert_eq!(parse("[1, // comment\n]"), json!([1]));
+    }
+
+    #[test]
+    fn strings_are_untouched() {
+        assert_eq!(
+            parse(
+                r#"{"url": "https://example.com/a", "glob": "/* x */", "list": "a,]", "quote": "\"//"}"#
+            ),
+            json!({"url": "https://example.com/a", "glob": "/* x */", "list": "a,]", "quote": "\"//"})
+        );
+    }
+
+    #[test]
+    fn keeps_positions() {
+        let text = "{\n  /* comment\n  */ \"a\": 1,\n  \"b\": x,\n}";
+        let stripped = strip(text);
+        assert_eq!(stripped.len(), text.len());
+        assert_eq!(stripped.lines().count(), text.lines().count());
+
+        let error = from_str::<Value>(&stripped).unwrap_err();
+        assert_eq!(error.line(), 4);
+    }
+
+    #[test]
+    fn plain_json_is_borrowed() {
+        assert!(matches!(
+            strip(r#"{"a": [1, 2]}"#),
+            std::borrow::Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,103 @@
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::pointer::escape;
+use crate::{exit, natural, output, value_printer, variable_or_value, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "leaves",
+    description = "Print every scalar in the map as its pointer, = and its compact json on its own line",
+    note = "Empty arrays and objects are printed like scalars, so nothing in the map is left out. The lines are in the order of the map, or sorted on the pointer byte by byte with --sort, which puts /a/10 before /a/2 just like `sort`, or with --natural /a/2 before /a/10.
+Given a pointer only the part under it is printed, still with the pointers from the top. The output is meant for grep, sort and diff, it can not be read back.",
+    example = r#"input                                       arguments             output
+{{"server": {{"host": "a", "ports": [80]}}}}    ""                    /server/host="a" and /server/ports/0=80 on their own line
+{{"b": 1, "a": {{}}}}                           "" --sort             /a={{}} and /b=1 on their own line
+{{"a/b": null}}                               "" --separator " "    /a~1b null"#
+)]
+pub struct SubCommandLeaves {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(option, default = "String::from(\"=\")")]
+    /// text between the pointer and the value (default =)
+    separator: String,
+    #[argh(switch)]
+    /// sort the lines on the pointer instead of keeping the order of the map
+    sort: bool,
+    #[argh(switch)]
+    /// with --sort compare the indices and other digits in the pointers by value
+    natural: bool,
+}
+
+pub fn do_leaves(args: SubCommandLeaves) -> Result<String, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_ref().map_or("", Pointer::as_str);
+    let target = match value.pointer(pointer) {
+        Some(target) => target,
+        None => return Err(format!("{} does not exist", pointer).into()),
+    };
+
+    let mut found = Vec::new();
+    leaves(target, pointer.to_string(), &mut found);
+    if args.natural && !args.sort {
+        return Err(exit::usage(String::from(
+            "--natural only applies to --sort",
+        )));
+    }
+    if args.sort {
+        match args.natural {
+            true => found.sort_by(|(a, _), (b, _)| natural::compare(a, b)),
+            false => found.sort_by(|(a, _), (b, _)| a.cmp(b)),
+        }
+    }
+
+    let lines: Vec<String> = found
+        .into_iter()
+        .map(|(path, leaf)| format!("{}{}{}", path, args.separator, value_printer(false, leaf)))
+        .collect();
+    Ok(lines.join(output::terminator()))
+}
+
+/// The pointer of every scalar and empty array or object, in document order.
+fn leaves<'a>(value: &'a Value, path: String, found: &mut Vec<(String, &'a Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, item) in map {
+                let token = escape(key);
+                leaves(item, format!("{}/{}", path, token), found);
+            }
+        }
+        Value::Array(list) if !list.is_empty() => {
+            for (index, item) in list.iter().enumerate() {
+                leaves(item, format!("{}/{}", path, index), found);
+            }
+        }
+        leaf => found.push((path, leaf)),
+    }
+}
+
+#[cfg(test)]
+mod leaves_test {
+    use super::leaves;
+    use serde_json::json;
+
+    #[test]
+    fn document_order() {
+        let value = json!({"b": {"x~y": [1, {}], "a/b": null}, "a": [], "": "empty"});
+        let mut found = Vec::new();
+        leaves(&value, String::new(), &mut found);
+        let paths: Vec<&str> = found.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["/b/x~0y/0", "/b/x~0y/1", "/b/a~1b", "/a", "/"]);
+    }
+
+    #[test]
+    fn scalar() {
+        let value = json!(1);
+        let mut found = Vec::new();
+        leaves(&value, String::new(), &mut found);
+        assert_eq!(found, [(String::new(), &value)]);
+    }
+}
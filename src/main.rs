@@ -1,18 +1,155 @@
 use serde_json::{from_str, to_string, to_string_pretty, Value};
-use std::env::var;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 
 use argh::FromArgs;
-
-#[derive(PartialEq, Debug)]
+use pointer::{parse_index, pointer_mut, resolve_mut};
+
+mod apply;
+mod bash;
+mod dedup;
+mod depth;
+mod diff;
+mod each;
+mod embed;
+mod env;
+mod escape;
+mod exit;
+mod file;
+mod front_matter;
+mod grep;
+mod gron;
+mod ini;
+mod input;
+mod jcs;
+#[cfg(feature = "jmespath")]
+mod jp;
+mod jq;
+mod json5;
+mod jsonc;
+mod leaves;
+mod merge;
+mod natural;
+mod ndjson;
+mod normalize;
+mod now;
+mod number;
+mod output;
+mod patch;
+mod pointer;
+mod properties;
+mod prune;
+mod redact;
+mod rename;
+mod serve;
+mod sets;
+mod size;
+mod sort;
+mod splice;
+mod stats;
+mod store;
+mod stream;
+mod strings;
+mod table;
+mod transpose;
+mod upsert;
+mod verbose;
+mod watch;
+mod xml;
+
+/// Subcommands that take the map as their first positional argument, in file mode
+/// that argument is omitted and filled in from `--file`.
+const FILE_COMMANDS: &[&str] = &[
+    "get",
+    "set",
+    "type",
+    "compare",
+    "validate",
+    "has-value",
+    "index-of",
+    "delete",
+    "prune-nulls",
+    "prune-empty",
+    "normalize",
+    "sort-by",
+    "transpose",
+    "upsert",
+    "splice",
+    "rename-keys",
+    "array-union",
+    "array-intersect",
+    "array-diff",
+    "diff",
+    "patch",
+    "resolve-conflicts",
+    "trim",
+    "grep",
+    "sub",
+    "substr",
+    "canonicalize",
+    "redact",
+    "stats",
+    "depth",
+    "size",
+    "table",
+    "from-xml",
+    "to-xml",
+    "from-ini",
+    "to-ini",
+    "from-properties",
+    "to-properties",
+    "to-bash-array",
+    "export",
+    "shell-quote",
+    "each",
+    "leaves",
+    "gron",
+    "ungron",
+    "merge",
+    "resolve",
+    "defaults",
+    "jq",
+    "jp",
+    "test",
+    "apply",
+    "stringify",
+    "parse-embedded",
+    "fix-encoding",
+];
+
+/// Subcommands like `FILE_COMMANDS` that take an operation before the map.
+const OPERATION_FILE_COMMANDS: &[&str] = &["str"];
+
+/// Top-level options that consume the next argument as their value.
+const GLOBAL_OPTIONS: &[&str] = &[
+    "--file",
+    "--escape-style",
+    "--lock-timeout",
+    "--store",
+    "--input-format",
+    "--output-format",
+    "--max-depth",
+    "--float-precision",
+    "--key-order",
+    "--backup-suffix",
+    "--connect",
+];
+
+#[derive(PartialEq, Debug, Clone)]
 pub struct Pointer {
     inner: String,
 }
 
+/// Arguments that mean the empty pointer. Older PowerShell versions drop empty arguments
+/// when they start a program, so the quotes of the empty string get passed along instead,
+/// as is or backslash escaped, or nested in the other kind of quotes.
+const EMPTY_POINTERS: &[&str] = &["", "''", r#""""#, r#"\"\""#, r"\'\'", r#"'""'"#, r#""''""#];
+
 impl std::str::FromStr for Pointer {
-    type Err = String;
+    type Err = exit::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if ["", "''", r#""""#].contains(&s) {
+        if EMPTY_POINTERS.contains(&s) {
             // fix for powershell to allow empty pointers
             return Ok(Pointer {
                 inner: String::new(),
@@ -20,6 +157,11 @@ impl std::str::FromStr for Pointer {
         }
 
         let inner = s.replace(r"\/", "/").to_string();
+        if !inner.starts_with('/') {
+            return Err(exit::usage(String::from(
+                "a pointer has to start with / or be \"\" for the whole map",
+            )));
+        }
 
         Ok(Pointer { inner })
     }
@@ -37,8 +179,24 @@ impl Pointer {
     }
 }
 
-#[derive(FromArgs, PartialEq, Debug)]
+#[derive(FromArgs, PartialEq, Debug, Clone)]
 /// Top-level command.
+#[argh(
+    note = "With the environment variable BASH_MAP_STRICT=1 every command runs as if it was given --strict: get fails when nothing is found, and inputs that are not valid json or environment variables that are not set fail instead of counting as {{}}. A --strict on the command always applies, --no-strict ignores BASH_MAP_STRICT.
+With --file, commands that change the map, like set, merge, sort-by, trim or prune-nulls, write the result back to the file and print nothing. Commands that only read the map, like get, stats or table, and --dry-run, --print-only and --print-removed leave the file as it is.
+A pointer has to start with / or be \"\" for the whole map. Earlier versions took other pointers and left the map as it was, even set without --strict now exits with 2 for them.",
+    error_code(
+        1,
+        "a check came out negative, like compare finding a difference, or another error"
+    ),
+    error_code(
+        2,
+        "the arguments can not be used, like an unknown option or a pointer not starting with /"
+    ),
+    error_code(3, "an input could not be parsed"),
+    error_code(4, "a file or stream could not be read or written"),
+    error_code(75, "the lock on --file could not be acquired within --lock-timeout")
+)]
 struct TopLevel {
     #[argh(subcommand)]
     command: MySubCommandEnum,
@@ -48,24 +206,382 @@ struct TopLevel {
     #[argh(switch)]
     /// print the output as an escaped string
     escaped: bool,
+    #[argh(option)]
+    /// how --escaped quotes the output: rust (default), bash-single for a single quoted string safe to eval or paste anywhere, bash-double, powershell for a double quoted PowerShell string, fish or zsh; implies --escaped
+    escape_style: Option<escape::Style>,
+    #[argh(option)]
+    /// read the map from this file instead of the variable argument, mutating commands write the result back to it
+    file: Option<PathBuf>,
+    #[argh(switch)]
+    /// create the --file as an empty map when it does not exist yet
+    create: bool,
+    #[argh(option)]
+    /// seconds to wait for the lock on --file, exits with code 75 when it could not be acquired in time
+    lock_timeout: Option<f64>,
+    #[argh(switch)]
+    /// do not lock the --file while reading and writing it
+    no_lock: bool,
+    #[argh(switch)]
+    /// keep the previous version of a file that is written next to it, as FILE~; --backup=SUFFIX picks the suffix
+    backup: bool,
+    #[argh(option)]
+    /// the suffix of --backup files, implies --backup
+    backup_suffix: Option<String>,
+    #[argh(switch)]
+    /// when a written file is a symlink, replace the link instead of the file it points to
+    no_follow_symlinks: bool,
+    #[argh(option)]
+    /// run the command through the unix socket of a bash_map serve, on the map it serves
+    connect: Option<PathBuf>,
+    #[argh(option)]
+    /// use the named store kept in the data directory ($XDG_DATA_HOME/bash_map) as --file, created on first use
+    store: Option<String>,
+    #[argh(switch)]
+    /// keep running a read command on a @file or --file input, printing it again whenever the file changes
+    watch: bool,
+    #[argh(switch)]
+    /// like --watch, but only print when the output differs from the previous print
+    watch_changed_only: bool,
+    #[argh(switch)]
+    /// let get read a @file or --file input up to the pointer instead of parsing it completely, done automatically for stdin and files over 64 MiB, never with --dedup-check or --decode-unicode-keys
+    stream: bool,
+    #[argh(switch)]
+    /// fail when an input contains an object with the same key more than once
+    dedup_check: bool,
+    #[argh(switch)]
+    /// print object keys sorted instead of in the order of the input
+    sort_keys: bool,
+    #[argh(switch)]
+    /// with --sort-keys compare the digits in keys by their value, so key2 comes before key10
+    natural: bool,
+    #[argh(option)]
+    /// print object keys in the order of this spec, like @order.json holding {"$order": ["name", "version"], "server": {"$order": ["host"]}}, keys that are not listed follow sorted
+    key_order: Option<String>,
+    #[argh(option, default = "input::Format::Json")]
+    /// syntax of the inputs: json (default), jsonc allowing comments and trailing commas, or json5
+    input_format: input::Format,
+    #[argh(option, default = "output::Format::Json")]
+    /// syntax of the output: json (default) or json5
+    output_format: output::Format,
+    #[argh(option, default = "depth::DEFAULT_MAX_DEPTH")]
+    /// how deep arrays and objects may be nested in the inputs and results (default 128)
+    max_depth: usize,
+    #[argh(switch)]
+    /// start the output with a byte order mark when an input started with one
+    keep_bom: bool,
+    #[argh(switch)]
+    /// accept comments and trailing commas in the inputs, same as --input-format jsonc
+    lenient_input: bool,
+    #[argh(switch, short = '0')]
+    /// end every printed item with a NUL byte instead of a newline, like find -print0, strings in get --lines and each are printed without quotes
+    print0: bool,
+    #[argh(option)]
+    /// round floats in the json output to at most this many decimals, halves away from zero, trailing zeros are left out; integers stay as they are
+    float_precision: Option<usize>,
+    #[argh(switch)]
+    /// write floats rounded by --float-precision with exactly that many decimals
+    keep_trailing_zeros: bool,
+    #[argh(switch)]
+    /// write every character outside of printable ASCII in the json output as a \uXXXX escape
+    escape_unicode: bool,
+    #[argh(switch)]
+    /// decode \uXXXX escapes that are still in the object keys of the inputs after parsing, like keys of json that was escaped twice
+    decode_unicode_keys: bool,
+    #[argh(switch)]
+    /// fail when a map or value argument names an environment variable that is not set or does not contain valid json, instead of using {}
+    strict_env: bool,
+    #[argh(switch)]
+    /// do not read a map or value argument from the file it names when it is neither valid json nor a set environment variable
+    no_file_fallback: bool,
+    #[argh(switch)]
+    /// ignore the BASH_MAP_STRICT environment variable
+    no_strict: bool,
+    #[argh(switch, short = 'q')]
+    /// print nothing on stdout, only the exit code and errors on stderr tell the result
+    quiet: bool,
+    #[argh(switch, short = 'v')]
+    /// describe on stderr where the input was read from, how the pointer was resolved and how many places set and delete changed, give it twice to log every step
+    verbose: u8,
 }
 
-#[derive(FromArgs, PartialEq, Debug)]
+#[derive(FromArgs, PartialEq, Debug, Clone)]
 #[argh(subcommand)]
 enum MySubCommandEnum {
     Init(SubCommandInit),
+    Uuid(SubCommandUuid),
+    Env(env::SubCommandEnv),
     Get(SubCommandGet),
     Set(SubCommandSet),
+    Delete(SubCommandDelete),
     Compare(SubCommandCompare),
+    HasValue(SubCommandHasValue),
+    Test(SubCommandTest),
+    IndexOf(SubCommandIndexOf),
     Type(SubCommandType),
+    Validate(SubCommandValidate),
+    StoreList(store::SubCommandStoreList),
+    StoreDelete(store::SubCommandStoreDelete),
+    FrontMatter(front_matter::SubCommandFrontMatter),
+    PruneNulls(prune::SubCommandPruneNulls),
+    PruneEmpty(prune::SubCommandPruneEmpty),
+    Normalize(normalize::SubCommandNormalize),
+    SortBy(sort::SubCommandSortBy),
+    Transpose(transpose::SubCommandTranspose),
+    Upsert(upsert::SubCommandUpsert),
+    Splice(splice::SubCommandSplice),
+    RenameKeys(rename::SubCommandRenameKeys),
+    Str(strings::SubCommandStr),
+    Trim(strings::SubCommandTrim),
+    Grep(grep::SubCommandGrep),
+    Jq(jq::SubCommandJq),
+    #[cfg(feature = "jmespath")]
+    Jp(jp::SubCommandJp),
+    Sub(strings::SubCommandSub),
+    Substr(strings::SubCommandSubstr),
+    Canonicalize(jcs::SubCommandCanonicalize),
+    Redact(redact::SubCommandRedact),
+    Stats(stats::SubCommandStats),
+    Depth(depth::SubCommandDepth),
+    Size(size::SubCommandSize),
+    Table(table::SubCommandTable),
+    FromXml(xml::SubCommandFromXml),
+    ToXml(xml::SubCommandToXml),
+    FromIni(ini::SubCommandFromIni),
+    ToIni(ini::SubCommandToIni),
+    FromProperties(properties::SubCommandFromProperties),
+    ToProperties(properties::SubCommandToProperties),
+    ToBashArray(bash::SubCommandToBashArray),
+    Export(bash::SubCommandExport),
+    ShellQuote(bash::SubCommandShellQuote),
+    Each(each::SubCommandEach),
+    Apply(apply::SubCommandApply),
+    Stringify(embed::SubCommandStringify),
+    ParseEmbedded(embed::SubCommandParseEmbedded),
+    FixEncoding(embed::SubCommandFixEncoding),
+    Leaves(leaves::SubCommandLeaves),
+    Gron(gron::SubCommandGron),
+    Ungron(gron::SubCommandUngron),
+    Merge(merge::SubCommandMerge),
+    Resolve(merge::SubCommandResolve),
+    Defaults(merge::SubCommandDefaults),
+    Merge3(merge::SubCommandMerge3),
+    ResolveConflicts(merge::SubCommandResolveConflicts),
+    Serve(serve::SubCommandServe),
+    Ndjson(ndjson::SubCommandNdjson),
+    ArrayUnion(sets::SubCommandArrayUnion),
+    ArrayIntersect(sets::SubCommandArrayIntersect),
+    ArrayDiff(sets::SubCommandArrayDiff),
+    Diff(diff::SubCommandDiff),
+    Patch(patch::SubCommandPatch),
 }
 
-#[derive(FromArgs, PartialEq, Debug)]
-/// Create empty map
-#[argh(subcommand, name = "init")]
-struct SubCommandInit {}
+impl MySubCommandEnum {
+    fn is_mutating(&self) -> bool {
+        use MySubCommandEnum::*;
+
+        match self {
+            Set(args) => !args.dry_run,
+            Delete(args) => !args.dry_run,
+            Splice(args) => !args.print_removed,
+            Merge(args) => !args.dry_run,
+            Substr(args) => !args.print_only,
+            Init(_) | Upsert(_) | Apply(_) | Patch(_) | ResolveConflicts(_) => true,
+            Stringify(_) | ParseEmbedded(_) | FixEncoding(_) => true,
+            PruneNulls(_) | PruneEmpty(_) | Normalize(_) | SortBy(_) | Transpose(_) => true,
+            RenameKeys(_) | Str(_) | Trim(_) | Sub(_) | Defaults(_) => true,
+            _ => false,
+        }
+    }
+
+    /// The first map argument given as `@path`.
+    fn input_file(&self) -> Option<&str> {
+        use MySubCommandEnum::*;
+
+        let variables = match self {
+            Get(args) => vec![&args.variable],
+            Set(args) => vec![&args.variable],
+            Delete(args) => vec![&args.variable],
+            Type(args) => vec![&args.variable],
+            Compare(args) => vec![&args.first, &args.second],
+            ArrayUnion(args) => vec![&args.first, &args.second],
+            ArrayIntersect(args) => vec![&args.first, &args.second],
+            ArrayDiff(args) => vec![&args.first, &args.second],
+            Diff(args) => vec![&args.first, &args.second],
+            Patch(args) => vec![&args.variable],
+            HasValue(args) => vec![&args.variable],
+            Test(args) => vec![&args.variable],
+            IndexOf(args) => vec![&args.variable],
+            PruneNulls(args) => vec![&args.variable],
+            PruneEmpty(args) => vec![&args.variable],
+            Normalize(args) => vec![&args.variable],
+            SortBy(args) => vec![&args.variable],
+            Transpose(args) => vec![&args.variable],
+            Upsert(args) => vec![&args.variable],
+            Splice(args) => vec![&args.variable],
+            RenameKeys(args) => vec![&args.variable],
+            Str(args) => vec![&args.variable],
+            Trim(args) => vec![&args.variable],
+            Grep(args) => vec![&args.variable],
+            Jq(args) => vec![&args.variable],
+            #[cfg(feature = "jmespath")]
+            Jp(args) => vec![&args.variable],
+            Sub(args) => vec![&args.variable],
+            Substr(args) => vec![&args.variable],
+            Canonicalize(args) => vec![&args.variable],
+            Redact(args) => vec![&args.variable],
+            Stats(args) => vec![&args.variable],
+            Depth(args) => vec![&args.variable],
+            Size(args) => vec![&args.variable],
+            Table(args) => vec![&args.variable],
+            FromXml(args) => vec![&args.variable],
+            ToXml(args) => vec![&args.variable],
+            FromIni(args) => vec![&args.variable],
+            ToIni(args) => vec![&args.variable],
+            FromProperties(args) => vec![&args.variable],
+            ToProperties(args) => vec![&args.variable],
+            ToBashArray(args) => vec![&args.variable],
+            Export(args) => vec![&args.variable],
+            ShellQuote(args) => vec![&args.variable],
+            Each(args) => vec![&args.variable],
+            Apply(args) => vec![&args.variable],
+            Stringify(args) => vec![&args.variable],
+            ParseEmbedded(args) => vec![&args.variable],
+            FixEncoding(args) => vec![&args.variable],
+            Leaves(args) => vec![&args.variable],
+            Gron(args) => vec![&args.variable],
+            Ungron(args) => vec![&args.variable],
+            Merge(args) => args.variables.iter().collect(),
+            Defaults(args) => vec![&args.variable, &args.defaults],
+            Merge3(args) => vec![&args.base, &args.ours, &args.theirs],
+            ResolveConflicts(args) => vec![&args.variable],
+            Resolve(args) => {
+                let path = args.sources.iter().find_map(|x| x.strip_prefix('@'));
+                return path.map(|x| x.strip_suffix('?').unwrap_or(x));
+            }
+            Validate(args) => vec![&args.variable],
+            _ => Vec::new(),
+        };
 
-#[derive(FromArgs, PartialEq, Debug)]
+        variables.into_iter().find_map(|x| x.strip_prefix('@'))
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "init",
+    description = "Create empty map, or a map holding the given key=value pairs",
+    note = "The key is everything up to the first =, the value is read like the value of set. A key like server.port or /server/port nests, pointer keys are needed for keys with a dot in them.
+With --from-env PREFIX every environment variable starting with PREFIX is added, without the prefix, lowercased and nested on --separator, so APP_DB_HOST=db gives {{\"db\":{{\"host\":\"db\"}}}}. Values are json or else strings. Two variables where one would be a value and the other a key inside it fail, key=value pairs are set after the variables.",
+    example = r#"arguments                          output
+name='"svc"' server.port=8080      {{"name":"svc","server":{{"port":8080}}}}
+--lenient name=svc tags='["a"]'    {{"name":"svc","tags":["a"]}}"#
+)]
+struct SubCommandInit {
+    #[argh(positional, from_str_fn(seed_from_str))]
+    seeds: Vec<(String, String)>,
+    #[argh(switch)]
+    /// use values that are not json as strings
+    lenient: bool,
+    #[argh(option)]
+    /// add the environment variables starting with this prefix
+    from_env: Option<String>,
+    #[argh(option)]
+    /// split the names of --from-env into nested keys on this (default _)
+    separator: Option<String>,
+}
+
+/// Splits `key=value` on the first `=`, the key becomes a pointer.
+fn seed_from_str(input: &str) -> Result<(String, String), String> {
+    let (key, value) = input
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got {:?}", input))?;
+    let pointer = match key.starts_with('/') {
+        true => key.to_string(),
+        false => pointer_of(key.split('.')),
+    };
+    Ok((pointer, value.to_string()))
+}
+
+fn init(args: &SubCommandInit, pretty: bool) -> Result<String, exit::Error> {
+    if args.separator.is_some() && args.from_env.is_none() {
+        return Err(exit::usage(String::from(
+            "--separator only applies to --from-env",
+        )));
+    }
+    if args.seeds.is_empty() && args.from_env.is_none() {
+        return Ok(String::from("{}"));
+    }
+    let mut map = Value::Object(serde_json::Map::new());
+    if let Some(prefix) = &args.from_env {
+        let separator = args.separator.as_deref().unwrap_or("_");
+        for (pointer, value) in from_env(prefix, separator)? {
+            *resolve_mut(&mut map, &pointer, false)? = value;
+        }
+    }
+    for (pointer, text) in &args.seeds {
+        let literal = !text.starts_with('@') && !text.starts_with("env:");
+        let value = match value_from_str(text) {
+            Err(_) if args.lenient && literal => Value::String(text.clone()),
+            _ => read_value(text, false, false)?,
+        };
+        *resolve_mut(&mut map, pointer, false)? = value;
+    }
+    Ok(value_printer(pretty, &map))
+}
+
+/// The pointers and values of the environment variables starting with `prefix`,
+/// fails when one variable would be a value and another a key inside of it.
+fn from_env(prefix: &str, separator: &str) -> Result<Vec<(String, Value)>, exit::Error> {
+    if separator.is_empty() {
+        return Err(exit::usage(String::from("--separator can not be empty")));
+    }
+    let mut found: Vec<(Vec<String>, String, String)> = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .filter_map(|(name, value)| {
+            let rest = name.strip_prefix(prefix)?;
+            if rest.is_empty() {
+                return None;
+            }
+            let keys = rest.split(separator).map(str::to_lowercase).collect();
+            Some((keys, name, value))
+        })
+        .collect();
+    found.sort();
+
+    if let Some(pair) = found
+        .windows(2)
+        .find(|pair| pair[1].0.starts_with(&pair[0].0))
+    {
+        return Err(format!(
+            "{} and {} can not both be set, {} would be a value and an object",
+            pair[0].1,
+            pair[1].1,
+            pointer_of(pair[0].0.iter().map(String::as_str))
+        )
+        .into());
+    }
+    Ok(found
+        .into_iter()
+        .map(|(keys, _, value)| {
+            let value = from_str(&value).unwrap_or(Value::String(value));
+            (pointer_of(keys.iter().map(String::as_str)), value)
+        })
+        .collect())
+}
+
+/// The pointer to the nested keys.
+fn pointer_of<'a>(keys: impl Iterator<Item = &'a str>) -> String {
+    keys.map(|key| format!("/{}", pointer::escape(key)))
+        .collect()
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+/// Print a new random (version 4) uuid
+#[argh(subcommand, name = "uuid")]
+struct SubCommandUuid {}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
 /// Check the current variable on json type
 #[argh(subcommand, name = "type")]
 struct SubCommandType {
@@ -73,40 +589,198 @@ struct SubCommandType {
     variable: String,
 }
 
-#[derive(FromArgs, PartialEq, Debug)]
-/// Compare the two jsons
-#[argh(subcommand, name = "compare")]
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+/// Check that the variable is valid json without duplicate keys
+#[argh(subcommand, name = "validate")]
+struct SubCommandValidate {
+    #[argh(positional)]
+    variable: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "compare",
+    description = "Compare the two jsons",
+    note = "Object keys can be in any order and numbers are compared by value, so 1, 1.0 and 1e0 are equal. With --strict-numbers numbers are only equal when they are written the same, which tells an integer 1 from a float 1.0."
+)]
 struct SubCommandCompare {
     #[argh(positional)]
     first: String,
     #[argh(positional)]
     second: String,
+    #[argh(switch)]
+    /// compare numbers as they are written instead of by value
+    strict_numbers: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "delete",
+    description = "Remove the key or array item at the given pointer",
+    note = "With --print-removed the removed value is printed as compact json instead of the map, or every removed value on its own line for a --wildcard pointer, and it exits with 1 without printing anything when nothing is removed. In file mode the map is written back to the --file as usual, so `bash_map --file state.json delete /job --print-removed` pops a key.",
+    example = r#"input                         arguments                   output
+{{"test": 1, "other": 2}}       "/test"                     {{"other":2}}
+{{"test": [1, 2, 3]}}           "/test/1"                   {{"test":[1,3]}}
+{{"test": [{{"a": 1}}, {{}}]}}      "/test/*/a" --wildcard      {{"test":[{{}},{{}}]}}"#
+)]
+struct SubCommandDelete {
+    #[argh(positional)]
+    variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(switch)]
+    /// treat `*` tokens in the pointer as matching every key or index, removing every match
+    wildcard: bool,
+    #[argh(switch)]
+    /// fail when nothing is removed
+    strict: bool,
+    #[argh(switch)]
+    /// print the removed value instead of the map, with --file the map is still written back
+    print_removed: bool,
+    #[argh(switch)]
+    /// print a removed string without quotes
+    raw: bool,
+    #[argh(switch)]
+    /// print what would change as `pointer: old -> new` lines instead of the map, exits with 1 when nothing would change, a --file is left untouched
+    dry_run: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "has-value",
+    description = "Check that the array or object at the pointer contains the value",
+    note = "Fails like compare when the value is not found. Strings can be searched with --substring."
+)]
+struct SubCommandHasValue {
+    #[argh(switch)]
+    /// check whether the string at the pointer contains the value
+    substring: bool,
+    #[argh(positional)]
+    variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional, from_str_fn(value_from_str))]
+    value: Value,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "test",
+    description = "Check that the value at the pointer equals the value",
+    note = "Prints nothing and exits with 0 when the values are equal, like compare objects are equal with their keys in any order and numbers by value. Otherwise it fails with 1 and shows both values, a pointer that does not exist always fails.
+The value is given like the value of set, so it can be @path or env:NAME, and --string takes an env:NAME variable as a string.",
+    example = r#"input                      arguments                 exit code
+{{"status": "ready"}}        /status '"ready"'         0
+{{"status": "ready"}}        /status '"ready"' --not   1
+{{"port": 80}}               /port 80.0                0"#
+)]
+struct SubCommandTest {
+    #[argh(positional)]
+    variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional)]
+    value: String,
+    #[argh(switch)]
+    /// fail when the values are equal instead
+    not: bool,
+    #[argh(switch)]
+    /// take the text of an env:NAME value as a string instead of parsing it as json
+    string: bool,
 }
 
-#[derive(FromArgs, PartialEq, Debug)]
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "index-of",
+    description = "Print the index of the first item in the array at the pointer equal to the value",
+    note = "Exits with 1 without printing anything when no item matches.",
+    example = r#"input                            arguments             output
+{{"items": [{{"id": 1}}, {{"id": 3}}]}}  /items '{{"id":3}}'     1
+{{"items": [1, 2, 1]}}             /items 1 --all        [0,2]
+{{"items": [{{"id": 1}}, {{"id": 3}}]}}  /items --by /id=3     1"#
+)]
+struct SubCommandIndexOf {
+    #[argh(switch)]
+    /// print the indices of all matching items as a json array
+    all: bool,
+    #[argh(option, from_str_fn(field_from_str))]
+    /// match items on the field at a pointer instead of the whole item, like /id=3,
+    /// the value is json or else a string
+    by: Option<(Pointer, Value)>,
+    #[argh(positional)]
+    variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional, from_str_fn(value_from_str))]
+    value: Option<Value>,
+}
+
+/// Parses `pointer=value`, the value is json or else a string.
+fn field_from_str(input: &str) -> Result<(Pointer, Value), String> {
+    let (pointer, value) = input
+        .split_once('=')
+        .ok_or_else(|| format!("expected pointer=value, got {:?}", input))?;
+    let value = from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+
+    let pointer = pointer.parse().map_err(|e: exit::Error| e.message)?;
+    Ok((pointer, value))
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
 #[argh(
     subcommand,
     name = "get",
     description = "Get item from the map with given json pointer",
-    note = "Check https://tools.ietf.org/html/rfc6901 for the spec on json pointer",
+    note = "Check https://tools.ietf.org/html/rfc6901 for the spec on json pointer.
+A last token like `start:end` on an array selects the items from start up to end, either bound can be left out and negative bounds count from the end. On objects it is a key like any other.
+With --lines the items of the array are printed as compact json one per line, nothing is printed for an empty array. Combine it with --print0 to get NUL terminated items with strings unquoted.
+With --project any number of pointers can be given and their values are printed as one object, so /a /b/c gives {{\"a\":...,\"c\":...}}. Pointers with the same last token fail unless --full-keys is given, missing pointers are left out or fail with --strict.",
     example = r#"input                        pointer           output
 {{"test": "input"}}            "/test"           "input"
 {{"test": [1, 2, 3, 4]}}       "/test/2"         3
-{{"test": [{{"sub": ["ok"]}}]}}  "/test/0/sub/0"   "ok""#
+{{"test": [{{"sub": ["ok"]}}]}}  "/test/0/sub/0"   "ok"
+{{"test": [1, 2, 3, 4]}}       "/test/1:3"       [2,3]
+{{"test": [1, 2, 3, 4]}}       "/test/-2:"       [3,4]"#
 )]
 struct SubCommandGet {
     #[argh(positional)]
     variable: String,
     #[argh(positional)]
     pointer: Pointer,
+    #[argh(positional)]
+    pointers: Vec<Pointer>,
+    #[argh(switch)]
+    /// print every item of the array at the pointer on its own line
+    lines: bool,
+    #[argh(switch)]
+    /// fail when nothing is found at the pointer instead of printing an empty line
+    strict: bool,
+    #[argh(switch)]
+    /// print the values at all the pointers as one object, keyed by the last token of each pointer
+    project: bool,
+    #[argh(switch)]
+    /// key the --project object by the whole pointers instead of their last tokens
+    full_keys: bool,
 }
 
-#[derive(FromArgs, PartialEq, Debug)]
+#[derive(FromArgs, PartialEq, Debug, Clone)]
 #[argh(
     subcommand,
     name = "set",
     description = "Set the value or the object in variable at the given pointer",
-    note = "Check https://tools.ietf.org/html/rfc6901 for the spec on json pointer",
+    note = "Check https://tools.ietf.org/html/rfc6901 for the spec on json pointer.
+With --now the current time is set instead of a value, as UTC text like 2024-05-01T12:30:00Z or with --now-format unix or unix-ms as a number.
+With --uuid a new random uuid is set instead of a value, a different one at every --wildcard match.
+A pointer with an index past the end of an array leaves the map unchanged, or fails with --strict. With --pad the array is extended with null up to the index, and missing arrays are created, so /list/2 on {{}} gives {{\"list\":[null,null,value]}}.
+Numbers are printed as they were written, both the value and the numbers of the map that are left alone, so 1.0 stays 1.0 and 1E3 stays 1E3.
+A value starting with @ is read from the file at the path after it, start it with @@ for a value that starts with a literal @.
+A value env:NAME is read from the environment variable NAME, which has to be set.
+With --value-stdin the value is read from stdin up to the end, so it can not be combined with a map read from stdin. With --string the text of stdin or of the env:NAME variable is set as a string instead of parsed as json, without the single line break at its end unless --keep-trailing-newline is given.",
     example = r#"input                    pointer          value      output
 {{"test": "input"}}        "/test"          "input"    {{"test":"input"}}
 {{}}                       "/test"          "input"    {{"test":"input"}}
@@ -117,154 +791,1244 @@ struct SubCommandSet {
     variable: String,
     #[argh(positional)]
     pointer: Pointer,
-    #[argh(positional, from_str_fn(value_from_str))]
-    value: Value,
+    #[argh(positional)]
+    value: Option<String>,
+    #[argh(switch)]
+    /// read the value from stdin instead of the command line
+    value_stdin: bool,
+    #[argh(switch)]
+    /// take the --value-stdin or env:NAME text as a string instead of parsing it as json
+    string: bool,
+    #[argh(switch)]
+    /// keep the line break at the end of a --string value instead of trimming it
+    keep_trailing_newline: bool,
+    #[argh(switch)]
+    /// set the current time instead of a value
+    now: bool,
+    #[argh(option)]
+    /// how --now writes the time: rfc3339 (default), unix or unix-ms
+    now_format: Option<now::Format>,
+    #[argh(switch)]
+    /// set a new random uuid instead of a value
+    uuid: bool,
+    #[argh(switch)]
+    /// treat `*` tokens in the pointer as matching every key or index, setting the value at every match
+    wildcard: bool,
+    #[argh(switch)]
+    /// fail when a --wildcard pointer matches nothing or the pointer can not be set,
+    /// like an array index past the end, instead of printing the map unchanged
+    strict: bool,
+    #[argh(switch)]
+    /// extend arrays with null up to an index past the end, at most index 1000000, and create arrays for index tokens
+    pad: bool,
+    #[argh(switch)]
+    /// print what would change as `pointer: old -> new` lines instead of the map, exits with 1 when nothing would change, a --file is left untouched
+    dry_run: bool,
 }
 
 fn value_from_str(input: &str) -> Result<Value, String> {
-    from_str(input).map_err(|x| x.to_string())
+    let text = input::strip_bom(input);
+    let mut value = from_str(text).map_err(|e| input::value_error(text, &e))?;
+    number::keep_written(text, &mut value);
+    Ok(value)
+}
+
+/// The json text of a `set` or `test` value given on the command line, where a leading `@@`
+/// stands for a literal `@`. `None` for a `@path` or `env:NAME` value.
+fn literal_value(input: &str) -> Option<&str> {
+    if input.starts_with("env:") {
+        return None;
+    }
+    match input.strip_prefix('@') {
+        Some(text) if text.starts_with('@') => Some(text),
+        Some(_) => None,
+        None => Some(input),
+    }
+}
+
+/// Checks a literal value of `set` or `test` before anything is read, so a mistake is shown
+/// with its caret and hint, like the errors of argh. A `@path` or `env:NAME` value is only
+/// read when the command runs.
+fn check_value(command: &MySubCommandEnum) -> Result<(), String> {
+    let value = match command {
+        MySubCommandEnum::Set(args) => args.value.as_deref(),
+        MySubCommandEnum::Test(args) => Some(args.value.as_str()),
+        _ => None,
+    };
+    match value.and_then(literal_value) {
+        Some(text) => value_from_str(text).map(drop),
+        None => Ok(()),
+    }
+}
+
+/// The value of a `set` or `test` argument, read from the file of a `@path` argument,
+/// where a leading `@@` stands for a literal `@`, or from the environment variable of
+/// an `env:NAME` argument, which is taken as a string with `string`.
+fn read_value(
+    input: &str,
+    string: bool,
+    keep_trailing_newline: bool,
+) -> Result<Value, exit::Error> {
+    if let Some(name) = input.strip_prefix("env:") {
+        let text = std::env::var(name).map_err(|e| match e {
+            std::env::VarError::NotPresent => input::not_set(name),
+            e => exit::parse(format!("environment variable {}: {}", name, e)),
+        })?;
+        return match string {
+            true => Ok(string_value(text, keep_trailing_newline)),
+            false => value_from_str(&text)
+                .map_err(|e| exit::parse(format!("environment variable {} contains {}", name, e))),
+        };
+    }
+    if let Some(text) = literal_value(input) {
+        return value_from_str(text).map_err(exit::usage);
+    }
+    let path = &input[1..];
+    let text = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => exit::io(format!("value file {} not found", path)),
+        _ => exit::io(format!("unable to read value file {}: {}", path, e)),
+    })?;
+    let text = input::strip_bom(&text);
+    let mut value: Value = from_str(text)
+        .map_err(|e| exit::parse(format!("invalid json in value file {}: {}", path, e)))?;
+    number::keep_written(text, &mut value);
+    Ok(value)
+}
+
+fn parse_args() -> TopLevel {
+    let strings: Vec<String> = std::env::args().collect();
+    let cmd = Path::new(&strings[0])
+        .file_name()
+        .and_then(|x| x.to_str())
+        .unwrap_or(&strings[0]);
+    let args = insert_file_argument(strings[1..].to_vec()).unwrap_or_else(|e| {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(e.code)
+    });
+    let strs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+    let arg = TopLevel::from_args(&[cmd], &strs).unwrap_or_else(|early_exit| {
+        std::process::exit(match early_exit.status {
+            Ok(()) => {
+                println!("{}", early_exit.output);
+                0
+            }
+            Err(()) => {
+                eprintln!(
+                    "{}\nRun {} --help for more information.",
+                    early_exit.output, cmd
+                );
+                exit::USAGE
+            }
+        })
+    });
+    if let Err(e) = check_value(&arg.command) {
+        eprintln!(
+            "Error parsing the value: {}\nRun {} --help for more information.",
+            e, cmd
+        );
+        std::process::exit(exit::USAGE)
+    }
+    arg
+}
+
+/// In file mode the variable positional is omitted on the command line,
+/// so the file is passed on to the subcommand as a `@path` variable.
+/// A `--backup=SUFFIX` is passed on as `--backup-suffix SUFFIX`, a repeated short flag
+/// like `-vv` as `-v -v`, and the `-U3` of diff as `-U 3`.
+fn insert_file_argument(mut args: Vec<String>) -> Result<Vec<String>, exit::Error> {
+    let mut file = None;
+    let mut index = 0;
+
+    while let Some(arg) = args.get(index) {
+        if let Some(suffix) = arg.strip_prefix("--backup=") {
+            let suffix = suffix.to_string();
+            args.splice(index..=index, [String::from("--backup-suffix"), suffix]);
+            index += 2;
+        } else if let Some(flags) = repeated_short_flag(arg) {
+            let count = flags.len();
+            let flag = format!("-{}", &flags[..1]);
+            args.splice(index..=index, std::iter::repeat_n(flag, count));
+            index += count;
+        } else if GLOBAL_OPTIONS.contains(&arg.as_str()) {
+            match (arg.as_str(), args.get(index + 1)) {
+                // the socket only fills in the map argument, the server has the map
+                ("--file" | "--connect", Some(path)) => file = Some(path.clone()),
+                ("--store", Some(name)) => {
+                    file = Some(store::store_path(name)?.to_string_lossy().into_owned())
+                }
+                _ => (),
+            }
+            index += 2;
+        } else if arg.starts_with('-') {
+            index += 1;
+        } else {
+            break;
+        }
+    }
+
+    if let (Some(path), Some(command)) = (file, args.get(index)) {
+        if FILE_COMMANDS.contains(&command.as_str()) {
+            args.insert(index + 1, format!("@{}", path));
+        } else if OPERATION_FILE_COMMANDS.contains(&command.as_str()) && args.len() > index + 1 {
+            args.insert(index + 2, format!("@{}", path));
+        }
+    }
+    if args.get(index).is_some_and(|x| x == "diff") {
+        split_context_lines(&mut args, index + 1);
+    }
+    Ok(args)
+}
+
+/// Splits a `-U<N>` of diff into `-U N`, up to a `--`.
+fn split_context_lines(args: &mut Vec<String>, mut index: usize) {
+    while let Some(arg) = args.get(index) {
+        if arg == "--" {
+            break;
+        }
+        let lines = arg
+            .strip_prefix("-U")
+            .filter(|x| !x.is_empty() && x.bytes().all(|b| b.is_ascii_digit()));
+        if let Some(lines) = lines {
+            let lines = lines.to_string();
+            args.splice(index..=index, [String::from("-U"), lines]);
+            index += 1;
+        }
+        index += 1;
+    }
+}
+
+/// The letters of an argument like `-vv` that repeats one short flag.
+fn repeated_short_flag(arg: &str) -> Option<&str> {
+    let flags = arg.strip_prefix('-')?;
+    let first = flags.chars().next()?;
+    match flags.len() > 1 && first.is_ascii_alphabetic() && flags.chars().all(|x| x == first) {
+        true => Some(flags),
+        false => None,
+    }
+}
+
+fn main() {
+    if let Err(e) = start() {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(e.code);
+    }
+}
+
+fn start() -> Result<(), exit::Error> {
+    let mut arg = parse_args();
+    if let Some(name) = &arg.store {
+        if arg.file.is_some() {
+            return Err(exit::usage(String::from(
+                "--store and --file can not be combined",
+            )));
+        }
+        arg.file = Some(store::store_path(name)?);
+        arg.create = true;
+    }
+
+    if arg.key_order.is_some() && arg.sort_keys {
+        return Err(exit::usage(String::from(
+            "--key-order can not be combined with --sort-keys",
+        )));
+    }
+    if arg.natural && !arg.sort_keys {
+        return Err(exit::usage(String::from(
+            "--natural only applies to --sort-keys",
+        )));
+    }
+    if arg.keep_trailing_zeros && arg.float_precision.is_none() {
+        return Err(exit::usage(String::from(
+            "--keep-trailing-zeros only applies to --float-precision",
+        )));
+    }
+    input::configure(input::Options {
+        dedup_check: arg.dedup_check,
+        format: match arg.lenient_input {
+            true => input::Format::Jsonc,
+            false => arg.input_format,
+        },
+        max_depth: match arg.command {
+            // depth is the guard for deep maps, so it has to read them
+            MySubCommandEnum::Depth(_) => usize::MAX,
+            _ => arg.max_depth,
+        },
+        decode_unicode_keys: arg.decode_unicode_keys,
+        strict: !arg.no_strict && input::strict_requested(),
+        strict_env: arg.strict_env,
+        no_file_fallback: arg.no_file_fallback,
+    });
+    verbose::configure(arg.verbose);
+    if arg.backup_suffix.as_deref() == Some("") {
+        return Err(exit::usage(String::from(
+            "the --backup suffix can not be empty",
+        )));
+    }
+    file::configure(file::Options {
+        backup: match (&arg.backup_suffix, arg.backup) {
+            (Some(suffix), _) => Some(suffix.clone()),
+            (None, true) => Some(String::from("~")),
+            (None, false) => None,
+        },
+        no_follow_symlinks: arg.no_follow_symlinks,
+    });
+    let key_order = match &arg.key_order {
+        Some(spec) => {
+            let value = input::Source::of(spec).read()?.ok_or_else(|| {
+                exit::usage(format!("--key-order {:?} is not a json object", spec))
+            })?;
+            output::check_key_order(&value, "").map_err(exit::usage)?;
+            Some(value)
+        }
+        None => None,
+    };
+    output::configure(output::Options {
+        sort_keys: arg.sort_keys,
+        natural: arg.natural,
+        key_order,
+        format: arg.output_format,
+        keep_bom: arg.keep_bom,
+        print0: arg.print0,
+        quiet: arg.quiet,
+        escape_unicode: arg.escape_unicode,
+        float_precision: arg.float_precision,
+        keep_trailing_zeros: arg.keep_trailing_zeros,
+    });
+
+    if let Some(socket) = &arg.connect {
+        if arg.file.is_some() {
+            return Err(exit::usage(String::from(
+                "--connect can not be combined with --file or --store",
+            )));
+        }
+        let output_options = [
+            ("--sort-keys", arg.sort_keys),
+            ("--natural", arg.natural),
+            ("--key-order", arg.key_order.is_some()),
+            ("--output-format", arg.output_format != output::Format::Json),
+            ("--escape-unicode", arg.escape_unicode),
+            ("--float-precision", arg.float_precision.is_some()),
+            ("--keep-trailing-zeros", arg.keep_trailing_zeros),
+        ];
+        if let Some((name, _)) = output_options.iter().find(|(_, given)| *given) {
+            return Err(exit::usage(format!(
+                "{} can not be combined with --connect, give it to the serve command instead",
+                name
+            )));
+        }
+        return match serve::connect(socket, &connect_arguments()) {
+            Ok(Some(output)) => {
+                print_output(&arg, &output);
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(e) if e.message.is_empty() => std::process::exit(e.code),
+            Err(e) => Err(e),
+        };
+    }
+    if let MySubCommandEnum::Serve(args) = &arg.command {
+        return serve::serve(&arg, args);
+    }
+    if arg.watch || arg.watch_changed_only {
+        return watch::watch(&arg);
+    }
+
+    match run(&arg) {
+        Ok(Some(output)) => print_output(&arg, &output),
+        Ok(None) => (),
+        Err(e) if e.message.is_empty() => std::process::exit(e.code),
+        Err(e) => return Err(e),
+    }
+
+    Ok(())
 }
 
-fn main() -> Result<(), String> {
+/// The command line without `--connect SOCKET`, for the server to run.
+fn connect_arguments() -> Vec<String> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(index) = args.iter().position(|x| x == "--connect") {
+        args.drain(index..(index + 2).min(args.len()));
+    }
+    args
+}
+
+/// Runs the subcommand once, returning the output to print.
+/// An empty error fails without a message, like `index-of` not finding the value.
+///
+/// In file mode the file is locked for the duration of the run and the output
+/// of mutating commands is written back to the file instead.
+fn run(arg: &TopLevel) -> Result<Option<String>, exit::Error> {
     use MySubCommandEnum::*;
-    let arg: TopLevel = argh::from_env();
-
-    let output = match arg.command {
-        Init(_) => String::from("{}"),
-        Get(args) => do_get(args, arg.pretty),
-        Set(args) => do_set(args, arg.pretty),
-        Type(args) => do_type(args),
-        Compare(args) => match do_compare(args) {
-            Ok(_) => "true".to_string(),
-            Err(_) => return Err("false".to_string())
+    let mutating = arg.command.is_mutating();
+
+    let _lock = match &arg.file {
+        Some(path) if !arg.no_lock => {
+            let timeout = arg
+                .lock_timeout
+                .map(std::time::Duration::try_from_secs_f64)
+                .transpose()
+                .map_err(|_| {
+                    exit::usage(String::from(
+                        "--lock-timeout must be a non-negative number of seconds",
+                    ))
+                })?;
+            match file::lock(path, mutating || arg.create, timeout) {
+                Ok(lock) => Some(lock),
+                Err(file::LockError::Timeout) => {
+                    eprintln!(
+                        "Error: timed out waiting for the lock on {}",
+                        path.display()
+                    );
+                    std::process::exit(file::LOCK_TIMEOUT_EXIT_CODE)
+                }
+                Err(file::LockError::Io(e)) => return Err(exit::io(e)),
+            }
+        }
+        _ => None,
+    };
+
+    if let Some(path) = &arg.file {
+        if !path.exists() {
+            if arg.create || mutating {
+                file::write_atomic(path, "{}")?;
+            } else {
+                return Err(exit::io(format!("file {} does not exist", path.display())));
+            }
+        }
+    }
+
+    let output = match arg.command.clone() {
+        Init(args) => init(&args, arg.pretty)?,
+        Uuid(_) => new_uuid(),
+        Env(args) => env::do_env(args, arg.pretty)?,
+        Get(args) => {
+            check_get(&args)?;
+            let output = match stream_source(arg, &args) {
+                Some(path) => {
+                    let found = stream_get_value(path, &args.pointer)?;
+                    get_output(&args, found, arg.pretty)?
+                }
+                None => do_get(args, arg.pretty)?,
+            };
+            match output {
+                Some(output) => output,
+                None => return Ok(None),
+            }
+        }
+        Set(args) if args.dry_run => {
+            let original = variable_or_object(&args.variable)?;
+            let mut value = original.clone();
+            set(args, &mut value)?;
+            return Ok(Some(diff::dry_run(&original, &value)?));
+        }
+        Set(args) => do_set(args, arg.pretty)?,
+        Delete(args) if args.dry_run => {
+            let original = variable_or_object(&args.variable)?;
+            let mut value = original.clone();
+            delete(&args, &mut value)?;
+            return Ok(Some(diff::dry_run(&original, &value)?));
+        }
+        Delete(args) if args.print_removed => {
+            let mut value = variable_or_object(&args.variable)?;
+            let removed = delete(&args, &mut value)?;
+            if removed.is_empty() {
+                return Err(String::new().into());
+            }
+            if let Some(path) = &arg.file {
+                let output = value_printer(arg.pretty, &value);
+                file::write_atomic(path, &format!("{}{}", output::bom(), output))?;
+            }
+            return Ok(output::lines(&removed, arg.pretty, args.raw));
+        }
+        Delete(args) => do_delete(args, arg.pretty)?,
+        PruneNulls(args) => prune::do_prune_nulls(args, arg.pretty)?,
+        PruneEmpty(args) => prune::do_prune_empty(args, arg.pretty)?,
+        Normalize(args) => normalize::do_normalize(args)?,
+        SortBy(args) => sort::do_sort_by(args, arg.pretty)?,
+        Transpose(args) => transpose::do_transpose(args, arg.pretty)?,
+        Upsert(args) => upsert::do_upsert(args, arg.pretty)?,
+        Splice(args) => splice::do_splice(args, arg.pretty)?,
+        Patch(args) => patch::do_patch(args, arg.pretty)?,
+        RenameKeys(args) => rename::do_rename_keys(args, arg.pretty)?,
+        Str(args) => strings::do_str(args, arg.pretty)?,
+        Trim(args) => strings::do_trim(args, arg.pretty)?,
+        Grep(args) => grep::do_grep(args)?,
+        Jq(args) => match jq::do_jq(args, arg.pretty)? {
+            Some(output) => output,
+            None => return Ok(None),
+        },
+        #[cfg(feature = "jmespath")]
+        Jp(args) => jp::do_jp(args, arg.pretty)?,
+        Sub(args) => strings::do_sub(args, arg.pretty)?,
+        Substr(args) => strings::do_substr(args, arg.pretty)?,
+        Canonicalize(args) => jcs::do_canonicalize(args)?,
+        Redact(args) => redact::do_redact(args, arg.pretty)?,
+        Stats(args) => stats::do_stats(args, arg.pretty)?,
+        Depth(args) => depth::do_depth(args)?,
+        Size(args) => size::do_size(args, arg.pretty)?,
+        FromXml(args) => xml::do_from_xml(args, arg.pretty)?,
+        ToXml(args) => xml::do_to_xml(args, arg.pretty)?,
+        FromIni(args) => ini::do_from_ini(args, arg.pretty)?,
+        ToIni(args) => ini::do_to_ini(args)?,
+        FromProperties(args) => properties::do_from_properties(args, arg.pretty)?,
+        ToProperties(args) => properties::do_to_properties(args)?,
+        ToBashArray(args) => bash::do_to_bash_array(args)?,
+        Export(args) => bash::do_export(args, arg.escape_style)?,
+        ShellQuote(args) => bash::do_shell_quote(args)?,
+        Leaves(args) => leaves::do_leaves(args)?,
+        Gron(args) => gron::do_gron(args)?,
+        Ungron(args) => gron::do_ungron(args, arg.pretty)?,
+        Merge(args) => merge::do_merge(args, arg.pretty)?,
+        Resolve(args) => merge::do_resolve(args, arg.pretty)?,
+        Defaults(args) => merge::do_defaults(args, arg.pretty)?,
+        ResolveConflicts(args) => merge::do_resolve_conflicts(args, arg.pretty)?,
+        Merge3(args) => match merge::do_merge3(args, arg.pretty)? {
+            (output, false) => output,
+            (conflicts, true) => {
+                print_output(arg, &conflicts);
+                return Err(String::new().into());
+            }
+        },
+        Type(args) => do_type(args)?,
+        Compare(args) => {
+            if !do_compare(args)? {
+                return Err("false".to_string().into());
+            }
+            "true".to_string()
+        }
+        Test(args) => {
+            do_test(args)?;
+            return Ok(None);
+        }
+        HasValue(args) => {
+            if !do_has_value(args)? {
+                return Err("false".to_string().into());
+            }
+            "true".to_string()
+        }
+        IndexOf(args) => {
+            let all = args.all;
+            match do_index_of(args)?.as_slice() {
+                [] => return Err(String::new().into()),
+                [first, ..] if !all => first.to_string(),
+                indices => to_string(indices).unwrap_or(String::new()),
+            }
+        }
+        Validate(args) => do_validate(args)?,
+        StoreList(args) => {
+            let names = store::do_store_list(args)?;
+            if names.is_empty() {
+                return Ok(None);
+            }
+            names.join(output::terminator())
+        }
+        StoreDelete(args) => {
+            store::do_store_delete(args)?;
+            return Ok(None);
+        }
+        Ndjson(args) => {
+            ndjson::do_ndjson(args)?;
+            return Ok(None);
+        }
+        FrontMatter(args) => match front_matter::do_front_matter(args, arg.pretty)? {
+            Some(output) => output,
+            None => return Ok(None),
+        },
+        Apply(args) => apply::do_apply(args, arg.pretty)?,
+        Stringify(args) => embed::do_stringify(args, arg.pretty)?,
+        ParseEmbedded(args) => embed::do_parse_embedded(args, arg.pretty)?,
+        FixEncoding(args) => embed::do_fix_encoding(args, arg.pretty)?,
+        Each(args) => match each::do_each(args, arg.pretty)? {
+            Some(output) => output,
+            None => return Ok(None),
+        },
+        Table(args) => match table::do_table(args)? {
+            Some(output) => output,
+            None => return Ok(None),
+        },
+        ArrayUnion(args) => match sets::do_array_union(args, arg.pretty)? {
+            Some(output) => output,
+            None => return Ok(None),
+        },
+        ArrayIntersect(args) => match sets::do_array_intersect(args, arg.pretty)? {
+            Some(output) => output,
+            None => return Ok(None),
+        },
+        ArrayDiff(args) => match sets::do_array_diff(args, arg.pretty)? {
+            Some(output) => output,
+            None => return Ok(None),
+        },
+        // started by `start`, not run per command
+        Serve(_) => return Err(exit::usage(String::from("serve can not be run here"))),
+        Diff(args) => match diff::do_diff(args, arg.pretty)? {
+            Some(output) => output,
+            None => return Ok(None),
         },
     };
 
-    if arg.escaped {
-        println!("{:?}", output)
+    if let (Some(path), true) = (&arg.file, mutating) {
+        file::write_atomic(path, &format!("{}{}", output::bom(), output))?;
+        return Ok(None);
+    }
+
+    Ok(Some(output))
+}
+
+fn print_output(arg: &TopLevel, output: &str) {
+    if output::options().quiet {
+        return;
+    }
+    let end = output::terminator();
+    // export quotes the values for the shell of --escape-style itself
+    let escaped = !matches!(arg.command, MySubCommandEnum::Export(_));
+    if escaped && (arg.escaped || arg.escape_style.is_some()) {
+        let style = arg.escape_style.unwrap_or_default();
+        print!("{}{}{}", output::bom(), escape::escape(output, style), end)
     } else {
-        println!("{}", output)
+        print!("{}{}{}", output::bom(), output, end)
     };
-
-    Ok(())
 }
 
 fn value_printer(pretty: bool, value: &serde_json::Value) -> String {
-    if pretty {
-        to_string_pretty(value)
+    let options = output::options();
+    // only json5 inputs can contain numbers that json can not represent
+    let non_finite =
+        options.format == output::Format::Json && input::options().format == input::Format::Json5;
+
+    let mut owned;
+    let reorder = options.sort_keys || options.key_order.is_some();
+    let value = if reorder || non_finite || options.float_precision.is_some() {
+        owned = value.clone();
+        if options.sort_keys {
+            output::sort_keys(&mut owned, options.natural);
+        }
+        if let Some(spec) = &options.key_order {
+            output::order_keys(&mut owned, spec);
+        }
+        if let Some(places) = options.float_precision {
+            output::round_floats(&mut owned, places, options.keep_trailing_zeros);
+        }
+        if non_finite {
+            json5::replace_non_finite(&mut owned);
+        }
+        &owned
     } else {
-        to_string(value)
+        value
+    };
+
+    let text = match (options.format, pretty) {
+        (output::Format::Json5, _) => json5::to_string(value, pretty),
+        (output::Format::Json, true) => to_string_pretty(value).unwrap_or(String::new()),
+        (output::Format::Json, false) => to_string(value).unwrap_or(String::new()),
+    };
+    match options.escape_unicode {
+        true => output::escape_unicode(&text),
+        false => text,
     }
-    .unwrap_or(String::new())
 }
 
-fn do_type(args: SubCommandType) -> String {
-    use Value::*;
+fn do_type(args: SubCommandType) -> Result<String, exit::Error> {
+    Ok(type_name(&variable_or_value(&args.variable)?).to_string())
+}
 
-    match variable_or_value(&args.variable) {
-        Null => "null",
-        Bool(_) => "boolean",
-        Number(_) => "number",
-        String(_) => "string",
-        Array(_) => "array",
-        Object(_) => "object",
+/// The json type of the value, as printed by `type`.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
     }
-    .to_string()
 }
 
-fn do_compare(args: SubCommandCompare) -> Result<(), ()> {
-    let first = variable_or_object(&args.first);
-    let second = variable_or_object(&args.second);
+fn do_validate(args: SubCommandValidate) -> Result<String, exit::Error> {
+    let text = input::Source::of(&args.variable)
+        .text()?
+        .ok_or_else(|| input::not_set(&args.variable))?;
 
-    if first == second {
-        Ok(())
-    } else {
-        Err(())
+    input::check_depth(&text)?;
+    input::parse(&text).map_err(|e| exit::parse(format!("invalid json: {}", e)))?;
+    input::check_duplicates(&text)?;
+
+    Ok("true".to_string())
+}
+
+fn do_compare(args: SubCommandCompare) -> Result<bool, exit::Error> {
+    let first = variable_or_object(&args.first)?;
+    let second = variable_or_object(&args.second)?;
+
+    match args.strict_numbers {
+        true => Ok(number::values_equal_strict(&first, &second)),
+        false => Ok(number::values_equal(&first, &second)),
     }
 }
 
-fn do_set(args: SubCommandSet, pretty: bool) -> String {
-    let mut value = variable_or_object(&args.variable);
-    match pointer_mut(&mut value, &args.pointer.as_str()) {
-        Some(val) => {
-            *val = args.value;
-            value_printer(pretty, &value)
+fn do_has_value(args: SubCommandHasValue) -> Result<bool, exit::Error> {
+    let map = variable_or_object(&args.variable)?;
+
+    match (map.pointer(args.pointer.as_str()), &args.value) {
+        (Some(Value::Array(list)), value) => {
+            Ok(list.iter().any(|x| number::values_equal(x, value)))
         }
-        None => value_printer(pretty, &value),
+        (Some(Value::Object(map)), value) => {
+            Ok(map.values().any(|x| number::values_equal(x, value)))
+        }
+        (Some(Value::String(text)), Value::String(part)) if args.substring => {
+            Ok(text.contains(part.as_str()))
+        }
+        (Some(Value::String(_)), _) if !args.substring => Err(format!(
+            "{} is a string, use --substring to search in it",
+            args.pointer.as_str()
+        )
+        .into()),
+        _ => Ok(false),
     }
 }
 
-pub fn pointer_mut<'a>(value: &'a mut Value, pointer: &str) -> Option<&'a mut Value> {
-    // partial copy from https://github.com/serde-rs/json/blob/master/src/value/mod.rs
-    if pointer.is_empty() {
-        return Some(value);
+/// Fails with both values when the value at the pointer is not equal to the value,
+/// or when it is equal with `--not`.
+fn do_test(args: SubCommandTest) -> Result<(), exit::Error> {
+    if args.string && !args.value.starts_with("env:") {
+        return Err(exit::usage(String::from(
+            "--string only applies to env: values",
+        )));
+    }
+    let expected = read_value(&args.value, args.string, false)?;
+    let map = variable_or_object(&args.variable)?;
+    let pointer = args.pointer.as_str();
+    let found = select(&map, pointer).ok_or_else(|| format!("{} does not exist", pointer))?;
+
+    match (number::values_equal(&found, &expected), args.not) {
+        (true, false) | (false, true) => Ok(()),
+        (false, false) => Err(format!(
+            "{} is {}, expected {}",
+            pointer,
+            value_printer(false, &found),
+            value_printer(false, &expected)
+        )
+        .into()),
+        (true, true) => Err(format!(
+            "{} is {}, expected anything else",
+            pointer,
+            value_printer(false, &found)
+        )
+        .into()),
+    }
+}
+
+/// The indices of the items matching the value or the `--by` field.
+fn do_index_of(args: SubCommandIndexOf) -> Result<Vec<usize>, exit::Error> {
+    if args.by.is_some() == args.value.is_some() {
+        return Err(exit::usage(String::from("give either a value or --by")));
+    }
+
+    let map = variable_or_object(&args.variable)?;
+    let list = match map.pointer(args.pointer.as_str()) {
+        Some(Value::Array(list)) => list,
+        Some(_) => return Err(format!("{} is not an array", args.pointer.as_str()).into()),
+        None => return Ok(Vec::new()),
+    };
+
+    let matches = |item: &Value| match (&args.by, &args.value) {
+        (Some((pointer, value)), None) => item
+            .pointer(pointer.as_str())
+            .is_some_and(|x| number::values_equal(x, value)),
+        (None, Some(value)) => number::values_equal(item, value),
+        _ => false,
+    };
+
+    Ok(list
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| matches(item))
+        .map(|(index, _)| index)
+        .collect())
+}
+
+fn do_set(args: SubCommandSet, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    set(args, &mut value)?;
+    Ok(value_printer(pretty, &value))
+}
+
+/// Sets the value, the time or uuids of the arguments in the map.
+fn set(args: SubCommandSet, value: &mut Value) -> Result<(), exit::Error> {
+    if args.now_format.is_some() && !args.now {
+        return Err(exit::usage(String::from(
+            "--now-format only applies to --now",
+        )));
+    }
+    let new_value: Box<dyn Fn() -> Value> = match (set_value(&args)?, args.now, args.uuid) {
+        (Some(new_value), false, false) => Box::new(move || new_value.clone()),
+        (None, true, false) => {
+            let time = now::now(args.now_format.unwrap_or_default());
+            Box::new(move || time.clone())
+        }
+        // every match gets its own
+        (None, false, true) => Box::new(|| Value::String(new_uuid())),
+        (None, false, false) => {
+            return Err(exit::usage(String::from(
+                "missing the value, --now or --uuid",
+            )))
+        }
+        _ => {
+            return Err(exit::usage(String::from(
+                "only one of the value, --now and --uuid can be given",
+            )))
+        }
+    };
+    let pointers = match args.wildcard {
+        true => expand_wildcards(value, args.pointer.as_str()),
+        false => vec![args.pointer.as_str().to_string()],
+    };
+    if args.wildcard {
+        verbose::log(1, || format!("{} matches", pointers.len()));
+    }
+    let strict = input::strict(args.strict);
+    if strict && pointers.is_empty() {
+        return Err(format!("{} matches nothing", args.pointer.as_str()).into());
+    }
+    if args.pad {
+        let too_far = pointer::tokens(args.pointer.as_str())
+            .filter_map(|x| parse_index(&x))
+            .find(|&x| x > pointer::MAX_PAD_INDEX);
+        if let Some(index) = too_far {
+            return Err(exit::usage(format!(
+                "--pad extends arrays up to index {}, not {}",
+                pointer::MAX_PAD_INDEX,
+                index
+            )));
+        }
+    }
+
+    for pointer in pointers {
+        match resolve_mut(value, &pointer, args.pad) {
+            Ok(val) => *val = new_value(),
+            Err(e) if strict => return Err(format!("can not set {}: {}", pointer, e).into()),
+            Err(_) => (),
+        }
+    }
+    Ok(depth::check(value, input::options().max_depth)?)
+}
+
+/// The value argument of `set`, given on the command line, read from stdin
+/// or from the environment variable of an `env:NAME` value.
+fn set_value(args: &SubCommandSet) -> Result<Option<Value>, exit::Error> {
+    if args.keep_trailing_newline && !args.string {
+        return Err(exit::usage(String::from(
+            "--keep-trailing-newline only applies to --string",
+        )));
+    }
+    let env_name = args.value.as_deref().and_then(|x| x.strip_prefix("env:"));
+    if args.string && !args.value_stdin && env_name.is_none() {
+        return Err(exit::usage(String::from(
+            "--string only applies to --value-stdin and env: values",
+        )));
+    }
+    if !args.value_stdin {
+        return args
+            .value
+            .as_deref()
+            .map(|x| read_value(x, args.string, args.keep_trailing_newline))
+            .transpose();
+    }
+    if args.value.is_some() {
+        return Err(exit::usage(String::from(
+            "only one of the value and --value-stdin can be given",
+        )));
+    }
+    if input::reads_stdin(&args.variable) {
+        return Err(exit::usage(String::from(
+            "the map and --value-stdin can not both read stdin",
+        )));
+    }
+
+    let mut text = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)
+        .map_err(|e| exit::io(format!("unable to read stdin: {}", e)))?;
+    match args.string {
+        true => Ok(Some(string_value(text, args.keep_trailing_newline))),
+        false => value_from_str(&text).map(Some).map_err(exit::parse),
+    }
+}
+
+/// The text of a `--string` value, without the single line break at its end unless it is kept.
+fn string_value(mut text: String, keep_trailing_newline: bool) -> Value {
+    if !keep_trailing_newline {
+        let trimmed = text
+            .strip_suffix("\r\n")
+            .or_else(|| text.strip_suffix('\n'))
+            .unwrap_or(&text);
+        text.truncate(trimmed.len());
+    }
+    Value::String(text)
+}
+
+/// A random (version 4) uuid in the usual hyphenated form.
+fn new_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn do_delete(args: SubCommandDelete, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    delete(&args, &mut value)?;
+    Ok(value_printer(pretty, &value))
+}
+
+/// Removes the matches of the pointer from the map, returning the removed values in document order.
+fn delete(args: &SubCommandDelete, value: &mut Value) -> Result<Vec<Value>, exit::Error> {
+    if args.raw && !args.print_removed {
+        return Err(exit::usage(String::from(
+            "--raw only applies to --print-removed",
+        )));
+    }
+    let pointers = match args.wildcard {
+        true => expand_wildcards(value, args.pointer.as_str()),
+        false => vec![args.pointer.as_str().to_string()],
+    };
+    // matches are in document order, going through them backwards removes the higher
+    // indices of an array first, so the lower ones still point at the same items
+    let mut removed: Vec<Value> = pointers
+        .iter()
+        .rev()
+        .filter_map(|pointer| remove(value, pointer))
+        .collect();
+    removed.reverse();
+
+    verbose::log(1, || format!("{} removed", removed.len()));
+    if input::strict(args.strict) && removed.is_empty() {
+        return Err(format!("{} matches nothing", args.pointer.as_str()).into());
+    }
+    Ok(removed)
+}
+
+/// Removes the key or array item at the pointer, the whole value can not be removed.
+fn remove(value: &mut Value, pointer: &str) -> Option<Value> {
+    let (parent, token) = pointer.rsplit_once('/')?;
+    let token = pointer::unescape(token);
+
+    match value.pointer_mut(parent)? {
+        Value::Object(map) => map.shift_remove(token.as_ref()),
+        Value::Array(list) => {
+            let index = parse_index(&token).filter(|x| *x < list.len())?;
+            Some(list.remove(index))
+        }
+        _ => None,
     }
+}
+
+/// The pointers matching a pointer with `*` tokens, which match every key of an object
+/// or index of an array. Tokens after a `*` do not have to exist yet,
+/// but matches going through something other than an object or array are left out.
+///
+/// Everything is expanded against `value` before anything is changed,
+/// so the matches do not depend on the order they are written in.
+fn expand_wildcards(value: &Value, pointer: &str) -> Vec<String> {
     if !pointer.starts_with('/') {
-        return None;
+        return vec![pointer.to_string()];
     }
-    pointer
-        .split('/')
-        .skip(1)
-        .map(|x| x.replace("~1", "/").replace("~0", "~"))
-        .try_fold(value, pointer_inner)
-}
-
-fn pointer_inner(target: &mut serde_json::Value, token: String) -> Option<&mut Value> {
-    match target {
-        Value::Object(map) => {
-            map.entry(&token).or_insert(Value::Null);
-            map.get_mut(&token)
-        }
-        Value::Array(list) => parse_index(&token).and_then(move |x| list.get_mut(x)),
-        other => {
-            let mut map = serde_json::Map::new();
-            map.entry(&token).or_insert(Value::Null);
-            *other = Value::Object(map);
-            match other {
-                Value::Object(map) => {
-                    map.entry(&token).or_insert(Value::Null);
-                    map.get_mut(&token)
-                }
-                _ => None,
+
+    let mut matches = vec![(String::new(), Some(value))];
+    for token in pointer.split('/').skip(1) {
+        matches = match token {
+            "*" => matches
+                .into_iter()
+                .flat_map(|(path, value)| {
+                    let children: Vec<(String, &Value)> = match value {
+                        Some(Value::Object(map)) => map
+                            .iter()
+                            .map(|(key, value)| (pointer::escape(key).into_owned(), value))
+                            .collect(),
+                        Some(Value::Array(list)) => list
+                            .iter()
+                            .enumerate()
+                            .map(|(index, value)| (index.to_string(), value))
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    children
+                        .into_iter()
+                        .map(move |(token, child)| (format!("{}/{}", path, token), Some(child)))
+                })
+                .collect(),
+            token => matches
+                .into_iter()
+                .filter(|(_, value)| value.is_none_or(|x| x.is_object() || x.is_array()))
+                .map(|(path, value)| {
+                    let child = value.and_then(|x| x.pointer(&format!("/{}", token)));
+                    (format!("{}/{}", path, token), child)
+                })
+                .collect(),
+        };
+    }
+    matches.into_iter().map(|(path, _)| path).collect()
+}
+
+/// `start:end` as the last token of a `get` pointer. Both bounds are optional,
+/// negative bounds count from the end of the array and bounds out of range are clamped.
+#[derive(PartialEq, Debug)]
+struct Range {
+    start: Option<i64>,
+    end: Option<i64>,
+}
+
+impl Range {
+    /// The range when the token looks like `-?\d*:-?\d*`.
+    fn parse(token: &str) -> Option<Range> {
+        fn bound(s: &str) -> Option<Option<i64>> {
+            let digits = s.strip_prefix('-').unwrap_or(s);
+            match (s.is_empty(), digits.chars().all(|c| c.is_ascii_digit())) {
+                (true, _) => Some(None),
+                (false, true) => s.parse().ok().map(Some),
+                (false, false) => None,
             }
         }
+
+        let (start, end) = token.split_once(':')?;
+        Some(Range {
+            start: bound(start)?,
+            end: bound(end)?,
+        })
     }
-}
 
-fn parse_index(s: &str) -> Option<usize> {
-    if s.starts_with('+') || (s.starts_with('0') && s.len() != 1) {
-        return None;
+    fn slice<'a>(&self, list: &'a [Value]) -> &'a [Value] {
+        let len = list.len() as i64;
+        let bound = |x: i64| (if x < 0 { len + x } else { x }).clamp(0, len) as usize;
+        let start = self.start.map_or(0, bound);
+        let end = self.end.map_or(list.len(), bound);
+
+        if start < end {
+            &list[start..end]
+        } else {
+            &[]
+        }
     }
-    s.parse().ok()
 }
 
-fn do_get(args: SubCommandGet, pretty: bool) -> String {
-    match variable_or_object(&args.variable).pointer(&args.pointer.as_str()) {
-        Some(val) => value_printer(pretty, val),
-        None => String::new(),
+/// Splits a pointer ending in a range token into the pointer of its parent and the range.
+fn split_range(pointer: &str) -> Option<(&str, Range)> {
+    let (parent, token) = pointer.rsplit_once('/')?;
+    Range::parse(token).map(|range| (parent, range))
+}
+
+/// Resolves the pointer like `Value::pointer`, except that a last token like `1:3`
+/// on an array selects part of it, see `Range`.
+fn select<'a>(value: &'a Value, pointer: &str) -> Option<Cow<'a, Value>> {
+    if let Some((parent, range)) = split_range(pointer) {
+        if let Some(Value::Array(list)) = value.pointer(parent) {
+            trace(value, parent);
+            verbose::log(1, || {
+                format!(
+                    "slicing the array at {:?} with {}",
+                    parent,
+                    &pointer[parent.len() + 1..]
+                )
+            });
+            return Some(Cow::Owned(Value::Array(range.slice(list).to_vec())));
+        }
     }
+    trace(value, pointer);
+    value.pointer(pointer).map(Cow::Borrowed)
 }
 
-fn variable_or_object(input: &str) -> Value {
-    match from_str(input) {
-        Ok(x) => Value::Object(x),
-        _ => {
-            let item = var(input).unwrap_or(String::new());
-            from_str(&item).unwrap_or(Value::Object(Default::default()))
+/// Logs the tokens of the pointer and the steps of resolving it in the value for `--verbose`.
+fn trace(value: &Value, pointer: &str) {
+    if pointer.is_empty() {
+        verbose::log(1, || {
+            String::from("empty pointer, selecting the whole document")
+        });
+    }
+    if !pointer.starts_with('/') {
+        return;
+    }
+    pointer::log_tokens(pointer);
+    let mut target = Some(value);
+    for (step, token) in pointer::tokens(pointer).enumerate() {
+        let current = match target {
+            Some(current) => current,
+            None => break,
+        };
+        verbose::log(2, || {
+            format!("step {}: {:?} in {}", step + 1, token, type_name(current))
+        });
+        target = match current {
+            Value::Object(map) => map.get(token.as_ref()),
+            Value::Array(list) => parse_index(&token).and_then(|index| list.get(index)),
+            _ => None,
+        };
+        if target.is_none() {
+            verbose::log(1, || {
+                format!(
+                    "step {}: {:?} not found in {}",
+                    step + 1,
+                    token,
+                    type_name(current)
+                )
+            });
         }
     }
 }
 
-fn variable_or_value(input: &str) -> Value {
-    match from_str(input) {
-        Ok(x) => x,
-        _ => {
-            let item = var(input).unwrap_or(String::new());
-            from_str(&item).unwrap_or(Value::Null)
+fn do_get(args: SubCommandGet, pretty: bool) -> Result<Option<String>, exit::Error> {
+    let map = variable_or_object(&args.variable)?;
+    get(&args, &map, pretty)
+}
+
+/// The options of `get` that can not be combined.
+fn check_get(args: &SubCommandGet) -> Result<(), exit::Error> {
+    if !args.project && (!args.pointers.is_empty() || args.full_keys) {
+        return Err(exit::usage(String::from(
+            "more than one pointer and --full-keys only apply to --project",
+        )));
+    }
+    if args.project && args.lines {
+        return Err(exit::usage(String::from(
+            "--lines can not be combined with --project",
+        )));
+    }
+    Ok(())
+}
+
+/// The output of `get` on the map.
+fn get(args: &SubCommandGet, map: &Value, pretty: bool) -> Result<Option<String>, exit::Error> {
+    if args.project {
+        return project(args, map).map(|x| Some(value_printer(pretty, &x)));
+    }
+    let found = select(map, args.pointer.as_str()).map(Cow::into_owned);
+    Ok(get_output(args, found, pretty)?)
+}
+
+/// The values at the pointers of `get --project` as one object, keyed by the last token
+/// of every pointer or by the whole pointer with `--full-keys`.
+fn project(args: &SubCommandGet, map: &Value) -> Result<Value, exit::Error> {
+    let pointers: Vec<&str> = std::iter::once(&args.pointer)
+        .chain(&args.pointers)
+        .map(Pointer::as_str)
+        .collect();
+    let mut keys: Vec<String> = Vec::new();
+    for pointer in &pointers {
+        let key = match args.full_keys {
+            true => pointer.to_string(),
+            false => pointer::tokens(pointer)
+                .last()
+                .map(Cow::into_owned)
+                .unwrap_or_default(),
+        };
+        if keys.contains(&key) {
+            return Err(exit::usage(format!(
+                "more than one pointer gives the key {:?}, use --full-keys",
+                key
+            )));
         }
+        keys.push(key);
+    }
+
+    let mut object = serde_json::Map::new();
+    for (pointer, key) in pointers.into_iter().zip(keys) {
+        match select(map, pointer) {
+            Some(found) => {
+                object.insert(key, found.into_owned());
+            }
+            None if input::strict(args.strict) => {
+                return Err(format!("{} does not exist", pointer).into())
+            }
+            None => (),
+        }
+    }
+    Ok(Value::Object(object))
+}
+
+/// What `get` prints for the value found at the pointer, None when `--lines` has nothing to print.
+fn get_output(
+    args: &SubCommandGet,
+    found: Option<Value>,
+    pretty: bool,
+) -> Result<Option<String>, String> {
+    if found.is_none() && input::strict(args.strict) {
+        return Err(format!("{} does not exist", args.pointer.as_str()));
+    }
+    match (found, args.lines) {
+        (Some(val), false) => Ok(Some(value_printer(pretty, &val))),
+        (None, false) => Ok(Some(String::new())),
+        (Some(Value::Array(list)), true) => Ok(output::lines(&list, pretty, false)),
+        (Some(other), true) => Err(format!(
+            "{} is of type {}, not array",
+            args.pointer.as_str(),
+            type_name(&other)
+        )),
+        (None, true) => Ok(None),
+    }
+}
+
+/// The file `get` should stream from: a `@path` or `--file` that is large or `--stream` is given,
+/// or stdin and other pipes, which can not be sized up front and are only read once anyway.
+/// Only plain json can be streamed, and not with the checks that need the whole document.
+fn stream_source<'a>(arg: &TopLevel, args: &'a SubCommandGet) -> Option<&'a Path> {
+    let options = input::options();
+    if options.format != input::Format::Json
+        || options.dedup_check
+        || options.decode_unicode_keys
+        || args.project
+    {
+        return None;
+    }
+    let path = match input::Source::of(&args.variable) {
+        input::Source::File(path) => path,
+        _ => return None,
+    };
+    let large = std::fs::metadata(path)
+        .map(|x| x.len() > stream::STREAM_THRESHOLD)
+        .unwrap_or(false);
+
+    if arg.stream || large || input::is_pipe(&path.to_string_lossy()) {
+        Some(path)
+    } else {
+        None
     }
 }
 
+fn stream_get_value(path: &Path, pointer: &Pointer) -> Result<Option<Value>, exit::Error> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| exit::io(format!("unable to read {}: {}", path.display(), e)))?;
+
+    verbose::log(1, || {
+        format!(
+            "input is the file {}, read up to the pointer",
+            path.display()
+        )
+    });
+    let mut reader = std::io::BufReader::new(file);
+    input::skip_bom(&mut reader)
+        .map_err(|e| exit::io(format!("unable to read {}: {}", path.display(), e)))?;
+
+    let invalid = |e| exit::parse(format!("invalid json in {}: {}", path.display(), e));
+    // a range needs the complete parent
+    let found = match split_range(pointer.as_str()) {
+        Some((parent, _)) => stream::get(reader, parent)
+            .map_err(invalid)?
+            .and_then(|value| {
+                let token = &pointer.as_str()[parent.len()..];
+                select(&value, token).map(Cow::into_owned)
+            }),
+        None => stream::get(reader, pointer.as_str()).map_err(invalid)?,
+    };
+
+    Ok(found)
+}
+
+/// The map named by `input`, see `input::Source` for where it is read from.
+/// Anything that can not be read becomes `{}`, or fails when strict, see `input::strict`.
+fn variable_or_object(input: &str) -> Result<Value, exit::Error> {
+    let value = input::Source::of(input).read()?;
+    Ok(value.unwrap_or_else(|| Value::Object(Default::default())))
+}
+
+fn variable_or_value(input: &str) -> Result<Value, exit::Error> {
+    Ok(input::Source::of(input).read()?.unwrap_or(Value::Null))
+}
+
 #[cfg(test)]
 mod doc_test {
     use super::{do_get, do_set, SubCommandGet, SubCommandSet};
@@ -323,12 +2087,12 @@ mod doc_test {
             let args =
                 SubCommandGet::from_args(&[], &[line.input, &line.pointer.replace("\"", "")])
                     .unwrap();
-            let output = do_get(args, false);
+            let output = do_get(args, false).unwrap().unwrap();
 
             assert_eq!(output, line.output);
             amount_of_lines += 1;
         }
-        assert_eq!(amount_of_lines, 3)
+        assert_eq!(amount_of_lines, 5)
     }
 
     #[test]
@@ -349,10 +2113,10 @@ mod doc_test {
 
             let args = SubCommandSet::from_args(
                 &[],
-                &[line.input, &line.pointer.replace("\"", ""), &line.value],
+                &[line.input, &line.pointer.replace("\"", ""), line.value],
             )
             .unwrap();
-            let output = do_set(args, false);
+            let output = do_set(args, false).unwrap();
 
             assert_eq!(output, line.output);
             amount_of_lines += 1;
@@ -367,8 +2131,13 @@ mod set_test {
 
     #[test]
     fn invalid_key_returns_the_input() {
+        // a key that is not a pointer is a usage error now, one that can not be set
+        // still leaves the input as it is without --strict
+        let error = "invalid key".parse::<Pointer>().unwrap_err();
+        assert_eq!(error.code, crate::exit::USAGE);
+
         let data = serde_json::json!({
-            "key": "number"
+            "key": ["number"]
         })
         .to_string();
 
@@ -377,14 +2146,33 @@ mod set_test {
             do_set(
                 SubCommandSet {
                     variable: data,
-                    pointer: Pointer::new_unwrap("invalid key"),
-                    value: serde_json::json!(1.0)
+                    pointer: Pointer::new_unwrap("/key/invalid key"),
+                    value: Some(String::from("1.0")),
+                    value_stdin: false,
+                    string: false,
+                    keep_trailing_newline: false,
+                    now: false,
+                    now_format: None,
+                    uuid: false,
+                    wildcard: false,
+                    strict: false,
+                    pad: false,
+                    dry_run: false,
                 },
                 false
             )
+            .unwrap()
         );
     }
 
+    #[test]
+    fn invalid_key_is_rejected() {
+        assert!("invalid key".parse::<Pointer>().is_err());
+        assert!("key/a".parse::<Pointer>().is_err());
+        assert_eq!("''".parse::<Pointer>().unwrap().as_str(), "");
+        assert_eq!("\\/key".parse::<Pointer>().unwrap().as_str(), "/key");
+    }
+
     #[test]
     fn replace_works() {
         let data = serde_json::json!({
@@ -401,10 +2189,21 @@ mod set_test {
                 SubCommandSet {
                     variable: data,
                     pointer: Pointer::new_unwrap("/key"),
-                    value: serde_json::json!(1.0)
+                    value: Some(String::from("1.0")),
+                    value_stdin: false,
+                    string: false,
+                    keep_trailing_newline: false,
+                    now: false,
+                    now_format: None,
+                    uuid: false,
+                    wildcard: false,
+                    strict: false,
+                    pad: false,
+                    dry_run: false,
                 },
                 false
             )
+            .unwrap()
         );
     }
 
@@ -425,10 +2224,21 @@ mod set_test {
                 SubCommandSet {
                     variable: data,
                     pointer: Pointer::new_unwrap("/other"),
-                    value: serde_json::json!(1.0)
+                    value: Some(String::from("1.0")),
+                    value_stdin: false,
+                    string: false,
+                    keep_trailing_newline: false,
+                    now: false,
+                    now_format: None,
+                    uuid: false,
+                    wildcard: false,
+                    strict: false,
+                    pad: false,
+                    dry_run: false,
                 },
                 false
             )
+            .unwrap()
         );
     }
 
@@ -449,10 +2259,21 @@ mod set_test {
                 SubCommandSet {
                     variable: data,
                     pointer: Pointer::new_unwrap("/nested/other"),
-                    value: serde_json::json!(1.0)
+                    value: Some(String::from("1.0")),
+                    value_stdin: false,
+                    string: false,
+                    keep_trailing_newline: false,
+                    now: false,
+                    now_format: None,
+                    uuid: false,
+                    wildcard: false,
+                    strict: false,
+                    pad: false,
+                    dry_run: false,
                 },
                 false
             )
+            .unwrap()
         );
     }
 
@@ -473,12 +2294,191 @@ mod set_test {
                 SubCommandSet {
                     variable: data,
                     pointer: Pointer::new_unwrap("/a/b/c/d/e/f/g/h"),
-                    value: serde_json::json!(1.0)
+                    value: Some(String::from("1.0")),
+                    value_stdin: false,
+                    string: false,
+                    keep_trailing_newline: false,
+                    now: false,
+                    now_format: None,
+                    uuid: false,
+                    wildcard: false,
+                    strict: false,
+                    pad: false,
+                    dry_run: false,
                 },
                 false
             )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn array_and_scalar_roots() {
+        use argh::FromArgs;
+
+        let set = |arguments: &[&str]| {
+            do_set(SubCommandSet::from_args(&[], arguments).unwrap(), false).unwrap()
+        };
+        assert_eq!(set(&["[1, 2]", "/0", "5"]), "[5,2]");
+        assert_eq!(set(&["[[1], 2]", "/0/1", "5"]), "[[1],2]");
+        assert_eq!(set(&["[[1], 2]", "/0/1", "5", "--pad"]), "[[1,5],2]");
+        assert_eq!(set(&["[1, 2]", "", "{}"]), "{}");
+        assert_eq!(set(&["1", "/a", "true"]), r#"{"a":true}"#);
+        assert_eq!(set(&["{\"a\": [1]}", "/a/0", "2"]), r#"{"a":[2]}"#);
+    }
+}
+
+#[cfg(test)]
+mod delete_test {
+    use super::{do_delete, Pointer, SubCommandDelete};
+    use serde_json::{json, Value};
+
+    fn delete(data: Value, pointer: &str, wildcard: bool) -> Result<String, String> {
+        do_delete(
+            SubCommandDelete {
+                variable: data.to_string(),
+                pointer: Pointer::new_unwrap(pointer),
+                wildcard,
+                strict: true,
+                print_removed: false,
+                raw: false,
+                dry_run: false,
+            },
+            false,
+        )
+        .map_err(|e| e.message)
+    }
+
+    #[test]
+    fn keys_and_items() {
+        let data = json!({"a": 1, "b": [1, 2, 3], "c/d": {"e": 1}});
+
+        assert_eq!(
+            delete(data.clone(), "/a", false).unwrap(),
+            r#"{"b":[1,2,3],"c/d":{"e":1}}"#
+        );
+        assert_eq!(
+            delete(data.clone(), "/b/0", false).unwrap(),
+            r#"{"a":1,"b":[2,3],"c/d":{"e":1}}"#
+        );
+        assert_eq!(
+            delete(data.clone(), "/c~1d/e", false).unwrap(),
+            r#"{"a":1,"b":[1,2,3],"c/d":{}}"#
+        );
+        assert!(delete(data.clone(), "/b/3", false).is_err());
+        assert!(delete(data.clone(), "/missing/a", false).is_err());
+        assert!(delete(data, "", false).is_err());
+    }
+
+    #[test]
+    fn wildcard_keys() {
+        let data = json!({"services": [{"debug": true, "name": "a"}, {"name": "b"}, {"debug": 1}]});
+        assert_eq!(
+            delete(data, "/services/*/debug", true).unwrap(),
+            r#"{"services":[{"name":"a"},{"name":"b"},{}]}"#
+        );
+    }
+
+    #[test]
+    fn wildcard_items() {
+        let data = json!({"lists": [[1, 2, 3], [4]], "other": [5]});
+        assert_eq!(
+            delete(data.clone(), "/lists/*/*", true).unwrap(),
+            r#"{"lists":[[],[]],"other":[5]}"#
+        );
+        assert_eq!(
+            delete(data, "/*/0", true).unwrap(),
+            r#"{"lists":[[4]],"other":[]}"#
+        );
+    }
+}
+
+#[cfg(test)]
+mod wildcard_test {
+    use super::{do_set, expand_wildcards, Pointer, SubCommandSet};
+    use serde_json::{json, Value};
+
+    fn set(data: Value, pointer: &str, value: Value, strict: bool) -> Result<String, String> {
+        do_set(
+            SubCommandSet {
+                variable: data.to_string(),
+                pointer: Pointer::new_unwrap(pointer),
+                value: Some(value.to_string()),
+                value_stdin: false,
+                string: false,
+                keep_trailing_newline: false,
+                now: false,
+                now_format: None,
+                uuid: false,
+                wildcard: true,
+                strict,
+                pad: false,
+                dry_run: false,
+            },
+            false,
+        )
+        .map_err(|e| e.message)
+    }
+
+    #[test]
+    fn expand() {
+        let data = json!({"services": {"web": {"port": 80}, "a/b": {}}, "list": [{"x": 1}, 2]});
+
+        assert_eq!(
+            expand_wildcards(&data, "/services/*/enabled"),
+            vec!["/services/web/enabled", "/services/a~1b/enabled"]
+        );
+        assert_eq!(expand_wildcards(&data, "/list/*/x"), vec!["/list/0/x"]);
+        assert_eq!(
+            expand_wildcards(&data, "/*/*/port"),
+            vec!["/services/web/port", "/services/a~1b/port", "/list/0/port"]
+        );
+        assert_eq!(expand_wildcards(&data, "/list/1/*"), Vec::<String>::new());
+        assert_eq!(expand_wildcards(&data, "/missing/*"), Vec::<String>::new());
+        assert_eq!(
+            expand_wildcards(&data, "/services/web"),
+            vec!["/services/web"]
+        );
+    }
+
+    #[test]
+    fn set_every_match() {
+        let data = json!({"services": {"web": {"enabled": true}, "db": {}}});
+        assert_eq!(
+            set(data, "/services/*/enabled", json!(false), false).unwrap(),
+            r#"{"services":{"web":{"enabled":false},"db":{"enabled":false}}}"#
+        );
+    }
+
+    #[test]
+    fn expands_before_writing() {
+        // the written objects contain keys `/*/*` would match, they are not matches themselves
+        let data = json!({"a": {"b": {}}, "c": {"d": 1}});
+        assert_eq!(expand_wildcards(&data, "/*/*"), vec!["/a/b", "/c/d"]);
+        assert_eq!(
+            set(data, "/*/*", json!({"e": {"f": 1}}), false).unwrap(),
+            r#"{"a":{"b":{"e":{"f":1}}},"c":{"d":{"e":{"f":1}}}}"#
+        );
+    }
+
+    #[test]
+    fn scalars_are_not_replaced() {
+        let data = json!({"services": [{"name": "web"}, "disabled", null]});
+        assert_eq!(
+            set(data, "/services/*/enabled", json!(false), false).unwrap(),
+            r#"{"services":[{"name":"web","enabled":false},"disabled",null]}"#
         );
     }
+
+    #[test]
+    fn no_matches() {
+        let data = json!({"services": {}});
+        assert_eq!(
+            set(data.clone(), "/services/*/enabled", json!(false), false).unwrap(),
+            r#"{"services":{}}"#
+        );
+        assert!(set(data, "/services/*/enabled", json!(false), true).is_err());
+    }
 }
 
 #[cfg(test)]
@@ -497,10 +2497,17 @@ mod get_test {
             do_get(
                 SubCommandGet {
                     variable: data,
-                    pointer: Pointer::new_unwrap("\\/key")
+                    pointer: Pointer::new_unwrap("\\/key"),
+                    pointers: Vec::new(),
+                    lines: false,
+                    strict: false,
+                    project: false,
+                    full_keys: false,
                 },
                 false
             )
+            .unwrap()
+            .unwrap()
         );
     }
 
@@ -516,10 +2523,17 @@ mod get_test {
             do_get(
                 SubCommandGet {
                     variable: data,
-                    pointer: Pointer::new_unwrap("/key")
+                    pointer: Pointer::new_unwrap("/key"),
+                    pointers: Vec::new(),
+                    lines: false,
+                    strict: false,
+                    project: false,
+                    full_keys: false,
                 },
                 false
             )
+            .unwrap()
+            .unwrap()
         );
     }
 
@@ -539,10 +2553,17 @@ mod get_test {
             do_get(
                 SubCommandGet {
                     variable: data.to_string(),
-                    pointer: Pointer::new_unwrap("/key/1")
+                    pointer: Pointer::new_unwrap("/key/1"),
+                    pointers: Vec::new(),
+                    lines: false,
+                    strict: false,
+                    project: false,
+                    full_keys: false,
                 },
                 false
             )
+            .unwrap()
+            .unwrap()
         );
 
         assert_eq!(
@@ -550,10 +2571,17 @@ mod get_test {
             do_get(
                 SubCommandGet {
                     variable: data.to_string(),
-                    pointer: Pointer::new_unwrap("/key/2")
+                    pointer: Pointer::new_unwrap("/key/2"),
+                    pointers: Vec::new(),
+                    lines: false,
+                    strict: false,
+                    project: false,
+                    full_keys: false,
                 },
                 false
             )
+            .unwrap()
+            .unwrap()
         );
     }
 
@@ -573,10 +2601,17 @@ mod get_test {
             do_get(
                 SubCommandGet {
                     variable: data.to_string(),
-                    pointer: Pointer::new_unwrap("/key/2/three")
+                    pointer: Pointer::new_unwrap("/key/2/three"),
+                    pointers: Vec::new(),
+                    lines: false,
+                    strict: false,
+                    project: false,
+                    full_keys: false,
                 },
                 false
             )
+            .unwrap()
+            .unwrap()
         );
 
         assert_eq!(
@@ -584,10 +2619,17 @@ mod get_test {
             do_get(
                 SubCommandGet {
                     variable: data.to_string(),
-                    pointer: Pointer::new_unwrap("/key/1/two")
+                    pointer: Pointer::new_unwrap("/key/1/two"),
+                    pointers: Vec::new(),
+                    lines: false,
+                    strict: false,
+                    project: false,
+                    full_keys: false,
                 },
                 false
             )
+            .unwrap()
+            .unwrap()
         );
 
         assert_eq!(
@@ -595,12 +2637,61 @@ mod get_test {
             do_get(
                 SubCommandGet {
                     variable: data.to_string(),
-                    pointer: Pointer::new_unwrap("/key/0")
+                    pointer: Pointer::new_unwrap("/key/0"),
+                    pointers: Vec::new(),
+                    lines: false,
+                    strict: false,
+                    project: false,
+                    full_keys: false,
                 },
                 false
             )
+            .unwrap()
+            .unwrap()
         );
     }
+
+    fn get(variable: &str, pointer: &str) -> String {
+        do_get(
+            SubCommandGet {
+                variable: variable.to_string(),
+                pointer: Pointer::new_unwrap(pointer),
+                pointers: Vec::new(),
+                lines: false,
+                strict: false,
+                project: false,
+                full_keys: false,
+            },
+            false,
+        )
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn range() {
+        let data = r#"{"items": [0, 1, 2, 3, 4], "1:3": "key", "nested": {"2:": [5]}}"#;
+
+        assert_eq!(get(data, "/items/1:3"), "[1,2]");
+        assert_eq!(get(data, "/items/:2"), "[0,1]");
+        assert_eq!(get(data, "/items/3:"), "[3,4]");
+        assert_eq!(get(data, "/items/:"), "[0,1,2,3,4]");
+        assert_eq!(get(data, "/items/-2:"), "[3,4]");
+        assert_eq!(get(data, "/items/1:-1"), "[1,2,3]");
+        assert_eq!(get(data, "/items/-10:10"), "[0,1,2,3,4]");
+        assert_eq!(get(data, "/items/3:1"), "[]");
+        assert_eq!(get(data, "/items/+1:2"), "");
+    }
+
+    #[test]
+    fn range_tokens_are_keys_on_objects() {
+        let data = r#"{"items": [0, 1, 2, 3, 4], "1:3": "key", "nested": {"2:": [5]}}"#;
+
+        assert_eq!(get(data, "/1:3"), r#""key""#);
+        assert_eq!(get(data, "/nested/2:"), "[5]");
+        assert_eq!(get(data, "/nested/2:/0"), "5");
+        assert_eq!(get(data, "/missing/1:3"), "");
+    }
 }
 
 #[cfg(test)]
@@ -614,24 +2705,28 @@ mod type_test {
             do_type(SubCommandType {
                 variable: "1.123".to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "number",
             do_type(SubCommandType {
                 variable: "1".to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "number",
             do_type(SubCommandType {
                 variable: "3e-12".to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "number",
             do_type(SubCommandType {
                 variable: "-2.1e5".to_string()
             })
+            .unwrap()
         );
     }
 
@@ -642,12 +2737,14 @@ mod type_test {
             do_type(SubCommandType {
                 variable: "{}".to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "object",
             do_type(SubCommandType {
                 variable: "{\"key\": 123}".to_string()
             })
+            .unwrap()
         );
     }
 
@@ -658,12 +2755,14 @@ mod type_test {
             do_type(SubCommandType {
                 variable: "[]".to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "array",
             do_type(SubCommandType {
                 variable: "[1,2,3,4]".to_string()
             })
+            .unwrap()
         );
     }
 
@@ -674,24 +2773,28 @@ mod type_test {
             do_type(SubCommandType {
                 variable: "null".to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "null",
             do_type(SubCommandType {
                 variable: "".to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "null",
             do_type(SubCommandType {
                 variable: "unknown_variable".to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "null",
             do_type(SubCommandType {
                 variable: "{not json ".to_string()
             })
+            .unwrap()
         );
     }
 
@@ -702,12 +2805,14 @@ mod type_test {
             do_type(SubCommandType {
                 variable: "true".to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "boolean",
             do_type(SubCommandType {
                 variable: "false".to_string()
             })
+            .unwrap()
         );
     }
 
@@ -718,24 +2823,28 @@ mod type_test {
             do_type(SubCommandType {
                 variable: r#""test""#.to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "string",
             do_type(SubCommandType {
                 variable: r#""false""#.to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "string",
             do_type(SubCommandType {
                 variable: r#""1.123""#.to_string()
             })
+            .unwrap()
         );
         assert_eq!(
             "string",
             do_type(SubCommandType {
                 variable: "\"string\"".to_string()
             })
+            .unwrap()
         );
     }
 
@@ -749,6 +2858,7 @@ mod type_test {
             do_type(SubCommandType {
                 variable: "testing_var".to_string()
             })
+            .unwrap()
         );
         set_var("testing_var", r#""string""#);
         assert_eq!(
@@ -756,6 +2866,7 @@ mod type_test {
             do_type(SubCommandType {
                 variable: "testing_var".to_string()
             })
+            .unwrap()
         );
         set_var("testing_var", "1.123");
         assert_eq!(
@@ -763,6 +2874,109 @@ mod type_test {
             do_type(SubCommandType {
                 variable: "testing_var".to_string()
             })
+            .unwrap()
+        );
+    }
+}
+
+#[cfg(test)]
+mod has_value_test {
+    use super::{do_has_value, Pointer, SubCommandHasValue};
+    use serde_json::{json, Value};
+
+    fn has_value(pointer: &str, value: Value, substring: bool) -> Result<bool, String> {
+        let data = json!({
+            "users": ["alice", {"name": "bob"}, 1.0],
+            "roles": {"admin": "alice"},
+            "motd": "hello world",
+            "count": 1
+        });
+
+        do_has_value(SubCommandHasValue {
+            substring,
+            variable: data.to_string(),
+            pointer: Pointer::new_unwrap(pointer),
+            value,
+        })
+        .map_err(|e| e.message)
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(has_value("/users", json!("alice"), false), Ok(true));
+        assert_eq!(has_value("/users", json!({"name": "bob"}), false), Ok(true));
+        assert_eq!(has_value("/users", json!(1), false), Ok(true));
+        assert_eq!(has_value("/users", json!("ali"), false), Ok(false));
+        assert_eq!(
+            has_value("/users", json!({"name": "eve"}), false),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn objects() {
+        assert_eq!(has_value("/roles", json!("alice"), false), Ok(true));
+        assert_eq!(has_value("/roles", json!("admin"), false), Ok(false));
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(has_value("/motd", json!("lo wo"), true), Ok(true));
+        assert_eq!(has_value("/motd", json!("bye"), true), Ok(false));
+        assert_eq!(has_value("/motd", json!(1), true), Ok(false));
+        assert!(has_value("/motd", json!("lo wo"), false).is_err());
+    }
+
+    #[test]
+    fn other_values() {
+        assert_eq!(has_value("/count", json!(1), false), Ok(false));
+        assert_eq!(has_value("/missing", json!(1), false), Ok(false));
+    }
+}
+
+#[cfg(test)]
+mod index_of_test {
+    use super::{do_index_of, field_from_str, Pointer, SubCommandIndexOf};
+    use serde_json::{json, Value};
+
+    fn index_of(value: Option<Value>, by: Option<&str>) -> Result<Vec<usize>, String> {
+        let data = json!({
+            "items": [{"id": 1, "tags": ["a"]}, {"id": 3}, 2, {"id": 3.0}],
+            "name": "text"
+        });
+
+        do_index_of(SubCommandIndexOf {
+            all: false,
+            by: by.map(|x| field_from_str(x).unwrap()),
+            variable: data.to_string(),
+            pointer: Pointer::new_unwrap("/items"),
+            value,
+        })
+        .map_err(|e| e.message)
+    }
+
+    #[test]
+    fn whole_items() {
+        assert_eq!(index_of(Some(json!({"id": 3})), None), Ok(vec![1, 3]));
+        assert_eq!(index_of(Some(json!(2)), None), Ok(vec![2]));
+        assert_eq!(index_of(Some(json!(4)), None), Ok(vec![]));
+    }
+
+    #[test]
+    fn by_field() {
+        assert_eq!(index_of(None, Some("/id=3")), Ok(vec![1, 3]));
+        assert_eq!(index_of(None, Some("/tags/0=a")), Ok(vec![0]));
+        assert_eq!(index_of(None, Some("/tags/0=\"a\"")), Ok(vec![0]));
+        assert!(index_of(Some(json!(1)), Some("/id=1")).is_err());
+        assert!(index_of(None, None).is_err());
+    }
+
+    #[test]
+    fn field_parsing() {
+        assert_eq!(
+            field_from_str("/a=b=c").unwrap(),
+            (Pointer::new_unwrap("/a"), json!("b=c"))
         );
+        assert!(field_from_str("/a").is_err());
     }
 }
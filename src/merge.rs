@@ -0,0 +1,831 @@
+use argh::FromArgs;
+use serde_json::{Map, Value};
+
+use crate::pointer::escape;
+use crate::{
+    diff, exit, input, number, value_printer, variable_or_object, variable_or_value, verbose,
+};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "merge",
+    description = "Deep merge two or more maps, by default the later maps win",
+    note = "The maps are merged from left to right: objects are merged key by key, anything else, arrays included, is replaced by the value of the later map. Every map can be json text, an environment variable or @path, like `bash_map merge DEFAULTS ENV_OVERRIDES @local.json`.
+--strategy changes what happens when both maps have something other than two objects at the same place, at any depth: overwrite (default) takes the later value, keep keeps the earlier value and only adds what is missing, concat-arrays appends the items of the later array to the earlier one and otherwise overwrites, error fails with the pointer of the first place where the values differ.
+Environment variables that are not set count as {{}}, use --strict to fail on them instead.",
+    example = r#"maps                                            arguments                   output
+'{{"a": {{"b": 1, "c": 2}}}}' '{{"a": {{"c": 3}}}}'                                 {{"a":{{"b":1,"c":3}}}}
+'{{"a": [1, 2]}}' '{{"a": [3]}}' '{{"b": null}}'                                  {{"a":[3],"b":null}}
+'{{"a": [1, 2], "b": 1}}' '{{"a": [3], "b": 2}}'    --strategy keep             {{"a":[1,2],"b":1}}
+'{{"a": [1, 2]}}' '{{"a": [3]}}'                    --strategy concat-arrays    {{"a":[1,2,3]}}"#
+)]
+pub struct SubCommandMerge {
+    #[argh(positional)]
+    pub variables: Vec<String>,
+    #[argh(option, default = "Strategy::Overwrite")]
+    /// how conflicting values are merged: overwrite (default), keep, concat-arrays or error
+    strategy: Strategy,
+    #[argh(switch)]
+    /// fail when an environment variable is not set
+    strict: bool,
+    #[argh(switch)]
+    /// print what the later maps change in the first one as `pointer: old -> new` lines, exits with 1 when they change nothing
+    pub dry_run: bool,
+}
+
+/// What `merge` does with two values at the same place that are not both objects.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// the later value replaces the earlier one
+    #[default]
+    Overwrite,
+    /// the earlier value stays
+    Keep,
+    /// arrays are appended to each other, other values are overwritten
+    ConcatArrays,
+    /// values that differ are an error
+    Error,
+}
+
+impl std::str::FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "overwrite" => Ok(Strategy::Overwrite),
+            "keep" => Ok(Strategy::Keep),
+            "concat-arrays" => Ok(Strategy::ConcatArrays),
+            "error" => Ok(Strategy::Error),
+            _ => Err(format!(
+                "unknown merge strategy {:?}, use overwrite, keep, concat-arrays or error",
+                s
+            )),
+        }
+    }
+}
+
+pub fn do_merge(args: SubCommandMerge, pretty: bool) -> Result<String, exit::Error> {
+    if args.variables.len() < 2 {
+        return Err(exit::usage(String::from("give at least two maps to merge")));
+    }
+
+    let mut first = None;
+    let mut merged = None;
+    for variable in &args.variables {
+        if input::strict(args.strict) {
+            if let input::Source::Env(name) = input::Source::of(variable) {
+                if std::env::var_os(name).is_none() {
+                    return Err(input::not_set(name));
+                }
+            }
+        }
+        let value = variable_or_object(variable)?;
+        match &mut merged {
+            None => {
+                if args.dry_run {
+                    first = Some(value.clone());
+                }
+                merged = Some(value)
+            }
+            Some(target) => merge(target, value, args.strategy, "")?,
+        }
+    }
+    let merged = merged.unwrap_or_default();
+    match first {
+        Some(first) => Ok(diff::dry_run(&first, &merged)?),
+        None => Ok(value_printer(pretty, &merged)),
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "resolve",
+    description = "Deep merge layers of configuration in order, failing on the ones that are missing",
+    note = "The sources are merged like merge does, later sources win. A source is json text, an environment variable or @path, and one ending in ? is optional: it is left out when the variable is not set or the file does not exist. Missing sources without ? fail, all of them are listed. When all sources are optional and missing the output is {{}}.
+With --verbose the source that gave each top-level key is printed to stderr.",
+    example = r#"sources                                                    output
+'{{"port": 80, "debug": false}}' APP_CONFIG? @local.json?    {{"port":80,"debug":false}} when the other two are missing"#
+)]
+pub struct SubCommandResolve {
+    #[argh(positional)]
+    pub sources: Vec<String>,
+    #[argh(option, default = "Strategy::Overwrite")]
+    /// how conflicting values are merged: overwrite (default), keep, concat-arrays or error
+    strategy: Strategy,
+}
+
+pub fn do_resolve(args: SubCommandResolve, pretty: bool) -> Result<String, exit::Error> {
+    if args.sources.is_empty() {
+        return Err(exit::usage(String::from("give at least one source")));
+    }
+
+    let mut layers = Vec::new();
+    let mut missing = Vec::new();
+    for source in &args.sources {
+        let (name, optional) = match source.strip_suffix('?') {
+            Some(name) => (name, true),
+            None => (source.as_str(), false),
+        };
+        let present = match input::Source::of(name) {
+            input::Source::Env(name) => std::env::var_os(name).is_some(),
+            input::Source::File(path) => path.exists(),
+            input::Source::Literal(_) => true,
+        };
+        match (present, optional) {
+            (true, _) => layers.push(name),
+            (false, true) => verbose::log(1, || format!("optional source {} is missing", name)),
+            (false, false) => missing.push(name),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(format!("missing sources: {}", missing.join(", ")).into());
+    }
+
+    let mut merged = None;
+    let mut providers = Vec::new();
+    for layer in layers {
+        let value = variable_or_object(layer)?;
+        if let Value::Object(map) = &value {
+            for key in map.keys() {
+                match providers.iter_mut().find(|(found, _)| found == key) {
+                    Some(provider) if args.strategy != Strategy::Keep => provider.1 = layer,
+                    Some(_) => (),
+                    None => providers.push((key.clone(), layer)),
+                }
+            }
+        }
+        match &mut merged {
+            None => merged = Some(value),
+            Some(target) => merge(target, value, args.strategy, "")?,
+        }
+    }
+    for (key, layer) in providers {
+        verbose::log(1, || format!("{:?} is from {}", key, layer));
+    }
+    // with every source optional and missing there is still an (empty) config
+    let merged = merged.unwrap_or_else(|| Value::Object(Map::new()));
+    Ok(value_printer(pretty, &merged))
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "defaults",
+    description = "Fill in the values of the defaults that the map is missing",
+    note = "Objects are filled key by key at any depth, a key the map does not have gets the whole value of the defaults. Anything the map has is kept: scalars and arrays are never replaced, and values of the defaults under a key that is not an object in the map are left out.
+A key set to null counts as set, unless --fill-null is given.",
+    example = r#"map                      defaults                                  arguments      output
+{{"a": {{"b": 1}}}}          {{"a": {{"b": 2, "c": 3}}, "d": {{"e": 4}}}}                   {{"a":{{"b":1,"c":3}},"d":{{"e":4}}}}
+{{"a": null, "b": "x"}}    {{"a": 1, "b": {{"c": 2}}}}                                  {{"a":null,"b":"x"}}
+{{"a": null, "b": "x"}}    {{"a": 1, "b": {{"c": 2}}}}                   --fill-null    {{"a":1,"b":"x"}}"#
+)]
+pub struct SubCommandDefaults {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pub defaults: String,
+    #[argh(switch)]
+    /// fill keys that are null in the map as well
+    fill_null: bool,
+}
+
+pub fn do_defaults(args: SubCommandDefaults, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    let defaults = variable_or_object(&args.defaults)?;
+    fill(&mut value, defaults, args.fill_null);
+    Ok(value_printer(pretty, &value))
+}
+
+/// Adds the keys of the defaults the target does not have, in objects at any depth.
+fn fill(target: &mut Value, defaults: Value, fill_null: bool) {
+    if let (Value::Object(target), Value::Object(defaults)) = (target, defaults) {
+        for (key, default) in defaults {
+            match target.get_mut(&key) {
+                Some(item) if fill_null && item.is_null() => *item = default,
+                Some(item) => fill(item, default, fill_null),
+                None => {
+                    target.insert(key, default);
+                }
+            }
+        }
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "merge3",
+    description = "Merge the changes two maps made to a common base, like a three-way merge in git",
+    note = "At every place the side that changed the base wins, when both changed different keys of an object both changes are taken. A key that is missing on one side was removed there. Arrays are compared as a whole.
+When both sides changed the same place to different values nothing is merged: a json array of the conflicts with their pointer and base, ours and theirs values is printed and it exits with 1. A side that does not have the key is left out of its conflict.
+With --union conflicts are resolved by keeping both sides: the items of their array that ours does not have are added to ours, and other values are replaced by an array of ours and theirs. The pointers of the conflicts are printed to stderr.
+With --annotate the merged map is printed with every conflict replaced by an object holding ours, theirs and base under the keys <<<<<<<, >>>>>>> and |||||||, and it exits with 1 when there are any. Fix them in an editor or keep one side with resolve-conflicts. When a map has one of the keys already choose others with --marker-ours, --marker-theirs and --marker-base.",
+    example = r#"base                  ours                  theirs                arguments    output
+{{"a": 1, "b": 1}}      {{"a": 2, "b": 1}}      {{"a": 1, "b": 3}}                   {{"a":2,"b":3}}
+{{"a": 1}}              {{"a": 2}}              {{"a": 3}}                           [{{"pointer":"/a","base":1,"ours":2,"theirs":3}}]
+{{"l": [1], "s": 1}}    {{"l": [2], "s": 2}}    {{"l": [3], "s": 3}}    --union      {{"l":[2,3],"s":[2,3]}}
+{{"a": 1}}              {{"a": 2}}              {{"a": 3}}              --annotate   {{"a":{{"<<<<<<<":2,">>>>>>>":3,"|||||||":1}}}}"#
+)]
+pub struct SubCommandMerge3 {
+    #[argh(positional)]
+    pub base: String,
+    #[argh(positional)]
+    pub ours: String,
+    #[argh(positional)]
+    pub theirs: String,
+    #[argh(switch)]
+    /// keep both sides of a conflict instead of failing
+    union: bool,
+    #[argh(switch)]
+    /// replace conflicts by an object holding both sides and the base
+    annotate: bool,
+    #[argh(option, default = "String::from(\"<<<<<<<\")")]
+    /// the key of ours in the objects of --annotate (default <<<<<<<)
+    marker_ours: String,
+    #[argh(option, default = "String::from(\">>>>>>>\")")]
+    /// the key of theirs in the objects of --annotate (default >>>>>>>)
+    marker_theirs: String,
+    #[argh(option, default = "String::from(\"|||||||\")")]
+    /// the key of the base in the objects of --annotate (default |||||||)
+    marker_base: String,
+}
+
+/// The keys of the object that replaces a conflict with `--annotate`.
+#[derive(Debug, Clone, PartialEq)]
+struct Markers {
+    ours: String,
+    theirs: String,
+    base: String,
+}
+
+impl Markers {
+    fn new(ours: String, theirs: String, base: String) -> Result<Markers, exit::Error> {
+        if ours == theirs || ours == base || theirs == base {
+            return Err(exit::usage(String::from(
+                "the markers of ours, theirs and the base have to differ",
+            )));
+        }
+        Ok(Markers { ours, theirs, base })
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        key == self.ours || key == self.theirs || key == self.base
+    }
+
+    /// The pointer of the first object key in the value that is a marker.
+    fn find(&self, value: &Value, pointer: String) -> Option<String> {
+        match value {
+            Value::Object(map) => map.iter().find_map(|(key, item)| {
+                let path = format!("{}/{}", pointer, escape(key));
+                match self.contains(key) {
+                    true => Some(path),
+                    false => self.find(item, path),
+                }
+            }),
+            Value::Array(list) => list
+                .iter()
+                .enumerate()
+                .find_map(|(index, item)| self.find(item, format!("{}/{}", pointer, index))),
+            _ => None,
+        }
+    }
+}
+
+/// What `merge3` puts in the place of a conflict.
+enum Resolution<'m> {
+    /// ours, the conflicts are reported instead of the map
+    Ours,
+    /// both sides, for `--union`
+    Union,
+    /// an object holding the sides under the markers, for `--annotate`
+    Annotate(&'m Markers),
+}
+
+/// A place where ours and theirs changed the base to different values, `None` where the key is missing.
+#[derive(Debug, PartialEq)]
+struct Conflict<'a> {
+    pointer: String,
+    base: Option<&'a Value>,
+    ours: Option<&'a Value>,
+    theirs: Option<&'a Value>,
+}
+
+/// The merged map, or the conflicts with `true` when they could not be resolved.
+pub fn do_merge3(args: SubCommandMerge3, pretty: bool) -> Result<(String, bool), exit::Error> {
+    let base = variable_or_value(&args.base)?;
+    let ours = variable_or_value(&args.ours)?;
+    let theirs = variable_or_value(&args.theirs)?;
+
+    let markers = Markers::new(args.marker_ours, args.marker_theirs, args.marker_base)?;
+    let resolution = match (args.union, args.annotate) {
+        (true, true) => {
+            return Err(exit::usage(String::from(
+                "--union can not be combined with --annotate",
+            )))
+        }
+        (true, false) => Resolution::Union,
+        (false, true) => {
+            for (name, value) in [("base", &base), ("ours", &ours), ("theirs", &theirs)] {
+                if let Some(pointer) = markers.find(value, String::new()) {
+                    return Err(exit::usage(format!(
+                        "{} has a marker key at {:?}, choose other markers",
+                        name, pointer
+                    )));
+                }
+            }
+            Resolution::Annotate(&markers)
+        }
+        (false, false) => Resolution::Ours,
+    };
+
+    let mut conflicts = Vec::new();
+    let merged = merge3(
+        Some(&base),
+        Some(&ours),
+        Some(&theirs),
+        &resolution,
+        String::new(),
+        &mut conflicts,
+    );
+    let merged = value_printer(pretty, &merged.unwrap_or_default());
+    match resolution {
+        _ if conflicts.is_empty() => return Ok((merged, false)),
+        Resolution::Union => {
+            for conflict in &conflicts {
+                eprintln!("conflict at {:?}, kept both sides", conflict.pointer);
+            }
+            return Ok((merged, false));
+        }
+        Resolution::Annotate(_) => {
+            for conflict in &conflicts {
+                eprintln!("conflict at {:?}, annotated", conflict.pointer);
+            }
+            return Ok((merged, true));
+        }
+        Resolution::Ours => (),
+    }
+
+    let report: Vec<Value> = conflicts
+        .into_iter()
+        .map(|conflict| {
+            let mut map = Map::new();
+            map.insert(String::from("pointer"), Value::String(conflict.pointer));
+            for (side, value) in [
+                ("base", conflict.base),
+                ("ours", conflict.ours),
+                ("theirs", conflict.theirs),
+            ] {
+                if let Some(value) = value {
+                    map.insert(String::from(side), value.clone());
+                }
+            }
+            Value::Object(map)
+        })
+        .collect();
+    Ok((value_printer(pretty, &Value::Array(report)), true))
+}
+
+/// Merges what ours and theirs changed in base at the pointer, `None` is a missing key.
+/// Conflicts are collected and replaced as the resolution says.
+fn merge3<'a>(
+    base: Option<&'a Value>,
+    ours: Option<&'a Value>,
+    theirs: Option<&'a Value>,
+    resolution: &Resolution,
+    pointer: String,
+    conflicts: &mut Vec<Conflict<'a>>,
+) -> Option<Value> {
+    let same = |first: Option<&Value>, second: Option<&Value>| match (first, second) {
+        (Some(first), Some(second)) => number::values_equal(first, second),
+        (first, second) => first.is_none() && second.is_none(),
+    };
+    if same(ours, theirs) || same(base, theirs) {
+        return ours.cloned();
+    }
+    if same(base, ours) {
+        return theirs.cloned();
+    }
+
+    match (ours, theirs) {
+        (Some(Value::Object(ours)), Some(Value::Object(theirs))) => {
+            // keys both sides added count as added to an empty base
+            let base = base.and_then(Value::as_object);
+            let keys = ours
+                .keys()
+                .chain(theirs.keys().filter(|key| !ours.contains_key(*key)));
+            let merged = keys
+                .filter_map(|key| {
+                    let value = merge3(
+                        base.and_then(|x| x.get(key)),
+                        ours.get(key),
+                        theirs.get(key),
+                        resolution,
+                        format!("{}/{}", pointer, escape(key)),
+                        conflicts,
+                    )?;
+                    Some((key.clone(), value))
+                })
+                .collect();
+            Some(Value::Object(merged))
+        }
+        _ => {
+            conflicts.push(Conflict {
+                pointer,
+                base,
+                ours,
+                theirs,
+            });
+            let union = matches!(resolution, Resolution::Union);
+            match (ours, theirs) {
+                (Some(Value::Array(ours)), Some(Value::Array(theirs))) if union => {
+                    let mut items = ours.clone();
+                    for item in theirs {
+                        if !ours.iter().any(|x| number::values_equal(x, item)) {
+                            items.push(item.clone());
+                        }
+                    }
+                    Some(Value::Array(items))
+                }
+                (Some(ours), Some(theirs)) if union => {
+                    Some(Value::Array(vec![ours.clone(), theirs.clone()]))
+                }
+                (ours, theirs) if union => ours.or(theirs).cloned(),
+                (ours, _) => match resolution {
+                    Resolution::Annotate(markers) => {
+                        let mut sides = Map::new();
+                        for (marker, value) in [
+                            (&markers.ours, ours),
+                            (&markers.theirs, theirs),
+                            (&markers.base, base),
+                        ] {
+                            if let Some(value) = value {
+                                sides.insert(marker.clone(), value.clone());
+                            }
+                        }
+                        Some(Value::Object(sides))
+                    }
+                    _ => ours.cloned(),
+                },
+            }
+        }
+    }
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "resolve-conflicts",
+    description = "Replace the conflicts merge3 --annotate left in the map by one of their sides",
+    note = "A conflict is an object with the key of ours or theirs and no keys other than the markers, at any depth. When the side to take is missing from a conflict its key is removed, or its item from an array.
+Give the same --marker-ours, --marker-theirs and --marker-base as to merge3.",
+    example = r#"input                                                arguments        output
+{{"a": {{"<<<<<<<": 2, ">>>>>>>": 3, "|||||||": 1}}}}    --take ours      {{"a":2}}
+{{"a": {{"<<<<<<<": 2, "|||||||": 1}}, "b": 1}}          --take theirs    {{"b":1}}"#
+)]
+pub struct SubCommandResolveConflicts {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(option)]
+    /// the side to keep: ours or theirs
+    take: Side,
+    #[argh(option, default = "String::from(\"<<<<<<<\")")]
+    /// the key of ours in the conflicts (default <<<<<<<)
+    marker_ours: String,
+    #[argh(option, default = "String::from(\">>>>>>>\")")]
+    /// the key of theirs in the conflicts (default >>>>>>>)
+    marker_theirs: String,
+    #[argh(option, default = "String::from(\"|||||||\")")]
+    /// the key of the base in the conflicts (default |||||||)
+    marker_base: String,
+}
+
+/// The side `resolve-conflicts` keeps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+impl std::str::FromStr for Side {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ours" => Ok(Side::Ours),
+            "theirs" => Ok(Side::Theirs),
+            _ => Err(format!("unknown side {:?}, use ours or theirs", s)),
+        }
+    }
+}
+
+pub fn do_resolve_conflicts(
+    args: SubCommandResolveConflicts,
+    pretty: bool,
+) -> Result<String, exit::Error> {
+    let markers = Markers::new(args.marker_ours, args.marker_theirs, args.marker_base)?;
+    let value = variable_or_value(&args.variable)?;
+    let marker = match args.take {
+        Side::Ours => &markers.ours,
+        Side::Theirs => &markers.theirs,
+    };
+    let resolved = take(value, &markers, marker).unwrap_or_default();
+    Ok(value_printer(pretty, &resolved))
+}
+
+/// The value with its conflicts replaced by the side under `marker`, `None` when the side is missing.
+fn take(value: Value, markers: &Markers, marker: &str) -> Option<Value> {
+    match value {
+        Value::Object(mut map) if is_conflict(&map, markers) => {
+            take(map.shift_remove(marker)?, markers, marker)
+        }
+        Value::Object(map) => Some(Value::Object(
+            map.into_iter()
+                .filter_map(|(key, item)| Some((key, take(item, markers, marker)?)))
+                .collect(),
+        )),
+        Value::Array(list) => Some(Value::Array(
+            list.into_iter()
+                .filter_map(|item| take(item, markers, marker))
+                .collect(),
+        )),
+        other => Some(other),
+    }
+}
+
+fn is_conflict(map: &Map<String, Value>, markers: &Markers) -> bool {
+    (map.contains_key(&markers.ours) || map.contains_key(&markers.theirs))
+        && map.keys().all(|key| markers.contains(key))
+}
+
+/// Merges objects key by key, the strategy decides about everything else.
+/// `path` is the pointer of the target, for the error of `Strategy::Error`.
+fn merge(target: &mut Value, value: Value, strategy: Strategy, path: &str) -> Result<(), String> {
+    match (target, value, strategy) {
+        (Value::Object(target), Value::Object(value), _) => {
+            for (key, value) in value {
+                match target.get_mut(&key) {
+                    Some(item) => {
+                        let token = escape(&key);
+                        merge(item, value, strategy, &format!("{}/{}", path, token))?
+                    }
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(target), Value::Array(value), Strategy::ConcatArrays) => target.extend(value),
+        (_, _, Strategy::Keep) => (),
+        (target, value, Strategy::Error) => {
+            if !number::values_equal(target, &value) {
+                return Err(format!(
+                    "conflicting values at {:?}: {} and {}",
+                    path, target, value
+                ));
+            }
+        }
+        (target, value, _) => *target = value,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod merge_test {
+    use super::{fill, merge, merge3, take, Conflict, Markers, Resolution, Strategy};
+    use serde_json::{json, Value};
+
+    fn merged(strategy: Strategy) -> Result<Value, String> {
+        let mut target = json!({
+            "name": "app",
+            "tags": ["a"],
+            "server": {"port": 80, "hosts": ["x"], "tls": {"a": {"b": {"c": {"d": 1}}}}}
+        });
+        let value = json!({
+            "tags": ["b"],
+            "debug": true,
+            "server": {"port": 8080, "hosts": ["y"], "tls": {"a": {"b": {"c": {"d": 2, "e": 3}}}}}
+        });
+        merge(&mut target, value, strategy, "").map(|_| target)
+    }
+
+    #[test]
+    fn overwrite() {
+        assert_eq!(
+            merged(Strategy::Overwrite).unwrap(),
+            json!({
+                "name": "app",
+                "tags": ["b"],
+                "server": {"port": 8080, "hosts": ["y"], "tls": {"a": {"b": {"c": {"d": 2, "e": 3}}}}},
+                "debug": true
+            })
+        );
+    }
+
+    #[test]
+    fn keep() {
+        assert_eq!(
+            merged(Strategy::Keep).unwrap(),
+            json!({
+                "name": "app",
+                "tags": ["a"],
+                "server": {"port": 80, "hosts": ["x"], "tls": {"a": {"b": {"c": {"d": 1, "e": 3}}}}},
+                "debug": true
+            })
+        );
+    }
+
+    #[test]
+    fn concat_arrays() {
+        assert_eq!(
+            merged(Strategy::ConcatArrays).unwrap(),
+            json!({
+                "name": "app",
+                "tags": ["a", "b"],
+                "server": {"port": 8080, "hosts": ["x", "y"], "tls": {"a": {"b": {"c": {"d": 2, "e": 3}}}}},
+                "debug": true
+            })
+        );
+    }
+
+    #[test]
+    fn error() {
+        assert_eq!(
+            merged(Strategy::Error).unwrap_err(),
+            r#"conflicting values at "/tags": ["a"] and ["b"]"#
+        );
+
+        let mut target = json!({"a": {"b": {"c": {"d": {"e": 1, "f": 1}}}}});
+        let value = json!({"a": {"b": {"c": {"d": {"e": 1.0, "g": 2}}}}});
+        merge(&mut target, value, Strategy::Error, "").unwrap();
+        assert_eq!(
+            target,
+            json!({"a": {"b": {"c": {"d": {"e": 1, "f": 1, "g": 2}}}}})
+        );
+
+        let value = json!({"a": {"b": {"c": {"d": {"f": "1"}}}}});
+        assert_eq!(
+            merge(&mut target, value, Strategy::Error, "").unwrap_err(),
+            r#"conflicting values at "/a/b/c/d/f": 1 and "1""#
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let mut target = json!({"a": {"b": 1, "n": null}, "s": "x", "l": [1]});
+        let defaults =
+            json!({"a": {"b": 2, "c": 3, "n": 4}, "s": {"t": 1}, "l": [2], "d": {"e": {"f": 5}}});
+        fill(&mut target, defaults.clone(), false);
+        assert_eq!(
+            target,
+            json!({"a": {"b": 1, "n": null, "c": 3}, "s": "x", "l": [1], "d": {"e": {"f": 5}}})
+        );
+
+        let mut target = json!({"a": {"n": null}, "m": null});
+        fill(&mut target, json!({"a": {"n": 4}, "m": {"x": 1}}), true);
+        assert_eq!(target, json!({"a": {"n": 4}, "m": {"x": 1}}));
+    }
+
+    #[test]
+    fn three_way() {
+        let base = json!({"a": 1, "b": {"c": 1, "d": 1}, "gone": 1, "l": [1]});
+        let ours = json!({"a": 2, "b": {"c": 1, "d": 2}, "l": [1], "new": 1});
+        let theirs = json!({"a": 1, "b": {"c": 3, "d": 1}, "gone": 1, "l": [1, 2], "new": 1.0});
+        let mut conflicts = Vec::new();
+        assert_eq!(
+            merge3(
+                Some(&base),
+                Some(&ours),
+                Some(&theirs),
+                &Resolution::Ours,
+                String::new(),
+                &mut conflicts
+            ),
+            Some(json!({"a": 2, "b": {"c": 3, "d": 2}, "l": [1, 2], "new": 1}))
+        );
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn three_way_conflicts() {
+        let base = json!({"a": 1, "b": {"c": 1}, "e/f": 1, "l": [1]});
+        let ours = json!({"a": 2, "b": {"c": 2}, "l": [1, 2]});
+        let theirs = json!({"a": 2, "b": {"c": 3}, "e/f": 2, "l": [1, 3], "x": {"y": 1}});
+        let mut conflicts = Vec::new();
+        merge3(
+            Some(&base),
+            Some(&ours),
+            Some(&theirs),
+            &Resolution::Ours,
+            String::new(),
+            &mut conflicts,
+        );
+        assert_eq!(
+            conflicts,
+            vec![
+                Conflict {
+                    pointer: String::from("/b/c"),
+                    base: Some(&json!(1)),
+                    ours: Some(&json!(2)),
+                    theirs: Some(&json!(3)),
+                },
+                Conflict {
+                    pointer: String::from("/l"),
+                    base: Some(&json!([1])),
+                    ours: Some(&json!([1, 2])),
+                    theirs: Some(&json!([1, 3])),
+                },
+                Conflict {
+                    pointer: String::from("/e~1f"),
+                    base: Some(&json!(1)),
+                    ours: None,
+                    theirs: Some(&json!(2)),
+                },
+            ]
+        );
+
+        let mut conflicts = Vec::new();
+        assert_eq!(
+            merge3(
+                Some(&base),
+                Some(&ours),
+                Some(&theirs),
+                &Resolution::Union,
+                String::new(),
+                &mut conflicts
+            ),
+            Some(json!({"a": 2, "b": {"c": [2, 3]}, "l": [1, 2, 3], "e/f": 2, "x": {"y": 1}}))
+        );
+        assert_eq!(conflicts.len(), 3);
+    }
+
+    fn markers() -> Markers {
+        Markers::new(
+            String::from("<<<<<<<"),
+            String::from(">>>>>>>"),
+            String::from("|||||||"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn annotate_round_trip() {
+        let base = json!({"a": {"b": {"c": 1, "d": [1]}, "e": 1}, "f": 1, "g": 1});
+        let ours = json!({"a": {"b": {"c": 2, "d": [1]}, "e": 2}, "g": 2});
+        let theirs = json!({"a": {"b": {"c": 3, "d": [2]}, "e": 1}, "f": 2, "g": 1});
+        let markers = markers();
+        let mut conflicts = Vec::new();
+        let annotated = merge3(
+            Some(&base),
+            Some(&ours),
+            Some(&theirs),
+            &Resolution::Annotate(&markers),
+            String::new(),
+            &mut conflicts,
+        )
+        .unwrap();
+        assert_eq!(
+            annotated,
+            json!({
+                "a": {
+                    "b": {
+                        "c": {"<<<<<<<": 2, ">>>>>>>": 3, "|||||||": 1},
+                        "d": [2]
+                    },
+                    "e": 2
+                },
+                "g": 2,
+                "f": {">>>>>>>": 2, "|||||||": 1}
+            })
+        );
+        assert_eq!(conflicts.len(), 2);
+
+        assert_eq!(
+            take(annotated.clone(), &markers, &markers.ours),
+            Some(json!({"a": {"b": {"c": 2, "d": [2]}, "e": 2}, "g": 2}))
+        );
+        assert_eq!(
+            take(annotated, &markers, &markers.theirs),
+            Some(json!({"a": {"b": {"c": 3, "d": [2]}, "e": 2}, "g": 2, "f": 2}))
+        );
+    }
+
+    #[test]
+    fn markers_in_maps() {
+        let markers = markers();
+        assert_eq!(
+            markers.find(&json!({"a": [{"|||||||": 1}]}), String::new()),
+            Some(String::from("/a/0/|||||||"))
+        );
+        assert_eq!(
+            markers.find(&json!({"a": ["<<<<<<<"]}), String::new()),
+            None
+        );
+        assert!(Markers::new(String::from("x"), String::from("y"), String::from("x")).is_err());
+
+        // objects with other keys or only the base are not conflicts
+        let value = json!({"a": {"<<<<<<<": 1, "b": 2}, "c": [{"|||||||": 1}]});
+        assert_eq!(take(value.clone(), &markers, &markers.ours), Some(value));
+    }
+}
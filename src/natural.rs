@@ -0,0 +1,108 @@
+use std::cmp::Ordering;
+
+/// Orders strings like people do, with runs of digits compared by their value,
+/// so `item2` comes before `item10` and `v1.9.0` before `v1.10.0`.
+///
+/// Everything else is compared by code point. Only the ASCII digits 0-9 are digits,
+/// other digits like `٣` are compared as text. Strings that are only different in
+/// leading zeros, like `a01` and `a1`, are ordered by code point, so the order is total.
+pub fn compare(first: &str, second: &str) -> Ordering {
+    let (mut left, mut right) = (first, second);
+    loop {
+        let (a, b) = match (left.chars().next(), right.chars().next()) {
+            (None, None) => return first.cmp(second),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => (a, b),
+        };
+        let order = if a.is_ascii_digit() && b.is_ascii_digit() {
+            let (a, rest) = split_digits(left);
+            left = rest;
+            let (b, rest) = split_digits(right);
+            right = rest;
+            compare_digits(a, b)
+        } else {
+            left = &left[a.len_utf8()..];
+            right = &right[b.len_utf8()..];
+            a.cmp(&b)
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+}
+
+/// The leading ASCII digits of the text and the rest.
+fn split_digits(text: &str) -> (&str, &str) {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text.split_at(end)
+}
+
+/// Compares runs of digits by value, however long they are.
+fn compare_digits(first: &str, second: &str) -> Ordering {
+    let first = first.trim_start_matches('0');
+    let second = second.trim_start_matches('0');
+    first
+        .len()
+        .cmp(&second.len())
+        .then_with(|| first.cmp(second))
+}
+
+#[cfg(test)]
+mod natural_test {
+    use super::compare;
+    use std::cmp::Ordering;
+
+    fn sorted(items: &[&str]) -> Vec<String> {
+        let mut items: Vec<String> = items.iter().map(|x| x.to_string()).collect();
+        items.sort_by(|a, b| compare(a, b));
+        items
+    }
+
+    #[test]
+    fn numbers_by_value() {
+        assert_eq!(
+            sorted(&["item10", "item2", "item1", "item20", "item3"]),
+            ["item1", "item2", "item3", "item10", "item20"]
+        );
+        assert_eq!(
+            sorted(&["v1.10.0", "v1.9.0", "v1.9.10", "v1.9.2", "v10.0.0"]),
+            ["v1.9.0", "v1.9.2", "v1.9.10", "v1.10.0", "v10.0.0"]
+        );
+        assert_eq!(
+            sorted(&["99", "123456789012345678901234567890", "100", "9"]),
+            ["9", "99", "100", "123456789012345678901234567890"]
+        );
+    }
+
+    #[test]
+    fn text_and_prefixes() {
+        assert_eq!(
+            sorted(&["b", "a10", "a", "A2", ""]),
+            ["", "A2", "a", "a10", "b"]
+        );
+        assert_eq!(compare("file", "file1"), Ordering::Less);
+        assert_eq!(compare("x1y", "x1"), Ordering::Greater);
+        assert_eq!(compare("same", "same"), Ordering::Equal);
+    }
+
+    #[test]
+    fn leading_zeros() {
+        assert_eq!(compare("a01", "a1"), Ordering::Less);
+        assert_eq!(compare("a001", "a01"), Ordering::Less);
+        assert_eq!(compare("a010", "a9"), Ordering::Greater);
+        assert_eq!(
+            sorted(&["a1", "a01", "a2", "a0"]),
+            ["a0", "a01", "a1", "a2"]
+        );
+    }
+
+    #[test]
+    fn other_digits_are_text() {
+        // Arabic-Indic digits are not compared by value
+        assert_eq!(compare("\u{663}", "\u{661}\u{660}"), Ordering::Greater);
+        assert_eq!(compare("é2", "é10"), Ordering::Less);
+    }
+}
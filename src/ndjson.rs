@@ -0,0 +1,106 @@
+use std::io::{BufRead, Write};
+
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::{
+    depth, exit, input, output, pointer_mut, select, value_from_str, value_printer, Pointer,
+};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "ndjson",
+    description = "Run get or set on every line of newline delimited json read from stdin",
+    note = "Every line of stdin is a json document and gives one line of output, written as soon as the line is read, so `tail -f app.ndjson | bash_map ndjson get /level` follows a live log. Empty lines are skipped. Lines that are not valid json are reported on stderr with their line number and skipped, or stop the run with --strict."
+)]
+pub struct SubCommandNdjson {
+    #[argh(switch)]
+    /// stop at the first line that is not valid json
+    strict: bool,
+    #[argh(subcommand)]
+    command: NdjsonCommand,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(subcommand)]
+enum NdjsonCommand {
+    Get(SubCommandNdjsonGet),
+    Set(SubCommandNdjsonSet),
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+/// Print the item at the pointer of every line, an empty line when it is missing
+#[argh(subcommand, name = "get")]
+struct SubCommandNdjsonGet {
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(switch)]
+    /// print nothing instead of an empty line for lines without the item
+    skip_missing: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+/// Set the value at the pointer of every line and print the changed lines
+#[argh(subcommand, name = "set")]
+struct SubCommandNdjsonSet {
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional, from_str_fn(value_from_str))]
+    value: Value,
+}
+
+/// Filters stdin to stdout line by line, only one line is kept in memory at a time.
+pub fn do_ndjson(args: SubCommandNdjson) -> Result<(), exit::Error> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout().lock();
+
+    for (number, line) in stdin.lock().lines().enumerate() {
+        let line = line.map_err(|e| exit::io(format!("unable to read stdin: {}", e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value = match input::check_depth(&line).and_then(|_| Ok(input::parse(&line)?)) {
+            Ok(value) => value,
+            Err(e) if args.strict => {
+                return Err(exit::parse(format!(
+                    "line {}: invalid json: {}",
+                    number + 1,
+                    e
+                )))
+            }
+            Err(e) => {
+                eprintln!("line {}: invalid json: {}", number + 1, e);
+                continue;
+            }
+        };
+
+        let output = match &args.command {
+            NdjsonCommand::Get(args) => match select(&value, args.pointer.as_str()) {
+                Some(found) => value_printer(false, &found),
+                None if args.skip_missing => continue,
+                None => String::new(),
+            },
+            NdjsonCommand::Set(args) => {
+                let mut value = value;
+                if let Some(target) = pointer_mut(&mut value, args.pointer.as_str()) {
+                    *target = args.value.clone();
+                }
+                depth::check(&value, input::options().max_depth)?;
+                value_printer(false, &value)
+            }
+        };
+
+        if output::options().quiet {
+            continue;
+        }
+        // a closed stdout, like `| head -n 1`, ends the filter
+        if writeln!(stdout, "{}", output)
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+    Ok(())
+}
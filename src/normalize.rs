@@ -0,0 +1,107 @@
+use argh::FromArgs;
+use serde_json::{Number, Value};
+
+use crate::number::Decimal;
+use crate::{exit, json5, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "normalize",
+    description = "Print the canonical form of the value, the same text for every value that compares equal",
+    note = "Object keys are sorted (by code point) at every level and the output is compact json, --pretty and --output-format are ignored.
+Numbers are written by value: without trailing zeros, -0 as 0, in plain notation from 1e-6 up to 1e21 and otherwise as one digit, a fraction and an exponent (1.5e-7, 1e21). No digits are dropped.
+Infinity and NaN from json5 inputs become null.",
+    example = r#"input                          arguments               output
+{{"b": 1.50, "a": [1e3, -0]}}        ""                      {{"a":[1000,0],"b":1.5}}"#
+)]
+pub struct SubCommandNormalize {
+    #[argh(positional)]
+    pub variable: String,
+}
+
+pub fn do_normalize(args: SubCommandNormalize) -> Result<String, exit::Error> {
+    Ok(canonical(&variable_or_value(&args.variable)?))
+}
+
+/// The canonical text of the value, equal values have the same text.
+pub fn canonical(value: &Value) -> String {
+    let mut value = value.clone();
+    normalize(&mut value);
+    value.to_string()
+}
+
+fn normalize(value: &mut Value) {
+    match value {
+        Value::Number(number) if json5::is_non_finite(number) => *value = Value::Null,
+        Value::Number(number) => {
+            if let Some(decimal) = Decimal::parse(number.as_str()) {
+                *number = Number::from_string_unchecked(decimal.canonical());
+            }
+        }
+        Value::Object(map) => {
+            map.sort_keys();
+            map.values_mut().for_each(normalize);
+        }
+        Value::Array(list) => list.iter_mut().for_each(normalize),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod normalize_test {
+    use super::normalize;
+    use crate::number::values_equal;
+    use serde_json::{from_str, Value};
+
+    fn normalized(text: &str) -> String {
+        let mut value: Value = from_str(text).unwrap();
+        normalize(&mut value);
+        value.to_string()
+    }
+
+    #[test]
+    fn canonical_form() {
+        assert_eq!(
+            normalized(r#"{"b": [1.0, -0, 2.50e2], "a": {"d": null, "c": "x"}}"#),
+            r#"{"a":{"c":"x","d":null},"b":[1,0,250]}"#
+        );
+    }
+
+    #[test]
+    fn equal_values_normalize_to_the_same_text() {
+        let cases = [
+            r#"{"é": 1, "e": 2}"#,
+            r#"{"e": 2.0, "é": 1}"#,
+            r#"{"é": 1, "e": 2}"#,
+            r#"{"😀": "a", "￿": 0}"#,
+            r#"{"😀": "a", "￿": -0.0}"#,
+            r#"{"\u00e9": 1.0, "e": 2}"#,
+            r#"{"\ud83d\ude00": "a", "\uffff": 0e5}"#,
+            "[0, -0, 0.0, -0e10, 0E-3]",
+            "[-0, 0, 0e1, 0.000, -0.0]",
+            "[1e3, 1E+3, 1000, 1000.00, 0.1e4]",
+            "[1000, 1e3, 10e2, 1000.0, 100e1]",
+            "[1e21, 10e20, 1e-7, 0.0000001]",
+            "[1000000000000000000000, 1.0e21, 0.1e-6, 1e-7]",
+            "[1e20, 1.5e-7, 1e400]",
+            "[100000000000000000000, 0.00000015, 10e399]",
+            "[123456789012345678901234567890.5, -0.000001]",
+            "[1.234567890123456789012345678905e29, -1e-6]",
+            "[123456789012345678901234567890.50000000001, -1e-6]",
+        ];
+
+        for first in &cases {
+            for second in &cases {
+                let equal = values_equal(&from_str(first).unwrap(), &from_str(second).unwrap());
+                assert_eq!(
+                    equal,
+                    normalized(first) == normalized(second),
+                    "{} and {}",
+                    first,
+                    second
+                );
+            }
+        }
+    }
+}
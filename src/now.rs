@@ -0,0 +1,95 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+/// How `set --now` writes the current time.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// seconds since the epoch, a number
+    Unix,
+    /// milliseconds since the epoch, a number
+    UnixMs,
+    /// `2024-05-01T12:30:00Z`, a string
+    #[default]
+    Rfc3339,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unix" => Ok(Format::Unix),
+            "unix-ms" => Ok(Format::UnixMs),
+            "rfc3339" => Ok(Format::Rfc3339),
+            _ => Err(format!(
+                "unknown time format {:?}, use unix, unix-ms or rfc3339",
+                s
+            )),
+        }
+    }
+}
+
+/// The current time in the format.
+pub fn now(format: Format) -> Value {
+    // a clock before 1970 is not worth an error
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    match format {
+        Format::Unix => Value::from(since_epoch.as_secs()),
+        Format::UnixMs => Value::from(since_epoch.as_millis() as u64),
+        Format::Rfc3339 => Value::String(rfc3339(since_epoch.as_secs())),
+    }
+}
+
+/// The UTC time `seconds` after the epoch as RFC 3339 text.
+fn rfc3339(seconds: u64) -> String {
+    let (days, time) = (seconds / 86400, seconds % 86400);
+    let (year, month, day) = civil_from_days(days as i64);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// The date `days` after 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod now_test {
+    use super::rfc3339;
+
+    #[test]
+    fn dates() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(951782400), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339(1709251199), "2024-02-29T23:59:59Z");
+        assert_eq!(rfc3339(1735689600), "2025-01-01T00:00:00Z");
+        assert_eq!(rfc3339(4107542400), "2100-03-01T00:00:00Z");
+    }
+}
@@ -0,0 +1,508 @@
+use std::cmp::Ordering;
+
+use serde_json::{Number, Value};
+
+/// Exact decimal value of a json number, `0.<digits> * 10^exponent`.
+///
+/// Numbers keep the text they were written with (serde_json's `arbitrary_precision`),
+/// so `1`, `1.0` and `1e0` are different `Number`s, this is what they have in common.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Decimal {
+    negative: bool,
+    digits: String,
+    exponent: i64,
+}
+
+impl Decimal {
+    pub fn parse(text: &str) -> Option<Decimal> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(index) => (&text[..index], text[index + 1..].parse::<i64>().ok()?),
+            None => (text, 0),
+        };
+        let (integer, fraction) = match mantissa.find('.') {
+            Some(index) => (&mantissa[..index], &mantissa[index + 1..]),
+            None => (mantissa, ""),
+        };
+        if integer.is_empty()
+            || !integer
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let all_digits = format!("{}{}", integer, fraction);
+        let leading_zeros = all_digits.len() - all_digits.trim_start_matches('0').len();
+        let digits = all_digits.trim_matches('0').to_string();
+        if digits.is_empty() {
+            return Some(Decimal {
+                negative: false,
+                digits,
+                exponent: 0,
+            });
+        }
+
+        let exponent = exponent
+            .saturating_add(integer.len() as i64)
+            .saturating_sub(leading_zeros as i64);
+
+        Some(Decimal {
+            negative,
+            digits,
+            exponent,
+        })
+    }
+
+    /// The canonical text of the value, the same for every way of writing it.
+    ///
+    /// Trailing zeros are dropped and negative zero is `0`. Like JavaScript, values from `1e-6`
+    /// up to `1e21` (exclusive) are written in plain notation (`1000`, `0.25`), others with
+    /// one digit before the point and an exponent (`1e21`, `-1.5e-7`).
+    /// No digits are lost, so values that differ print differently.
+    pub fn canonical(&self) -> String {
+        if self.digits.is_empty() {
+            return String::from("0");
+        }
+        let sign = if self.negative { "-" } else { "" };
+        let length = self.digits.len() as i64;
+
+        match self.exponent {
+            point if length <= point && point <= 21 => {
+                format!(
+                    "{}{}{}",
+                    sign,
+                    self.digits,
+                    "0".repeat((point - length) as usize)
+                )
+            }
+            point if 0 < point && point <= 21 => {
+                let (integer, fraction) = self.digits.split_at(point as usize);
+                format!("{}{}.{}", sign, integer, fraction)
+            }
+            point if -6 < point && point <= 0 => {
+                format!("{}0.{}{}", sign, "0".repeat(-point as usize), self.digits)
+            }
+            point => {
+                let (first, rest) = self.digits.split_at(1);
+                let dot = if rest.is_empty() { "" } else { "." };
+                format!("{}{}{}{}e{}", sign, first, dot, rest, point - 1)
+            }
+        }
+    }
+}
+
+impl Decimal {
+    fn zero() -> Decimal {
+        Decimal {
+            negative: false,
+            digits: String::new(),
+            exponent: 0,
+        }
+    }
+
+    /// The value rounded to `places` decimals, halves are rounded away from zero.
+    fn round(&self, places: usize) -> Decimal {
+        let keep = self.exponent.saturating_add(places as i64);
+        if keep >= self.digits.len() as i64 {
+            return self.clone();
+        }
+        if keep < 0 {
+            return Decimal::zero();
+        }
+
+        let (head, rest) = self.digits.split_at(keep as usize);
+        let mut digits = head.as_bytes().to_vec();
+        let mut exponent = self.exponent;
+        if rest.as_bytes()[0] >= b'5' {
+            match digits.iter().rposition(|digit| *digit != b'9') {
+                Some(index) => {
+                    digits[index] += 1;
+                    digits.truncate(index + 1);
+                }
+                // 9.99 becomes 10.0
+                None => {
+                    digits = vec![b'1'];
+                    exponent += 1;
+                }
+            }
+        }
+        let digits = String::from_utf8(digits).expect("ascii digits");
+        let digits = digits.trim_end_matches('0');
+        if digits.is_empty() {
+            return Decimal::zero();
+        }
+
+        Decimal {
+            negative: self.negative,
+            digits: digits.to_string(),
+            exponent,
+        }
+    }
+
+    /// The value in plain notation with at most `places` decimals, at least one unless
+    /// `places` is 0, and exactly `places` with `keep_zeros`. The value has to be rounded first.
+    fn fixed(&self, places: usize, keep_zeros: bool) -> String {
+        let sign = if self.negative { "-" } else { "" };
+        let length = self.digits.len() as i64;
+        let (integer, mut fraction) = match self.exponent {
+            point if point >= length => (
+                format!("{}{}", self.digits, "0".repeat((point - length) as usize)),
+                String::new(),
+            ),
+            point if point > 0 => {
+                let (integer, fraction) = self.digits.split_at(point as usize);
+                (integer.to_string(), fraction.to_string())
+            }
+            point => (
+                String::from("0"),
+                format!("{}{}", "0".repeat(-point as usize), self.digits),
+            ),
+        };
+        let integer = if integer.is_empty() { "0" } else { &integer };
+
+        let width = match keep_zeros {
+            true => places,
+            false => places.min(1),
+        };
+        while fraction.len() < width {
+            fraction.push('0');
+        }
+        match fraction.is_empty() {
+            true => format!("{}{}", sign, integer),
+            false => format!("{}{}.{}", sign, integer, fraction),
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // zero has no digits and is never negative
+        let sign = |x: &Decimal| match (x.negative, x.digits.is_empty()) {
+            (true, _) => -1,
+            (false, true) => 0,
+            (false, false) => 1,
+        };
+        let magnitude = || {
+            self.exponent
+                .cmp(&other.exponent)
+                .then_with(|| self.digits.cmp(&other.digits))
+        };
+
+        match (sign(self), sign(other)) {
+            (1, 1) => magnitude(),
+            (-1, -1) => magnitude().reverse(),
+            (first, second) => first.cmp(&second),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders the numbers by value, `-Infinity` and `Infinity` from json5 inputs go first and last,
+/// `NaN` is equal to everything.
+pub fn compare_numbers(first: &Number, second: &Number) -> Ordering {
+    match (
+        Decimal::parse(first.as_str()),
+        Decimal::parse(second.as_str()),
+    ) {
+        (Some(first), Some(second)) => first.cmp(&second),
+        // `as_f64` leaves out numbers that are not finite
+        _ => first
+            .as_str()
+            .parse::<f64>()
+            .ok()
+            .partial_cmp(&second.as_str().parse::<f64>().ok())
+            .unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Whether the two numbers have the same value, regardless of how they were written.
+pub fn numbers_equal(first: &Number, second: &Number) -> bool {
+    let (first, second) = (first.to_string(), second.to_string());
+    if first == second {
+        return true;
+    }
+    match (Decimal::parse(&first), Decimal::parse(&second)) {
+        (Some(first), Some(second)) => first == second,
+        // `Infinity` and `NaN` from json5 inputs
+        _ => false,
+    }
+}
+
+/// The float rounded to at most `places` decimals, halves away from zero, and written in plain
+/// notation, see `Decimal::fixed`. `None` for numbers that stay as they are: integers, numbers
+/// that are not finite and numbers with an exponent that have no decimals to round.
+pub fn round_float(number: &Number, places: usize, keep_zeros: bool) -> Option<Number> {
+    let text = number.as_str();
+    if !text.contains(['.', 'e', 'E']) {
+        return None;
+    }
+    let decimal = Decimal::parse(text)?;
+    let rounded = decimal.round(places);
+    if text.contains(['e', 'E']) && rounded == decimal {
+        return None;
+    }
+    // with `arbitrary_precision` a number is its text, see `json5::non_finite`
+    Some(Number::from_string_unchecked(
+        rounded.fixed(places, keep_zeros),
+    ))
+}
+
+/// Gives the numbers of a value parsed from the json `text` back the text they were written with.
+/// serde_json keeps the digits of every number, but writes an exponent as `e+3` or `e-3`,
+/// so without this `1E3` would be printed as `1e+3`. Nothing changes when the numbers of the
+/// text and the value do not line up, like when a duplicate key replaced a value.
+pub fn keep_written(text: &str, value: &mut Value) {
+    if !text.contains(['e', 'E']) {
+        return;
+    }
+    let written = literals(text);
+    let mut numbers = Vec::new();
+    collect(value, &mut numbers);
+
+    let lined_up = written.len() == numbers.len()
+        && written
+            .iter()
+            .zip(&numbers)
+            .all(|(text, number)| normalized(text) == number.as_str());
+    if lined_up {
+        for (text, number) in written.into_iter().zip(numbers) {
+            // with `arbitrary_precision` a number is its text, see `json5::non_finite`
+            *number = Number::from_string_unchecked(text.to_string());
+        }
+    }
+}
+
+/// The number literals of the json text in document order.
+fn literals(text: &str) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut literals = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'"' => {
+                index += 1;
+                while index < bytes.len() && bytes[index] != b'"' {
+                    index += if bytes[index] == b'\\' { 2 } else { 1 };
+                }
+                index += 1;
+            }
+            b'-' | b'0'..=b'9' => {
+                let start = index;
+                while index < bytes.len() && b"0123456789+-.eE".contains(&bytes[index]) {
+                    index += 1;
+                }
+                literals.push(&text[start..index]);
+            }
+            _ => index += 1,
+        }
+    }
+    literals
+}
+
+fn collect<'a>(value: &'a mut Value, numbers: &mut Vec<&'a mut Number>) {
+    match value {
+        Value::Number(number) => numbers.push(number),
+        Value::Array(list) => list.iter_mut().for_each(|item| collect(item, numbers)),
+        Value::Object(map) => map.values_mut().for_each(|item| collect(item, numbers)),
+        _ => (),
+    }
+}
+
+/// The literal written like serde_json keeps it.
+fn normalized(literal: &str) -> String {
+    match literal.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) if exponent.starts_with(['+', '-']) => {
+            format!("{}e{}", mantissa, exponent)
+        }
+        Some((mantissa, exponent)) => format!("{}e+{}", mantissa, exponent),
+        None => literal.to_string(),
+    }
+}
+
+/// Deep equality of json values where numbers are compared by value, object key order is ignored.
+pub fn values_equal(first: &Value, second: &Value) -> bool {
+    equal_with(first, second, numbers_equal)
+}
+
+/// Like `values_equal`, but numbers are only equal when they are written the same,
+/// so `1` and `1.0` differ and so do `1000` and `1e3`.
+pub fn values_equal_strict(first: &Value, second: &Value) -> bool {
+    equal_with(first, second, |first, second| {
+        first.as_str() == second.as_str()
+    })
+}
+
+fn equal_with(first: &Value, second: &Value, numbers: fn(&Number, &Number) -> bool) -> bool {
+    match (first, second) {
+        (Value::Number(first), Value::Number(second)) => numbers(first, second),
+        (Value::Array(first), Value::Array(second)) => {
+            first.len() == second.len()
+                && first
+                    .iter()
+                    .zip(second)
+                    .all(|(a, b)| equal_with(a, b, numbers))
+        }
+        (Value::Object(first), Value::Object(second)) => {
+            first.len() == second.len()
+                && first
+                    .iter()
+                    .all(|(key, a)| second.get(key).is_some_and(|b| equal_with(a, b, numbers)))
+        }
+        (first, second) => first == second,
+    }
+}
+
+#[cfg(test)]
+mod number_test {
+    use super::{keep_written, round_float, values_equal, values_equal_strict, Decimal};
+    use serde_json::{from_str, Value};
+
+    fn equal(first: &str, second: &str) -> bool {
+        let first: Value = from_str(first).unwrap();
+        let second: Value = from_str(second).unwrap();
+        values_equal(&first, &second)
+    }
+
+    #[test]
+    fn same_values() {
+        assert!(equal("1", "1.0"));
+        assert!(equal("1000", "1e3"));
+        assert!(equal("1000", "1E+3"));
+        assert!(equal("0.001", "1e-3"));
+        assert!(equal("0.10", "0.1"));
+        assert!(equal("0", "-0.0"));
+        assert!(equal("-2.5", "-25e-1"));
+        assert!(equal(
+            "123456789012345678901234567890",
+            "1.23456789012345678901234567890e29"
+        ));
+    }
+
+    #[test]
+    fn different_values() {
+        assert!(!equal("1", "-1"));
+        assert!(!equal("1", "10"));
+        assert!(!equal("0.1", "0.01"));
+        assert!(!equal(
+            "123456789012345678901234567890",
+            "123456789012345678901234567891"
+        ));
+        assert!(!equal("0.12345678901234567890", "0.12345678901234567891"));
+    }
+
+    #[test]
+    fn nested() {
+        assert!(equal(
+            r#"{"a": [1, {"b": 2.0}]}"#,
+            r#"{"a": [1.0, {"b": 2}]}"#
+        ));
+        assert!(!equal(r#"{"a": [1, 2]}"#, r#"{"a": [2, 1]}"#));
+        assert!(!equal(r#"{"a": 1}"#, r#"{"a": 1, "b": 1}"#));
+    }
+
+    #[test]
+    fn strict() {
+        let strict = |first: &str, second: &str| {
+            values_equal_strict(&from_str(first).unwrap(), &from_str(second).unwrap())
+        };
+        assert!(strict(
+            r#"{"a": [1, 2.5], "b": 1e3}"#,
+            r#"{"b": 1e3, "a": [1, 2.5]}"#
+        ));
+        assert!(!strict("1", "1.0"));
+        assert!(!strict("1000", "1e3"));
+        assert!(!strict("0.10", "0.1"));
+        assert!(!strict(r#"{"a": [1]}"#, r#"{"a": [1.0]}"#));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Decimal::parse("1.5e"), None);
+        assert_eq!(Decimal::parse(".5"), None);
+        assert_eq!(Decimal::parse("12.50"), Decimal::parse("1.25e1"));
+    }
+
+    #[test]
+    fn order() {
+        let mut numbers: Vec<Decimal> =
+            ["10", "-1.5", "0.25", "-0", "1e-3", "-20", "9.99", "2.5e-1"]
+                .iter()
+                .map(|x| Decimal::parse(x).unwrap())
+                .collect();
+        numbers.sort();
+        let expected: Vec<Decimal> = ["-20", "-1.5", "0", "1e-3", "0.25", "0.25", "9.99", "10"]
+            .iter()
+            .map(|x| Decimal::parse(x).unwrap())
+            .collect();
+        assert_eq!(numbers, expected);
+    }
+
+    #[test]
+    fn canonical() {
+        let canonical = |text| Decimal::parse(text).unwrap().canonical();
+        assert_eq!(canonical("1.0"), "1");
+        assert_eq!(canonical("-0.0e5"), "0");
+        assert_eq!(canonical("1E+3"), "1000");
+        assert_eq!(canonical("12.50"), "12.5");
+        assert_eq!(canonical("-25e-3"), "-0.025");
+        assert_eq!(canonical("1e-6"), "0.000001");
+        assert_eq!(canonical("1.5e-7"), "1.5e-7");
+        assert_eq!(canonical("1e20"), "100000000000000000000");
+        assert_eq!(canonical("-1.20e21"), "-1.2e21");
+        assert_eq!(canonical("1e400"), "1e400");
+    }
+
+    #[test]
+    fn written_numbers() {
+        let text = r#"{"a": [1, 1.0, 1e3, 1E+3, -0, 0.10, 2.5E-7], "e": "1e3", "b": {"c": -1e0}}"#;
+        let mut value: Value = from_str(text).unwrap();
+        keep_written(text, &mut value);
+        assert_eq!(
+            value.to_string(),
+            r#"{"a":[1,1.0,1e3,1E+3,-0,0.10,2.5E-7],"e":"1e3","b":{"c":-1e0}}"#
+        );
+
+        // the duplicate key leaves one number less in the value than in the text
+        let text = r#"{"a": 1e3, "a": 2E3, "b": 3e3}"#;
+        let mut value: Value = from_str(text).unwrap();
+        keep_written(text, &mut value);
+        assert_eq!(value.to_string(), r#"{"a":2e+3,"b":3e+3}"#);
+    }
+
+    #[test]
+    fn rounded_floats() {
+        let round = |text: &str, places: usize, keep_zeros: bool| {
+            round_float(&text.parse().unwrap(), places, keep_zeros).map(|x| x.to_string())
+        };
+        let some = |text: &str| Some(text.to_string());
+
+        assert_eq!(round("0.30000000000000004", 2, false), some("0.3"));
+        assert_eq!(round("0.30000000000000004", 2, true), some("0.30"));
+        assert_eq!(round("2.675", 2, false), some("2.68"));
+        assert_eq!(round("-2.665", 2, false), some("-2.67"));
+        assert_eq!(round("2.664", 2, false), some("2.66"));
+        assert_eq!(round("9.995", 2, false), some("10.0"));
+        assert_eq!(round("9.995", 2, true), some("10.00"));
+        assert_eq!(round("0.5", 0, false), some("1"));
+        assert_eq!(round("0.004", 2, false), some("0.0"));
+        assert_eq!(round("-0.004", 2, false), some("0.0"));
+        assert_eq!(round("1.50000", 3, false), some("1.5"));
+        assert_eq!(round("1.2345e2", 1, false), some("123.5"));
+        assert_eq!(round("1.2345E-3", 3, false), some("0.001"));
+        assert_eq!(round("1e-7", 2, true), some("0.00"));
+        assert_eq!(round("1e300", 2, false), None);
+        assert_eq!(round("1.5e2", 2, false), None);
+        assert_eq!(round("12", 2, false), None);
+        assert_eq!(round("123456789012345678901234567890", 2, false), None);
+    }
+}
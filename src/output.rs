@@ -0,0 +1,261 @@
+use std::sync::OnceLock;
+
+use serde_json::Value;
+
+use crate::pointer::escape;
+
+/// Settings from the command line that apply to every value that is printed.
+#[derive(Debug, Default)]
+pub struct Options {
+    pub sort_keys: bool,
+    /// sort the keys with `natural::compare`
+    pub natural: bool,
+    /// the spec of `--key-order`, see `order_keys`
+    pub key_order: Option<Value>,
+    pub format: Format,
+    pub keep_bom: bool,
+    pub print0: bool,
+    /// write every character outside of printable ASCII as `\uXXXX`, see `escape_unicode`
+    pub escape_unicode: bool,
+    /// round floats to at most this many decimals, see `round_floats`
+    pub float_precision: Option<usize>,
+    pub keep_trailing_zeros: bool,
+    /// print nothing on stdout, see `--quiet`
+    pub quiet: bool,
+}
+
+/// Syntax values are printed in.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Format {
+    #[default]
+    Json,
+    /// see `json5::to_string`
+    Json5,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "json5" => Ok(Format::Json5),
+            _ => Err(format!("unknown output format {:?}, use json or json5", s)),
+        }
+    }
+}
+
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+/// Sets the output options for the rest of the process, only the first call has effect.
+pub fn configure(options: Options) {
+    let _ = OPTIONS.set(options);
+}
+
+pub fn options() -> &'static Options {
+    OPTIONS.get_or_init(Options::default)
+}
+
+/// The byte order mark to put in front of the output, under `--keep-bom` when an input had one.
+pub fn bom() -> &'static str {
+    if options().keep_bom && crate::input::saw_bom() {
+        "\u{feff}"
+    } else {
+        ""
+    }
+}
+
+/// What ends every printed item, a NUL byte under `--print0` and otherwise a newline.
+pub fn terminator() -> &'static str {
+    if options().print0 {
+        "\0"
+    } else {
+        "\n"
+    }
+}
+
+/// The items one per line, or NUL separated under `--print0` where strings are printed
+/// without quotes, as nothing they contain can be mistaken for the end of the item.
+/// With `raw` strings are always printed without quotes.
+/// None when there are no items, so nothing is printed.
+pub fn lines(items: &[Value], pretty: bool, raw: bool) -> Option<String> {
+    if items.is_empty() {
+        return None;
+    }
+    let raw = raw || options().print0;
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| match item {
+            Value::String(text) if raw => text.clone(),
+            item => crate::value_printer(pretty, item),
+        })
+        .collect();
+    Some(items.join(terminator()))
+}
+
+/// The printed json with every character outside of printable ASCII written as a `\uXXXX`
+/// escape, characters outside of the basic multilingual plane as a surrogate pair. Such
+/// characters only appear in strings and keys of printed json, where the escape means the same.
+pub fn escape_unicode(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if (c.is_ascii() && !c.is_ascii_control()) || matches!(c, '\n' | '\r' | '\t') {
+            escaped.push(c);
+            continue;
+        }
+        let mut units = [0; 2];
+        for unit in c.encode_utf16(&mut units) {
+            escaped.push_str(&format!("\\u{:04x}", unit));
+        }
+    }
+    escaped
+}
+
+/// Rounds every float to at most `places` decimals, see `number::round_float`.
+pub fn round_floats(value: &mut Value, places: usize, keep_zeros: bool) {
+    match value {
+        Value::Number(number) => {
+            if let Some(rounded) = crate::number::round_float(number, places, keep_zeros) {
+                *number = rounded;
+            }
+        }
+        Value::Object(map) => map
+            .values_mut()
+            .for_each(|item| round_floats(item, places, keep_zeros)),
+        Value::Array(list) => list
+            .iter_mut()
+            .for_each(|item| round_floats(item, places, keep_zeros)),
+        _ => (),
+    }
+}
+
+/// Sorts the keys of every object, objects otherwise keep the order the keys were written in.
+/// With `natural` digits in the keys are compared by their value.
+pub fn sort_keys(value: &mut Value, natural: bool) {
+    match value {
+        Value::Object(map) if natural => {
+            let mut entries: Vec<(String, Value)> = std::mem::take(map).into_iter().collect();
+            entries.sort_by(|(first, _), (second, _)| crate::natural::compare(first, second));
+            map.extend(entries);
+            map.values_mut().for_each(|item| sort_keys(item, natural));
+        }
+        Value::Object(map) => {
+            map.sort_keys();
+            map.values_mut().for_each(|item| sort_keys(item, natural));
+        }
+        Value::Array(list) => list.iter_mut().for_each(|item| sort_keys(item, natural)),
+        _ => (),
+    }
+}
+
+/// The key of a `--key-order` spec that lists the keys in order.
+const ORDER_KEY: &str = "$order";
+
+/// Orders the keys of the objects the spec describes. The spec mirrors the value: at every level
+/// `$order` lists the keys that go first, the other keys follow sorted, and every other key of the
+/// spec is the spec for the value under that key, or for every item when that value is an array.
+/// Objects that the spec does not describe keep their order.
+pub fn order_keys(value: &mut Value, spec: &Value) {
+    match value {
+        Value::Object(map) if spec.is_object() => {
+            let order: Vec<&str> = spec
+                .get(ORDER_KEY)
+                .and_then(Value::as_array)
+                .map(|list| list.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            let rank = |key: &str| order.iter().position(|x| *x == key).unwrap_or(order.len());
+            let mut entries: Vec<(String, Value)> = std::mem::take(map).into_iter().collect();
+            entries.sort_by(|(first, _), (second, _)| {
+                rank(first)
+                    .cmp(&rank(second))
+                    .then_with(|| first.cmp(second))
+            });
+            map.extend(entries);
+            for (key, item) in map.iter_mut() {
+                if let Some(nested) = spec.get(key).filter(|_| key != ORDER_KEY) {
+                    order_keys(item, nested);
+                }
+            }
+        }
+        Value::Array(list) => list.iter_mut().for_each(|item| order_keys(item, spec)),
+        _ => (),
+    }
+}
+
+/// Checks that every level of a `--key-order` spec is an object with `$order` a list of keys.
+pub fn check_key_order(spec: &Value, path: &str) -> Result<(), String> {
+    let map = spec
+        .as_object()
+        .ok_or_else(|| format!("the key order at {:?} is not an object", path))?;
+    for (key, nested) in map {
+        match (key.as_str(), nested) {
+            (ORDER_KEY, Value::Array(list)) if list.iter().all(Value::is_string) => (),
+            (ORDER_KEY, _) => {
+                return Err(format!(
+                    "{} at {:?} is not an array of keys",
+                    ORDER_KEY, path
+                ))
+            }
+            (key, nested) => check_key_order(nested, &format!("{}/{}", path, escape(key)))?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod output_test {
+    use super::{check_key_order, escape_unicode, order_keys, sort_keys};
+
+    #[test]
+    fn sorts_nested_keys() {
+        let mut value = serde_json::json!({"b": [{"z": 1, "y": 2}], "a": {"d": 1, "c": 2}});
+        sort_keys(&mut value, false);
+        assert_eq!(
+            value.to_string(),
+            r#"{"a":{"c":2,"d":1},"b":[{"y":2,"z":1}]}"#
+        );
+    }
+
+    #[test]
+    fn sorts_keys_naturally() {
+        let mut value = serde_json::json!({"k10": {"b2": 1, "b10": 2, "b1": 3}, "k9": 1, "k1": 2});
+        sort_keys(&mut value, true);
+        assert_eq!(
+            value.to_string(),
+            r#"{"k1":2,"k9":1,"k10":{"b1":3,"b2":1,"b10":2}}"#
+        );
+    }
+
+    #[test]
+    fn escapes_non_ascii() {
+        assert_eq!(
+            escape_unicode("{\"caf\u{e9}\": \"\u{1f600}\u{7f}\"}"),
+            r#"{"caf\u00e9": "\ud83d\ude00\u007f"}"#
+        );
+        assert_eq!(escape_unicode("{\n  \"a\": 1\n}"), "{\n  \"a\": 1\n}");
+    }
+
+    #[test]
+    fn orders_keys_by_spec() {
+        let spec = serde_json::json!({
+            "$order": ["name", "version"],
+            "deps": {"$order": ["id"]},
+            "server": {}
+        });
+        let mut value = serde_json::json!({
+            "z": {"b": 1, "a": 2},
+            "version": 2,
+            "deps": [{"v": 1, "id": "x", "a": 0}],
+            "server": {"port": 1, "host": "a"},
+            "name": "app"
+        });
+        order_keys(&mut value, &spec);
+        assert_eq!(
+            value.to_string(),
+            r#"{"name":"app","version":2,"deps":[{"id":"x","a":0,"v":1}],"server":{"host":"a","port":1},"z":{"b":1,"a":2}}"#
+        );
+        assert!(check_key_order(&spec, "").is_ok());
+        assert!(check_key_order(&serde_json::json!({"a": {"$order": [1]}}), "").is_err());
+        assert!(check_key_order(&serde_json::json!([]), "").is_err());
+    }
+}
@@ -0,0 +1,400 @@
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::number::values_equal;
+use crate::pointer::{parse_index, unescape};
+use crate::{depth, exit, input, type_name, value_printer, variable_or_object, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "patch",
+    description = "Apply a json patch (RFC 6902) to the map",
+    note = "The patch is an array of add, remove, replace, move, copy and test operations, given like a map. When an operation fails nothing is printed and the map is left as it is. A remove of the index - removes the last item of the array.
+With --reverse the patch is undone: the operations are applied from the last to the first, add becomes remove, remove becomes add, replace swaps the old and new value, move swaps from and path, copy becomes a remove of the copy and test stays as it is. Remove and replace operations need their previous value in \"_prev\", like diff --invertible writes them. When any of them lacks it nothing is applied and their indices are listed.",
+    example = r#"input                 patch                                                    arguments    output
+{{"a": 1}}              [{{"op": "add", "path": "/b", "value": 2}}]                               {{"a":1,"b":2}}
+{{"a": 2}}              [{{"op": "replace", "path": "/a", "value": 2, "_prev": 1}}]  --reverse    {{"a":1}}"#
+)]
+pub struct SubCommandPatch {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    patch: String,
+    #[argh(switch)]
+    /// undo the patch instead of applying it
+    reverse: bool,
+}
+
+/// One operation of a json patch, `prev` is the value the operation removes or replaces.
+#[derive(Debug, Clone, PartialEq)]
+enum Operation {
+    Add {
+        path: String,
+        value: Value,
+    },
+    Remove {
+        path: String,
+        prev: Option<Value>,
+    },
+    Replace {
+        path: String,
+        value: Value,
+        prev: Option<Value>,
+    },
+    Move {
+        from: String,
+        path: String,
+    },
+    Copy {
+        from: String,
+        path: String,
+    },
+    Test {
+        path: String,
+        value: Value,
+    },
+}
+
+pub fn do_patch(args: SubCommandPatch, pretty: bool) -> Result<String, exit::Error> {
+    let operations = match variable_or_value(&args.patch)? {
+        Value::Array(list) => list
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                Operation::from_value(item).map_err(|e| format!("operation {}: {}", index, e))
+            })
+            .collect::<Result<Vec<_>, String>>()?,
+        other => {
+            return Err(format!("the patch is of type {}, not array", type_name(&other)).into())
+        }
+    };
+    let operations: Vec<(usize, Operation)> = match args.reverse {
+        true => reverse(operations)?,
+        false => operations.into_iter().enumerate().collect(),
+    };
+
+    let mut map = variable_or_object(&args.variable)?;
+    for (index, operation) in operations {
+        operation
+            .apply(&mut map)
+            .map_err(|e| format!("operation {} ({}): {}", index, operation.name(), e))?;
+    }
+    depth::check(&map, input::options().max_depth)?;
+    Ok(value_printer(pretty, &map))
+}
+
+/// The operations that undo the patch, in the order to apply them, with their index in the patch.
+fn reverse(operations: Vec<Operation>) -> Result<Vec<(usize, Operation)>, String> {
+    let missing: Vec<String> = operations
+        .iter()
+        .enumerate()
+        .filter(|(_, operation)| operation.inverse().is_none())
+        .map(|(index, operation)| format!("{} ({} {})", index, operation.name(), operation.path()))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "can not reverse operations without \"_prev\": {}",
+            missing.join(", ")
+        ));
+    }
+    Ok(operations
+        .iter()
+        .enumerate()
+        .rev()
+        .filter_map(|(index, operation)| Some((index, operation.inverse()?)))
+        .collect())
+}
+
+impl Operation {
+    fn from_value(value: &Value) -> Result<Operation, String> {
+        let map = value
+            .as_object()
+            .ok_or_else(|| format!("is of type {}, not object", type_name(value)))?;
+        let text = |key: &str| match map.get(key) {
+            Some(Value::String(text)) => Ok(text.clone()),
+            Some(other) => Err(format!(
+                "{:?} is of type {}, not string",
+                key,
+                type_name(other)
+            )),
+            None => Err(format!("missing {:?}", key)),
+        };
+        let value = || {
+            map.get("value")
+                .cloned()
+                .ok_or_else(|| String::from("missing \"value\""))
+        };
+        let prev = map.get("_prev").cloned();
+
+        let path = text("path")?;
+        if !path.is_empty() && !path.starts_with('/') {
+            return Err(String::from("a pointer has to start with /"));
+        }
+        match text("op")?.as_str() {
+            "add" => Ok(Operation::Add {
+                path,
+                value: value()?,
+            }),
+            "remove" => Ok(Operation::Remove { path, prev }),
+            "replace" => Ok(Operation::Replace {
+                path,
+                value: value()?,
+                prev,
+            }),
+            "move" => Ok(Operation::Move {
+                from: text("from")?,
+                path,
+            }),
+            "copy" => Ok(Operation::Copy {
+                from: text("from")?,
+                path,
+            }),
+            "test" => Ok(Operation::Test {
+                path,
+                value: value()?,
+            }),
+            other => Err(format!("unknown op {:?}", other)),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Operation::Add { .. } => "add",
+            Operation::Remove { .. } => "remove",
+            Operation::Replace { .. } => "replace",
+            Operation::Move { .. } => "move",
+            Operation::Copy { .. } => "copy",
+            Operation::Test { .. } => "test",
+        }
+    }
+
+    fn path(&self) -> &str {
+        match self {
+            Operation::Add { path, .. }
+            | Operation::Remove { path, .. }
+            | Operation::Replace { path, .. }
+            | Operation::Move { path, .. }
+            | Operation::Copy { path, .. }
+            | Operation::Test { path, .. } => path,
+        }
+    }
+
+    /// The operation that undoes this one, `None` when the previous value is not known.
+    fn inverse(&self) -> Option<Operation> {
+        let operation = match self.clone() {
+            Operation::Add { path, value } => Operation::Remove {
+                path,
+                prev: Some(value),
+            },
+            Operation::Remove { path, prev } => Operation::Add { path, value: prev? },
+            Operation::Replace { path, value, prev } => Operation::Replace {
+                path,
+                value: prev?,
+                prev: Some(value),
+            },
+            Operation::Move { from, path } => Operation::Move {
+                from: path,
+                path: from,
+            },
+            Operation::Copy { path, .. } => Operation::Remove { path, prev: None },
+            test @ Operation::Test { .. } => test,
+        };
+        Some(operation)
+    }
+
+    fn apply(&self, map: &mut Value) -> Result<(), String> {
+        match self {
+            Operation::Add { path, value } => add(map, path, value.clone()),
+            Operation::Remove { path, .. } => take(map, path).map(drop),
+            Operation::Replace { path, value, .. } => {
+                let target = map
+                    .pointer_mut(path)
+                    .ok_or_else(|| format!("{} does not exist", path))?;
+                *target = value.clone();
+                Ok(())
+            }
+            Operation::Move { from, path } => {
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(format!("{} can not be moved into itself", from));
+                }
+                let value = take(map, from)?;
+                add(map, path, value)
+            }
+            Operation::Copy { from, path } => {
+                let value = map
+                    .pointer(from)
+                    .cloned()
+                    .ok_or_else(|| format!("{} does not exist", from))?;
+                add(map, path, value)
+            }
+            Operation::Test { path, value } => match map.pointer(path) {
+                Some(found) if values_equal(found, value) => Ok(()),
+                Some(found) => Err(format!("{} is {}, not {}", path, found, value)),
+                None => Err(format!("{} does not exist", path)),
+            },
+        }
+    }
+}
+
+/// Adds the value at the pointer, replacing a key or inserting into an array
+/// like RFC 6902 does. The parent has to exist.
+fn add(map: &mut Value, pointer: &str, value: Value) -> Result<(), String> {
+    let (parent, token) = match pointer.rsplit_once('/') {
+        Some(split) => split,
+        None => {
+            *map = value;
+            return Ok(());
+        }
+    };
+    match map.pointer_mut(parent) {
+        Some(Value::Object(object)) => {
+            object.insert(unescape(token).into_owned(), value);
+            Ok(())
+        }
+        Some(Value::Array(list)) => {
+            let index = match token {
+                "-" => list.len(),
+                _ => parse_index(token)
+                    .filter(|x| *x <= list.len())
+                    .ok_or_else(|| {
+                        format!(
+                            "{:?} is not an index of an array of length {}",
+                            token,
+                            list.len()
+                        )
+                    })?,
+            };
+            list.insert(index, value);
+            Ok(())
+        }
+        Some(other) => Err(format!(
+            "{} is of type {}, not object or array",
+            parent,
+            type_name(other)
+        )),
+        None => Err(format!("{} does not exist", parent)),
+    }
+}
+
+/// Removes and returns the value at the pointer, `-` is the last item of an array.
+fn take(map: &mut Value, pointer: &str) -> Result<Value, String> {
+    let missing = || format!("{} does not exist", pointer);
+    let (parent, token) = pointer
+        .rsplit_once('/')
+        .ok_or_else(|| String::from("the whole map can not be removed"))?;
+    match map.pointer_mut(parent) {
+        Some(Value::Object(object)) => object
+            .shift_remove(unescape(token).as_ref())
+            .ok_or_else(missing),
+        Some(Value::Array(list)) => {
+            let index = match token {
+                "-" => list.len().checked_sub(1),
+                _ => parse_index(token).filter(|x| *x < list.len()),
+            };
+            Ok(list.remove(index.ok_or_else(missing)?))
+        }
+        _ => Err(missing()),
+    }
+}
+
+#[cfg(test)]
+mod patch_test {
+    use super::{reverse, Operation};
+    use serde_json::{json, Value};
+
+    fn apply(map: Value, patch: Value, reversed: bool) -> Result<Value, String> {
+        let operations: Vec<Operation> = patch
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| Operation::from_value(x).unwrap())
+            .collect();
+        let operations = match reversed {
+            true => reverse(operations)?,
+            false => operations.into_iter().enumerate().collect(),
+        };
+        let mut map = map;
+        for (_, operation) in operations {
+            operation.apply(&mut map)?;
+        }
+        Ok(map)
+    }
+
+    #[test]
+    fn operations() {
+        let patch = json!([
+            {"op": "add", "path": "/list/1", "value": "x"},
+            {"op": "add", "path": "/list/-", "value": "end"},
+            {"op": "remove", "path": "/gone"},
+            {"op": "replace", "path": "/a~1b", "value": 2},
+            {"op": "move", "from": "/list/0", "path": "/first"},
+            {"op": "copy", "from": "/first", "path": "/copy"},
+            {"op": "test", "path": "/a~1b", "value": 2.0}
+        ]);
+        assert_eq!(
+            apply(json!({"list": [1, 2], "gone": 0, "a/b": 1}), patch, false).unwrap(),
+            json!({"list": ["x", 2, "end"], "a/b": 2, "first": 1, "copy": 1})
+        );
+    }
+
+    #[test]
+    fn failures() {
+        let map = json!({"a": [1]});
+        for patch in [
+            json!([{"op": "add", "path": "/b/c", "value": 1}]),
+            json!([{"op": "add", "path": "/a/2", "value": 1}]),
+            json!([{"op": "remove", "path": "/b"}]),
+            json!([{"op": "replace", "path": "/a/1", "value": 1}]),
+            json!([{"op": "move", "from": "/a", "path": "/a/0"}]),
+            json!([{"op": "test", "path": "/a/0", "value": 2}]),
+        ] {
+            assert!(
+                apply(map.clone(), patch.clone(), false).is_err(),
+                "{}",
+                patch
+            );
+        }
+        assert_eq!(
+            Operation::from_value(&json!({"op": "jump", "path": ""})),
+            Err(String::from("unknown op \"jump\""))
+        );
+    }
+
+    #[test]
+    fn reverse_round_trip() {
+        let patch = json!([
+            {"op": "add", "path": "/list/-", "value": 3},
+            {"op": "remove", "path": "/gone", "_prev": {"x": [1]}},
+            {"op": "replace", "path": "/a", "value": 2, "_prev": 1},
+            {"op": "move", "from": "/b", "path": "/c"},
+            {"op": "copy", "from": "/c", "path": "/d"},
+            {"op": "test", "path": "/d", "value": true}
+        ]);
+        let map = json!({"a": 1, "b": true, "list": [1, 2], "gone": {"x": [1]}});
+        let patched = apply(map.clone(), patch.clone(), false).unwrap();
+        assert_eq!(
+            patched,
+            json!({"a": 2, "list": [1, 2, 3], "c": true, "d": true})
+        );
+        assert_eq!(apply(patched, patch, true).unwrap(), map);
+    }
+
+    #[test]
+    fn reverse_needs_prev() {
+        let operations: Vec<Operation> = [
+            json!({"op": "remove", "path": "/a"}),
+            json!({"op": "add", "path": "/b", "value": 1}),
+            json!({"op": "replace", "path": "/c", "value": 1}),
+        ]
+        .iter()
+        .map(|x| Operation::from_value(x).unwrap())
+        .collect();
+        assert_eq!(
+            reverse(operations),
+            Err(String::from(
+                "can not reverse operations without \"_prev\": 0 (remove /a), 2 (replace /c)"
+            ))
+        );
+    }
+}
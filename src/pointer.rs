@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+
+use serde_json::Value;
+
+use crate::{type_name, verbose};
+
+/// The highest index arrays are extended to with `pad`, so a mistyped index can not
+/// take all memory.
+pub const MAX_PAD_INDEX: usize = 1_000_000;
+
+/// The value at the pointer, missing object keys on the way are created as null,
+/// `None` when the pointer can not be resolved.
+pub fn pointer_mut<'a>(value: &'a mut Value, pointer: &str) -> Option<&'a mut Value> {
+    resolve_mut(value, pointer, false).ok()
+}
+
+/// Like `pointer_mut`, with an error naming the token that could not be resolved.
+/// With `pad` arrays are extended with null up to indices past their end, and index tokens
+/// on something other than an object or array create an array instead of an object.
+pub fn resolve_mut<'a>(
+    value: &'a mut Value,
+    pointer: &str,
+    pad: bool,
+) -> Result<&'a mut Value, String> {
+    // partial copy from https://github.com/serde-rs/json/blob/master/src/value/mod.rs
+    if pointer.is_empty() {
+        return Ok(value);
+    }
+    if !pointer.starts_with('/') {
+        return Err(String::from("a pointer has to start with /"));
+    }
+    log_tokens(pointer);
+    tokens(pointer)
+        .enumerate()
+        .try_fold(value, |target, (step, token)| {
+            verbose::log(2, || {
+                format!("step {}: {:?} in {}", step + 1, token, type_name(target))
+            });
+            inner(target, token, pad)
+        })
+}
+
+/// The unescaped tokens of a pointer that starts with `/`. A token is only copied
+/// when it contains an escape.
+pub fn tokens(pointer: &str) -> impl Iterator<Item = Cow<'_, str>> {
+    pointer.split('/').skip(1).map(unescape)
+}
+
+/// Logs the tokens of the pointer for `--verbose`.
+pub fn log_tokens(pointer: &str) {
+    verbose::log(1, || {
+        let tokens: Vec<Cow<str>> = tokens(pointer).collect();
+        format!("pointer {:?} has tokens {:?}", pointer, tokens)
+    });
+}
+
+/// The token with `~1` and `~0` replaced by `/` and `~`.
+pub fn unescape(token: &str) -> Cow<'_, str> {
+    match token.contains('~') {
+        true => Cow::Owned(token.replace("~1", "/").replace("~0", "~")),
+        false => Cow::Borrowed(token),
+    }
+}
+
+/// The token with `~` and `/` escaped as `~0` and `~1`.
+pub fn escape(token: &str) -> Cow<'_, str> {
+    match token.contains(['~', '/']) {
+        true => Cow::Owned(token.replace('~', "~0").replace('/', "~1")),
+        false => Cow::Borrowed(token),
+    }
+}
+
+pub fn parse_index(s: &str) -> Option<usize> {
+    if s.starts_with('+') || (s.starts_with('0') && s.len() != 1) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn inner<'a>(target: &'a mut Value, token: Cow<str>, pad: bool) -> Result<&'a mut Value, String> {
+    match target {
+        // one lookup, a token that was unescaped already is moved into the key
+        Value::Object(map) => Ok(map.entry(token).or_insert(Value::Null)),
+        Value::Array(list) => {
+            let len = list.len();
+            match parse_index(&token) {
+                Some(index) if index < len => Ok(&mut list[index]),
+                Some(index) if pad && index <= MAX_PAD_INDEX => {
+                    list.resize(index + 1, Value::Null);
+                    Ok(&mut list[index])
+                }
+                Some(index) if pad => Err(format!(
+                    "index {} is above {}, the highest index arrays are padded to",
+                    index, MAX_PAD_INDEX
+                )),
+                Some(index) => Err(format!(
+                    "index {} is past the end of an array of length {}",
+                    index, len
+                )),
+                None => Err(format!("{:?} is not an index of an array", token)),
+            }
+        }
+        other => {
+            let created = match pad && parse_index(&token).is_some() {
+                true => Value::Array(Vec::new()),
+                false => Value::Object(serde_json::Map::new()),
+            };
+            verbose::log(1, || {
+                format!(
+                    "created an intermediate {} in place of {} to hold {:?}",
+                    type_name(&created),
+                    type_name(other),
+                    token
+                )
+            });
+            *other = created;
+            inner(other, token, pad)
+        }
+    }
+}
+
+#[cfg(test)]
+mod pointer_test {
+    use super::{pointer_mut, resolve_mut, tokens};
+    use serde_json::json;
+    use std::borrow::Cow;
+
+    #[test]
+    fn borrowed_tokens() {
+        let list: Vec<Cow<str>> = tokens("/a/b~1c/d~0e/").collect();
+        assert_eq!(list, ["a", "b/c", "d~e", ""]);
+        assert!(matches!(list[0], Cow::Borrowed(_)));
+        assert!(matches!(list[1], Cow::Owned(_)));
+    }
+
+    #[test]
+    fn pad_limit() {
+        let mut value = json!({"a": [1]});
+        assert!(resolve_mut(&mut value, "/a/99999999999", true).is_err());
+        assert_eq!(value, json!({"a": [1]}));
+        *resolve_mut(&mut value, "/a/3", true).unwrap() = json!(2);
+        assert_eq!(value, json!({"a": [1, null, null, 2]}));
+    }
+
+    #[test]
+    fn existing_and_missing_keys() {
+        let mut value = json!({"a": {"b": 1}});
+        *pointer_mut(&mut value, "/a/b").unwrap() = json!(2);
+        *pointer_mut(&mut value, "/a/c~1d").unwrap() = json!(3);
+        assert_eq!(value, json!({"a": {"b": 2, "c/d": 3}}));
+    }
+}
@@ -0,0 +1,351 @@
+use argh::FromArgs;
+use serde_json::{Map, Value};
+
+use crate::{exit, input, type_name, value_printer, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "from-properties",
+    description = "Convert a Java .properties file to json",
+    note = "The file is read like java.util.Properties reads it: `#` and `!` start comments, keys end at the first `=`, `:` or whitespace that is not escaped, lines ending in a backslash continue on the next one and \\uXXXX, \\t, \\n, \\r and \\f are unescaped. Of keys that appear twice the last value is kept.
+The keys are split at `.` into nested objects and objects with only the keys 0, 1, 2, ... become arrays, unless --no-unflatten is given. All values are strings.",
+    example = r#"input                                       arguments           output
+server.port=8080\nhosts.0=a\nhosts.1=b      ""                  {{"server":{{"port":"8080"}},"hosts":["a","b"]}}
+server.port=8080                            --no-unflatten      {{"server.port":"8080"}}"#
+)]
+pub struct SubCommandFromProperties {
+    #[argh(positional)]
+    /// the properties text, an environment variable or @path
+    pub variable: String,
+    #[argh(switch)]
+    /// keep the keys as they are instead of splitting them into nested objects
+    no_unflatten: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "to-properties",
+    description = "Convert the map to a Java .properties file, the reverse of from-properties",
+    note = "Nested keys are joined with `.` and array items get their index as key, like server.port=8080 and hosts.0=a. null is written as an empty value, empty objects and arrays are left out.
+Keys and values are escaped like java.util.Properties writes them: backslashes, `=`, `:`, `#`, `!`, the spaces of keys and the leading spaces of values get a backslash, tabs and line breaks become \\t, \\n, \\r and \\f, and everything outside of printable ASCII becomes \\uXXXX.",
+    example = r#"input                                           output
+{{"server": {{"port": 8080}}, "hosts": ["a"]}}      server.port=8080 and hosts.0=a on their own line
+{{"msg": "a=b: ü"}}                               msg=a\=b\: \u00FC"#
+)]
+pub struct SubCommandToProperties {
+    #[argh(positional)]
+    pub variable: String,
+}
+
+pub fn do_from_properties(
+    args: SubCommandFromProperties,
+    pretty: bool,
+) -> Result<String, exit::Error> {
+    let text = input::Source::of(&args.variable)
+        .text()?
+        .ok_or_else(|| input::not_set(&args.variable))?;
+    let flat = parse(&text).map_err(exit::parse)?;
+    let value = match args.no_unflatten {
+        true => Value::Object(flat),
+        false => unflatten(flat)?,
+    };
+    Ok(value_printer(pretty, &value))
+}
+
+pub fn do_to_properties(args: SubCommandToProperties) -> Result<String, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    if !matches!(value, Value::Object(_) | Value::Array(_)) {
+        return Err(format!(
+            "the map is of type {}, properties need an object or array",
+            type_name(&value)
+        )
+        .into());
+    }
+
+    let mut lines = Vec::new();
+    flatten(&value, &mut String::new(), &mut lines);
+    Ok(lines.join("\n"))
+}
+
+/// Adds a `key=value` line for every value in the value, `key` is the key of the value.
+fn flatten(value: &Value, key: &mut String, lines: &mut Vec<String>) {
+    let children: Vec<(String, &Value)> = match value {
+        Value::Object(map) => map.iter().map(|(k, v)| (k.clone(), v)).collect(),
+        Value::Array(list) => list
+            .iter()
+            .enumerate()
+            .map(|(index, v)| (index.to_string(), v))
+            .collect(),
+        Value::Null => return lines.push(format!("{}=", escape(key, true))),
+        Value::String(text) => {
+            return lines.push(format!("{}={}", escape(key, true), escape(text, false)))
+        }
+        other => return lines.push(format!("{}={}", escape(key, true), other)),
+    };
+
+    for (child, item) in children {
+        let length = key.len();
+        if !key.is_empty() {
+            key.push('.');
+        }
+        key.push_str(&child);
+        flatten(item, key, lines);
+        key.truncate(length);
+    }
+}
+
+/// The text escaped like `java.util.Properties.store` does, all spaces of keys are escaped
+/// and only a leading space of values.
+fn escape(text: &str, key: bool) -> String {
+    let mut escaped = String::new();
+    for (index, c) in text.chars().enumerate() {
+        match c {
+            ' ' if key || index == 0 => escaped.push_str("\\ "),
+            '\\' | '=' | ':' | '#' | '!' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{c}' => escaped.push_str("\\f"),
+            ' '..='~' => escaped.push(c),
+            _ => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    escaped.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+/// The keys and values of the properties text, in the order they first appear.
+fn parse(text: &str) -> Result<Map<String, Value>, String> {
+    let mut map = Map::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((number, line)) = lines.next() {
+        let mut logical = line.trim_start().to_string();
+        if logical.is_empty() || logical.starts_with(['#', '!']) {
+            continue;
+        }
+        // an odd number of backslashes at the end continues the line
+        while (logical.len() - logical.trim_end_matches('\\').len()) % 2 == 1 {
+            logical.pop();
+            match lines.next() {
+                Some((_, next)) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+
+        let (key, value) = split(&logical);
+        let key = unescape(key).map_err(|e| format!("{} at line {}", e, number + 1))?;
+        let value = unescape(value).map_err(|e| format!("{} at line {}", e, number + 1))?;
+        map.insert(key, Value::String(value));
+    }
+    Ok(map)
+}
+
+/// The escaped key and value of a logical line without leading whitespace.
+fn split(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    let end = line.char_indices().find_map(|(index, c)| {
+        let separator = !escaped && (c == '=' || c == ':' || c.is_whitespace());
+        escaped = !escaped && c == '\\';
+        separator.then_some(index)
+    });
+    let end = match end {
+        Some(end) => end,
+        None => return (line, ""),
+    };
+
+    let rest = line[end..].trim_start();
+    let rest = match rest.strip_prefix(['=', ':']) {
+        Some(rest) => rest.trim_start(),
+        None => rest,
+    };
+    (&line[..end], rest)
+}
+
+fn unescape(text: &str) -> Result<String, String> {
+    let mut units = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next() {
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('f') => '\u{c}',
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    let unit = match hex.len() {
+                        4 => u16::from_str_radix(&hex, 16).ok(),
+                        _ => None,
+                    };
+                    units.push(unit.ok_or("malformed \\uXXXX escape")?);
+                    continue;
+                }
+                Some(other) => other,
+                None => continue,
+            },
+            c => c,
+        };
+        units.extend(c.encode_utf16(&mut [0; 2]).iter());
+    }
+    // \u escapes can be the two halves of a character outside of the basic plane
+    String::from_utf16(&units).map_err(|_| String::from("unpaired surrogate in \\uXXXX escape"))
+}
+
+/// Splits the keys at `.` into nested objects and turns objects with only index keys into arrays.
+fn unflatten(flat: Map<String, Value>) -> Result<Value, String> {
+    let mut root = Value::Object(Map::new());
+    for (key, value) in flat {
+        let mut target = &mut root;
+        let mut segments = key.split('.').peekable();
+        while let Some(segment) = segments.next() {
+            let map = match target {
+                Value::Object(map) => map,
+                _ => return Err(format!("key {:?} is inside of a key with a value", key)),
+            };
+            if segments.peek().is_none() {
+                if map.contains_key(segment) {
+                    return Err(format!("key {:?} has keys inside of it", key));
+                }
+                map.insert(segment.to_string(), value);
+                break;
+            }
+            target = map
+                .entry(segment)
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+    }
+    to_arrays(&mut root);
+    Ok(root)
+}
+
+fn to_arrays(value: &mut Value) {
+    let map = match value {
+        Value::Object(map) => map,
+        _ => return,
+    };
+    map.values_mut().for_each(to_arrays);
+
+    let is_index =
+        |key: &str| key == "0" || (!key.starts_with('0') && key.parse::<usize>().is_ok());
+    let indexes: Option<Vec<usize>> = map
+        .keys()
+        .map(|key| is_index(key).then(|| key.parse().unwrap()))
+        .collect();
+    match indexes {
+        Some(mut indexes) if !indexes.is_empty() => {
+            indexes.sort_unstable();
+            if indexes.iter().enumerate().all(|(index, x)| index == *x) {
+                let mut items: Vec<(usize, Value)> = std::mem::take(map)
+                    .into_iter()
+                    .map(|(key, item)| (key.parse().unwrap(), item))
+                    .collect();
+                items.sort_by_key(|(index, _)| *index);
+                *value = Value::Array(items.into_iter().map(|(_, item)| item).collect());
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod properties_test {
+    use super::{escape, flatten, parse, unflatten};
+    use serde_json::{json, Map, Value};
+
+    fn to_properties(value: &Value) -> String {
+        let mut lines = Vec::new();
+        flatten(value, &mut String::new(), &mut lines);
+        lines.join("\n")
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("a b=c:d", true), r"a\ b\=c\:d");
+        assert_eq!(escape("  a b", false), r"\  a b");
+        assert_eq!(escape("#!\\", false), r"\#\!\\");
+        assert_eq!(escape("\t\n\r\u{c}", false), r"\t\n\r\f");
+        assert_eq!(escape("naïve €", false), r"na\u00EFve \u20AC");
+        assert_eq!(escape("😀", true), r"\uD83D\uDE00");
+    }
+
+    #[test]
+    fn reading() {
+        let text = "# comment\n  ! also a comment\n\
+            key1 = value 1\n\
+            key2:value2\n\
+            key\\ 3\tvalue 3 \n\
+            key4\n\
+            a\\=b\\:c = \\ leading\\u0020\\u00FCber\\n\n\
+            multi = one, \\\n    two, \\\n    three\n\
+            path = C:\\\\dir\\\\\n\
+            emoji=\\uD83D\\uDE00\n\
+            key1 = last";
+        let expected = json!({
+            "key1": "last",
+            "key2": "value2",
+            "key 3": "value 3 ",
+            "key4": "",
+            "a=b:c": " leading über\n",
+            "multi": "one, two, three",
+            "path": "C:\\dir\\",
+            "emoji": "😀"
+        });
+        assert_eq!(Value::Object(parse(text).unwrap()), expected);
+        assert_eq!(
+            parse("a = \\u12").unwrap_err(),
+            "malformed \\uXXXX escape at line 1"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = json!({
+            "server": {"host": " 0.0.0.0", "port": "8080"},
+            "hosts": ["a b", "c=d", {"name": "ü:#!"}],
+            "tricky key": {"x=y": "\\ value\twith\nbreaks"},
+            "empty": ""
+        });
+        let text = to_properties(&value);
+        assert_eq!(
+            text.lines().take(3).collect::<Vec<_>>(),
+            [r"server.host=\ 0.0.0.0", "server.port=8080", r"hosts.0=a b"]
+        );
+        assert_eq!(unflatten(parse(&text).unwrap()).unwrap(), value);
+    }
+
+    #[test]
+    fn unflattening() {
+        let flat = |value: Value| match value {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        assert_eq!(
+            unflatten(flat(
+                json!({"a.1": "x", "a.0": "y", "b.01": "z", "b.0": "w"})
+            ))
+            .unwrap(),
+            json!({"a": ["y", "x"], "b": {"01": "z", "0": "w"}})
+        );
+        assert_eq!(
+            unflatten(flat(json!({"a.0": "x", "a.2": "y"}))).unwrap(),
+            json!({"a": {"0": "x", "2": "y"}})
+        );
+        assert_eq!(
+            unflatten(flat(json!({"a": "x", "a.b": "y"}))).unwrap_err(),
+            r#"key "a.b" is inside of a key with a value"#
+        );
+        assert_eq!(
+            unflatten(flat(json!({"a.b": "y", "a": "x"}))).unwrap_err(),
+            r#"key "a" has keys inside of it"#
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::{exit, value_printer, variable_or_object, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "prune-nulls",
+    description = "Remove the keys with null values from the map, or from the part at the pointer",
+    example = r#"input                          arguments               output
+{{"a": null, "b": {{"c": null}}}}  ""                      {{"b":{{}}}}
+{{"a": null, "b": {{"c": null}}}}  "" --cascade            {{}}
+{{"a": [1, null]}}               /a --arrays             {{"a":[1]}}"#
+)]
+pub struct SubCommandPruneNulls {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(switch)]
+    /// remove null items from arrays too
+    arrays: bool,
+    #[argh(switch)]
+    /// also remove objects (and arrays, with --arrays) that are empty after pruning
+    cascade: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "prune-empty",
+    description = "Remove the keys and array items that are empty objects or arrays, until none are left",
+    note = "The map itself is never removed, even when it ends up empty.",
+    example = r#"input                          arguments               output
+{{"a": {{"b": {{}}}}, "c": [[]]}}      ""                      {{}}
+{{"a": {{"b": null}}, "c": 1}}       "" --also-null          {{"c":1}}"#
+)]
+pub struct SubCommandPruneEmpty {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(switch)]
+    /// remove null values as well
+    also_null: bool,
+}
+
+pub fn do_prune_nulls(args: SubCommandPruneNulls, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    if let Some(target) = subtree(&mut value, &args.pointer) {
+        prune_nulls(target, args.arrays, args.cascade);
+    }
+    Ok(value_printer(pretty, &value))
+}
+
+pub fn do_prune_empty(args: SubCommandPruneEmpty, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    if let Some(target) = subtree(&mut value, &args.pointer) {
+        prune_empty(target, args.also_null);
+    }
+    Ok(value_printer(pretty, &value))
+}
+
+fn subtree<'a>(value: &'a mut Value, pointer: &Option<Pointer>) -> Option<&'a mut Value> {
+    match pointer {
+        Some(pointer) => value.pointer_mut(pointer.as_str()),
+        None => Some(value),
+    }
+}
+
+/// Removes null values from objects, and from arrays when `arrays` is set.
+///
+/// Returns whether the value became empty by pruning, under `cascade` those values are removed too.
+fn prune_nulls(value: &mut Value, arrays: bool, cascade: bool) -> bool {
+    match value {
+        Value::Object(map) => {
+            let before = map.len();
+            map.retain(|_, value| !value.is_null() && !prune_nulls(value, arrays, cascade));
+            cascade && map.is_empty() && map.len() != before
+        }
+        Value::Array(list) if arrays => {
+            let before = list.len();
+            list.retain_mut(|value| !value.is_null() && !prune_nulls(value, arrays, cascade));
+            cascade && list.is_empty() && list.len() != before
+        }
+        Value::Array(list) => {
+            for value in list {
+                prune_nulls(value, arrays, cascade);
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Removes empty objects and arrays from objects and arrays, and null values under `also_null`.
+///
+/// Children are pruned before their parent is looked at, so a value that only contained
+/// empty values is removed as well, which is the same as repeating until nothing changes.
+fn prune_empty(value: &mut Value, also_null: bool) {
+    let keep = |value: &mut Value| {
+        prune_empty(value, also_null);
+        match value {
+            Value::Object(map) => !map.is_empty(),
+            Value::Array(list) => !list.is_empty(),
+            Value::Null => !also_null,
+            _ => true,
+        }
+    };
+
+    match value {
+        Value::Object(map) => map.retain(|_, value| keep(value)),
+        Value::Array(list) => list.retain_mut(keep),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod prune_test {
+    use super::{prune_empty, prune_nulls};
+    use serde_json::{json, Value};
+
+    fn nulls(mut value: Value, arrays: bool, cascade: bool) -> Value {
+        prune_nulls(&mut value, arrays, cascade);
+        value
+    }
+
+    fn empty(mut value: Value, also_null: bool) -> Value {
+        prune_empty(&mut value, also_null);
+        value
+    }
+
+    #[test]
+    fn nested_nulls() {
+        assert_eq!(
+            nulls(json!({"a": {"b": null}}), false, false),
+            json!({"a": {}})
+        );
+        assert_eq!(nulls(json!({"a": {"b": null}}), false, true), json!({}));
+        assert_eq!(
+            nulls(json!({"a": {"b": {"c": null}}, "d": 1}), false, true),
+            json!({"d": 1})
+        );
+    }
+
+    #[test]
+    fn cascade_keeps_objects_that_were_empty() {
+        assert_eq!(
+            nulls(json!({"a": {}, "b": {"c": null}}), false, true),
+            json!({"a": {}})
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        let value = json!({"a": [null, 1, {"b": null}], "c": [null]});
+        assert_eq!(
+            nulls(value.clone(), false, false),
+            json!({"a": [null, 1, {}], "c": [null]})
+        );
+        assert_eq!(
+            nulls(value.clone(), true, false),
+            json!({"a": [1, {}], "c": []})
+        );
+        assert_eq!(nulls(value, true, true), json!({"a": [1]}));
+    }
+
+    #[test]
+    fn empty_to_fixed_point() {
+        assert_eq!(empty(json!({"a": {"b": {"c": {}}}}), false), json!({}));
+        assert_eq!(
+            empty(
+                json!({"a": {"b": {"c": []}, "d": 1}, "e": [[], [{}], 2]}),
+                false
+            ),
+            json!({"a": {"d": 1}, "e": [2]})
+        );
+    }
+
+    #[test]
+    fn empty_and_null() {
+        let value = json!({"a": {"b": null}, "c": [null, 0, ""]});
+        assert_eq!(empty(value.clone(), false), value);
+        assert_eq!(empty(value, true), json!({"c": [0, ""]}));
+    }
+}
@@ -0,0 +1,177 @@
+use argh::FromArgs;
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+
+use crate::{exit, value_printer, variable_or_object};
+
+/// The keys `--defaults` redacts.
+const DEFAULT_KEYS: &[&str] = &["password", "token", "secret", "api_key"];
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "redact",
+    description = "Replace the values of the given keys, anywhere in the map, by \"***\"",
+    note = "Arguments starting with `/` are pointers and redact only the value at that pointer, the others are key names that are redacted at every depth, inside arrays too. Key names and --pattern match without regard to case, unless --case-sensitive is given.
+--defaults adds the key names password, token, secret and api_key.",
+    example = r#"input                                         arguments                   output
+{{"user": "x", "password": "hunter2"}}          password                    {{"user":"x","password":"***"}}
+{{"a": [{{"Token": 1}}], "b": {{"c": 2}}}}          --defaults /b/c             {{"a":[{{"Token":"***"}}],"b":{{"c":"***"}}}}
+{{"db_pass": "x", "aws_key": "y"}}              --pattern '_(pass|key)$'    {{"db_pass":"***","aws_key":"***"}}"#
+)]
+pub struct SubCommandRedact {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    keys: Vec<String>,
+    #[argh(option)]
+    /// also redact the keys that match this regular expression
+    pattern: Option<String>,
+    #[argh(option, default = "String::from(\"***\")")]
+    /// the string the values are replaced by, defaults to "***"
+    replacement: String,
+    #[argh(switch)]
+    /// also redact password, token, secret and api_key
+    defaults: bool,
+    #[argh(switch)]
+    /// match key names and --pattern with regard to case
+    case_sensitive: bool,
+}
+
+/// Which keys to redact.
+struct Matcher {
+    names: Vec<String>,
+    pattern: Option<Regex>,
+    case_sensitive: bool,
+}
+
+impl Matcher {
+    fn matches(&self, key: &str) -> bool {
+        let name_matches = match self.case_sensitive {
+            true => self.names.iter().any(|x| x == key),
+            false => self
+                .names
+                .iter()
+                .any(|x| x.to_lowercase() == key.to_lowercase()),
+        };
+        name_matches || self.pattern.as_ref().is_some_and(|x| x.is_match(key))
+    }
+}
+
+pub fn do_redact(args: SubCommandRedact, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    let (pointers, mut names): (Vec<String>, Vec<String>) =
+        args.keys.into_iter().partition(|x| x.starts_with('/'));
+    if args.defaults {
+        names.extend(DEFAULT_KEYS.iter().map(|x| x.to_string()));
+    }
+    if names.is_empty() && pointers.is_empty() && args.pattern.is_none() {
+        return Err(
+            "give the keys or pointers to redact, --pattern or --defaults"
+                .to_string()
+                .into(),
+        );
+    }
+
+    let pattern = match args.pattern {
+        Some(pattern) => Some(
+            RegexBuilder::new(&pattern)
+                .case_insensitive(!args.case_sensitive)
+                .build()
+                .map_err(|e| exit::usage(format!("invalid pattern: {}", e)))?,
+        ),
+        None => None,
+    };
+    let matcher = Matcher {
+        names,
+        pattern,
+        case_sensitive: args.case_sensitive,
+    };
+
+    // a pointer that does not exist has nothing to redact
+    for pointer in pointers {
+        if let Some(target) = value.pointer_mut(&pointer) {
+            *target = Value::String(args.replacement.clone());
+        }
+    }
+    redact(&mut value, &matcher, &args.replacement);
+    Ok(value_printer(pretty, &value))
+}
+
+/// Replaces the value of every matching key, the values of matching keys are not searched further.
+fn redact(value: &mut Value, matcher: &Matcher, replacement: &str) {
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                match matcher.matches(key) {
+                    true => *item = Value::String(replacement.to_string()),
+                    false => redact(item, matcher, replacement),
+                }
+            }
+        }
+        Value::Array(list) => list
+            .iter_mut()
+            .for_each(|x| redact(x, matcher, replacement)),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod redact_test {
+    use super::{redact, Matcher, DEFAULT_KEYS};
+    use regex::Regex;
+    use serde_json::json;
+
+    fn names(names: &[&str]) -> Matcher {
+        Matcher {
+            names: names.iter().map(|x| x.to_string()).collect(),
+            pattern: None,
+            case_sensitive: false,
+        }
+    }
+
+    #[test]
+    fn nested_depths() {
+        let mut value = json!({
+            "password": "a",
+            "user": {"name": "x", "Password": {"hash": "b"}},
+            "sessions": [{"token": "c", "id": 1}, [{"deep": {"TOKEN": null}}]],
+            "tokens": ["not", "redacted"]
+        });
+        redact(&mut value, &names(&["password", "token"]), "***");
+        assert_eq!(
+            value,
+            json!({
+                "password": "***",
+                "user": {"name": "x", "Password": "***"},
+                "sessions": [{"token": "***", "id": 1}, [{"deep": {"TOKEN": "***"}}]],
+                "tokens": ["not", "redacted"]
+            })
+        );
+    }
+
+    #[test]
+    fn case_sensitive_and_pattern() {
+        let mut value = json!({"Secret": 1, "secret": 2, "db_pass": 3, "pass": 4});
+        let matcher = Matcher {
+            case_sensitive: true,
+            pattern: Some(Regex::new("_pass$").unwrap()),
+            ..names(&["secret"])
+        };
+        redact(&mut value, &matcher, "");
+        assert_eq!(
+            value,
+            json!({"Secret": 1, "secret": "", "db_pass": "", "pass": 4})
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let mut value = json!({"API_KEY": "k", "list": [{"Secret": "s"}], "keep": "v"});
+        redact(&mut value, &names(DEFAULT_KEYS), "***");
+        assert_eq!(
+            value,
+            json!({"API_KEY": "***", "list": [{"Secret": "***"}], "keep": "v"})
+        );
+    }
+}
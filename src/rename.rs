@@ -0,0 +1,230 @@
+use argh::FromArgs;
+use serde_json::{Map, Value};
+
+use crate::pointer::escape;
+use crate::{exit, value_printer, variable_or_object, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "rename-keys",
+    description = "Rewrite every object key in the map, or in the part at the pointer, to another case",
+    note = "Keys are split into words at `_`, `-` and spaces, and where a lowercase letter or digit is followed by an uppercase letter. A run of uppercase letters is one word, except for its last letter when a lowercase letter follows, so HTTPServer is http_server and getHTTP2Url is get_http2_url. Leading and trailing `_` and `-` are kept.
+Fails when two keys of the same object become the same key.",
+    example = r#"input                          arguments               output
+{{"user_id": {{"first-name": 1}}}}   "" --to camel           {{"userId":{{"firstName":1}}}}
+{{"HTTPServer": [{{"userID": 1}}]}}  "" --to snake           {{"http_server":[{{"user_id":1}}]}}"#
+)]
+pub struct SubCommandRenameKeys {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(option)]
+    /// the case to use: camel, snake, kebab or pascal
+    to: Case,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Case {
+    /// `httpServer`
+    Camel,
+    /// `http_server`
+    Snake,
+    /// `http-server`
+    Kebab,
+    /// `HttpServer`
+    Pascal,
+}
+
+impl std::str::FromStr for Case {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "camel" => Ok(Case::Camel),
+            "snake" => Ok(Case::Snake),
+            "kebab" => Ok(Case::Kebab),
+            "pascal" => Ok(Case::Pascal),
+            _ => Err(format!(
+                "unknown case {:?}, use camel, snake, kebab or pascal",
+                s
+            )),
+        }
+    }
+}
+
+pub fn do_rename_keys(args: SubCommandRenameKeys, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    let pointer = args.pointer.as_ref().map_or("", Pointer::as_str);
+    if let Some(target) = value.pointer_mut(pointer) {
+        rename_keys(target, args.to, pointer)?;
+    }
+    Ok(value_printer(pretty, &value))
+}
+
+/// Renames the keys of every object in the value, `path` is the pointer to the value for errors.
+fn rename_keys(value: &mut Value, case: Case, path: &str) -> Result<(), String> {
+    match value {
+        Value::Object(map) => {
+            let mut renamed = Map::new();
+            // the original of every renamed key, for the error on collisions
+            let mut originals = Map::new();
+            for (key, mut item) in std::mem::take(map) {
+                let child = format!("{}/{}", path, escape(&key));
+                rename_keys(&mut item, case, &child)?;
+
+                let new_key = convert(&key, case);
+                if let Some(Value::String(original)) = originals.get(&new_key) {
+                    let place = match path {
+                        "" => String::from("the root"),
+                        path => path.to_string(),
+                    };
+                    return Err(format!(
+                        "keys {:?} and {:?} both become {:?} at {}",
+                        original, key, new_key, place
+                    ));
+                }
+                originals.insert(new_key.clone(), Value::String(key));
+                renamed.insert(new_key, item);
+            }
+            *map = renamed;
+        }
+        Value::Array(list) => {
+            for (index, item) in list.iter_mut().enumerate() {
+                rename_keys(item, case, &format!("{}/{}", path, index))?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// The key in the case, see the note of `rename-keys` for how keys are split into words.
+fn convert(key: &str, case: Case) -> String {
+    let is_separator = |c: char| c == '_' || c == '-';
+    let inner = key.trim_matches(is_separator);
+    let start = key.len() - key.trim_start_matches(is_separator).len();
+    let (prefix, suffix) = (&key[..start], &key[start + inner.len()..]);
+
+    let words: Vec<String> = words(inner)
+        .iter()
+        .enumerate()
+        .map(|(index, word)| match case {
+            Case::Snake | Case::Kebab => word.to_lowercase(),
+            Case::Camel if index == 0 => word.to_lowercase(),
+            Case::Camel | Case::Pascal => capitalize(word),
+        })
+        .collect();
+    let separator = match case {
+        Case::Snake => "_",
+        Case::Kebab => "-",
+        Case::Camel | Case::Pascal => "",
+    };
+    format!("{}{}{}", prefix, words.join(separator), suffix)
+}
+
+/// The word with its first letter in uppercase and the rest in lowercase.
+pub fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+fn words(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (position, &(index, c)) in chars.iter().enumerate() {
+        if matches!(c, '_' | '-' | ' ') {
+            if let Some(start) = start.take() {
+                words.push(&text[start..index]);
+            }
+            continue;
+        }
+        let previous = position.checked_sub(1).map(|x| chars[x].1);
+        let next = chars.get(position + 1).map(|x| x.1);
+        let boundary = c.is_uppercase()
+            && match previous {
+                Some(previous) if previous.is_lowercase() || previous.is_ascii_digit() => true,
+                Some(previous) if previous.is_uppercase() => next.is_some_and(char::is_lowercase),
+                _ => false,
+            };
+
+        match start {
+            Some(word_start) if boundary => {
+                words.push(&text[word_start..index]);
+                start = Some(index);
+            }
+            Some(_) => (),
+            None => start = Some(index),
+        }
+    }
+    if let Some(start) = start {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+#[cfg(test)]
+mod rename_test {
+    use super::{convert, rename_keys, words, Case};
+    use serde_json::json;
+
+    #[test]
+    fn split_words() {
+        assert_eq!(words("fooBar"), ["foo", "Bar"]);
+        assert_eq!(words("foo_bar-baz qux"), ["foo", "bar", "baz", "qux"]);
+        assert_eq!(words("HTTPServer"), ["HTTP", "Server"]);
+        assert_eq!(words("getHTTP2Url"), ["get", "HTTP2", "Url"]);
+        assert_eq!(words("userID"), ["user", "ID"]);
+        assert_eq!(words("v2Api"), ["v2", "Api"]);
+        assert_eq!(words("IPv4"), ["I", "Pv4"]);
+        assert_eq!(words("ÉtéÀParis"), ["Été", "À", "Paris"]);
+        assert_eq!(words("a__b"), ["a", "b"]);
+    }
+
+    #[test]
+    fn cases() {
+        assert_eq!(convert("HTTPServer", Case::Snake), "http_server");
+        assert_eq!(convert("HTTPServer", Case::Camel), "httpServer");
+        assert_eq!(convert("http_server", Case::Pascal), "HttpServer");
+        assert_eq!(convert("userID", Case::Kebab), "user-id");
+        assert_eq!(convert("first-name", Case::Camel), "firstName");
+        assert_eq!(convert("FirstName", Case::Camel), "firstName");
+        assert_eq!(convert("getHTTP2Url", Case::Snake), "get_http2_url");
+        assert_eq!(convert("_private_key__", Case::Camel), "_privateKey__");
+        assert_eq!(convert("--", Case::Camel), "--");
+        assert_eq!(convert("", Case::Snake), "");
+    }
+
+    #[test]
+    fn nested_keys() {
+        let mut value = json!({"user_id": [{"first_name": {"last-seen": 1}}], "b": "snake_value"});
+        rename_keys(&mut value, Case::Camel, "").unwrap();
+        assert_eq!(
+            value,
+            json!({"userId": [{"firstName": {"lastSeen": 1}}], "b": "snake_value"})
+        );
+    }
+
+    #[test]
+    fn collisions() {
+        let mut value = json!({"a": [{"fooBar": 1, "x": 2, "foo_bar": 3}]});
+        assert_eq!(
+            rename_keys(&mut value, Case::Snake, "").unwrap_err(),
+            r#"keys "fooBar" and "foo_bar" both become "foo_bar" at /a/0"#
+        );
+        let mut value = json!({"a-b": 1, "a_b": 2});
+        assert_eq!(
+            rename_keys(&mut value, Case::Snake, "").unwrap_err(),
+            r#"keys "a-b" and "a_b" both become "a_b" at the root"#
+        );
+    }
+}
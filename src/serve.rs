@@ -0,0 +1,324 @@
+// unix sockets are only there on unix, elsewhere serve and --connect fail
+#![cfg_attr(not(unix), allow(dead_code, unused_imports))]
+
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use argh::FromArgs;
+use serde_json::{Map, Value};
+
+use crate::{
+    check_get, delete, exit, file, get, input, insert_file_argument, output, run, set,
+    value_printer, variable_or_object, MySubCommandEnum, TopLevel,
+};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "serve",
+    description = "Keep the --file map in memory and answer commands sent over a unix socket",
+    note = "Every line sent to the socket is one command with the arguments of the file mode, like `get /a`, `set /a 1` or `dump` for the whole map, or a json array of the arguments. Every answer is one line of json with the exit code and the output or the error, like {{\"code\":0,\"output\":\"1\"}}.
+bash_map --connect SOCKET runs a command through the socket instead of reading the file, so scripts only have to add --connect to their commands. get, set and delete work on the map in memory, other commands read the file as usual. Changes are written to the file right away, and the map is read again when the file was changed by someone else. Commands are run one at a time, in the order they arrive.
+Top-level options other than --pretty are taken from the serve command, not from the commands sent to it; the options that change how json is printed, like --sort-keys or --float-precision, are rejected with --connect. With --timeout it stops after that many seconds without commands.",
+    example = r#"arguments                                            effect
+--file map.json serve --socket /tmp/bm.sock          serve map.json until interrupted
+--connect /tmp/bm.sock set /a 1                      set /a in the served map
+--store app serve --socket /tmp/bm.sock --timeout 60 serve a store, stopping after a minute without commands"#
+)]
+pub struct SubCommandServe {
+    #[argh(option)]
+    /// the path of the unix socket to listen on
+    socket: PathBuf,
+    #[argh(option)]
+    /// stop after this many seconds without commands
+    timeout: Option<f64>,
+}
+
+/// How often the listener checks for --timeout.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+type Stamp = Option<(SystemTime, u64)>;
+
+/// The served map, the state of the file it was read from and when the last command came.
+struct State {
+    map: Value,
+    stamp: Stamp,
+    last_command: Instant,
+}
+
+#[cfg(not(unix))]
+pub fn serve(_arg: &TopLevel, _args: &SubCommandServe) -> Result<(), exit::Error> {
+    Err(String::from(
+        "serve needs unix sockets, which this system does not have",
+    ))
+}
+
+#[cfg(not(unix))]
+pub fn connect(_socket: &Path, _arguments: &[String]) -> Result<Option<String>, exit::Error> {
+    Err(String::from(
+        "--connect needs unix sockets, which this system does not have",
+    ))
+}
+
+#[cfg(unix)]
+pub fn serve(arg: &TopLevel, args: &SubCommandServe) -> Result<(), exit::Error> {
+    let path = arg
+        .file
+        .clone()
+        .ok_or_else(|| exit::usage(String::from("serve needs --file or --store")))?;
+    let timeout = match args.timeout {
+        Some(seconds) if !(seconds.is_finite() && seconds > 0.0) => {
+            return Err(exit::usage(String::from(
+                "--timeout has to be a positive number of seconds",
+            )))
+        }
+        seconds => seconds.map(Duration::from_secs_f64),
+    };
+
+    let listener = bind(&args.socket)?;
+    let state = Arc::new(Mutex::new(State {
+        map: Value::Null,
+        stamp: None,
+        last_command: Instant::now(),
+    }));
+    reload(&path, &mut state.lock().unwrap_or_else(|e| e.into_inner()))?;
+
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let (arg, path, state) = (arg.clone(), path.clone(), Arc::clone(&state));
+                std::thread::spawn(move || answer(stream, &arg, &path, &state));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                let idle = state
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .last_command
+                    .elapsed();
+                if timeout.is_some_and(|timeout| idle >= timeout) {
+                    break;
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(exit::io(format!("unable to accept a connection: {}", e))),
+        }
+    }
+    let _ = std::fs::remove_file(&args.socket);
+    Ok(())
+}
+
+#[cfg(unix)]
+/// Listens on the socket, replacing a socket file left behind by a server that is gone.
+fn bind(socket: &Path) -> Result<UnixListener, exit::Error> {
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(exit::usage(format!(
+                "{} is served already",
+                socket.display()
+            )));
+        }
+        std::fs::remove_file(socket)
+            .map_err(|e| exit::io(format!("unable to remove {}: {}", socket.display(), e)))?;
+    }
+    let listener = UnixListener::bind(socket)
+        .map_err(|e| exit::io(format!("unable to listen on {}: {}", socket.display(), e)))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| exit::io(e.to_string()))?;
+    Ok(listener)
+}
+
+#[cfg(unix)]
+/// Answers the commands of one connection until it is closed.
+fn answer(stream: UnixStream, arg: &TopLevel, path: &Path, state: &Mutex<State>) {
+    let _ = stream.set_nonblocking(false);
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            state.last_command = Instant::now();
+            let response = response(command(&line, arg, path, &mut state));
+            state.last_command = Instant::now();
+            response
+        };
+        if writeln!(writer, "{}", response).is_err() {
+            return;
+        }
+    }
+}
+
+/// The answer to a command, with the exit code of an error.
+fn response(result: Result<Option<String>, exit::Error>) -> String {
+    let mut map = Map::new();
+    match result {
+        Ok(output) => {
+            map.insert(String::from("code"), Value::from(0));
+            if let Some(output) = output {
+                map.insert(String::from("output"), Value::String(output));
+            }
+        }
+        Err(error) => {
+            map.insert(String::from("code"), Value::from(error.code));
+            if !error.message.is_empty() {
+                map.insert(String::from("error"), Value::String(error.message));
+            }
+        }
+    }
+    Value::Object(map).to_string()
+}
+
+/// Runs one command line on the served map.
+fn command(
+    line: &str,
+    arg: &TopLevel,
+    path: &Path,
+    state: &mut State,
+) -> Result<Option<String>, exit::Error> {
+    let arguments = parse_request(line).map_err(exit::usage)?;
+    if let Some(option) = arguments.iter().find(|x| {
+        [
+            "--file",
+            "--store",
+            "--connect",
+            "--watch",
+            "--watch-changed-only",
+        ]
+        .contains(&x.as_str())
+    }) {
+        return Err(exit::usage(format!("{} can not be sent to serve", option)));
+    }
+    let mut full = vec![String::from("--file"), path.display().to_string()];
+    full.extend(arguments);
+    let full = insert_file_argument(full)?;
+    let strs: Vec<&str> = full.iter().map(String::as_str).collect();
+    let request = match TopLevel::from_args(&["bash_map"], &strs) {
+        Ok(request) => request,
+        Err(early_exit) => {
+            return match early_exit.status {
+                Ok(()) => Ok(Some(early_exit.output)),
+                Err(()) => Err(exit::usage(early_exit.output)),
+            }
+        }
+    };
+    if stamp(path) != state.stamp {
+        reload(path, state)?;
+    }
+
+    let pretty = request.pretty || arg.pretty;
+    match request.command.clone() {
+        MySubCommandEnum::Serve(_) => {
+            Err(exit::usage(String::from("serve can not be sent to serve")))
+        }
+        MySubCommandEnum::Get(args) => {
+            check_get(&args).and_then(|_| get(&args, &state.map, pretty))
+        }
+        MySubCommandEnum::Set(args) if !args.dry_run => {
+            let mut map = state.map.clone();
+            set(args, &mut map)?;
+            write(path, map, pretty, state)
+        }
+        MySubCommandEnum::Delete(args) if !args.dry_run && !args.print_removed => {
+            let mut map = state.map.clone();
+            delete(&args, &mut map)?;
+            write(path, map, pretty, state)
+        }
+        _ => run(&TopLevel { pretty, ..request }),
+    }
+}
+
+/// The arguments of a command line: a json array of strings, or words split on whitespace.
+/// `dump` is the whole map.
+fn parse_request(line: &str) -> Result<Vec<String>, String> {
+    let arguments: Vec<String> = match line.trim_start().starts_with('[') {
+        true => serde_json::from_str(line)
+            .map_err(|e| format!("the command is not a json array of strings: {}", e))?,
+        false => line.split_whitespace().map(String::from).collect(),
+    };
+    match arguments.as_slice() {
+        [command] if command == "dump" => Ok(vec![String::from("get"), String::new()]),
+        _ => Ok(arguments),
+    }
+}
+
+/// Writes the changed map to the file and keeps it as the served map.
+fn write(
+    path: &Path,
+    map: Value,
+    pretty: bool,
+    state: &mut State,
+) -> Result<Option<String>, exit::Error> {
+    let _lock = file::lock(path, true, None).map_err(|e| match e {
+        file::LockError::Io(e) => exit::io(e),
+        file::LockError::Timeout => exit::io(format!("unable to lock {}", path.display())),
+    })?;
+    let output = value_printer(pretty, &map);
+    file::write_atomic(path, &format!("{}{}", output::bom(), output))?;
+    state.map = map;
+    state.stamp = stamp(path);
+    Ok(None)
+}
+
+/// Reads the map from the file again.
+fn reload(path: &Path, state: &mut State) -> Result<(), exit::Error> {
+    let stamp = stamp(path);
+    let source = format!("@{}", path.display());
+    state.map = match stamp {
+        Some(_) => variable_or_object(&source)?,
+        None if input::strict(false) => {
+            return Err(exit::io(format!("file {} does not exist", path.display())))
+        }
+        None => Value::Object(Map::new()),
+    };
+    state.stamp = stamp;
+    Ok(())
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(unix)]
+/// Sends the command line to the server at the socket and returns its output,
+/// failing with the exit code and error of the command.
+pub fn connect(socket: &Path, arguments: &[String]) -> Result<Option<String>, exit::Error> {
+    let unable =
+        |e: std::io::Error| exit::io(format!("unable to talk to {}: {}", socket.display(), e));
+    let mut stream = UnixStream::connect(socket).map_err(unable)?;
+    let request = serde_json::to_string(arguments).map_err(|e| e.to_string())?;
+    writeln!(stream, "{}", request).map_err(unable)?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(unable)?;
+    let response: Value = serde_json::from_str(&line)
+        .map_err(|e| exit::io(format!("invalid answer from {}: {}", socket.display(), e)))?;
+
+    let code = response.get("code").and_then(Value::as_i64).unwrap_or(1) as i32;
+    if code != 0 {
+        let error = response
+            .get("error")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        return Err(exit::command(code, error.to_string()));
+    }
+    Ok(response
+        .get("output")
+        .and_then(Value::as_str)
+        .map(String::from))
+}
@@ -0,0 +1,246 @@
+use std::collections::HashSet;
+
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::normalize::canonical;
+use crate::{exit, value_printer, variable_or_value, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "array-union",
+    description = "Print the items of the first array followed by the items of the second array that are not in it",
+    note = "Items are compared like compare does and every item is printed once.",
+    example = r#"first            pointer                  second           output
+{{"a": [1, 2]}}    /a                       {{"a": [2, 3, 3]}} [1,2,3]
+[{{"n": 1}}]       ""                       [{{"n": 1.0}}]     [{{"n":1}}]"#
+)]
+pub struct SubCommandArrayUnion {
+    #[argh(positional)]
+    pub first: String,
+    #[argh(positional)]
+    first_pointer: Pointer,
+    #[argh(positional)]
+    pub second: String,
+    #[argh(positional)]
+    /// pointer in the second document, the first pointer when left out
+    second_pointer: Option<Pointer>,
+    #[argh(switch)]
+    /// print every item on its own line, strings without quotes
+    lines: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "array-intersect",
+    description = "Print the items of the first array that are also in the second array",
+    note = "Items are compared like compare does and every item is printed once, in the order of the first array.",
+    example = r#"first            pointer                  second           output
+{{"a": [1, 2]}}    /a                       {{"a": [2, 3]}}    [2]"#
+)]
+pub struct SubCommandArrayIntersect {
+    #[argh(positional)]
+    pub first: String,
+    #[argh(positional)]
+    first_pointer: Pointer,
+    #[argh(positional)]
+    pub second: String,
+    #[argh(positional)]
+    /// pointer in the second document, the first pointer when left out
+    second_pointer: Option<Pointer>,
+    #[argh(switch)]
+    /// print every item on its own line, strings without quotes
+    lines: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "array-diff",
+    description = "Print the items of the first array that are not in the second array",
+    note = "Items are compared like compare does and every item is printed once, in the order of the first array.",
+    example = r#"first            pointer                  second           output
+{{"a": [1, 2, 1]}} /a                       {{"a": [2, 3]}}    [1]"#
+)]
+pub struct SubCommandArrayDiff {
+    #[argh(positional)]
+    pub first: String,
+    #[argh(positional)]
+    first_pointer: Pointer,
+    #[argh(positional)]
+    pub second: String,
+    #[argh(positional)]
+    /// pointer in the second document, the first pointer when left out
+    second_pointer: Option<Pointer>,
+    #[argh(switch)]
+    /// print every item on its own line, strings without quotes
+    lines: bool,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Operation {
+    Union,
+    Intersect,
+    Diff,
+}
+
+pub fn do_array_union(
+    args: SubCommandArrayUnion,
+    pretty: bool,
+) -> Result<Option<String>, exit::Error> {
+    let list = combine(
+        Operation::Union,
+        array(&args.first, &args.first_pointer)?,
+        array(
+            &args.second,
+            args.second_pointer.as_ref().unwrap_or(&args.first_pointer),
+        )?,
+    );
+    Ok(print(list, args.lines, pretty))
+}
+
+pub fn do_array_intersect(
+    args: SubCommandArrayIntersect,
+    pretty: bool,
+) -> Result<Option<String>, exit::Error> {
+    let list = combine(
+        Operation::Intersect,
+        array(&args.first, &args.first_pointer)?,
+        array(
+            &args.second,
+            args.second_pointer.as_ref().unwrap_or(&args.first_pointer),
+        )?,
+    );
+    Ok(print(list, args.lines, pretty))
+}
+
+pub fn do_array_diff(
+    args: SubCommandArrayDiff,
+    pretty: bool,
+) -> Result<Option<String>, exit::Error> {
+    let list = combine(
+        Operation::Diff,
+        array(&args.first, &args.first_pointer)?,
+        array(
+            &args.second,
+            args.second_pointer.as_ref().unwrap_or(&args.first_pointer),
+        )?,
+    );
+    Ok(print(list, args.lines, pretty))
+}
+
+/// The array at the pointer, a document that is an array itself can be given without one.
+fn array(input: &str, pointer: &Pointer) -> Result<Vec<Value>, exit::Error> {
+    let value = variable_or_value(input)?;
+    match value.pointer(pointer.as_str()) {
+        Some(Value::Array(list)) => Ok(list.clone()),
+        _ => Err(format!("{} is not an array", pointer.as_str()).into()),
+    }
+}
+
+fn combine(operation: Operation, first: Vec<Value>, second: Vec<Value>) -> Vec<Value> {
+    let in_second: HashSet<String> = second.iter().map(canonical).collect();
+    let mut seen = HashSet::new();
+
+    let mut items: Vec<Value> = first
+        .into_iter()
+        .filter(|item| {
+            let key = canonical(item);
+            let keep = match operation {
+                Operation::Union => true,
+                Operation::Intersect => in_second.contains(&key),
+                Operation::Diff => !in_second.contains(&key),
+            };
+            keep && seen.insert(key)
+        })
+        .collect();
+    if operation == Operation::Union {
+        items.extend(
+            second
+                .into_iter()
+                .filter(|item| seen.insert(canonical(item))),
+        );
+    }
+    items
+}
+
+fn print(list: Vec<Value>, lines: bool, pretty: bool) -> Option<String> {
+    if !lines {
+        return Some(value_printer(pretty, &Value::Array(list)));
+    }
+    if list.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = list
+        .iter()
+        .map(|item| match item {
+            Value::String(text) => text.clone(),
+            item => value_printer(false, item),
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod sets_test {
+    use super::{combine, Operation};
+    use serde_json::{json, Value};
+
+    fn combined(operation: Operation, first: Value, second: Value) -> Value {
+        let list = |value: Value| match value {
+            Value::Array(list) => list,
+            _ => unreachable!(),
+        };
+        Value::Array(combine(operation, list(first), list(second)))
+    }
+
+    #[test]
+    fn union() {
+        assert_eq!(
+            combined(
+                Operation::Union,
+                json!(["b", "a", "b"]),
+                json!(["c", "a", "c"])
+            ),
+            json!(["b", "a", "c"])
+        );
+        assert_eq!(
+            combined(
+                Operation::Union,
+                json!([{"id": 1, "n": [1]}, {"id": 2}]),
+                json!([{"n": [1.0], "id": 1}, {"id": 3}, {"id": 3.0}])
+            ),
+            json!([{"id": 1, "n": [1]}, {"id": 2}, {"id": 3}])
+        );
+    }
+
+    #[test]
+    fn intersect() {
+        assert_eq!(
+            combined(
+                Operation::Intersect,
+                json!([{"id": 2}, {"id": 1}, {"id": 2}, [1]]),
+                json!([{"id": 1}, {"id": 2.0}, [1, 2]])
+            ),
+            json!([{"id": 2}, {"id": 1}])
+        );
+    }
+
+    #[test]
+    fn diff() {
+        assert_eq!(
+            combined(
+                Operation::Diff,
+                json!([{"id": 2}, {"id": 1}, {"id": 3}, {"id": 3}, null]),
+                json!([{"id": 1}, {"id": 4}])
+            ),
+            json!([{"id": 2}, {"id": 3}, null])
+        );
+        assert_eq!(
+            combined(Operation::Diff, json!([1, 2]), json!([2.0, 1e0])),
+            json!([])
+        );
+    }
+}
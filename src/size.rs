@@ -0,0 +1,114 @@
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::pointer::escape;
+use crate::{exit, type_name, value_printer, variable_or_value, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "size",
+    description = "Print the size in bytes of the map, or of the part at the pointer, as it would be printed",
+    note = "The size is of the compact json, or of the pretty json with --pretty, in the --output-format and without the trailing newline. With --max it fails when the size is above the maximum.
+--breakdown prints the size of every key or item instead, a tab and its pointer on each line, in the order of the map. Sort it with `sort -rn`, or `sort -rh` with --human, to find the largest.",
+    example = r#"input                          arguments               output
+{{"a": "xyz", "b": [1, 2]}}        ""                      21
+{{"a": "xyz", "b": [1, 2]}}        "" --breakdown          5 /a and 5 /b on their own line
+{{"a": "xyz", "b": [1, 2]}}        "" --max 20             error: 21 bytes is above the maximum of 20"#
+)]
+pub struct SubCommandSize {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(option)]
+    /// fail when the size in bytes is above this
+    max: Option<usize>,
+    #[argh(switch)]
+    /// print sizes like 1.5 KiB
+    human: bool,
+    #[argh(switch)]
+    /// print the size of every key, or of every item of an array
+    breakdown: bool,
+}
+
+pub fn do_size(args: SubCommandSize, pretty: bool) -> Result<String, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_ref().map_or("", Pointer::as_str);
+    let target = value
+        .pointer(pointer)
+        .ok_or_else(|| format!("{} does not exist", pointer))?;
+
+    let size = value_printer(pretty, target).len();
+    if let Some(max) = args.max {
+        if size > max {
+            return Err(format!("{} bytes is above the maximum of {}", size, max).into());
+        }
+    }
+    let format = |size: usize| match args.human {
+        true => human(size),
+        false => size.to_string(),
+    };
+    if !args.breakdown {
+        return Ok(format(size));
+    }
+
+    let parts: Vec<(String, &Value)> = match target {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, item)| (escape(key).into_owned(), item))
+            .collect(),
+        Value::Array(list) => list
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (index.to_string(), item))
+            .collect(),
+        _ => {
+            return Err(format!(
+                "{} is of type {}, --breakdown needs an object or array",
+                pointer,
+                type_name(target)
+            )
+            .into())
+        }
+    };
+    let lines: Vec<String> = parts
+        .into_iter()
+        .map(|(token, item)| {
+            let size = value_printer(pretty, item).len();
+            format!("{}\t{}/{}", format(size), pointer, token)
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+/// The size in bytes, KiB, MiB or GiB, with one decimal from KiB on.
+fn human(size: usize) -> String {
+    let mut amount = size as f64;
+    for unit in ["B", "KiB", "MiB"] {
+        if amount < 1024.0 {
+            return match unit {
+                "B" => format!("{} B", size),
+                _ => format!("{:.1} {}", amount, unit),
+            };
+        }
+        amount /= 1024.0;
+    }
+    format!("{:.1} GiB", amount)
+}
+
+#[cfg(test)]
+mod size_test {
+    use super::human;
+
+    #[test]
+    fn human_sizes() {
+        assert_eq!(human(0), "0 B");
+        assert_eq!(human(1023), "1023 B");
+        assert_eq!(human(1024), "1.0 KiB");
+        assert_eq!(human(1536), "1.5 KiB");
+        assert_eq!(human(5 * 1024 * 1024 + 1), "5.0 MiB");
+        assert_eq!(human(3 << 30), "3.0 GiB");
+        assert_eq!(human(5000 << 30), "5000.0 GiB");
+    }
+}
@@ -0,0 +1,152 @@
+use std::cmp::Ordering;
+
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::number::compare_numbers;
+use crate::{exit, natural, type_name, value_printer, variable_or_object, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "sort-by",
+    description = "Sort the array at the pointer by the value its items have at the field pointer",
+    note = "Numbers are sorted by value and strings by code point, or with --natural with the digits in them compared by value, so item2 comes before item10. Values of different types are ordered by their type name.
+Items without the field go last, also with --desc. The sort is stable, items with equal fields keep their order.",
+    example = r#"input                          arguments               output
+{{"a": [{{"n": 2}}, {{"n": 1}}, {{}}]}}  /a /n                   {{"a":[{{"n":1}},{{"n":2}},{{}}]}}
+{{"a": [{{"n": 2}}, {{"n": 1}}, {{}}]}}  /a /n --desc            {{"a":[{{"n":2}},{{"n":1}},{{}}]}}
+{{"a": [3, 1, 2]}}               /a ""                   {{"a":[1,2,3]}}
+{{"a": ["v10", "v9"]}}           /a "" --natural         {{"a":["v9","v10"]}}"#
+)]
+pub struct SubCommandSortBy {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional)]
+    field: Pointer,
+    #[argh(switch)]
+    /// sort from high to low
+    desc: bool,
+    #[argh(switch)]
+    /// compare the digits in strings by their value
+    natural: bool,
+}
+
+pub fn do_sort_by(args: SubCommandSortBy, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    match value.pointer_mut(args.pointer.as_str()) {
+        Some(Value::Array(list)) => sort_by(list, args.field.as_str(), args.desc, args.natural),
+        Some(_) => return Err(format!("{} is not an array", args.pointer.as_str()).into()),
+        None => return Err(format!("{} does not exist", args.pointer.as_str()).into()),
+    }
+    Ok(value_printer(pretty, &value))
+}
+
+fn sort_by(list: &mut [Value], field: &str, desc: bool, natural: bool) {
+    list.sort_by(
+        |first, second| match (first.pointer(field), second.pointer(field)) {
+            (Some(first), Some(second)) if desc => compare(second, first, natural),
+            (Some(first), Some(second)) => compare(first, second, natural),
+            (first, second) => second.is_some().cmp(&first.is_some()),
+        },
+    );
+}
+
+/// Orders values of the same type by value, arrays and objects are all equal.
+fn compare(first: &Value, second: &Value, natural: bool) -> Ordering {
+    match (first, second) {
+        (Value::Number(first), Value::Number(second)) => compare_numbers(first, second),
+        (Value::String(first), Value::String(second)) if natural => natural::compare(first, second),
+        (Value::String(first), Value::String(second)) => first.cmp(second),
+        (Value::Bool(first), Value::Bool(second)) => first.cmp(second),
+        (first, second) => type_name(first).cmp(type_name(second)),
+    }
+}
+
+#[cfg(test)]
+mod sort_test {
+    use super::sort_by;
+    use serde_json::{json, Value};
+
+    fn sorted(value: Value, field: &str, desc: bool) -> Value {
+        sorted_with(value, field, desc, false)
+    }
+
+    fn sorted_with(value: Value, field: &str, desc: bool, natural: bool) -> Value {
+        let mut list = match value {
+            Value::Array(list) => list,
+            _ => unreachable!(),
+        };
+        sort_by(&mut list, field, desc, natural);
+        Value::Array(list)
+    }
+
+    #[test]
+    fn numbers_and_strings() {
+        assert_eq!(
+            sorted(
+                json!([{"n": 10}, {"n": 9.5}, {"n": -1}, {"n": 1e1}]),
+                "/n",
+                false
+            ),
+            json!([{"n": -1}, {"n": 9.5}, {"n": 10}, {"n": 1e1}])
+        );
+        assert_eq!(
+            sorted(json!(["b", "B", "a", "ab"]), "", false),
+            json!(["B", "a", "ab", "b"])
+        );
+    }
+
+    #[test]
+    fn stable_and_missing_last() {
+        let value = json!([
+            {"id": 1, "age": 30},
+            {"id": 2},
+            {"id": 3, "age": 20},
+            {"id": 4, "age": 30},
+            {"id": 5, "age": null},
+        ]);
+        assert_eq!(
+            sorted(value.clone(), "/age", false),
+            json!([
+                {"id": 5, "age": null},
+                {"id": 3, "age": 20},
+                {"id": 1, "age": 30},
+                {"id": 4, "age": 30},
+                {"id": 2},
+            ])
+        );
+        assert_eq!(
+            sorted(value, "/age", true),
+            json!([
+                {"id": 1, "age": 30},
+                {"id": 4, "age": 30},
+                {"id": 3, "age": 20},
+                {"id": 5, "age": null},
+                {"id": 2},
+            ])
+        );
+    }
+
+    #[test]
+    fn mixed_types_by_type_name() {
+        assert_eq!(
+            sorted(json!(["a", 1, null, true, [], {}, 0]), "", false),
+            json!([[], true, null, 0, 1, {}, "a"])
+        );
+    }
+
+    #[test]
+    fn natural_strings() {
+        assert_eq!(
+            sorted_with(json!(["item10", "item9", "Item1", 2]), "", false, true),
+            json!([2, "Item1", "item9", "item10"])
+        );
+        assert_eq!(
+            sorted_with(json!([{"v": "v1.10"}, {"v": "v1.9"}]), "/v", true, true),
+            json!([{"v": "v1.10"}, {"v": "v1.9"}])
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use argh::FromArgs;
+use serde_json::Value;
+use std::convert::TryFrom;
+
+use crate::{
+    depth, exit, input, type_name, value_from_str, value_printer, variable_or_value, Pointer,
+};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "splice",
+    description = "Remove a range of items from the array at the pointer and insert items in their place, like Array.splice in JavaScript",
+    note = "A start past the end of the array is the end, a negative start counts from the end, put -- before it so it is not taken as an option. At most the items up to the end of the array are removed.
+The items to insert are given as one json array. With --print-removed the removed items are printed as an array instead of the map.",
+    example = r#"input                  arguments                  output
+{{"a": [1, 2, 3, 4]}}    /a 1 2                     {{"a":[1,4]}}
+{{"a": [1, 2, 3, 4]}}    /a 1 0 '["x"]'             {{"a":[1,"x",2,3,4]}}
+{{"a": [1, 2, 3, 4]}}    /a -- -2 1 '["x", "y"]'    {{"a":[1,2,"x","y",4]}}
+{{"a": [1, 2, 3, 4]}}    /a 1 2 --print-removed     [2,3]"#
+)]
+pub struct SubCommandSplice {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional)]
+    start: i64,
+    #[argh(positional)]
+    count: usize,
+    #[argh(positional, from_str_fn(value_from_str))]
+    items: Option<Value>,
+    #[argh(switch)]
+    /// print the removed items instead of the map, the map is not written back to --file
+    pub print_removed: bool,
+}
+
+pub fn do_splice(args: SubCommandSplice, pretty: bool) -> Result<String, exit::Error> {
+    let items = match args.items {
+        Some(Value::Array(items)) => items,
+        Some(other) => {
+            return Err(exit::usage(format!(
+                "the items to insert are of type {}, not array",
+                type_name(&other)
+            )))
+        }
+        None => Vec::new(),
+    };
+    let mut map = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_str();
+    let list = match map.pointer_mut(pointer) {
+        Some(Value::Array(list)) => list,
+        Some(other) => {
+            return Err(format!("{} is of type {}, not array", pointer, type_name(other)).into())
+        }
+        None => return Err(format!("{} does not exist", pointer).into()),
+    };
+
+    let range = range(list.len(), args.start, args.count);
+    let removed: Vec<Value> = list.splice(range, items).collect();
+    if args.print_removed {
+        return Ok(value_printer(pretty, &Value::Array(removed)));
+    }
+    depth::check(&map, input::options().max_depth)?;
+    Ok(value_printer(pretty, &map))
+}
+
+/// The indices to remove, `start` clamped to the array and negative from the end.
+fn range(len: usize, start: i64, count: usize) -> std::ops::Range<usize> {
+    let start = match usize::try_from(start) {
+        Ok(start) => start.min(len),
+        Err(_) => len.saturating_sub(start.unsigned_abs() as usize),
+    };
+    start..start + count.min(len - start)
+}
+
+#[cfg(test)]
+mod splice_test {
+    use super::range;
+
+    #[test]
+    fn clamped_ranges() {
+        assert_eq!(range(4, 1, 2), 1..3);
+        assert_eq!(range(4, 1, 10), 1..4);
+        assert_eq!(range(4, 9, 1), 4..4);
+        assert_eq!(range(4, -1, 1), 3..4);
+        assert_eq!(range(4, -9, 1), 0..1);
+        assert_eq!(range(0, 0, 3), 0..0);
+    }
+}
@@ -0,0 +1,163 @@
+use argh::FromArgs;
+use serde_json::{json, Value};
+
+use crate::pointer::escape;
+use crate::{exit, value_printer, variable_or_value, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "stats",
+    description = "Print a summary of the map, or of the part at the pointer, as json",
+    note = "The summary has the number of values (nodes, the map itself included) and the number of each type, the deepest nesting of arrays and objects as --max-depth counts it, the longest array and the object with the most keys with their pointers, or null when there are none, and the size of the part as compact json in bytes.
+The first one in the order of the map wins ties.",
+    example = r#"input                          output
+{{"a": [1, 2], "b": {{"c": null}}}}  {{"nodes":6,"types":{{"object":2,"array":1,"string":0,"number":2,"boolean":0,"null":1}},"max_depth":2,"longest_array":{{"pointer":"/a","length":2}},"largest_object":{{"pointer":"","keys":2}},"bytes":26}}"#
+)]
+pub struct SubCommandStats {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+}
+
+/// What `stats` collects in a single walk over the value.
+#[derive(Debug, Default)]
+struct Stats {
+    nodes: u64,
+    objects: u64,
+    arrays: u64,
+    strings: u64,
+    numbers: u64,
+    booleans: u64,
+    nulls: u64,
+    max_depth: usize,
+    /// pointer and length
+    longest_array: Option<(String, usize)>,
+    /// pointer and number of keys
+    largest_object: Option<(String, usize)>,
+}
+
+pub fn do_stats(args: SubCommandStats, pretty: bool) -> Result<String, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_ref().map_or("", Pointer::as_str);
+    let target = value
+        .pointer(pointer)
+        .ok_or_else(|| format!("{} does not exist", pointer))?;
+
+    let mut stats = Stats::default();
+    stats.walk(target, &mut pointer.to_string(), 0);
+    let bytes = serde_json::to_string(target)
+        .map_err(|e| e.to_string())?
+        .len();
+    Ok(value_printer(pretty, &stats.to_value(bytes)))
+}
+
+impl Stats {
+    /// Counts the value and everything in it, `path` is the pointer to the value at `depth`.
+    fn walk(&mut self, value: &Value, path: &mut String, depth: usize) {
+        self.nodes += 1;
+        match value {
+            Value::Null => self.nulls += 1,
+            Value::Bool(_) => self.booleans += 1,
+            Value::Number(_) => self.numbers += 1,
+            Value::String(_) => self.strings += 1,
+            Value::Array(list) => {
+                self.arrays += 1;
+                self.max_depth = self.max_depth.max(depth + 1);
+                if self.longest_array.as_ref().is_none_or(|x| list.len() > x.1) {
+                    self.longest_array = Some((path.clone(), list.len()));
+                }
+                for (index, item) in list.iter().enumerate() {
+                    let length = path.len();
+                    path.push_str(&format!("/{}", index));
+                    self.walk(item, path, depth + 1);
+                    path.truncate(length);
+                }
+            }
+            Value::Object(map) => {
+                self.objects += 1;
+                self.max_depth = self.max_depth.max(depth + 1);
+                if self.largest_object.as_ref().is_none_or(|x| map.len() > x.1) {
+                    self.largest_object = Some((path.clone(), map.len()));
+                }
+                for (key, item) in map {
+                    let length = path.len();
+                    path.push('/');
+                    path.push_str(&escape(key));
+                    self.walk(item, path, depth + 1);
+                    path.truncate(length);
+                }
+            }
+        }
+    }
+
+    fn to_value(&self, bytes: usize) -> Value {
+        json!({
+            "nodes": self.nodes,
+            "types": {
+                "object": self.objects,
+                "array": self.arrays,
+                "string": self.strings,
+                "number": self.numbers,
+                "boolean": self.booleans,
+                "null": self.nulls,
+            },
+            "max_depth": self.max_depth,
+            "longest_array": self.longest_array.as_ref()
+                .map(|(pointer, length)| json!({"pointer": pointer, "length": length})),
+            "largest_object": self.largest_object.as_ref()
+                .map(|(pointer, keys)| json!({"pointer": pointer, "keys": keys})),
+            "bytes": bytes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod stats_test {
+    use super::Stats;
+    use serde_json::json;
+
+    #[test]
+    fn known_counts() {
+        let value = json!({
+            "id": 1,
+            "tags": ["a", "b", "c"],
+            "owner": {"name": "x", "admin": true, "team": null, "level": 2, "a/b": [[1.5, false]]},
+            "empty": []
+        });
+        let mut stats = Stats::default();
+        stats.walk(&value, &mut String::new(), 0);
+
+        assert_eq!(
+            stats.to_value(123),
+            json!({
+                "nodes": 16,
+                "types": {"object": 2, "array": 4, "string": 4, "number": 3, "boolean": 2, "null": 1},
+                "max_depth": 4,
+                "longest_array": {"pointer": "/tags", "length": 3},
+                "largest_object": {"pointer": "/owner", "keys": 5},
+                "bytes": 123
+            })
+        );
+    }
+
+    #[test]
+    fn scalars_and_subtrees() {
+        let mut stats = Stats::default();
+        stats.walk(&json!("text"), &mut String::new(), 0);
+        assert_eq!((stats.nodes, stats.strings, stats.max_depth), (1, 1, 0));
+        assert!(stats.longest_array.is_none() && stats.largest_object.is_none());
+
+        // ties go to the first, pointers start at the subtree
+        let mut stats = Stats::default();
+        stats.walk(
+            &json!({"x": [1, 2], "y": [[3, 4]]}),
+            &mut "/a".to_string(),
+            0,
+        );
+        assert_eq!(stats.longest_array, Some(("/a/x".to_string(), 2)));
+        assert_eq!(stats.largest_object, Some(("/a".to_string(), 2)));
+        assert_eq!(stats.max_depth, 3);
+    }
+}
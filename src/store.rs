@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::PathBuf;
+
+use argh::FromArgs;
+
+use crate::exit;
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+/// List the named stores
+#[argh(subcommand, name = "store-list")]
+pub struct SubCommandStoreList {}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+/// Delete a named store
+#[argh(subcommand, name = "store-delete")]
+pub struct SubCommandStoreDelete {
+    #[argh(positional)]
+    name: String,
+}
+
+/// Directory holding the stores, `$XDG_DATA_HOME/bash_map` when set (on every platform),
+/// otherwise the platform data directory (`~/.local/share`, `~/Library/Application Support`
+/// or `%APPDATA%`).
+pub fn store_dir() -> Result<PathBuf, exit::Error> {
+    std::env::var_os("XDG_DATA_HOME")
+        .filter(|x| !x.is_empty())
+        .map(PathBuf::from)
+        .or_else(dirs::data_dir)
+        .map(|x| x.join("bash_map"))
+        .ok_or_else(|| {
+            exit::io(String::from(
+                "unable to determine the data directory for stores",
+            ))
+        })
+}
+
+/// Path of the file backing the store `name`, creating the store directory when needed.
+pub fn store_path(name: &str) -> Result<PathBuf, exit::Error> {
+    validate_name(name)?;
+    let dir = store_dir()?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| exit::io(format!("unable to create {}: {}", dir.display(), e)))?;
+
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+/// Store names become file names, so only allow characters that can not escape the store directory.
+fn validate_name(name: &str) -> Result<(), exit::Error> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ['-', '_', '.'].contains(&c));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(exit::usage(format!(
+            "invalid store name {:?}, use letters, digits, '-', '_' and '.' (not leading)",
+            name
+        )))
+    }
+}
+
+pub fn do_store_list(_args: SubCommandStoreList) -> Result<Vec<String>, exit::Error> {
+    let dir = store_dir()?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(exit::io(format!("unable to read {}: {}", dir.display(), e))),
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|x| x.strip_suffix(".json"))
+                .filter(|x| validate_name(x).is_ok())
+                .map(String::from)
+        })
+        .collect();
+    names.sort();
+
+    Ok(names)
+}
+
+pub fn do_store_delete(args: SubCommandStoreDelete) -> Result<(), exit::Error> {
+    let path = store_path(&args.name)?;
+    fs::remove_file(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("store {} does not exist", args.name).into(),
+        _ => exit::io(format!("unable to delete {}: {}", path.display(), e)),
+    })?;
+
+    let _ = fs::remove_file(crate::file::lock_path(&path));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod store_test {
+    use super::validate_name;
+
+    #[test]
+    fn valid_names() {
+        assert!(validate_name("build-cache").is_ok());
+        assert!(validate_name("v1.2_state").is_ok());
+    }
+
+    #[test]
+    fn rejects_traversal() {
+        assert!(validate_name("").is_err());
+        assert!(validate_name("..").is_err());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name("a\\b").is_err());
+        assert!(validate_name(".hidden").is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::Read;
+
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::Value;
+
+use crate::pointer;
+
+/// Inputs larger than this many bytes are read with `get` instead of being parsed completely.
+pub const STREAM_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// Resolves `pointer` while reading the document from `reader`.
+///
+/// Only the value at the pointer is built, everything else is skipped over,
+/// so memory use depends on the size of the result instead of the size of the document.
+pub fn get<R: Read>(reader: R, pointer: &str) -> Result<Option<Value>, String> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Ok(None);
+    }
+    let tokens: Vec<Cow<str>> = pointer::tokens(pointer).collect();
+
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let value = Select { tokens: &tokens }
+        .deserialize(&mut deserializer)
+        .and_then(|value| deserializer.end().map(|_| value))
+        .map_err(|e| e.to_string())?;
+
+    Ok(value)
+}
+
+struct Select<'a> {
+    tokens: &'a [Cow<'a, str>],
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for Select<'a> {
+    type Value = Option<Value>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if self.tokens.is_empty() {
+            return serde::Deserialize::deserialize(deserializer).map(Some);
+        }
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for Select<'a> {
+    type Value = Option<Value>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any json value")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let (token, rest) = self.tokens.split_first().expect("tokens are not empty");
+        let mut found = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            if key == *token {
+                found = map.next_value_seed(Select { tokens: rest })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(found)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let (token, rest) = self.tokens.split_first().expect("tokens are not empty");
+        let index = pointer::parse_index(token);
+        let mut found = None;
+        let mut current = 0;
+
+        loop {
+            if Some(current) == index {
+                match seq.next_element_seed(Select { tokens: rest })? {
+                    Some(value) => found = value,
+                    None => break,
+                }
+            } else if seq.next_element::<IgnoredAny>()?.is_none() {
+                break;
+            }
+            current += 1;
+        }
+        Ok(found)
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod stream_test {
+    use super::get;
+    use serde_json::json;
+    use std::io::Read;
+
+    /// Produces `{"items":[{"id":0,"name":"item 0"},...],"last":true}` on the fly,
+    /// so the complete document never exists in memory.
+    struct Generated {
+        amount: usize,
+        next: usize,
+        buffer: Vec<u8>,
+        position: usize,
+    }
+
+    impl Generated {
+        fn new(amount: usize) -> Generated {
+            Generated {
+                amount,
+                next: 0,
+                buffer: br#"{"items":["#.to_vec(),
+                position: 0,
+            }
+        }
+    }
+
+    impl Read for Generated {
+        fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+            if self.position == self.buffer.len() {
+                self.position = 0;
+                self.buffer = if self.next < self.amount {
+                    let separator = if self.next == 0 { "" } else { "," };
+                    let item = format!(
+                        r#"{}{{"id":{},"name":"item {}"}}"#,
+                        separator, self.next, self.next
+                    );
+                    self.next += 1;
+                    item.into_bytes()
+                } else if self.next == self.amount {
+                    self.next += 1;
+                    br#"],"last":true}"#.to_vec()
+                } else {
+                    Vec::new()
+                };
+            }
+
+            let amount = out.len().min(self.buffer.len() - self.position);
+            out[..amount].copy_from_slice(&self.buffer[self.position..self.position + amount]);
+            self.position += amount;
+            Ok(amount)
+        }
+    }
+
+    #[test]
+    fn generated_array() {
+        assert_eq!(
+            get(Generated::new(100_000), "/items/99999").unwrap(),
+            Some(json!({"id": 99999, "name": "item 99999"}))
+        );
+        assert_eq!(
+            get(Generated::new(100_000), "/items/50000/name").unwrap(),
+            Some(json!("item 50000"))
+        );
+        assert_eq!(
+            get(Generated::new(100_000), "/last").unwrap(),
+            Some(json!(true))
+        );
+        assert_eq!(get(Generated::new(100_000), "/items/100000").unwrap(), None);
+    }
+
+    #[test]
+    fn same_as_pointer() {
+        let data = json!({
+            "a/b": {"m~n": [1, {"deep": null}]},
+            "list": [[0, 1], [2, 3]],
+            "scalar": "text"
+        });
+        let text = data.to_string();
+
+        for pointer in &[
+            "",
+            "/a~1b",
+            "/a~1b/m~0n/1/deep",
+            "/list/1/0",
+            "/list/01",
+            "/list/-1",
+            "/scalar/0",
+            "/missing",
+            "no slash",
+        ] {
+            assert_eq!(
+                get(text.as_bytes(), pointer).unwrap(),
+                data.pointer(pointer).cloned(),
+                "{}",
+                pointer
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_key_takes_last() {
+        assert_eq!(
+            get(r#"{"a":1,"a":2}"#.as_bytes(), "/a").unwrap(),
+            Some(json!(2))
+        );
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert!(get(r#"{"a":1"#.as_bytes(), "/a").is_err());
+        assert!(get(r#"{"a":1} trailing"#.as_bytes(), "/a").is_err());
+    }
+}
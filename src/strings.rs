@@ -0,0 +1,367 @@
+use std::borrow::Cow;
+
+use argh::FromArgs;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::rename::capitalize;
+use crate::{exit, type_name, value_printer, variable_or_object, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "str",
+    description = "Change the string at the pointer: upper, lower, trim or capitalize",
+    note = "Casing follows the Unicode rules, capitalize makes the first letter uppercase and the rest lowercase. With --recursive every string in the value at the pointer is changed, object keys are left alone.",
+    example = r#"operation    input                          pointer          output
+upper        {{"env": "prod"}}                /env             {{"env":"PROD"}}
+trim         {{"a": [" x ", {{"b": " y"}}]}}    /a --recursive   {{"a":["x",{{"b":"y"}}]}}"#
+)]
+pub struct SubCommandStr {
+    #[argh(positional)]
+    operation: Operation,
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(switch)]
+    /// change every string in the value at the pointer
+    recursive: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "trim",
+    description = "Trim the whitespace around every string in the map, or in the part at the pointer",
+    note = "Object keys are left alone and strings of only whitespace become empty strings.",
+    example = r#"input                          arguments               output
+{{"a": [" x ", {{"b": "\n"}}]}}    ""                      {{"a":["x",{{"b":""}}]}}
+{{"a": " x  \t y "}}             "" --collapse           {{"a":"x y"}}"#
+)]
+pub struct SubCommandTrim {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(switch)]
+    /// also replace every run of whitespace inside the strings by a single space
+    collapse: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "sub",
+    description = "Replace the first match of the regular expression in the string at the pointer",
+    note = "The replacement can refer to capture groups as $1 or ${{name}}, also when a letter follows like in $1r, write $$ for a literal $. With --recursive every string in the value at the pointer is changed and values that are not strings are skipped. Put `--` in front of the pointer when the pattern or replacement starts with -, so it is not read as an option.",
+    example = r#"input                    arguments                                output
+{{"a": "1-2-3"}}           -- /a - +                                {{"a":"1+2-3"}}
+{{"a": "1-2-3"}}           --global -- /a - +                       {{"a":"1+2+3"}}
+{{"a": ["x=1", "y=2"]}}    /a '(\w)=(\d)' '$2=$1' --recursive       {{"a":["1=x","2=y"]}}"#
+)]
+pub struct SubCommandSub {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional)]
+    pattern: String,
+    #[argh(positional)]
+    replacement: String,
+    #[argh(switch)]
+    /// replace every match instead of only the first
+    global: bool,
+    #[argh(switch)]
+    /// change every string in the value at the pointer
+    recursive: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "substr",
+    description = "Replace the string at the pointer by the part from start, of length characters or up to the end",
+    note = "Counts characters, not bytes. A negative start counts from the end, put `--` in front of it so it is not read as an option. Parts outside of the string are left out.",
+    example = r#"input                    arguments                    output
+{{"sha": "1a2b3c4d"}}      /sha 0 4                     {{"sha":"1a2b"}}
+{{"sha": "1a2b3c4d"}}      /sha 0 4 --print-only        1a2b
+{{"a": "naïve"}}           /a -- -3                     {{"a":"ïve"}}"#
+)]
+pub struct SubCommandSubstr {
+    #[argh(switch)]
+    /// print only the part, without quotes, instead of the map, the map is not written back to --file
+    pub print_only: bool,
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional)]
+    start: i64,
+    #[argh(positional)]
+    length: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operation {
+    Upper,
+    Lower,
+    Trim,
+    Capitalize,
+    /// trim and replace runs of whitespace by a single space
+    Collapse,
+}
+
+impl std::str::FromStr for Operation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "upper" => Ok(Operation::Upper),
+            "lower" => Ok(Operation::Lower),
+            "trim" => Ok(Operation::Trim),
+            "capitalize" => Ok(Operation::Capitalize),
+            _ => Err(format!(
+                "unknown operation {:?}, use upper, lower, trim or capitalize",
+                s
+            )),
+        }
+    }
+}
+
+impl Operation {
+    fn apply(self, text: &str) -> String {
+        match self {
+            Operation::Upper => text.to_uppercase(),
+            Operation::Lower => text.to_lowercase(),
+            Operation::Trim => text.trim().to_string(),
+            Operation::Capitalize => capitalize(text),
+            Operation::Collapse => text.split_whitespace().collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+pub fn do_str(args: SubCommandStr, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    let pointer = args.pointer.as_str();
+
+    match value.pointer_mut(pointer) {
+        Some(target) if args.recursive => apply_all(target, args.operation),
+        Some(Value::String(text)) => *text = args.operation.apply(text),
+        Some(other) => {
+            return Err(format!("{} is of type {}, not string", pointer, type_name(other)).into())
+        }
+        None => return Err(format!("{} does not exist", pointer).into()),
+    }
+    Ok(value_printer(pretty, &value))
+}
+
+pub fn do_trim(args: SubCommandTrim, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    let pointer = args.pointer.as_ref().map_or("", Pointer::as_str);
+    let operation = match args.collapse {
+        true => Operation::Collapse,
+        false => Operation::Trim,
+    };
+
+    match value.pointer_mut(pointer) {
+        Some(target) => apply_all(target, operation),
+        None => return Err(format!("{} does not exist", pointer).into()),
+    }
+    Ok(value_printer(pretty, &value))
+}
+
+pub fn do_sub(args: SubCommandSub, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    let pointer = args.pointer.as_str();
+    let regex =
+        Regex::new(&args.pattern).map_err(|e| exit::usage(format!("invalid pattern: {}", e)))?;
+    let replacement = group_numbers(&args.replacement);
+    let replace = |text: &str| match args.global {
+        true => regex.replace_all(text, replacement.as_ref()).into_owned(),
+        false => regex.replace(text, replacement.as_ref()).into_owned(),
+    };
+
+    match value.pointer_mut(pointer) {
+        Some(target) if args.recursive => change_strings(target, &replace),
+        Some(Value::String(text)) => *text = replace(text),
+        Some(other) => {
+            return Err(format!("{} is of type {}, not string", pointer, type_name(other)).into())
+        }
+        None => return Err(format!("{} does not exist", pointer).into()),
+    }
+    Ok(value_printer(pretty, &value))
+}
+
+pub fn do_substr(args: SubCommandSubstr, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    let pointer = args.pointer.as_str();
+
+    let part = match value.pointer_mut(pointer) {
+        Some(Value::String(text)) => {
+            *text = substring(text, args.start, args.length);
+            text.clone()
+        }
+        Some(other) => {
+            return Err(format!("{} is of type {}, not string", pointer, type_name(other)).into())
+        }
+        None => return Err(format!("{} does not exist", pointer).into()),
+    };
+    match args.print_only {
+        true => Ok(part),
+        false => Ok(value_printer(pretty, &value)),
+    }
+}
+
+/// The replacement with `$N` written as `${N}` when a letter, digit or `_` follows the number,
+/// so `$1r` is group 1 and an `r`, not the group named `1r` that the regex crate would look for.
+fn group_numbers(replacement: &str) -> Cow<'_, str> {
+    let bytes = replacement.as_bytes();
+    let mut out = String::new();
+    let mut copied = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] != b'$' {
+            index += 1;
+            continue;
+        }
+        if bytes.get(index + 1) == Some(&b'$') {
+            index += 2;
+            continue;
+        }
+        let digits = bytes[index + 1..]
+            .iter()
+            .take_while(|x| x.is_ascii_digit())
+            .count();
+        let end = index + 1 + digits;
+        if digits > 0
+            && bytes
+                .get(end)
+                .is_some_and(|x| x.is_ascii_alphanumeric() || *x == b'_')
+        {
+            out.push_str(&replacement[copied..index]);
+            out.push_str(&format!("${{{}}}", &replacement[index + 1..end]));
+            copied = end;
+        }
+        index = end;
+    }
+    match copied {
+        0 => Cow::Borrowed(replacement),
+        _ => {
+            out.push_str(&replacement[copied..]);
+            Cow::Owned(out)
+        }
+    }
+}
+
+fn substring(text: &str, start: i64, length: Option<usize>) -> String {
+    let count = text.chars().count();
+    let start = match start {
+        start if start < 0 => count.saturating_sub(start.unsigned_abs() as usize),
+        start => (start as usize).min(count),
+    };
+    text.chars()
+        .skip(start)
+        .take(length.unwrap_or(usize::MAX))
+        .collect()
+}
+
+fn apply_all(value: &mut Value, operation: Operation) {
+    change_strings(value, &|text| operation.apply(text))
+}
+
+/// Replaces every string in the value by the result of `change`, object keys are left alone.
+fn change_strings(value: &mut Value, change: &dyn Fn(&str) -> String) {
+    match value {
+        Value::String(text) => *text = change(text),
+        Value::Object(map) => map.values_mut().for_each(|x| change_strings(x, change)),
+        Value::Array(list) => list.iter_mut().for_each(|x| change_strings(x, change)),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod strings_test {
+    use super::{apply_all, change_strings, group_numbers, substring, Operation};
+    use regex::Regex;
+    use serde_json::json;
+
+    #[test]
+    fn operations() {
+        assert_eq!(Operation::Upper.apply("straße ǆ"), "STRASSE Ǆ");
+        assert_eq!(Operation::Lower.apply("ÉCOLE ΣΑΣ"), "école σας");
+        assert_eq!(Operation::Trim.apply("\u{3000} ok\t\n"), "ok");
+        assert_eq!(Operation::Capitalize.apply("éCOLE"), "École");
+        assert_eq!(Operation::Capitalize.apply(""), "");
+        assert_eq!(
+            Operation::Collapse.apply(" a \t\n b\u{a0}\u{a0}c  "),
+            "a b c"
+        );
+    }
+
+    #[test]
+    fn whitespace_only() {
+        let mut value = json!({" key ": " \n\t ", "list": ["", "\r\n", [" "]]});
+        apply_all(&mut value, Operation::Trim);
+        assert_eq!(value, json!({" key ": "", "list": ["", "", [""]]}));
+
+        let mut value = json!({" key ": " \n\t  x  y "});
+        apply_all(&mut value, Operation::Collapse);
+        assert_eq!(value, json!({" key ": "x y"}));
+    }
+
+    #[test]
+    fn recursive() {
+        let mut value = json!({"Name": " Zoë ", "tags": [" a", ["b "], {"c": " ü "}], "n": 1});
+        apply_all(&mut value, Operation::Trim);
+        assert_eq!(
+            value,
+            json!({"Name": "Zoë", "tags": ["a", ["b"], {"c": "ü"}], "n": 1})
+        );
+        apply_all(&mut value, Operation::Upper);
+        assert_eq!(
+            value,
+            json!({"Name": "ZOË", "tags": ["A", ["B"], {"c": "Ü"}], "n": 1})
+        );
+    }
+
+    #[test]
+    fn replace_captures() {
+        let regex = Regex::new(r"(?P<key>\w+)=(\d+)").unwrap();
+        let mut value = json!({"a": "x=1 y=22", "b": [{"c": "é=3"}, 4, null]});
+        change_strings(&mut value, &|text| {
+            regex.replace_all(text, "${2}:$key").into_owned()
+        });
+        assert_eq!(
+            value,
+            json!({"a": "1:x 22:y", "b": [{"c": "3:é"}, 4, null]})
+        );
+    }
+
+    #[test]
+    fn numbered_groups() {
+        assert_eq!(group_numbers("$1r"), "${1}r");
+        assert_eq!(group_numbers("$12_x $2"), "${12}_x $2");
+        assert_eq!(
+            group_numbers("$$1r ${1}r $name $1-"),
+            "$$1r ${1}r $name $1-"
+        );
+        assert_eq!(group_numbers("é$1é$2a$"), "é$1é${2}a$");
+
+        let regex = Regex::new("(b)").unwrap();
+        assert_eq!(regex.replace("abc", group_numbers("$1r").as_ref()), "abrc");
+    }
+
+    #[test]
+    fn substrings() {
+        assert_eq!(substring("1a2b3c4d", 0, Some(4)), "1a2b");
+        assert_eq!(substring("1a2b3c4d", 6, None), "4d");
+        assert_eq!(substring("1a2b3c4d", -3, Some(2)), "c4");
+        assert_eq!(substring("naïve 😀!", 2, Some(5)), "ïve 😀");
+        assert_eq!(substring("naïve 😀!", -2, None), "😀!");
+        assert_eq!(substring("abc", -10, Some(2)), "ab");
+        assert_eq!(substring("abc", 10, Some(2)), "");
+        assert_eq!(substring("abc", 1, Some(10)), "bc");
+        assert_eq!(substring("", 0, None), "");
+    }
+}
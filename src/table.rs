@@ -0,0 +1,364 @@
+use std::io::IsTerminal;
+
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::{exit, type_name, variable_or_value, Pointer};
+
+/// Spaces between the columns of the aligned table.
+const GAP: usize = 2;
+/// Columns are not narrowed below this to fit the terminal.
+const MIN_WIDTH: usize = 4;
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "table",
+    description = "Print the array of objects at the pointer as a table",
+    note = "The columns are the keys of the objects in the order they first appear, or the ones given with --columns. Strings are printed without quotes, null and missing keys as empty cells and arrays and objects as compact json. Tabs, newlines, carriage returns and backslashes in cells are written as \\t, \\n, \\r and \\\\.
+Columns are aligned with spaces, cells longer than --max-width are cut off with …. When the output is a terminal the table is narrowed to the width in $COLUMNS. With --tsv the cells are separated by tabs instead, without aligning or cutting off.
+--markdown prints a GitHub flavored Markdown table, with arrays and objects as inline code. There `|` and backticks are escaped with a backslash and newlines become <br>, other Markdown in strings is left as it is.",
+    example = r#"input                                                   arguments       output
+{{"pods": [{{"name": "a", "ready": true}}, {{"name": "bc"}}]}}    /pods           "name  ready", "a     true" and "bc" on their own line
+{{"pods": [{{"name": "a", "ready": true}}, {{"name": "bc"}}]}}    /pods --tsv     "name<TAB>ready", "a<TAB>true" and "bc<TAB>" on their own line
+[{{"a": "x|y", "b": [1]}}]                                ""  --markdown  "| a | b |", "| --- | --- |" and "| x\|y | `[1]` |" on their own line"#
+)]
+pub struct SubCommandTable {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(option)]
+    /// the keys to use as columns, in this order, separated by commas
+    columns: Option<String>,
+    #[argh(option)]
+    /// cut off cells longer than this many characters
+    max_width: Option<usize>,
+    #[argh(switch)]
+    /// separate the cells with tabs instead of aligning them
+    tsv: bool,
+    #[argh(switch)]
+    /// print a Markdown table
+    markdown: bool,
+}
+
+pub fn do_table(args: SubCommandTable) -> Result<Option<String>, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    let pointer = args.pointer.as_ref().map_or("", Pointer::as_str);
+    let list = match value.pointer(pointer) {
+        Some(Value::Array(list)) => list,
+        Some(other) => {
+            return Err(format!("{} is of type {}, not array", pointer, type_name(other)).into())
+        }
+        None => return Err(format!("{} does not exist", pointer).into()),
+    };
+
+    let columns = match &args.columns {
+        Some(columns) => columns.split(',').map(|x| x.trim().to_string()).collect(),
+        None => columns(list, pointer)?,
+    };
+    if columns.is_empty() {
+        return Ok(None);
+    }
+    if args.tsv && args.markdown {
+        return Err(exit::usage(String::from(
+            "--tsv and --markdown can not be combined",
+        )));
+    }
+    if args.markdown {
+        return Ok(Some(markdown(
+            &columns,
+            &rows(list, &columns, pointer, &markdown_cell)?,
+        )));
+    }
+
+    let rows = rows(list, &columns, pointer, &|x| escape(&cell(x)))?;
+    if args.tsv {
+        return Ok(Some(tsv(&columns, &rows)));
+    }
+    let terminal = match std::io::stdout().is_terminal() {
+        true => std::env::var("COLUMNS").ok().and_then(|x| x.parse().ok()),
+        false => None,
+    };
+    Ok(Some(aligned(&columns, &rows, args.max_width, terminal)))
+}
+
+/// The keys of the objects in the order they first appear.
+fn columns(list: &[Value], pointer: &str) -> Result<Vec<String>, String> {
+    let mut columns: Vec<String> = Vec::new();
+    for (index, item) in list.iter().enumerate() {
+        for key in object(item, pointer, index)?.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    Ok(columns)
+}
+
+fn object<'a>(
+    item: &'a Value,
+    pointer: &str,
+    index: usize,
+) -> Result<&'a serde_json::Map<String, Value>, String> {
+    match item {
+        Value::Object(map) => Ok(map),
+        other => Err(format!(
+            "{}/{} is of type {}, not object",
+            pointer,
+            index,
+            type_name(other)
+        )),
+    }
+}
+
+/// The text of every cell, as `format` writes the value of the column or `None` for missing keys.
+fn rows(
+    list: &[Value],
+    columns: &[String],
+    pointer: &str,
+    format: &dyn Fn(Option<&Value>) -> String,
+) -> Result<Vec<Vec<String>>, String> {
+    list.iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let map = object(item, pointer, index)?;
+            Ok(columns
+                .iter()
+                .map(|column| format(map.get(column)))
+                .collect())
+        })
+        .collect()
+}
+
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn markdown_cell(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::Array(_)) | Some(Value::Object(_)) => code(&cell(value)),
+        _ => markdown_escape(&cell(value)),
+    }
+}
+
+fn markdown_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('`', "\\`")
+        .replace("\r\n", "<br>")
+        .replace(['\r', '\n'], "<br>")
+}
+
+/// The text as inline code, fenced by more backticks than it contains in a row.
+fn code(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest + 1);
+    // a space keeps backticks at the ends apart from the fence, Markdown strips it again
+    let padding = match text.starts_with('`') || text.ends_with('`') {
+        true => " ",
+        false => "",
+    };
+    format!(
+        "{}{}{}{}{}",
+        fence,
+        padding,
+        // in tables the pipes need escaping even inside code
+        text.replace('|', "\\|"),
+        padding,
+        fence
+    )
+}
+
+fn markdown(columns: &[String], rows: &[Vec<String>]) -> String {
+    let header: Vec<String> = columns.iter().map(|x| markdown_escape(x)).collect();
+    let separator = vec![String::from("---"); columns.len()];
+    [header, separator]
+        .iter()
+        .chain(rows)
+        .map(|row| format!("| {} |", row.join(" | ")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn tsv(columns: &[String], rows: &[Vec<String>]) -> String {
+    let header: Vec<String> = columns.iter().map(|x| escape(x)).collect();
+    std::iter::once(&header)
+        .chain(rows)
+        .map(|row| row.join("\t"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn aligned(
+    columns: &[String],
+    rows: &[Vec<String>],
+    max_width: Option<usize>,
+    terminal: Option<usize>,
+) -> String {
+    let header: Vec<String> = columns.iter().map(|x| escape(x)).collect();
+    let table: Vec<&Vec<String>> = std::iter::once(&header).chain(rows).collect();
+
+    let mut widths: Vec<usize> = (0..columns.len())
+        .map(|column| {
+            let widest = table.iter().map(|row| row[column].chars().count()).max();
+            let widest = widest.unwrap_or_default();
+            max_width.map_or(widest, |max| widest.min(max.max(1)))
+        })
+        .collect();
+    if let Some(terminal) = terminal {
+        fit(&mut widths, terminal);
+    }
+
+    table
+        .iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| {
+                    let cell = cut(cell, width);
+                    let padding = width - cell.chars().count();
+                    format!("{}{}", cell, " ".repeat(padding))
+                })
+                .collect();
+            cells.join(&" ".repeat(GAP)).trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Narrows the widest columns until the table fits in `available` characters,
+/// or every column is `MIN_WIDTH` wide.
+fn fit(widths: &mut [usize], available: usize) {
+    let gaps = GAP * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + gaps > available {
+        match widths.iter_mut().max() {
+            Some(widest) if *widest > MIN_WIDTH => *widest -= 1,
+            _ => break,
+        }
+    }
+}
+
+/// The text cut off with `…` when it has more than `width` characters.
+fn cut(text: &str, width: usize) -> String {
+    match text.chars().count() > width {
+        true => text
+            .chars()
+            .take(width.saturating_sub(1))
+            .chain(std::iter::once('…'))
+            .collect(),
+        false => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod table_test {
+    use super::{
+        aligned, cell, code, columns, cut, escape, fit, markdown, markdown_cell, rows, tsv,
+    };
+    use serde_json::{json, Value};
+
+    fn table(list: Value) -> (Vec<String>, Vec<Vec<String>>) {
+        let list = list.as_array().unwrap();
+        let columns = columns(list, "").unwrap();
+        let rows = rows(list, &columns, "", &|x| escape(&cell(x))).unwrap();
+        (columns, rows)
+    }
+
+    #[test]
+    fn cells() {
+        let (columns, rows) = table(json!([
+            {"name": "a\tb", "tags": ["x", 1], "n": null},
+            {"ok": true, "name": "c\\d\ne", "n": 1.5}
+        ]));
+        assert_eq!(columns, ["name", "tags", "n", "ok"]);
+        assert_eq!(
+            tsv(&columns, &rows),
+            "name\ttags\tn\tok\na\\tb\t[\"x\",1]\t\t\nc\\\\d\\ne\t\t1.5\ttrue"
+        );
+    }
+
+    #[test]
+    fn not_objects() {
+        let list = json!([{"a": 1}, 2]);
+        assert_eq!(
+            columns(list.as_array().unwrap(), "/x").unwrap_err(),
+            "/x/1 is of type number, not object"
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        let (columns, rows) = table(json!([
+            {"name": "web-1", "status": "Running", "age": 3},
+            {"name": "db", "age": 12}
+        ]));
+        assert_eq!(
+            aligned(&columns, &rows, None, None),
+            "name   status   age\nweb-1  Running  3\ndb              12"
+        );
+        assert_eq!(
+            aligned(&columns, &rows, Some(4), None),
+            "name  sta…  age\nweb…  Run…  3\ndb          12"
+        );
+    }
+
+    #[test]
+    fn terminal_width() {
+        let mut widths = vec![10, 30, 5];
+        fit(&mut widths, 40);
+        assert_eq!(widths, [10, 21, 5]);
+
+        let mut widths = vec![10, 30, 5];
+        fit(&mut widths, 5);
+        assert_eq!(widths, [4, 4, 4]);
+
+        assert_eq!(cut("naïve", 3), "na…");
+        assert_eq!(cut("naïve", 5), "naïve");
+    }
+
+    #[test]
+    fn markdown_escaping() {
+        let list = json!([
+            {"name": "a|b `c` \\|", "note": "line 1\nline 2\r\n", "n": 1},
+            {"name": "*bold*", "note": {"pipe": "x|y", "tick": "`"}, "n": null}
+        ]);
+        let list = list.as_array().unwrap();
+        let columns = columns(list, "").unwrap();
+        let rows = rows(list, &columns, "", &markdown_cell).unwrap();
+        assert_eq!(
+            markdown(&columns, &rows),
+            [
+                "| name | note | n |",
+                "| --- | --- | --- |",
+                r"| a\|b \`c\` \\\| | line 1<br>line 2<br> | 1 |",
+                r#"| *bold* | ``{"pipe":"x\|y","tick":"`"}`` |  |"#,
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn code_spans() {
+        assert_eq!(code("[1]"), "`[1]`");
+        assert_eq!(code("a``b"), "```a``b```");
+        assert_eq!(code("`a"), "`` `a ``");
+        assert_eq!(code("[\"|\"]"), "`[\"\\|\"]`");
+    }
+}
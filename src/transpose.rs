@@ -0,0 +1,184 @@
+use argh::FromArgs;
+use serde_json::{Map, Value};
+
+use crate::{exit, value_printer, variable_or_object, Pointer};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "transpose",
+    description = "Turn an array of objects into an object of arrays, or the other way around",
+    note = "The keys of the object of arrays are the keys of the objects in the order they are first seen. Keys an object does not have, and the items of arrays shorter than the longest one, are filled with null, use --strict to fail on those instead.",
+    example = r#"input                          arguments               output
+[{{"a": 1, "b": 2}}, {{"a": 3}}]     ""                      {{"a":[1,3],"b":[2,null]}}
+{{"a": [1, 3], "b": [2]}}          ""                      [{{"a":1,"b":2}},{{"a":3,"b":null}}]"#
+)]
+pub struct SubCommandTranspose {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Option<Pointer>,
+    #[argh(switch)]
+    /// fail when objects have different keys or arrays have different lengths
+    strict: bool,
+}
+
+pub fn do_transpose(args: SubCommandTranspose, pretty: bool) -> Result<String, exit::Error> {
+    let mut value = variable_or_object(&args.variable)?;
+    let pointer = args.pointer.as_ref().map_or("", Pointer::as_str);
+    let target = value
+        .pointer_mut(pointer)
+        .ok_or_else(|| format!("{} does not exist", pointer))?;
+
+    *target = match target {
+        Value::Array(rows) => Value::Object(to_columns(rows, args.strict)?),
+        Value::Object(columns) => Value::Array(to_rows(columns, args.strict)?),
+        _ => return Err(format!("{} is not an array or an object", pointer).into()),
+    };
+    Ok(value_printer(pretty, &value))
+}
+
+/// The array of objects as an object of arrays, every array as long as the rows.
+fn to_columns(rows: &[Value], strict: bool) -> Result<Map<String, Value>, String> {
+    let mut columns = Map::new();
+    for (index, row) in rows.iter().enumerate() {
+        let row = row
+            .as_object()
+            .ok_or_else(|| format!("item {} is not an object", index))?;
+        for key in row.keys() {
+            if !columns.contains_key(key) {
+                if strict && index > 0 {
+                    return Err(format!(
+                        "item {} has key {:?} the items before do not have",
+                        index, key
+                    ));
+                }
+                columns.insert(key.clone(), Value::Array(Vec::with_capacity(rows.len())));
+            }
+        }
+        for (key, column) in columns.iter_mut() {
+            let cell = match row.get(key) {
+                Some(cell) => cell.clone(),
+                None if strict => return Err(format!("item {} is missing key {:?}", index, key)),
+                None => Value::Null,
+            };
+            if let Value::Array(column) = column {
+                column.resize(index, Value::Null);
+                column.push(cell);
+            }
+        }
+    }
+    Ok(columns)
+}
+
+/// The object of arrays as an array of objects, as many as the longest array.
+fn to_rows(columns: &Map<String, Value>, strict: bool) -> Result<Vec<Value>, String> {
+    let mut length = None;
+    for (key, column) in columns {
+        let column = column
+            .as_array()
+            .ok_or_else(|| format!("{:?} is not an array", key))?;
+        match length {
+            Some(length) if strict && length != column.len() => {
+                return Err(format!(
+                    "{:?} has {} items where the arrays before have {}",
+                    key,
+                    column.len(),
+                    length
+                ))
+            }
+            Some(length) if length >= column.len() => (),
+            _ => length = Some(column.len()),
+        }
+    }
+
+    Ok((0..length.unwrap_or(0))
+        .map(|index| {
+            Value::Object(
+                columns
+                    .iter()
+                    .map(|(key, column)| {
+                        (
+                            key.clone(),
+                            column.get(index).cloned().unwrap_or(Value::Null),
+                        )
+                    })
+                    .collect(),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod transpose_test {
+    use super::{to_columns, to_rows};
+    use serde_json::{json, Value};
+
+    fn columns(value: Value, strict: bool) -> Result<Value, String> {
+        to_columns(value.as_array().unwrap(), strict).map(Value::Object)
+    }
+
+    fn rows(value: Value, strict: bool) -> Result<Value, String> {
+        to_rows(value.as_object().unwrap(), strict).map(Value::Array)
+    }
+
+    #[test]
+    fn regular_round_trip() {
+        let table = json!([{"a": 1, "b": "x"}, {"a": 2, "b": "y"}, {"a": 3, "b": null}]);
+        let transposed = json!({"a": [1, 2, 3], "b": ["x", "y", null]});
+        assert_eq!(columns(table.clone(), true).unwrap(), transposed);
+        assert_eq!(rows(transposed.clone(), true).unwrap(), table);
+
+        assert_eq!(columns(json!([]), true).unwrap(), json!({}));
+        assert_eq!(rows(json!({}), true).unwrap(), json!([]));
+        assert_eq!(rows(json!({"a": []}), true).unwrap(), json!([]));
+    }
+
+    #[test]
+    fn ragged_rows() {
+        let table = json!([{"b": 1}, {"a": 2}, {"a": 3, "b": 4}]);
+        let transposed = json!({"b": [1, null, 4], "a": [null, 2, 3]});
+        assert_eq!(columns(table, false).unwrap(), transposed);
+        // the missing keys come back as null
+        assert_eq!(
+            rows(transposed, false).unwrap(),
+            json!([{"b": 1, "a": null}, {"b": null, "a": 2}, {"b": 4, "a": 3}])
+        );
+    }
+
+    #[test]
+    fn ragged_columns() {
+        let transposed = json!({"a": [1], "b": [2, 3, 4]});
+        let table = json!([{"a": 1, "b": 2}, {"a": null, "b": 3}, {"a": null, "b": 4}]);
+        assert_eq!(rows(transposed, false).unwrap(), table);
+        // the short arrays come back filled with null
+        assert_eq!(
+            columns(table, false).unwrap(),
+            json!({"a": [1, null, null], "b": [2, 3, 4]})
+        );
+    }
+
+    #[test]
+    fn strict() {
+        assert_eq!(
+            columns(json!([{"a": 1}, {"a": 2, "b": 3}]), true).unwrap_err(),
+            r#"item 1 has key "b" the items before do not have"#
+        );
+        assert_eq!(
+            columns(json!([{"a": 1, "b": 2}, {"a": 2}]), true).unwrap_err(),
+            r#"item 1 is missing key "b""#
+        );
+        assert_eq!(
+            rows(json!({"a": [1, 2], "b": [3]}), true).unwrap_err(),
+            r#""b" has 1 items where the arrays before have 2"#
+        );
+        assert_eq!(
+            columns(json!([{"a": 1}, 2]), false).unwrap_err(),
+            "item 1 is not an object"
+        );
+        assert_eq!(
+            rows(json!({"a": [1], "b": 2}), false).unwrap_err(),
+            r#""b" is not an array"#
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use argh::FromArgs;
+use serde_json::Value;
+
+use crate::number::values_equal;
+use crate::{
+    depth, exit, input, pointer_mut, value_from_str, value_printer, variable_or_object, Pointer,
+};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "upsert",
+    description = "Replace the object in the array at the pointer that has the same key as the value, or append the value",
+    note = "Items match when the field at the key pointer is equal to the one of the value, compared like compare does. More than one matching item is an error.
+With --merge the value is merged into the matching item: objects are merged key by key, anything else is replaced.",
+    example = r#"input                              arguments                          output
+{{"a": [{{"id": 1, "n": 1}}]}}         /a /id '{{"id": 1, "n": 2}}'         {{"a":[{{"id":1,"n":2}}]}}
+{{"a": [{{"id": 1, "n": 1}}]}}         /a /id '{{"id": 2}}'                 {{"a":[{{"id":1,"n":1}},{{"id":2}}]}}
+{{"a": [{{"id": 1, "n": 1}}]}}         /a /id '{{"id": 1, "m": 2}}' --merge {{"a":[{{"id":1,"n":1,"m":2}}]}}"#
+)]
+pub struct SubCommandUpsert {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(positional)]
+    pointer: Pointer,
+    #[argh(positional)]
+    key: Pointer,
+    #[argh(positional, from_str_fn(value_from_str))]
+    value: Value,
+    #[argh(switch)]
+    /// merge the value into the matching item instead of replacing it
+    merge: bool,
+}
+
+pub fn do_upsert(args: SubCommandUpsert, pretty: bool) -> Result<String, exit::Error> {
+    let mut map = variable_or_object(&args.variable)?;
+    if !args.value.is_object() {
+        return Err(String::from("the value is not an object").into());
+    }
+
+    let target = pointer_mut(&mut map, args.pointer.as_str())
+        .ok_or_else(|| format!("{} is not an array", args.pointer.as_str()))?;
+    if target.is_null() {
+        // a new pointer, like set creates it
+        *target = Value::Array(Vec::new());
+    }
+    let list = match target {
+        Value::Array(list) => list,
+        _ => return Err(format!("{} is not an array", args.pointer.as_str()).into()),
+    };
+    upsert(list, args.key.as_str(), args.value, args.merge)?;
+    depth::check(&map, input::options().max_depth)?;
+
+    Ok(value_printer(pretty, &map))
+}
+
+fn upsert(list: &mut Vec<Value>, key: &str, value: Value, merge: bool) -> Result<(), String> {
+    let field = value
+        .pointer(key)
+        .ok_or_else(|| format!("the value has nothing at {}", key))?;
+    let matches: Vec<usize> = list
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.pointer(key).is_some_and(|x| values_equal(x, field)))
+        .map(|(index, _)| index)
+        .collect();
+
+    match matches.as_slice() {
+        [] => list.push(value),
+        [index] if merge => merge_into(&mut list[*index], value),
+        [index] => list[*index] = value,
+        indices => {
+            return Err(format!(
+                "items {} all match on {}",
+                indices
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                key
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Merges objects key by key, any other value replaces the target.
+fn merge_into(target: &mut Value, value: Value) {
+    match (target, value) {
+        (Value::Object(target), Value::Object(value)) => {
+            for (key, value) in value {
+                match target.get_mut(&key) {
+                    Some(item) => merge_into(item, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
+#[cfg(test)]
+mod upsert_test {
+    use super::upsert;
+    use serde_json::{json, Value};
+
+    fn upserted(list: Value, key: &str, value: Value, merge: bool) -> Result<Value, String> {
+        let mut list = match list {
+            Value::Array(list) => list,
+            _ => unreachable!(),
+        };
+        upsert(&mut list, key, value, merge)?;
+        Ok(Value::Array(list))
+    }
+
+    #[test]
+    fn replace() {
+        assert_eq!(
+            upserted(
+                json!([{"id": 1, "a": 1}, {"id": 2, "a": 2}]),
+                "/id",
+                json!({"id": 2.0, "b": 3}),
+                false
+            ),
+            Ok(json!([{"id": 1, "a": 1}, {"id": 2.0, "b": 3}]))
+        );
+    }
+
+    #[test]
+    fn append() {
+        assert_eq!(
+            upserted(
+                json!([{"id": 1}, {"name": 2}]),
+                "/id",
+                json!({"id": 2}),
+                false
+            ),
+            Ok(json!([{"id": 1}, {"name": 2}, {"id": 2}]))
+        );
+        assert_eq!(
+            upserted(
+                json!([{"id": {"x": 1, "y": 2}}]),
+                "/id",
+                json!({"id": {"y": 2}}),
+                false
+            ),
+            Ok(json!([{"id": {"x": 1, "y": 2}}, {"id": {"y": 2}}]))
+        );
+    }
+
+    #[test]
+    fn merge() {
+        assert_eq!(
+            upserted(
+                json!([{"id": {"y": 2, "x": 1}, "a": {"b": 1, "c": [1]}}]),
+                "/id",
+                json!({"id": {"x": 1, "y": 2}, "a": {"c": [2], "d": null}}),
+                true
+            ),
+            Ok(json!([{"id": {"y": 2, "x": 1}, "a": {"b": 1, "c": [2], "d": null}}]))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            upserted(
+                json!([{"id": 1}, {"id": 2}, {"id": 1}]),
+                "/id",
+                json!({"id": 1}),
+                false
+            ),
+            Err(String::from("items 0, 2 all match on /id"))
+        );
+        assert_eq!(
+            upserted(json!([]), "/id", json!({"name": 1}), false),
+            Err(String::from("the value has nothing at /id"))
+        );
+    }
+}
@@ -0,0 +1,16 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static LEVEL: AtomicU8 = AtomicU8::new(0);
+
+/// Sets how many times `--verbose` was given.
+pub fn configure(level: u8) {
+    LEVEL.store(level, Ordering::Relaxed);
+}
+
+/// Prints the message to stderr when `--verbose` was given at least `level` times.
+/// The message is only made when it is printed.
+pub fn log(level: u8, message: impl FnOnce() -> String) {
+    if LEVEL.load(Ordering::Relaxed) >= level {
+        eprintln!("verbose: {}", message());
+    }
+}
@@ -0,0 +1,77 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+use crate::{exit, print_output, run, TopLevel};
+
+/// How often the watched file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long the file has to stay the same before a change is handled, editors that write
+/// through a temporary file and a rename would otherwise be seen half way.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+type Stamp = Option<(SystemTime, u64)>;
+
+/// Runs the read command and runs it again every time the input file changes, until interrupted.
+pub fn watch(arg: &TopLevel) -> Result<(), exit::Error> {
+    if arg.command.is_mutating() {
+        return Err(String::from("--watch only works with read commands").into());
+    }
+
+    let path = match (&arg.file, arg.command.input_file()) {
+        (Some(path), _) => path.clone(),
+        (None, Some(path)) => PathBuf::from(path),
+        (None, None) => {
+            return Err(String::from("--watch needs a file as input, use @file or --file").into())
+        }
+    };
+
+    let mut stamp = stamp(&path);
+    let mut last_output = None;
+
+    loop {
+        match run(arg) {
+            Ok(Some(output)) => {
+                if !(arg.watch_changed_only && last_output.as_ref() == Some(&output)) {
+                    print_output(arg, &output);
+                    let _ = std::io::stdout().flush();
+                    last_output = Some(output);
+                }
+            }
+            Ok(None) => (),
+            Err(e) if e.message.is_empty() => (),
+            Err(e) => eprintln!("Error: {:?}", e),
+        }
+
+        wait_for_change(&path, &mut stamp);
+    }
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn wait_for_change(path: &Path, last: &mut Stamp) {
+    loop {
+        sleep(POLL_INTERVAL);
+        let mut current = stamp(path);
+        if current.is_none() || current == *last {
+            continue;
+        }
+
+        loop {
+            sleep(DEBOUNCE);
+            let next = stamp(path);
+            if next.is_some() && next == current {
+                break;
+            }
+            current = next;
+        }
+
+        *last = current;
+        return;
+    }
+}
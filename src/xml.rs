@@ -0,0 +1,622 @@
+use argh::FromArgs;
+use serde_json::{Map, Value};
+
+use crate::{exit, input, pointer, type_name, value_printer, variable_or_value};
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "from-xml",
+    description = "Convert an XML document to json",
+    note = "The document becomes an object with the name of the root element as its only key. An element becomes:
+- null when it has no attributes, child elements or text,
+- a string when it only has text,
+- an object otherwise, with `@name` keys for its attributes, a key per child element name, and `#text` for its text.
+Child elements with the same name become an array in the order of the document, at the place of the first one. Names keep their namespace prefix (`soap:Body`, `@xmlns:soap`) and all values are strings.
+Text that is only whitespace is left out, other text and CDATA sections are joined as they are. Entities are decoded, comments, processing instructions and the doctype are left out.",
+    example = r#"input                                                   output
+<a id="1"><b>x</b><b>y</b><c/></a>                      {{"a":{{"@id":"1","b":["x","y"],"c":null}}}}
+<p>one <![CDATA[<two>]]></p>                            {{"p":"one <two>"}}"#
+)]
+pub struct SubCommandFromXml {
+    #[argh(positional)]
+    /// the XML text, an environment variable or @path
+    pub variable: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug, Clone)]
+#[argh(
+    subcommand,
+    name = "to-xml",
+    description = "Convert the map to an XML document, the reverse of from-xml",
+    note = "An object with a single key that is a valid element name is the root element, like from-xml writes it. Any other map, or every map when --root is given, becomes the content of the root element.
+Keys starting with `@` become attributes and `#text` the text of the element, other keys become child elements and arrays repeat the element for every item. Items of arrays in arrays, and of the map itself when it is an array, become `item` elements. null becomes an empty element.
+Keys that are not valid element names fail.",
+    example = r#"input                                           arguments           output
+{{"a": {{"@id": 1, "b": ["x", "y"], "c": null}}}}     ""                  <a id="1"><b>x</b><b>y</b><c/></a>
+{{"b": "x", "c": "y"}}                              --root doc          <doc><b>x</b><c>y</c></doc>"#
+)]
+pub struct SubCommandToXml {
+    #[argh(positional)]
+    pub variable: String,
+    #[argh(option)]
+    /// the name of the root element, defaults to the single key of the map or `root`
+    root: Option<String>,
+}
+
+pub fn do_from_xml(args: SubCommandFromXml, pretty: bool) -> Result<String, exit::Error> {
+    let text = input::Source::of(&args.variable)
+        .text()?
+        .ok_or_else(|| input::not_set(&args.variable))?;
+    Ok(value_printer(pretty, &parse(&text).map_err(exit::parse)?))
+}
+
+pub fn do_to_xml(args: SubCommandToXml, pretty: bool) -> Result<String, exit::Error> {
+    let value = variable_or_value(&args.variable)?;
+    let (name, content) = match (&args.root, &value) {
+        (Some(root), _) => (root.as_str(), &value),
+        (None, Value::Object(map)) if map.len() == 1 && is_name(map.keys().next().unwrap()) => map
+            .iter()
+            .next()
+            .map(|(key, item)| (key.as_str(), item))
+            .unwrap(),
+        (None, _) => ("root", &value),
+    };
+    if !is_name(name) {
+        return Err(format!("{:?} is not a valid element name", name).into());
+    }
+
+    let mut output = String::new();
+    Writer { pretty }.element(&mut output, name, content, "", 0)?;
+    Ok(output)
+}
+
+/// Parses an XML document to json, see the note of `from-xml` for how.
+///
+/// Errors mention the line and column like the json errors do.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser { text, index: 0 };
+    parser.misc()?;
+    if parser.eat("<!DOCTYPE") {
+        parser.doctype()?;
+        parser.misc()?;
+    }
+    if parser.peek() != Some('<') {
+        return Err(parser.error("expected root element"));
+    }
+    let (name, value) = parser.element()?;
+    parser.misc()?;
+    if parser.index < text.len() {
+        return Err(parser.error("trailing characters"));
+    }
+
+    let mut map = Map::new();
+    map.insert(name, value);
+    Ok(Value::Object(map))
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.index..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, word: &str) -> bool {
+        if self.text[self.index..].starts_with(word) {
+            self.index += word.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        let before = &self.text[..self.index];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+        format!("{} at line {} column {}", message, line, column)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    /// Skips everything up to and including `end`, `start` was just eaten.
+    fn skip_past(&mut self, start: &str, end: &str, what: &str) -> Result<&'a str, String> {
+        match self.text[self.index..].find(end) {
+            Some(length) => {
+                let skipped = &self.text[self.index..self.index + length];
+                self.index += length + end.len();
+                Ok(skipped)
+            }
+            None => {
+                self.index -= start.len();
+                Err(self.error(&format!("unterminated {}", what)))
+            }
+        }
+    }
+
+    /// Skips whitespace, comments and processing instructions, like the xml declaration.
+    fn misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            if self.eat("<!--") {
+                self.skip_past("<!--", "-->", "comment")?;
+            } else if self.eat("<?") {
+                self.skip_past("<?", "?>", "processing instruction")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Skips the doctype, `<!DOCTYPE` was just eaten. It can have declarations between `[ ]`.
+    fn doctype(&mut self) -> Result<(), String> {
+        let mut depth = 0;
+        loop {
+            match self.next() {
+                Some('[') => depth += 1,
+                Some(']') => depth -= 1,
+                Some('>') if depth == 0 => return Ok(()),
+                Some(quote @ '"') | Some(quote @ '\'') => {
+                    while !matches!(self.next(), Some(c) if c == quote) {
+                        if self.peek().is_none() {
+                            return Err(self.error("EOF while parsing the doctype"));
+                        }
+                    }
+                }
+                Some(_) => (),
+                None => return Err(self.error("EOF while parsing the doctype")),
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let start = self.index;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.'))
+        {
+            self.next();
+        }
+        let name = &self.text[start..self.index];
+        match is_name(name) {
+            true => Ok(name.to_string()),
+            false => {
+                self.index = start;
+                Err(self.error("expected a name"))
+            }
+        }
+    }
+
+    /// The element at the `<` and its value.
+    fn element(&mut self) -> Result<(String, Value), String> {
+        let start = self.index;
+        self.next();
+        let name = self.name()?;
+        let mut map = Map::new();
+
+        loop {
+            let before = self.index;
+            self.skip_whitespace();
+            if self.eat("/>") {
+                return Ok((name, element_value(map, String::new())));
+            }
+            if self.eat(">") {
+                break;
+            }
+            if self.index == before {
+                return Err(self.error("expected whitespace, `>` or `/>`"));
+            }
+
+            let attribute_start = self.index;
+            let key = format!("@{}", self.name()?);
+            self.skip_whitespace();
+            if !self.eat("=") {
+                return Err(self.error("expected `=`"));
+            }
+            self.skip_whitespace();
+            let quote = match self.next() {
+                Some(quote @ '"') | Some(quote @ '\'') => quote,
+                _ => {
+                    self.index -= 1;
+                    return Err(self.error("expected a quoted attribute value"));
+                }
+            };
+            let value = self.text_until(quote)?;
+            self.next();
+            if map.contains_key(&key) {
+                self.index = attribute_start;
+                return Err(self.error(&format!("duplicate attribute {:?}", &key[1..])));
+            }
+            map.insert(key, Value::String(value));
+        }
+
+        let mut text = String::new();
+        loop {
+            if self.eat("</") {
+                let end_start = self.index - 2;
+                if self.name().ok().as_ref() != Some(&name) {
+                    self.index = end_start;
+                    return Err(self.error(&format!("expected `</{}>`", name)));
+                }
+                self.skip_whitespace();
+                if !self.eat(">") {
+                    return Err(self.error("expected `>`"));
+                }
+                return Ok((name, element_value(map, text)));
+            } else if self.eat("<!--") {
+                self.skip_past("<!--", "-->", "comment")?;
+            } else if self.eat("<![CDATA[") {
+                text.push_str(self.skip_past("<![CDATA[", "]]>", "CDATA section")?);
+            } else if self.eat("<?") {
+                self.skip_past("<?", "?>", "processing instruction")?;
+            } else if self.peek() == Some('<') {
+                let (child, value) = self.element()?;
+                match map.get_mut(&child) {
+                    Some(Value::Array(list)) => list.push(value),
+                    Some(first) => *first = Value::Array(vec![first.take(), value]),
+                    None => {
+                        map.insert(child, value);
+                    }
+                }
+            } else if self.peek().is_none() {
+                self.index = start;
+                return Err(self.error(&format!("unclosed element <{}>", name)));
+            } else {
+                let chunk = self.text_until('<')?;
+                if !chunk.trim().is_empty() {
+                    text.push_str(&chunk);
+                }
+            }
+        }
+    }
+
+    /// The text up to `end` or the end of the document with the entities decoded.
+    fn text_until(&mut self, end: char) -> Result<String, String> {
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c == end => return Ok(text),
+                None => return Err(self.error("EOF while parsing text")),
+                Some('<') => return Err(self.error("`<` in attribute value")),
+                Some('&') => text.push(self.entity()?),
+                Some(c) => {
+                    self.next();
+                    text.push(c);
+                }
+            }
+        }
+    }
+
+    fn entity(&mut self) -> Result<char, String> {
+        let start = self.index;
+        let end = self.text[start..].find(';').filter(|x| *x <= 12);
+        let entity = end.map(|end| &self.text[start + 1..start + end]);
+        let c = match entity {
+            Some("lt") => Some('<'),
+            Some("gt") => Some('>'),
+            Some("amp") => Some('&'),
+            Some("quot") => Some('"'),
+            Some("apos") => Some('\''),
+            Some(number) if number.starts_with("#x") => u32::from_str_radix(&number[2..], 16)
+                .ok()
+                .and_then(char::from_u32),
+            Some(number) if number.starts_with('#') => {
+                number[1..].parse().ok().and_then(char::from_u32)
+            }
+            _ => None,
+        };
+        match (c, end) {
+            (Some(c), Some(end)) => {
+                self.index += end + 1;
+                Ok(c)
+            }
+            _ => Err(self.error("unknown entity")),
+        }
+    }
+}
+
+/// The json of an element with the attributes and child elements in `map`.
+fn element_value(mut map: Map<String, Value>, text: String) -> Value {
+    match (map.is_empty(), text.is_empty()) {
+        (true, true) => Value::Null,
+        (true, false) => Value::String(text),
+        (false, true) => Value::Object(map),
+        (false, false) => {
+            map.insert(String::from("#text"), Value::String(text));
+            Value::Object(map)
+        }
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == ':' => {
+            chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.'))
+        }
+        _ => false,
+    }
+}
+
+struct Writer {
+    pretty: bool,
+}
+
+/// A child element: its name, the pointer to its value and the value.
+type Child<'a> = (&'a str, String, &'a Value);
+
+impl Writer {
+    /// Writes the value as the element `name`, `path` is the pointer to the value for errors.
+    fn element(
+        &self,
+        output: &mut String,
+        name: &str,
+        value: &Value,
+        path: &str,
+        indent: usize,
+    ) -> Result<(), String> {
+        self.indent(output, indent);
+        match value {
+            Value::Null => output.push_str(&format!("<{}/>", name)),
+            Value::Array(list) => {
+                let items: Vec<Child> = list
+                    .iter()
+                    .enumerate()
+                    .map(|(index, x)| ("item", format!("{}/{}", path, index), x))
+                    .collect();
+                self.with_children(output, name, "", "", &items, indent)?;
+            }
+            Value::Object(map) => {
+                let mut attributes = String::new();
+                let mut text = String::new();
+                let mut children = Vec::new();
+                for (key, item) in map {
+                    let child = format!("{}/{}", path, pointer::escape(key));
+                    if let Some(attribute) = key.strip_prefix('@') {
+                        if !is_name(attribute) {
+                            return Err(format!(
+                                "{:?} at {} is not a valid attribute name",
+                                attribute, path
+                            ));
+                        }
+                        let value = scalar(item, &child)?;
+                        attributes.push_str(&format!(
+                            " {}=\"{}\"",
+                            attribute,
+                            escape(&value, true)
+                        ));
+                    } else if key == "#text" {
+                        text = escape(&scalar(item, &child)?, false);
+                    } else if !is_name(key) {
+                        return Err(format!("{:?} at {} is not a valid element name", key, path));
+                    } else if let Value::Array(list) = item {
+                        children.extend(
+                            list.iter().enumerate().map(|(index, x)| {
+                                (key.as_str(), format!("{}/{}", child, index), x)
+                            }),
+                        );
+                    } else {
+                        children.push((key.as_str(), child, item));
+                    }
+                }
+                self.with_children(output, name, &attributes, &text, &children, indent)?;
+            }
+            scalar => {
+                let text = match scalar {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                output.push_str(&format!("<{0}>{1}</{0}>", name, escape(&text, false)));
+            }
+        }
+        Ok(())
+    }
+
+    fn with_children(
+        &self,
+        output: &mut String,
+        name: &str,
+        attributes: &str,
+        text: &str,
+        children: &[Child],
+        indent: usize,
+    ) -> Result<(), String> {
+        if children.is_empty() && text.is_empty() {
+            output.push_str(&format!("<{}{}/>", name, attributes));
+            return Ok(());
+        }
+        output.push_str(&format!("<{}{}>{}", name, attributes, text));
+        if children.is_empty() {
+            output.push_str(&format!("</{}>", name));
+            return Ok(());
+        }
+        for (child, path, item) in children {
+            if self.pretty {
+                output.push('\n');
+            }
+            self.element(output, child, item, path, indent + 1)?;
+        }
+        if self.pretty {
+            output.push('\n');
+        }
+        self.indent(output, indent);
+        output.push_str(&format!("</{}>", name));
+        Ok(())
+    }
+
+    fn indent(&self, output: &mut String, indent: usize) {
+        if self.pretty {
+            output.push_str(&"  ".repeat(indent));
+        }
+    }
+}
+
+/// The text of an attribute or `#text`, which can not hold arrays or objects.
+fn scalar(value: &Value, path: &str) -> Result<String, String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Null => Ok(String::new()),
+        Value::Array(_) | Value::Object(_) => Err(format!(
+            "{} is of type {}, attributes and text have to be a string, number or boolean",
+            path,
+            type_name(value)
+        )),
+        other => Ok(other.to_string()),
+    }
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let text = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    match attribute {
+        true => text.replace('"', "&quot;"),
+        false => text,
+    }
+}
+
+#[cfg(test)]
+mod xml_test {
+    use super::{parse, Writer};
+    use serde_json::{json, Value};
+
+    fn to_xml(value: &Value) -> String {
+        let (name, content) = value.as_object().unwrap().iter().next().unwrap();
+        let mut output = String::new();
+        Writer { pretty: false }
+            .element(&mut output, name, content, "", 0)
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn mapping() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE order [ <!ELEMENT order ANY> ]>
+<!-- orders -->
+<order id="7" status='open'>
+    <item sku="a">Tea &amp; cake</item>
+    <note/>
+    <item>Milk &lt;1L&gt; &#233;&#x20AC;</item>
+    <customer><name>Zoë</name></customer>
+    Deliver <b>today</b>!
+</order>
+"#;
+        assert_eq!(
+            parse(text).unwrap(),
+            json!({"order": {
+                "@id": "7",
+                "@status": "open",
+                "item": [{"@sku": "a", "#text": "Tea & cake"}, "Milk <1L> é€"],
+                "note": null,
+                "customer": {"name": "Zoë"},
+                "b": "today",
+                "#text": "\n    Deliver !\n"
+            }})
+        );
+    }
+
+    #[test]
+    fn namespaces_and_cdata() {
+        let text = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body><m:code xmlns:m="urn:x"><![CDATA[if (a < b && c) {}]]></m:code></soap:Body>
+</soap:Envelope>"#;
+        assert_eq!(
+            parse(text).unwrap(),
+            json!({"soap:Envelope": {
+                "@xmlns:soap": "http://schemas.xmlsoap.org/soap/envelope/",
+                "soap:Body": {"m:code": {"@xmlns:m": "urn:x", "#text": "if (a < b && c) {}"}}
+            }})
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let texts = [
+            r#"<a id="1" x="&quot;q&quot;"><b>x</b><c/><b>y</b><b><d>1</d></b></a>"#,
+            r#"<list><n>1</n><n>2</n><m>x</m><n>3</n></list>"#,
+            r#"<p lang="en"><![CDATA[<b>bold</b> & more]]> text <i>it</i></p>"#,
+            r#"<ns:root xmlns:ns="urn:a"><ns:v ns:attr="1">&#x1F600;</ns:v></ns:root>"#,
+        ];
+        for text in texts {
+            let value = parse(text).unwrap();
+            assert_eq!(parse(&to_xml(&value)).unwrap(), value, "{}", text);
+        }
+        assert_eq!(
+            to_xml(&parse(texts[0]).unwrap()),
+            r#"<a id="1" x="&quot;q&quot;"><b>x</b><b>y</b><b><d>1</d></b><c/></a>"#
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("<a><b></a>", "expected `</b>` at line 1 column 7"),
+            (
+                "<a>\n  <b x=1/></a>",
+                "expected a quoted attribute value at line 2 column 8",
+            ),
+            (
+                "<a x='1' x='2'/>",
+                r#"duplicate attribute "x" at line 1 column 10"#,
+            ),
+            ("<a>&nbsp;</a>", "unknown entity at line 1 column 4"),
+            ("<a>\n<b>", "unclosed element <b> at line 2 column 1"),
+            ("<a/><b/>", "trailing characters at line 1 column 5"),
+            ("text", "expected root element at line 1 column 1"),
+            ("<a><!-- x </a>", "unterminated comment at line 1 column 4"),
+            ("<1a/>", "expected a name at line 1 column 2"),
+        ];
+        for (text, error) in cases {
+            assert_eq!(parse(text).unwrap_err(), error, "{}", text);
+        }
+    }
+
+    #[test]
+    fn writing() {
+        let value = json!({"doc": {
+            "@version": 2,
+            "title": "a < b",
+            "tags": ["x", null, ["y", 1]],
+            "empty": {},
+            "flag": true
+        }});
+        assert_eq!(
+            to_xml(&value),
+            r#"<doc version="2"><title>a &lt; b</title><tags>x</tags><tags/><tags><item>y</item><item>1</item></tags><empty/><flag>true</flag></doc>"#
+        );
+
+        let mut output = String::new();
+        Writer { pretty: true }
+            .element(
+                &mut output,
+                "a",
+                &json!({"b": {"c": "x"}, "d": null}),
+                "",
+                0,
+            )
+            .unwrap();
+        assert_eq!(output, "<a>\n  <b>\n    <c>x</c>\n  </b>\n  <d/>\n</a>");
+
+        let mut output = String::new();
+        let error = Writer { pretty: false }
+            .element(&mut output, "a", &json!({"b": [{"not valid": 1}]}), "", 0)
+            .unwrap_err();
+        assert_eq!(error, r#""not valid" at /b/0 is not a valid element name"#);
+    }
+}
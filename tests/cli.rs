@@ -3,15 +3,110 @@ use predicates::prelude::*;
 
 #[test]
 fn init() {
-    let mut cmd = Command::cargo_bin(assert_cmd::crate_name!()).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
 
     cmd.arg("init");
     cmd.assert().stdout(predicate::eq("{}\n")).success();
 }
 
+#[test]
+fn init_seeds() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("init")
+        .arg(r#"name="svc""#)
+        .arg("port=8080")
+        .arg(r#"tags=["a","b"]"#)
+        .arg("server.host=\"db\"")
+        .arg("/server/a.b=1")
+        .arg("query=\"a=b\"");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"name\":\"svc\",\"port\":8080,\"tags\":[\"a\",\"b\"],\"server\":{\"host\":\"db\",\"a.b\":1},\"query\":\"a=b\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--pretty")
+        .arg("init")
+        .arg("--lenient")
+        .arg("name=svc")
+        .arg("port=80");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\n  \"name\": \"svc\",\n  \"port\": 80\n}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("init").arg("name=svc");
+    cmd.assert().code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("init").arg("name");
+    cmd.assert()
+        .stderr(predicate::str::contains("expected key=value"))
+        .code(2);
+}
+
+#[test]
+fn init_from_env() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("SEED_APP_DB_HOST", "db")
+        .env("SEED_APP_DB_PORT", "5432")
+        .env("SEED_APP_DEBUG", "true")
+        .env("SEED_APP_TAGS", r#"["a","b"]"#)
+        .env("SEED_OTHER", "left out")
+        .arg("init")
+        .arg("--from-env")
+        .arg("SEED_APP_")
+        .arg("name=\"svc\"");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"db\":{\"host\":\"db\",\"port\":5432},\"debug\":true,\"tags\":[\"a\",\"b\"],\"name\":\"svc\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("SEED_APP_DB__HOST", "db")
+        .env("SEED_APP_LOG_LEVEL", "info")
+        .arg("init")
+        .arg("--from-env")
+        .arg("SEED_APP_")
+        .arg("--separator")
+        .arg("__");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"db\":{\"host\":\"db\"},\"log_level\":\"info\"}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn init_from_env_errors() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("SEED_APP_DB", "x")
+        .env("SEED_APP_DB_HOST", "y")
+        .arg("init")
+        .arg("--from-env")
+        .arg("SEED_APP_");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "SEED_APP_DB and SEED_APP_DB_HOST can not both be set, /db would be a value and an object",
+        ))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("init").arg("--separator").arg("__");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "--separator only applies to --from-env",
+        ))
+        .code(2);
+}
+
 #[test]
 fn set() {
-    let mut cmd = Command::cargo_bin(assert_cmd::crate_name!()).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
 
     cmd.arg("set").arg("{}").arg("/test").arg("1");
     cmd.assert()
@@ -21,7 +116,7 @@ fn set() {
 
 #[test]
 fn get() {
-    let mut cmd = Command::cargo_bin(assert_cmd::crate_name!()).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
 
     cmd.arg("get").arg("{\"test\":1}").arg("/test");
     cmd.assert().stdout(predicate::eq("1\n")).success();
@@ -29,7 +124,7 @@ fn get() {
 
 #[test]
 fn pretty() {
-    let mut cmd = Command::cargo_bin(assert_cmd::crate_name!()).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
 
     cmd.arg("--pretty").arg("get").arg("{\"test\":1}").arg("''");
     cmd.assert()
@@ -39,7 +134,7 @@ fn pretty() {
 
 #[test]
 fn escaped() {
-    let mut cmd = Command::cargo_bin(assert_cmd::crate_name!()).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
 
     cmd.arg("--escaped")
         .arg("get")
@@ -52,7 +147,7 @@ fn escaped() {
 
 #[test]
 fn escaped_pretty() {
-    let mut cmd = Command::cargo_bin(assert_cmd::crate_name!()).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
 
     cmd.arg("--pretty")
         .arg("--escaped")
@@ -66,7 +161,7 @@ fn escaped_pretty() {
 
 #[test]
 fn type_cmd() {
-    let mut cmd = Command::cargo_bin(assert_cmd::crate_name!()).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
 
     cmd.arg("type").arg("1");
     cmd.assert().stdout(predicate::eq("number\n")).success();
@@ -74,7 +169,7 @@ fn type_cmd() {
 
 #[test]
 fn compare_empty_cmd() {
-    let mut cmd = Command::cargo_bin(assert_cmd::crate_name!()).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
 
     cmd.arg("compare").arg("{}").arg("{}");
     cmd.assert().stdout(predicate::eq("true\n")).success();
@@ -82,18 +177,22 @@ fn compare_empty_cmd() {
 
 #[test]
 fn compare_cmd_equal_different_order() {
-    let mut cmd = Command::cargo_bin(assert_cmd::crate_name!()).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
 
-    cmd.arg("compare").arg(r#"{"testing": 5, "test": 1}"#).arg(r#"{"test": 1, "testing": 5}"#);
+    cmd.arg("compare")
+        .arg(r#"{"testing": 5, "test": 1}"#)
+        .arg(r#"{"test": 1, "testing": 5}"#);
     cmd.assert().stdout(predicate::eq("true\n")).success();
 }
 
 #[test]
 fn compare_cmd_not_equal() {
-    let mut cmd = Command::cargo_bin(assert_cmd::crate_name!()).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
 
     cmd.arg("compare").arg(r#"{"test": 1}"#).arg("{}");
-    cmd.assert().stderr(predicate::eq("Error: \"false\"\n")).failure();
+    cmd.assert()
+        .stderr(predicate::eq("Error: \"false\"\n"))
+        .failure();
 }
 
 #[test]
@@ -102,3 +201,5112 @@ fn integration_test() {
 
     cmd.assert().success();
 }
+
+#[test]
+fn file_set_writes_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"test":1}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("set")
+        .arg("/other")
+        .arg("2");
+    cmd.assert().stdout(predicate::eq("")).success();
+
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\"test\":1,\"other\":2}\n"
+    );
+}
+
+#[test]
+fn file_transforms_write_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    let cases: &[(&[&str], &str, &str)] = &[
+        (&["prune-nulls"], r#"{"a":null,"b":1}"#, r#"{"b":1}"#),
+        (
+            &["sort-by", "/l", "/n"],
+            r#"{"l":[{"n":2},{"n":1}]}"#,
+            r#"{"l":[{"n":1},{"n":2}]}"#,
+        ),
+        (
+            &["rename-keys", "--to", "camel"],
+            r#"{"a_b":1}"#,
+            r#"{"aB":1}"#,
+        ),
+        (&["str", "upper", "/a"], r#"{"a":"x"}"#, r#"{"a":"X"}"#),
+        (&["trim"], r#"{"a":" x "}"#, r#"{"a":"x"}"#),
+        (&["sub", "/a", "x", "y"], r#"{"a":"x"}"#, r#"{"a":"y"}"#),
+        (&["substr", "/a", "0", "1"], r#"{"a":"xy"}"#, r#"{"a":"x"}"#),
+        (&["merge", r#"{"b":2}"#], r#"{"a":1}"#, r#"{"a":1,"b":2}"#),
+        (
+            &["defaults", r#"{"b":2}"#],
+            r#"{"a":1}"#,
+            r#"{"a":1,"b":2}"#,
+        ),
+        (&["fix-encoding"], r#"{"a":"[1]"}"#, r#"{"a":[1]}"#),
+    ];
+    for (arguments, before, after) in cases {
+        std::fs::write(&path, before).unwrap();
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("--file").arg(&path).args(*arguments);
+        cmd.assert().stdout(predicate::eq("")).success();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n", after),
+            "{:?}",
+            arguments
+        );
+    }
+
+    // only printing
+    std::fs::write(&path, r#"{"a":"xy","n":null}"#).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .args(["substr", "/a", "0", "1", "--print-only"]);
+    cmd.assert().stdout(predicate::eq("x\n")).success();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .args(["merge", r#"{"b":2}"#, "--dry-run"]);
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file").arg(&path).arg("stats");
+    cmd.assert().success();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        r#"{"a":"xy","n":null}"#
+    );
+}
+
+#[test]
+fn file_get() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"test":{"nested":"value"}}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file").arg(&path).arg("get").arg("/test/nested");
+    cmd.assert().stdout(predicate::eq("\"value\"\n")).success();
+
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        r#"{"test":{"nested":"value"}}"#
+    );
+}
+
+#[test]
+fn file_missing_set_starts_empty() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("set")
+        .arg("/test")
+        .arg("1");
+    cmd.assert().success();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"test\":1}\n");
+}
+
+#[test]
+fn file_missing_get_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file").arg(&path).arg("get").arg("/test");
+    cmd.assert()
+        .stdout(predicate::eq(""))
+        .stderr(predicate::str::contains("does not exist"))
+        .failure();
+    assert!(!path.exists());
+}
+
+#[test]
+fn file_missing_get_with_create() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("--create")
+        .arg("get")
+        .arg("''");
+    cmd.assert().stdout(predicate::eq("{}\n")).success();
+    assert!(path.exists());
+}
+
+#[test]
+fn file_init() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file").arg(&path).arg("init");
+    cmd.assert().stdout(predicate::eq("")).success();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "{}\n");
+}
+
+#[test]
+fn file_compare() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"test":1}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("compare")
+        .arg(r#"{"test": 1}"#);
+    cmd.assert().stdout(predicate::eq("true\n")).success();
+}
+
+#[test]
+fn file_no_temp_files_left() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+
+    for i in 0..3 {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("--file")
+            .arg(&path)
+            .arg("set")
+            .arg(format!("/key{}", i))
+            .arg("true");
+        cmd.assert().success();
+    }
+
+    let temp_files = std::fs::read_dir(dir.path())
+        .unwrap()
+        .filter(|x| {
+            let name = x.as_ref().unwrap().file_name();
+            name.to_string_lossy().ends_with(".tmp")
+        })
+        .count();
+    assert_eq!(temp_files, 0);
+}
+
+#[test]
+fn file_concurrent_sets_are_not_lost() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    let amount = 16;
+
+    let children: Vec<_> = (0..amount)
+        .map(|i| {
+            std::process::Command::new(assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()))
+                .arg("--file")
+                .arg(&path)
+                .arg("set")
+                .arg(format!("/key{}", i))
+                .arg(i.to_string())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    let data: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(data.as_object().unwrap().len(), amount);
+}
+
+#[test]
+fn file_lock_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, "{}").unwrap();
+    let lock = std::fs::File::create(dir.path().join("state.json.lock")).unwrap();
+    lock.lock().unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("--lock-timeout")
+        .arg("0.1")
+        .arg("set")
+        .arg("/test")
+        .arg("1");
+    cmd.assert()
+        .stderr(predicate::str::contains("timed out"))
+        .code(75);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("--no-lock")
+        .arg("get")
+        .arg("''");
+    cmd.assert().stdout(predicate::eq("{}\n")).success();
+
+    for timeout in ["-1", "NaN", "inf"] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("--file")
+            .arg(&path)
+            .arg("--lock-timeout")
+            .arg(timeout)
+            .arg("get")
+            .arg("''");
+        cmd.assert()
+            .stderr(predicate::str::contains(
+                "--lock-timeout must be a non-negative number of seconds",
+            ))
+            .code(2);
+    }
+}
+
+#[test]
+fn store_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("XDG_DATA_HOME", dir.path())
+        .arg("--store")
+        .arg("build-cache")
+        .arg("set")
+        .arg("/last_run")
+        .arg("123");
+    cmd.assert().stdout(predicate::eq("")).success();
+    assert!(dir.path().join("bash_map/build-cache.json").exists());
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("XDG_DATA_HOME", dir.path())
+        .arg("--store")
+        .arg("build-cache")
+        .arg("get")
+        .arg("/last_run");
+    cmd.assert().stdout(predicate::eq("123\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("XDG_DATA_HOME", dir.path()).arg("store-list");
+    cmd.assert()
+        .stdout(predicate::eq("build-cache\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("XDG_DATA_HOME", dir.path())
+        .arg("store-delete")
+        .arg("build-cache");
+    cmd.assert().stdout(predicate::eq("")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("XDG_DATA_HOME", dir.path()).arg("store-list");
+    cmd.assert().stdout(predicate::eq("")).success();
+}
+
+#[test]
+fn store_get_creates_empty_store() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("XDG_DATA_HOME", dir.path())
+        .arg("--store")
+        .arg("fresh")
+        .arg("get")
+        .arg("''");
+    cmd.assert().stdout(predicate::eq("{}\n")).success();
+}
+
+#[test]
+fn store_rejects_path_traversal() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("XDG_DATA_HOME", dir.path().join("data"))
+        .arg("--store")
+        .arg("../escape")
+        .arg("set")
+        .arg("/test")
+        .arg("1");
+    cmd.assert()
+        .stderr(predicate::str::contains("invalid store name"))
+        .failure();
+    assert!(!dir.path().join("escape.json").exists());
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("XDG_DATA_HOME", dir.path())
+        .arg("store-delete")
+        .arg("../data");
+    cmd.assert()
+        .stderr(predicate::str::contains("invalid store name"))
+        .failure();
+}
+
+fn spawn_watch(args: &[&str]) -> (std::process::Child, std::sync::mpsc::Receiver<String>) {
+    use std::io::BufRead;
+
+    let mut child =
+        std::process::Command::new(assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()))
+            .args(args)
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout).lines() {
+            if sender.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    (child, receiver)
+}
+
+fn replace_file(path: &std::path::Path, contents: &str) {
+    let temp = path.with_extension("swp");
+    std::fs::write(&temp, contents).unwrap();
+    std::fs::rename(&temp, path).unwrap();
+}
+
+#[test]
+fn watch_reruns_on_change() {
+    let timeout = std::time::Duration::from_secs(10);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("status.json");
+    std::fs::write(&path, r#"{"status":"starting"}"#).unwrap();
+    let input = format!("@{}", path.display());
+
+    let (mut child, lines) = spawn_watch(&["--watch", "get", &input, "/status"]);
+    assert_eq!(lines.recv_timeout(timeout).unwrap(), r#""starting""#);
+
+    replace_file(&path, r#"{"status":"ready"}"#);
+    assert_eq!(lines.recv_timeout(timeout).unwrap(), r#""ready""#);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn watch_changed_only() {
+    let timeout = std::time::Duration::from_secs(10);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("status.json");
+    std::fs::write(&path, r#"{"a":1,"b":1}"#).unwrap();
+    let file = path.display().to_string();
+
+    let (mut child, lines) = spawn_watch(&["--file", &file, "--watch-changed-only", "get", "/a"]);
+    assert_eq!(lines.recv_timeout(timeout).unwrap(), "1");
+
+    replace_file(&path, r#"{"a":1,"b":2}"#);
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    replace_file(&path, r#"{"a":2,"b":2}"#);
+    assert_eq!(lines.recv_timeout(timeout).unwrap(), "2");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn watch_needs_file_input() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("--watch").arg("get").arg("{}").arg("/test");
+    cmd.assert()
+        .stderr(predicate::str::contains("needs a file"))
+        .failure();
+}
+
+#[test]
+fn stream_get() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("large.json");
+    let items: Vec<_> = (0..1000).map(|i| serde_json::json!({ "id": i })).collect();
+    std::fs::write(&path, serde_json::json!({ "items": items }).to_string()).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--stream")
+        .arg("get")
+        .arg(format!("@{}", path.display()))
+        .arg("/items/999");
+    cmd.assert()
+        .stdout(predicate::eq("{\"id\":999}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--stream")
+        .arg("--file")
+        .arg(&path)
+        .arg("get")
+        .arg("/items/1000");
+    cmd.assert().stdout(predicate::eq("\n")).success();
+
+    // stdin can not be sized, it is always streamed
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("-v")
+        .arg("get")
+        .arg("/dev/stdin")
+        .arg("/items/1")
+        .write_stdin(r#"{"items": [1, 2]}"#);
+    cmd.assert()
+        .stdout(predicate::eq("2\n"))
+        .stderr(predicate::str::contains("read up to the pointer"))
+        .success();
+
+    // checks on the whole document turn streaming off
+    std::fs::write(&path, r#"{"a": 1, "a": 2}"#).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--stream")
+        .arg("--dedup-check")
+        .arg("--file")
+        .arg(&path)
+        .arg("get")
+        .arg("/a");
+    cmd.assert()
+        .stderr(predicate::str::contains(r#"duplicate key \"a\" at /a"#))
+        .failure();
+}
+
+#[test]
+fn process_substitution() {
+    let mut cmd = Command::new("bash");
+
+    cmd.arg("-c").arg(format!(
+        r#"{} get <(echo '{{"test": [1, 2, 3]}}') /test/1"#,
+        assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()).display()
+    ));
+    cmd.assert().stdout(predicate::eq("2\n")).success();
+}
+
+#[test]
+fn process_substitution_invalid_json() {
+    let mut cmd = Command::new("bash");
+
+    cmd.arg("-c").arg(format!(
+        r#"{} get <(echo '{{"test": ') /test"#,
+        assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()).display()
+    ));
+    cmd.assert()
+        .stdout(predicate::eq(""))
+        .stderr(predicate::str::contains("invalid json in /dev/fd/"))
+        .failure();
+}
+
+#[test]
+fn missing_file_is_reported() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("get").arg("@does/not/exist.json").arg("/test");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "unable to read does/not/exist.json",
+        ))
+        .failure();
+}
+
+#[test]
+fn env_variable() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.env("MAP", r#"{"test": 1}"#)
+        .arg("get")
+        .arg("MAP")
+        .arg("/test");
+    cmd.assert().stdout(predicate::eq("1\n")).success();
+}
+
+#[test]
+fn dedup_check() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("get").arg(r#"{"a": 1, "a": 2}"#).arg("/a");
+    cmd.assert().stdout(predicate::eq("2\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("--dedup-check")
+        .arg("get")
+        .arg(r#"{"list": [{"a": 1, "a": 2}]}"#)
+        .arg("/list");
+    cmd.assert()
+        .stdout(predicate::eq(""))
+        .stderr(predicate::str::contains(
+            r#"duplicate key \"a\" at /list/0/a"#,
+        ))
+        .failure();
+}
+
+#[test]
+fn validate() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("validate").arg(r#"{"a": 1, "b": [1, 1]}"#);
+    cmd.assert().stdout(predicate::eq("true\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("validate").arg(r#"{"a": {"b": 1, "b": 2}}"#);
+    cmd.assert()
+        .stderr(predicate::str::contains(r#"duplicate key \"b\" at /a/b"#))
+        .failure();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("validate").arg(r#"{"a": "#);
+    cmd.assert()
+        .stderr(predicate::str::contains("invalid json"))
+        .failure();
+}
+
+#[test]
+fn big_numbers_round_trip() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("set")
+        .arg(r#"{"float": 0.12345678901234567890}"#)
+        .arg("/id")
+        .arg("123456789012345678901234567890");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"float\":0.12345678901234567890,\"id\":123456789012345678901234567890}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("get")
+        .arg(r#"{"id": 123456789012345678901234567890}"#)
+        .arg("/id");
+    cmd.assert()
+        .stdout(predicate::eq("123456789012345678901234567890\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("type").arg("123456789012345678901234567890");
+    cmd.assert().stdout(predicate::eq("number\n")).success();
+}
+
+#[test]
+fn compare_numbers_by_value() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("compare")
+        .arg(r#"{"a": 1000, "b": 0.10}"#)
+        .arg(r#"{"a": 1e3, "b": 0.1}"#);
+    cmd.assert().stdout(predicate::eq("true\n")).success();
+}
+
+#[test]
+fn compare_integer_and_float() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("compare").arg(r#"{"a": 1}"#).arg(r#"{"a": 1.0}"#);
+    cmd.assert().stdout(predicate::eq("true\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("compare")
+        .arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 1.0}"#)
+        .arg("--strict-numbers");
+    cmd.assert()
+        .stderr(predicate::eq("Error: \"false\"\n"))
+        .failure();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("compare")
+        .arg(r#"{"b": 2.5, "a": 1}"#)
+        .arg(r#"{"a": 1, "b": 2.5}"#)
+        .arg("--strict-numbers");
+    cmd.assert().stdout(predicate::eq("true\n")).success();
+}
+
+#[test]
+fn key_order_is_preserved() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("set")
+        .arg(r#"{"name": "svc", "version": 1, "all": {"z": 1, "a": 2}}"#)
+        .arg("/version")
+        .arg("2");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"name\":\"svc\",\"version\":2,\"all\":{\"z\":1,\"a\":2}}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("set")
+        .arg(r#"{"name": "svc", "all": {"z": 1}}"#)
+        .arg("/all/b")
+        .arg("true");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"name\":\"svc\",\"all\":{\"z\":1,\"b\":true}}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn sort_keys() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("--sort-keys")
+        .arg("set")
+        .arg(r#"{"name": "svc", "all": {"z": 1}}"#)
+        .arg("/all/b")
+        .arg("true");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"all\":{\"b\":true,\"z\":1},\"name\":\"svc\"}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn jsonc_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tsconfig.json");
+    std::fs::write(
+        &path,
+        "{\n  // compiler settings\n  \"compilerOptions\": {\n    \"baseUrl\": \"https://example.com/src\", /* not a comment */\n    \"strict\": true,\n  },\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--input-format")
+        .arg("jsonc")
+        .arg("get")
+        .arg(format!("@{}", path.display()))
+        .arg("/compilerOptions");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"baseUrl\":\"https://example.com/src\",\"strict\":true}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get")
+        .arg(format!("@{}", path.display()))
+        .arg("/compilerOptions");
+    cmd.assert()
+        .stderr(predicate::str::contains("line 2"))
+        .failure();
+}
+
+#[test]
+fn lenient_input() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+
+    cmd.arg("--lenient-input")
+        .arg("set")
+        .arg(r#"{"a": "//", /* b */ "c": [1,],}"#)
+        .arg("/d")
+        .arg("1");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":\"//\",\"c\":[1],\"d\":1}\n"))
+        .success();
+}
+
+#[test]
+fn json5_output() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--output-format")
+        .arg("json5")
+        .arg("--pretty")
+        .arg("set")
+        .arg(r#"{"name": "it's", "two words": [1]}"#)
+        .arg("/empty")
+        .arg("{}");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\n  name: 'it\\'s',\n  'two words': [\n    1,\n  ],\n  empty: {},\n}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn json5_round_trip() {
+    let input = "{limit: Infinity, ratio: NaN, 'b c': 'x', list: [0x10, .5,],}";
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--input-format")
+        .arg("json5")
+        .arg("--output-format")
+        .arg("json5")
+        .arg("set")
+        .arg(input)
+        .arg("/min")
+        .arg("1");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let printed = String::from_utf8(output).unwrap();
+    assert_eq!(
+        printed,
+        "{limit:Infinity,ratio:NaN,'b c':'x',list:[16,0.5],min:1}\n"
+    );
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--input-format")
+        .arg("json5")
+        .arg("--output-format")
+        .arg("json5")
+        .arg("get")
+        .arg(printed.trim_end())
+        .arg("");
+    cmd.assert()
+        .stdout(predicate::eq(printed.as_str()))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--input-format")
+        .arg("json5")
+        .arg("get")
+        .arg(input)
+        .arg("");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"limit\":null,\"ratio\":null,\"b c\":\"x\",\"list\":[16,0.5]}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn front_matter_yaml() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("post.md");
+    let body = "# Title\n\nsome text\n---\nafter a rule\n";
+    std::fs::write(
+        &path,
+        format!("---\ntitle: Hello\ndraft: true\n---\n{}", body),
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("front-matter")
+        .arg("get")
+        .arg(format!("@{}", path.display()))
+        .arg("/title");
+    cmd.assert().stdout(predicate::eq("\"Hello\"\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("front-matter")
+        .arg("set")
+        .arg("--in-place")
+        .arg(format!("@{}", path.display()))
+        .arg("/draft")
+        .arg("false");
+    cmd.assert().stdout(predicate::eq("")).success();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        text,
+        format!("---\ntitle: Hello\ndraft: false\n---\n{}", body)
+    );
+}
+
+#[test]
+fn front_matter_toml() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("post.md");
+    let body = "body\n+++\n---\n";
+    std::fs::write(&path, format!("+++\ntitle = \"Hello\"\n+++\n{}", body)).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("front-matter")
+        .arg("set")
+        .arg(format!("@{}", path.display()))
+        .arg("/weight")
+        .arg("3");
+    cmd.assert()
+        .stdout(predicate::eq(format!(
+            "+++\ntitle = \"Hello\"\nweight = 3\n+++\n{}",
+            body
+        )))
+        .success();
+}
+
+#[test]
+fn front_matter_missing() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("post.md");
+    std::fs::write(&path, "# Title\n---\n").unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("front-matter")
+        .arg("get")
+        .arg(format!("@{}", path.display()))
+        .arg("/title");
+    cmd.assert()
+        .stderr(predicate::str::contains("has no front matter"))
+        .failure();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("front-matter")
+        .arg("set")
+        .arg("--in-place")
+        .arg(format!("@{}", path.display()))
+        .arg("/title")
+        .arg("\"Hi\"");
+    cmd.assert().success();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(text, "---\ntitle: Hi\n---\n# Title\n---\n");
+}
+
+#[test]
+fn byte_order_mark_and_crlf() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("windows.json");
+    std::fs::write(
+        &path,
+        b"\xef\xbb\xbf{\r\n  \"a\": \"text\",\r\n  \"b\": [1]\r\n}\r\n",
+    )
+    .unwrap();
+
+    for stream in [false, true] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        if stream {
+            cmd.arg("--stream");
+        }
+        cmd.arg("get").arg(format!("@{}", path.display())).arg("");
+        cmd.assert()
+            .stdout(predicate::eq("{\"a\":\"text\",\"b\":[1]}\n"))
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("MAP", "\u{feff}{\"a\": 1}")
+        .arg("--keep-bom")
+        .arg("get")
+        .arg("MAP")
+        .arg("/a");
+    cmd.assert().stdout(predicate::eq("\u{feff}1\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--keep-bom")
+        .arg("get")
+        .arg(r#"{"a": 1}"#)
+        .arg("/a");
+    cmd.assert().stdout(predicate::eq("1\n")).success();
+}
+
+#[test]
+fn max_depth() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("deep.json");
+    std::fs::write(&path, format!("{}1{}", "[".repeat(1000), "]".repeat(1000))).unwrap();
+    let pointer = "/0".repeat(1000);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get")
+        .arg(format!("@{}", path.display()))
+        .arg(&pointer);
+    cmd.assert()
+        .stderr(predicate::str::starts_with(
+            "Error: \"maximum depth 128 exceeded at /0/0/0",
+        ))
+        .failure();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--max-depth")
+        .arg("1000")
+        .arg("get")
+        .arg(format!("@{}", path.display()))
+        .arg(&pointer);
+    cmd.assert().stdout(predicate::eq("1\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--max-depth")
+        .arg("2")
+        .arg("set")
+        .arg(r#"{"a": {}}"#)
+        .arg("/a/b")
+        .arg("[1]");
+    cmd.assert()
+        .stderr(predicate::eq(
+            "Error: \"maximum depth 2 exceeded at /a/b\"\n",
+        ))
+        .failure();
+}
+
+#[test]
+fn get_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("items.json");
+    std::fs::write(&path, r#"{"items": [0, 1, 2, 3, 4]}"#).unwrap();
+
+    for stream in [false, true] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        if stream {
+            cmd.arg("--stream");
+        }
+        cmd.arg("get")
+            .arg(format!("@{}", path.display()))
+            .arg("/items/-3:-1");
+        cmd.assert().stdout(predicate::eq("[2,3]\n")).success();
+    }
+}
+
+#[test]
+fn has_value() {
+    let doc = r#"{"allowed_users": ["alice", "bob"]}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("has-value")
+        .arg(doc)
+        .arg("/allowed_users")
+        .arg(r#""alice""#);
+    cmd.assert().stdout(predicate::eq("true\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("has-value")
+        .arg(doc)
+        .arg("/allowed_users")
+        .arg(r#""eve""#);
+    cmd.assert()
+        .stderr(predicate::eq("Error: \"false\"\n"))
+        .code(1);
+}
+
+#[test]
+fn index_of() {
+    let doc = r#"{"items": [{"id": 1}, {"id": 3}, {"id": 3}]}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("index-of")
+        .arg(doc)
+        .arg("/items")
+        .arg(r#"{"id":3}"#);
+    cmd.assert().stdout(predicate::eq("1\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("index-of")
+        .arg("--all")
+        .arg("--by")
+        .arg("/id=3")
+        .arg(doc)
+        .arg("/items");
+    cmd.assert().stdout(predicate::eq("[1,2]\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("index-of")
+        .arg(doc)
+        .arg("/items")
+        .arg(r#"{"id":4}"#);
+    cmd.assert()
+        .stdout(predicate::eq(""))
+        .stderr(predicate::eq(""))
+        .code(1);
+}
+
+#[test]
+fn wildcard_set() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--verbose")
+        .arg("set")
+        .arg("--wildcard")
+        .arg(r#"{"services": [{"name": "web"}, {"name": "db", "enabled": true}]}"#)
+        .arg("/services/*/enabled")
+        .arg("false");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"services\":[{\"name\":\"web\",\"enabled\":false},{\"name\":\"db\",\"enabled\":false}]}\n",
+        ))
+        .stderr(predicate::str::contains("verbose: 2 matches\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("--wildcard")
+        .arg("--strict")
+        .arg(r#"{"services": []}"#)
+        .arg("/services/*/enabled")
+        .arg("false");
+    cmd.assert()
+        .stderr(predicate::eq(
+            "Error: \"/services/*/enabled matches nothing\"\n",
+        ))
+        .failure();
+}
+
+#[test]
+fn wildcard_delete() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("-v")
+        .arg("delete")
+        .arg("--wildcard")
+        .arg(r#"{"services": [{"name": "web", "debug": true}, {"name": "db"}, {"name": "cache", "debug": false}]}"#)
+        .arg("/services/*/debug");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"services\":[{\"name\":\"web\"},{\"name\":\"db\"},{\"name\":\"cache\"}]}\n",
+        ))
+        .stderr(predicate::str::contains("verbose: 2 removed\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("delete")
+        .arg("--wildcard")
+        .arg(r#"{"services": []}"#)
+        .arg("/services/*/debug");
+    cmd.assert()
+        .stdout(predicate::eq("{\"services\":[]}\n"))
+        .success();
+}
+
+#[test]
+fn file_delete() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"a":1,"b":2}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file").arg(&path).arg("delete").arg("/a");
+    cmd.assert().stdout(predicate::eq("")).success();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"b\":2}\n");
+}
+
+#[test]
+fn prune_nulls() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("prune-nulls")
+        .arg(r#"{"a": {"b": null}, "c": null, "d": [null]}"#);
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":{},\"d\":[null]}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("prune-nulls")
+        .arg("--cascade")
+        .arg("--arrays")
+        .arg(r#"{"a": {"b": null}, "c": null, "d": [null]}"#);
+    cmd.assert().stdout(predicate::eq("{}\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("prune-nulls")
+        .arg(r#"{"a": {"b": null}, "c": null}"#)
+        .arg("/a");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":{},\"c\":null}\n"))
+        .success();
+}
+
+#[test]
+fn prune_empty() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("prune-empty")
+        .arg(r#"{"a": {"b": {"c": {}}}, "d": [[], 1], "e": null}"#);
+    cmd.assert()
+        .stdout(predicate::eq("{\"d\":[1],\"e\":null}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("prune-empty")
+        .arg("--also-null")
+        .arg(r#"{"a": {"b": null}, "c": {"d": {}}}"#)
+        .arg("/a");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":{},\"c\":{\"d\":{}}}\n"))
+        .success();
+}
+
+#[test]
+fn normalize() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--pretty")
+        .arg("normalize")
+        .arg(r#"{"b": [1.50, -0, 1e21], "a": {"d": 1E+3, "c": 0.0000001}}"#);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"a\":{\"c\":1e-7,\"d\":1000},\"b\":[1.5,0,1e21]}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn sort_by() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("sort-by")
+        .arg(r#"{"users": [{"age": 30}, {"name": "x"}, {"age": 4}, {"age": 100}]}"#)
+        .arg("/users")
+        .arg("/age")
+        .arg("--desc");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"users\":[{\"age\":100},{\"age\":30},{\"age\":4},{\"name\":\"x\"}]}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("sort-by")
+        .arg(r#"{"users": {"age": 1}}"#)
+        .arg("/users")
+        .arg("/age");
+    cmd.assert()
+        .stderr(predicate::str::contains("/users is not an array"))
+        .code(1);
+}
+
+#[test]
+fn transpose() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("transpose")
+        .arg(r#"{"table": [{"a": 1, "b": 2}, {"a": 3}]}"#)
+        .arg("/table");
+    cmd.assert()
+        .stdout(predicate::eq("{\"table\":{\"a\":[1,3],\"b\":[2,null]}}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("transpose").arg(r#"{"a": [1, 3], "b": [2, 4]}"#);
+    cmd.assert()
+        .stdout(predicate::eq("[{\"a\":1,\"b\":2},{\"a\":3,\"b\":4}]\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("transpose")
+        .arg("--strict")
+        .arg(r#"{"a": [1, 3], "b": [2]}"#);
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "has 1 items where the arrays before have 2",
+        ))
+        .code(1);
+}
+
+#[test]
+fn upsert() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("upsert")
+        .arg(r#"{"users": [{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]}"#)
+        .arg("/users")
+        .arg("/id")
+        .arg(r#"{"id": 2, "age": 3}"#)
+        .arg("--merge");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"users\":[{\"id\":1,\"name\":\"a\"},{\"id\":2,\"name\":\"b\",\"age\":3}]}\n",
+        ))
+        .success();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("users.json");
+    for _ in 0..2 {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("--file")
+            .arg(&path)
+            .arg("upsert")
+            .arg("/users")
+            .arg("/id")
+            .arg(r#"{"id": 1}"#);
+        cmd.assert().stdout(predicate::eq("")).success();
+    }
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\"users\":[{\"id\":1}]}\n"
+    );
+}
+
+#[test]
+fn array_sets() {
+    let first = r#"{"hosts": ["a", "b", "a", "c"]}"#;
+    let second = r#"["c", "d"]"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("array-union")
+        .arg(first)
+        .arg("/hosts")
+        .arg(second)
+        .arg("");
+    cmd.assert()
+        .stdout(predicate::eq("[\"a\",\"b\",\"c\",\"d\"]\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("array-intersect")
+        .arg(r#"[{"id": 1}, {"id": 2}]"#)
+        .arg("")
+        .arg(r#"[{"id": 2.0}]"#);
+    cmd.assert()
+        .stdout(predicate::eq("[{\"id\":2}]\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("array-diff")
+        .arg("--lines")
+        .arg(first)
+        .arg("/hosts")
+        .arg(second)
+        .arg("");
+    cmd.assert().stdout(predicate::eq("a\nb\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("array-diff")
+        .arg("--lines")
+        .arg(second)
+        .arg("")
+        .arg(second);
+    cmd.assert().stdout(predicate::eq("")).success();
+}
+
+#[test]
+fn rename_keys() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("rename-keys")
+        .arg("--to")
+        .arg("camel")
+        .arg(r#"{"user_id": 1, "api": {"HTTPServer": [{"last-seen": "snake_case"}]}}"#)
+        .arg("/api");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"user_id\":1,\"api\":{\"httpServer\":[{\"lastSeen\":\"snake_case\"}]}}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("rename-keys")
+        .arg("--to")
+        .arg("kebab")
+        .arg(r#"{"a_b": 1, "aB": 2}"#);
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            r#"keys \"a_b\" and \"aB\" both become \"a-b\" at the root"#,
+        ))
+        .code(1);
+}
+
+#[test]
+fn str_operations() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("str")
+        .arg("upper")
+        .arg(r#"{"env_name": "prod-ü"}"#)
+        .arg("/env_name");
+    cmd.assert()
+        .stdout(predicate::eq("{\"env_name\":\"PROD-Ü\"}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("str")
+        .arg("trim")
+        .arg(r#"{"env_name": 1}"#)
+        .arg("/env_name");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "/env_name is of type number, not string",
+        ))
+        .code(1);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.json");
+    std::fs::write(&path, r#"{"names": [" Ada ", {"b": "GRACE"}]}"#).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("str")
+        .arg("lower")
+        .arg("/names")
+        .arg("--recursive");
+    cmd.assert().stdout(predicate::eq("")).success();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\"names\":[\" ada \",{\"b\":\"grace\"}]}\n"
+    );
+}
+
+#[test]
+fn trim() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("trim")
+        .arg(r#"{" a ": "  x\n", "b": ["\t", {"c": " y  z "}]}"#);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\" a \":\"x\",\"b\":[\"\",{\"c\":\"y  z\"}]}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("trim")
+        .arg("--collapse")
+        .arg(r#"{"a": "  x\n", "b": ["\t", {"c": " y  z "}]}"#)
+        .arg("/b");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"a\":\"  x\\n\",\"b\":[\"\",{\"c\":\"y z\"}]}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn grep() {
+    let input = r#"{"log": ["ok", "Error: disk", {"a/b": "fail\nagain"}], "error_count": 2}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("grep").arg(input).arg("error|fail");
+    cmd.assert()
+        .stdout(predicate::eq("/log/2/a~1b\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("grep")
+        .arg("--ignore-case")
+        .arg("--keys")
+        .arg("--with-values")
+        .arg(input)
+        .arg("error|fail");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "/log/1\t\"Error: disk\"\n/log/2/a~1b\t\"fail\\nagain\"\n/error_count\t2\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("grep").arg(input).arg("missing");
+    cmd.assert().stdout(predicate::eq("")).code(1);
+}
+
+#[test]
+fn sub() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("sub")
+        .arg(r#"{"message": "foo and foo"}"#)
+        .arg("/message")
+        .arg("f(o+)")
+        .arg("b${1}m");
+    cmd.assert()
+        .stdout(predicate::eq("{\"message\":\"boom and foo\"}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("sub")
+        .arg(r#"{"a": "abc"}"#)
+        .arg("/a")
+        .arg("(b)")
+        .arg("$1r");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":\"abrc\"}\n"))
+        .success();
+
+    // the examples of the help, with -- in front of a pattern starting with -
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.args(["sub", r#"{"a": "1-2-3"}"#, "--", "/a", "-", "+"]);
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":\"1+2-3\"}\n"))
+        .success();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.args(["sub", r#"{"a": "1-2-3"}"#, "--global", "--", "/a", "-", "+"]);
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":\"1+2+3\"}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("sub")
+        .arg("--global")
+        .arg("--recursive")
+        .arg(r#"{"a": ["foo foo", 1, {"foo": "foo"}]}"#)
+        .arg("")
+        .arg("foo")
+        .arg("bar");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":[\"bar bar\",1,{\"foo\":\"bar\"}]}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("sub")
+        .arg(r#"{"a": [1]}"#)
+        .arg("/a")
+        .arg("foo")
+        .arg("bar");
+    cmd.assert()
+        .stderr(predicate::str::contains("/a is of type array, not string"))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("sub")
+        .arg(r#"{"a": "x"}"#)
+        .arg("/a")
+        .arg("a(b")
+        .arg("c");
+    cmd.assert()
+        .stderr(predicate::str::contains("invalid pattern").and(predicate::str::contains("a(b")))
+        .code(2);
+}
+
+#[test]
+fn substr() {
+    let input = r#"{"commit": {"sha": "9fceb02d0ae598e95dc970b74767f19372d61af8"}}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("substr")
+        .arg("--print-only")
+        .arg(input)
+        .arg("/commit/sha")
+        .arg("0")
+        .arg("7");
+    cmd.assert().stdout(predicate::eq("9fceb02\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("substr")
+        .arg(r#"{"a": "grüße"}"#)
+        .arg("/a")
+        .arg("--")
+        .arg("-3");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":\"üße\"}\n"))
+        .success();
+}
+
+#[test]
+fn set_now() {
+    let output = Command::cargo_bin(assert_cmd::pkg_name!())
+        .unwrap()
+        .arg("set")
+        .arg("{}")
+        .arg("/updated_at")
+        .arg("--now")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let text = value["updated_at"].as_str().unwrap();
+    assert_eq!(text.len(), "2024-05-01T12:30:00Z".len());
+    assert!(text.starts_with("20") && text.ends_with('Z'));
+    assert_eq!(&text[10..11], "T");
+
+    for (format, low, high) in [
+        ("unix", 1_600_000_000u64, 10_000_000_000u64),
+        ("unix-ms", 1_600_000_000_000, 10_000_000_000_000),
+    ] {
+        let output = Command::cargo_bin(assert_cmd::pkg_name!())
+            .unwrap()
+            .arg("set")
+            .arg("{}")
+            .arg("/updated_at")
+            .arg("--now")
+            .arg("--now-format")
+            .arg(format)
+            .output()
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let time = value["updated_at"].as_u64().unwrap();
+        assert!(low < time && time < high, "{} {}", format, time);
+    }
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("{}").arg("/a").arg("1").arg("--now");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "only one of the value, --now and --uuid can be given",
+        ))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("{}").arg("/a");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "missing the value, --now or --uuid",
+        ))
+        .code(2);
+}
+
+#[test]
+fn uuid() {
+    let uuid =
+        regex::Regex::new("^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$")
+            .unwrap();
+
+    let run = || {
+        let output = Command::cargo_bin(assert_cmd::pkg_name!())
+            .unwrap()
+            .arg("uuid")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .trim_end()
+            .to_string()
+    };
+    let (first, second) = (run(), run());
+    assert!(uuid.is_match(&first), "{}", first);
+    assert!(uuid.is_match(&second), "{}", second);
+    assert_ne!(first, second);
+
+    let output = Command::cargo_bin(assert_cmd::pkg_name!())
+        .unwrap()
+        .arg("set")
+        .arg(r#"{"requests": [{}, {}]}"#)
+        .arg("/requests/*/id")
+        .arg("--uuid")
+        .arg("--wildcard")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let first = value["requests"][0]["id"].as_str().unwrap();
+    let second = value["requests"][1]["id"].as_str().unwrap();
+    assert!(uuid.is_match(first), "{}", first);
+    assert!(uuid.is_match(second), "{}", second);
+    assert_ne!(first, second);
+}
+
+#[test]
+fn canonicalize() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("canonicalize")
+        .arg(r#"{"b": [4.50, 1E30, -0, 2e-3], "a": "€\n", "😀": 1, "דּ": 2}"#);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"a\":\"€\\n\",\"b\":[4.5,1e+30,0,0.002],\"😀\":1,\"\u{fb33}\":2}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("canonicalize").arg("[1e400]");
+    cmd.assert()
+        .stderr(predicate::str::contains("is not a double"))
+        .code(1);
+}
+
+#[test]
+fn redact() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("redact")
+        .arg(r#"{"user": {"Password": "x", "id": 1}, "list": [{"token": [1]}], "b": {"c": 2}}"#)
+        .arg("password")
+        .arg("token")
+        .arg("/b/c")
+        .arg("--replacement")
+        .arg("[hidden]");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"user\":{\"Password\":\"[hidden]\",\"id\":1},\"list\":[{\"token\":\"[hidden]\"}],\"b\":{\"c\":\"[hidden]\"}}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("redact")
+        .arg(r#"{"API_KEY": "k", "db_pass": "p", "Db_Pass": "q"}"#)
+        .arg("--defaults")
+        .arg("--pattern")
+        .arg("_pass$")
+        .arg("--case-sensitive");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"API_KEY\":\"k\",\"db_pass\":\"***\",\"Db_Pass\":\"q\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("redact").arg("{}");
+    cmd.assert()
+        .stderr(predicate::str::contains("--pattern or --defaults"))
+        .code(1);
+}
+
+#[test]
+fn stats() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("stats").arg(r#"{"a": [1, 2], "b": {"c": null}}"#);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"nodes\":6,\"types\":{\"object\":2,\"array\":1,\"string\":0,\"number\":2,\"boolean\":0,\"null\":1},\"max_depth\":2,\"longest_array\":{\"pointer\":\"/a\",\"length\":2},\"largest_object\":{\"pointer\":\"\",\"keys\":2},\"bytes\":26}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("stats").arg(r#"{"a": ["x"], "b": 1}"#).arg("/a/0");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"nodes\":1,\"types\":{\"object\":0,\"array\":0,\"string\":1,\"number\":0,\"boolean\":0,\"null\":0},\"max_depth\":0,\"longest_array\":null,\"largest_object\":null,\"bytes\":3}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("stats").arg("{}").arg("/missing");
+    cmd.assert()
+        .stderr(predicate::str::contains("/missing does not exist"))
+        .code(1);
+}
+
+#[test]
+fn depth() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("depth").arg(r#"{"a": [1], "b": {}}"#);
+    cmd.assert().stdout(predicate::eq("3\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("depth").arg(r#"{"a": [1], "b": {}}"#).arg("/b");
+    cmd.assert().stdout(predicate::eq("1\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("depth").arg("[[[[]]]]").arg("--max").arg("4");
+    cmd.assert().stdout(predicate::eq("4\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("depth").arg("[[[[]]]]").arg("--max").arg("3");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "depth 4 is above the maximum of 3",
+        ))
+        .code(1);
+
+    // --max-depth does not stop the guard from measuring
+    let dir = tempfile::tempdir().unwrap();
+    let deep = dir.path().join("deep.json");
+    std::fs::write(
+        &deep,
+        format!("{}{}", "[".repeat(10_000), "]".repeat(10_000)),
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("depth").arg(format!("@{}", deep.display()));
+    cmd.assert().stdout(predicate::eq("10000\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("depth")
+        .arg(format!("@{}", deep.display()))
+        .arg("--max")
+        .arg("128");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "depth 10000 is above the maximum of 128",
+        ))
+        .code(1);
+}
+
+#[test]
+fn size() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("size").arg(r#"{"a": "xyz", "b": [1, 2]}"#);
+    cmd.assert().stdout(predicate::eq("21\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--pretty").arg("size").arg(r#"{"a": "xyz"}"#);
+    cmd.assert().stdout(predicate::eq("16\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("size")
+        .arg(r#"{"x": {"a/b": "xyz", "c": [1, 2, 3]}}"#)
+        .arg("/x")
+        .arg("--breakdown");
+    cmd.assert()
+        .stdout(predicate::eq("5\t/x/a~1b\n7\t/x/c\n"))
+        .success();
+
+    let big = format!(r#"{{"a": "{}"}}"#, "x".repeat(2000));
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("size").arg(&big).arg("--human");
+    cmd.assert().stdout(predicate::eq("2.0 KiB\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("size").arg(&big).arg("--max").arg("2000");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "2008 bytes is above the maximum of 2000",
+        ))
+        .code(1);
+}
+
+#[test]
+fn table() {
+    let doc = r#"{"pods": [{"name": "web-1", "status": "Running", "restarts": 0}, {"name": "db", "labels": {"app": "db"}}]}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("table").arg(doc).arg("/pods").arg("--tsv");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "name\tstatus\trestarts\tlabels\nweb-1\tRunning\t0\t\ndb\t\t\t{\"app\":\"db\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("table")
+        .arg(doc)
+        .arg("/pods")
+        .arg("--columns")
+        .arg("status,name")
+        .arg("--tsv");
+    cmd.assert()
+        .stdout(predicate::eq("status\tname\nRunning\tweb-1\n\tdb\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("table").arg(doc).arg("/pods");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0].split_whitespace().count(), 4);
+    assert_eq!(lines[1].split_whitespace().count(), 3);
+    assert_eq!(lines[0].find("status"), lines[1].find("Running"));
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("table").arg(r#"{"a": 1}"#).arg("/a");
+    cmd.assert()
+        .stderr(predicate::str::contains("/a is of type number, not array"))
+        .code(1);
+}
+
+#[test]
+fn markdown_table() {
+    let doc = r#"[{"name": "a|b", "tags": ["x"], "n": 1}, {"name": "line\nbreak", "n": null}]"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("table")
+        .arg(doc)
+        .arg("")
+        .arg("--markdown")
+        .arg("--columns")
+        .arg("n,name,tags");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "| n | name | tags |\n| --- | --- | --- |\n| 1 | a\\|b | `[\"x\"]` |\n|  | line<br>break |  |\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("table")
+        .arg(doc)
+        .arg("")
+        .arg("--markdown")
+        .arg("--tsv");
+    cmd.assert()
+        .stderr(predicate::str::contains("can not be combined"))
+        .code(2);
+}
+
+#[test]
+fn xml_conversion() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("resp.xml");
+    std::fs::write(
+        &path,
+        "<?xml version=\"1.0\"?>\n<resp code=\"200\">\n  <user>a</user>\n  <user>b</user>\n  <raw><![CDATA[1 < 2]]></raw>\n</resp>\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("from-xml").arg(format!("@{}", path.display()));
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"resp\":{\"@code\":\"200\",\"user\":[\"a\",\"b\"],\"raw\":\"1 < 2\"}}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("to-xml")
+        .arg(r#"{"resp":{"@code":"200","user":["a","b"],"raw":"1 < 2"}}"#);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "<resp code=\"200\"><user>a</user><user>b</user><raw>1 &lt; 2</raw></resp>\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--pretty")
+        .arg("to-xml")
+        .arg(r#"{"a": {"b": 1}, "c": [true]}"#)
+        .arg("--root")
+        .arg("doc");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "<doc>\n  <a>\n    <b>1</b>\n  </a>\n  <c>true</c>\n</doc>\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("from-xml").arg("<a>\n  <b></c>\n</a>");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "expected `</b>` at line 2 column 6",
+        ))
+        .code(3);
+}
+
+#[test]
+fn ini_conversion() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.ini");
+    std::fs::write(
+        &path,
+        "; app\nname = demo\n\n[server]\nport = 8080\nport = 8443\nsecure = true\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("from-ini")
+        .arg(format!("@{}", path.display()))
+        .arg("--types")
+        .arg("--globals")
+        .arg("global");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"global\":{\"name\":\"demo\"},\"server\":{\"port\":8443,\"secure\":true}}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("to-ini")
+        .arg(r#"{"name": "demo", "server": {"port": 8443, "id": "7"}}"#);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "name = demo\n\n[server]\nport = 8443\nid = \"7\"\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("to-ini")
+        .arg(r#"{"server": {"tls": {"on": true}}}"#);
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "/server/tls is more than two levels deep",
+        ))
+        .code(1);
+}
+
+#[test]
+fn properties_conversion() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("to-properties")
+        .arg(r#"{"server": {"port": 8080, "name": " a=b"}, "hosts": ["x", "ü"], "key with: space": null}"#);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "server.port=8080\nserver.name=\\ a\\=b\nhosts.0=x\nhosts.1=\\u00FC\nkey\\ with\\:\\ space=\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("from-properties")
+        .arg("# app\nserver.port = 8080\nserver.name=\\ a\\=b\nhosts.0=x\nhosts.1=\\u00FC\nkey\\ with\\:\\ space=\n");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"server\":{\"port\":\"8080\",\"name\":\" a=b\"},\"hosts\":[\"x\",\"ü\"],\"key with: space\":\"\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("from-properties")
+        .arg("server.port=8080")
+        .arg("--no-unflatten");
+    cmd.assert()
+        .stdout(predicate::eq("{\"server.port\":\"8080\"}\n"))
+        .success();
+}
+
+#[test]
+fn to_bash_array() {
+    let values = [
+        "plain",
+        "it's a \"quote\"",
+        "line 1\nline 2\n",
+        "$HOME $(echo no) `echo no` \\ !x",
+        "",
+        " spaced  out ",
+    ];
+    let doc = serde_json::json!({"hosts": values, "env": {"a b": "$x", "it's": "y\nz", "n": 1}});
+
+    let mut cmd = Command::new("bash");
+    cmd.env("DOC", doc.to_string()).arg("-c").arg(format!(
+        r#"eval "$({} to-bash-array DOC /hosts HOSTS)" && printf '%s\0' "${{#HOSTS[@]}}" "${{HOSTS[@]}}""#,
+        assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()).display()
+    ));
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output).unwrap();
+    let items: Vec<&str> = output.split_terminator('\0').collect();
+    assert_eq!(items[0], values.len().to_string());
+    assert_eq!(&items[1..], values);
+
+    let mut cmd = Command::new("bash");
+    cmd.env("DOC", doc.to_string()).arg("-c").arg(format!(
+        r#"eval "$({} to-bash-array DOC /env ENV)" && for key in "${{!ENV[@]}}"; do printf '%s\0%s\0' "$key" "${{ENV[$key]}}"; done"#,
+        assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()).display()
+    ));
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output).unwrap();
+    let items: Vec<&str> = output.split_terminator('\0').collect();
+    let mut pairs: Vec<(&str, &str)> = items.chunks(2).map(|x| (x[0], x[1])).collect();
+    pairs.sort();
+    assert_eq!(pairs, [("a b", "$x"), ("it's", "y\nz"), ("n", "1")]);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("to-bash-array")
+        .arg(r#"{"a": []}"#)
+        .arg("/a")
+        .arg("1BAD");
+    cmd.assert()
+        .stderr(predicate::str::contains("not a valid bash variable name"))
+        .code(2);
+}
+
+#[test]
+fn export() {
+    let doc = serde_json::json!({
+        "server": {"port": 8080, "host": "it's $HOME", "tls": {"enabled": true}},
+        "hosts": ["a", "line 1\nline 2"],
+        "log-level": "debug",
+        "empty": {}
+    });
+
+    let mut cmd = Command::new("bash");
+    cmd.env("DOC", doc.to_string()).arg("-c").arg(format!(
+        r#"eval "$({} export DOC --flatten --separator __ --prefix CFG)" && printf '%s\0' "$CFG__SERVER__PORT" "$CFG__SERVER__HOST" "$CFG__SERVER__TLS__ENABLED" "$CFG__HOSTS__1" "$CFG__LOG_LEVEL" "$CFG__EMPTY""#,
+        assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()).display()
+    ));
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output).unwrap();
+    let items: Vec<&str> = output.split_terminator('\0').collect();
+    assert_eq!(
+        items,
+        [
+            "8080",
+            "it's $HOME",
+            "true",
+            "line 1\nline 2",
+            "debug",
+            "{}"
+        ]
+    );
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("export")
+        .arg(r#"{"port": 80, "tags": ["a"], "1st": "x"}"#);
+    cmd.assert()
+        .stdout(predicate::eq("PORT='80'\nTAGS='[\"a\"]'\n_1ST='x'\n"))
+        .success();
+
+    for (style, expected) in [
+        ("fish", "set -x A 'it\\'s x'\n"),
+        ("zsh", "export A='it'\\''s x'\n"),
+        ("powershell", "$env:A = \"it's x\"\n"),
+        ("bash-single", "A='it'\\''s x'\n"),
+    ] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("--escape-style")
+            .arg(style)
+            .arg("export")
+            .arg(r#"{"a": "it's x"}"#);
+        cmd.assert().stdout(predicate::eq(expected)).success();
+    }
+}
+
+#[test]
+fn export_errors() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("export")
+        .arg(r#"{"a": {"b-c": 1, "b_c": 2}}"#)
+        .arg("--flatten");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            r#"/a/b-c and /a/b_c are both exported as A_B_C"#,
+        ))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("export").arg("{}").arg("--prefix").arg("1BAD");
+    cmd.assert()
+        .stderr(predicate::str::contains("not a valid bash variable name"))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("export").arg("1");
+    cmd.assert()
+        .stderr(predicate::str::contains("not array or object"))
+        .code(1);
+}
+
+#[test]
+fn get_lines() {
+    let doc = r#"{"items": ["a b", "line 1\nline 2", 1, {"x": null}], "empty": [], "name": "x"}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg(doc).arg("/items").arg("--lines");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "\"a b\"\n\"line 1\\nline 2\"\n1\n{\"x\":null}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg(doc).arg("/empty").arg("--lines");
+    cmd.assert().stdout(predicate::eq("")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg(doc).arg("/name").arg("--lines");
+    cmd.assert()
+        .stderr(predicate::str::contains("not array"))
+        .code(1);
+}
+
+#[test]
+fn print0() {
+    let doc = r#"{"items": ["a b", "line 1\nline 2", 1, {"x": "y"}], "empty": []}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("-0")
+        .arg("get")
+        .arg(doc)
+        .arg("/items")
+        .arg("--lines");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(output, b"a b\0line 1\nline 2\x001\0{\"x\":\"y\"}\0");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--print0")
+        .arg("get")
+        .arg(doc)
+        .arg("/empty")
+        .arg("--lines");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(output, b"");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--print0").arg("get").arg(doc).arg("/items/0");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(output, b"\"a b\"\0");
+}
+
+#[test]
+fn each() {
+    let doc = r#"{"items": [{"name": "a"}, {"name": "b c", "id": 2}, {"id": 3}], "empty": [], "name": "x"}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("each").arg(doc).arg("/items");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"name\":\"a\"}\n{\"name\":\"b c\",\"id\":2}\n{\"id\":3}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("each").arg(doc).arg("/items").arg("/name");
+    cmd.assert().stdout(predicate::eq("a\nb c\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("each")
+        .arg(doc)
+        .arg("/items")
+        .arg("/id")
+        .arg("--null-missing");
+    cmd.assert().stdout(predicate::eq("null\n2\n3\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("each").arg(doc).arg("/empty");
+    cmd.assert().stdout(predicate::eq("")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("each").arg(doc).arg("/name");
+    cmd.assert()
+        .stderr(predicate::str::contains("not array"))
+        .code(1);
+}
+
+#[test]
+fn each_print0() {
+    let doc =
+        serde_json::json!({"files": [{"path": "a b"}, {"path": "line 1\nline 2"}, {"path": ""}]});
+
+    let mut cmd = Command::new("bash");
+    cmd.env("DOC", doc.to_string()).arg("-c").arg(format!(
+        r#"{} -0 each DOC /files /path | while IFS= read -r -d '' path; do printf '<%s>' "$path"; done"#,
+        assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()).display()
+    ));
+    cmd.assert()
+        .stdout(predicate::eq("<a b><line 1\nline 2><>"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--print0")
+        .arg("each")
+        .arg(doc.to_string())
+        .arg("/files");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(
+        output,
+        b"{\"path\":\"a b\"}\0{\"path\":\"line 1\\nline 2\"}\0{\"path\":\"\"}\0"
+    );
+}
+
+#[test]
+fn leaves() {
+    let doc = r#"{"server": {"host": "example.com", "ports": [8080, 8443]}, "a/b": {"~": null}, "tags": [], "debug": false}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("leaves").arg(doc).arg("");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "/server/host=\"example.com\"\n/server/ports/0=8080\n/server/ports/1=8443\n/a~1b/~0=null\n/tags=[]\n/debug=false\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("leaves").arg(doc).arg("").arg("--sort");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "/a~1b/~0=null\n/debug=false\n/server/host=\"example.com\"\n/server/ports/0=8080\n/server/ports/1=8443\n/tags=[]\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("leaves")
+        .arg(doc)
+        .arg("/server/ports")
+        .arg("--separator")
+        .arg("\t");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "/server/ports/0\t8080\n/server/ports/1\t8443\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("leaves").arg(doc).arg("/missing");
+    cmd.assert()
+        .stderr(predicate::str::contains("does not exist"))
+        .code(1);
+}
+
+#[test]
+fn gron() {
+    let doc = r#"{"items": [{"name": "x", "tags": []}, {"name": "y"}], "a b": {"class": null}, "n": 1.5}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("gron").arg(doc);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "json = {};
+json.items = [];
+json.items[0] = {};
+json.items[0].name = \"x\";
+json.items[0].tags = [];
+json.items[1] = {};
+json.items[1].name = \"y\";
+json[\"a b\"] = {};
+json[\"a b\"][\"class\"] = null;
+json.n = 1.5;
+",
+        ))
+        .success();
+}
+
+#[test]
+fn gron_round_trip() {
+    let docs = [
+        r#"{"items":[{"name":"x","tags":[]},{"name":"y"}],"a b":{"class":null},"n":1.5}"#,
+        r#"{"quote\"]":{"= ;":"a = b;","":[[],[{}]]},"ü":true}"#,
+        r#"[1,"two",{"three":[3]}]"#,
+        r#""text""#,
+    ];
+
+    for doc in docs {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("gron").arg(doc);
+        let gron = cmd.assert().success().get_output().stdout.clone();
+
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("ungron").arg(String::from_utf8(gron).unwrap());
+        cmd.assert()
+            .stdout(predicate::eq(format!("{}\n", doc)))
+            .success();
+    }
+}
+
+#[test]
+fn gron_filtered() {
+    let doc = r#"{"items": [{"name": "x", "id": 1}, {"name": "y", "id": 2}], "other": true}"#;
+
+    let mut cmd = Command::new("bash");
+    cmd.env("DOC", doc).arg("-c").arg(format!(
+        r#"{0} ungron <({0} gron DOC | grep name)"#,
+        assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()).display()
+    ));
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"items\":[{\"name\":\"x\"},{\"name\":\"y\"}]}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("ungron").arg("json.items[1].id = 2;");
+    cmd.assert()
+        .stdout(predicate::eq("{\"items\":[null,{\"id\":2}]}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("ungron").arg("json = {};\njson.a 1;");
+    cmd.assert()
+        .stderr(predicate::str::contains("line 2"))
+        .code(3);
+}
+
+#[test]
+fn merge_layers() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env(
+        "DEFAULTS",
+        r#"{"server": {"host": "localhost", "port": 80, "tls": {"enabled": false, "ciphers": ["a", "b"]}}, "debug": false}"#,
+    )
+    .env("ENV_OVERRIDES", r#"{"debug": true}"#)
+    .env(
+        "CLI_OVERRIDES",
+        r#"{"server": {"tls": {"ciphers": ["c"]}, "port": 8080}}"#,
+    )
+    .arg("merge")
+    .arg("DEFAULTS")
+    .arg("ENV_OVERRIDES")
+    .arg("CLI_OVERRIDES");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"server\":{\"host\":\"localhost\",\"port\":8080,\"tls\":{\"enabled\":false,\"ciphers\":[\"c\"]}},\"debug\":true}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn merge_unset_variables() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("local.json");
+    std::fs::write(&path, r#"{"b": 2}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env_remove("BASH_MAP_UNSET")
+        .arg("merge")
+        .arg(r#"{"a": 1}"#)
+        .arg("BASH_MAP_UNSET")
+        .arg(format!("@{}", path.display()));
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":1,\"b\":2}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env_remove("BASH_MAP_UNSET")
+        .arg("merge")
+        .arg(r#"{"a": 1}"#)
+        .arg("BASH_MAP_UNSET")
+        .arg("--strict");
+    cmd.assert()
+        .stderr(predicate::str::contains("BASH_MAP_UNSET is not set"))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge").arg(r#"{"a": 1}"#);
+    cmd.assert()
+        .stderr(predicate::str::contains("at least two"))
+        .code(2);
+}
+
+#[test]
+fn resolve_layers() {
+    let dir = tempfile::tempdir().unwrap();
+    let local = dir.path().join("local.json");
+    std::fs::write(&local, r#"{"server": {"port": 9000}}"#).unwrap();
+    let absent = dir.path().join("absent.json");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("RESOLVE_ENV", r#"{"debug": true, "server": {"host": "x"}}"#)
+        .env_remove("RESOLVE_UNSET")
+        .arg("--verbose")
+        .arg("resolve")
+        .arg(r#"{"server": {"port": 80, "host": "localhost"}, "debug": false, "name": "app"}"#)
+        .arg("RESOLVE_ENV?")
+        .arg("RESOLVE_UNSET?")
+        .arg(format!("@{}?", local.display()))
+        .arg(format!("@{}?", absent.display()));
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"server\":{\"port\":9000,\"host\":\"x\"},\"debug\":true,\"name\":\"app\"}\n",
+        ))
+        .stderr(predicate::str::contains(
+            "verbose: \"debug\" is from RESOLVE_ENV\n",
+        ))
+        .stderr(predicate::str::contains("verbose: \"name\" is from {"))
+        .stderr(predicate::str::contains(
+            "verbose: optional source RESOLVE_UNSET is missing",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env_remove("RESOLVE_UNSET")
+        .arg("resolve")
+        .arg(r#"{"a": 1}"#)
+        .arg("RESOLVE_UNSET")
+        .arg(format!("@{}", local.display()))
+        .arg(format!("@{}", absent.display()));
+    cmd.assert()
+        .stderr(predicate::str::contains(format!(
+            "missing sources: RESOLVE_UNSET, @{}",
+            absent.display()
+        )))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env_remove("RESOLVE_UNSET")
+        .arg("resolve")
+        .arg("RESOLVE_UNSET?")
+        .arg(format!("@{}?", absent.display()));
+    cmd.assert().stdout(predicate::eq("{}\n")).success();
+}
+
+#[test]
+fn defaults() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env(
+        "DEFAULTS",
+        r#"{"server": {"host": "localhost", "port": 80, "tls": {"enabled": false}}, "debug": false, "tags": ["a"]}"#,
+    )
+    .arg("defaults")
+    .arg(r#"{"server": {"port": 8080}, "debug": null, "tags": "none"}"#)
+    .arg("DEFAULTS");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"server\":{\"port\":8080,\"host\":\"localhost\",\"tls\":{\"enabled\":false}},\"debug\":null,\"tags\":\"none\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("defaults")
+        .arg(r#"{"debug": null, "server": {"tls": null}}"#)
+        .arg(r#"{"debug": false, "server": {"tls": {"enabled": true}}}"#)
+        .arg("--fill-null");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"debug\":false,\"server\":{\"tls\":{\"enabled\":true}}}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn merge_strategy() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge")
+        .arg(r#"{"a": {"b": [1], "c": 1}}"#)
+        .arg(r#"{"a": {"b": [2], "c": 2}}"#)
+        .arg("--strategy")
+        .arg("concat-arrays");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":{\"b\":[1,2],\"c\":2}}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge")
+        .arg(r#"{"a": {"b": [1], "c": 1}}"#)
+        .arg(r#"{"a": {"c": 2}}"#)
+        .arg("--strategy")
+        .arg("error");
+    cmd.assert()
+        .stderr(predicate::str::contains("/a/c"))
+        .code(1);
+}
+
+#[test]
+fn set_invalid_value_hints() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("{}").arg("/a").arg("foo");
+    cmd.assert()
+        .stderr(predicate::str::contains("  foo\n   ^\n"))
+        .stderr(predicate::str::contains(
+            "hint: strings need double quotes, did you mean '\"foo\"'?",
+        ))
+        .stdout(predicate::eq(""))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("{}").arg("/a").arg(r#"{"b": 1"#);
+    cmd.assert()
+        .stderr(predicate::str::contains("invalid json at byte 6"))
+        .stderr(predicate::str::contains(
+            "hint: the value ends too early, check for a missing }, ] or \"",
+        ))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("{}").arg("/a").arg("'hello world'");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "hint: json strings use double quotes, did you mean '\"hello world\"'?",
+        ))
+        .code(2);
+
+    // a huge value is not echoed in full
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("{}")
+        .arg("/a")
+        .arg(format!("foo {}", "x".repeat(100_000)));
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "Error parsing the value: invalid json at byte 1",
+        ))
+        .stderr(predicate::function(|x: &str| x.len() < 500))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("test").arg("{}").arg("/a").arg("foo");
+    cmd.assert()
+        .stderr(predicate::str::contains("did you mean '\"foo\"'?"))
+        .code(2);
+}
+
+#[test]
+fn bad_pointer_is_a_usage_error() {
+    let cases: &[&[&str]] = &[
+        &["get", r#"{"a":1}"#, "nopointer"],
+        &["get", r#"{"a":1}"#, "nopointer", "--strict"],
+        &["set", r#"{"a":1}"#, "a", "2"],
+        &["delete", r#"{"a":1}"#, "a"],
+        &["has-value", r#"{"a":[1]}"#, "a", "1"],
+        &["test", r#"{"a":1}"#, "a", "1"],
+    ];
+    for arguments in cases {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.args(*arguments);
+        cmd.assert()
+            .stdout(predicate::eq(""))
+            .stderr(predicate::str::contains("a pointer has to start with /"))
+            .code(2);
+    }
+}
+
+#[test]
+fn set_strict() {
+    let doc = r#"{"list":[1,2,3]}"#;
+    for pointer in ["/list/5", "/list/name/x"] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("set").arg(doc).arg(pointer).arg("9");
+        cmd.assert()
+            .stdout(predicate::eq(format!("{}\n", doc)))
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg(doc)
+        .arg("/list/5")
+        .arg("9")
+        .arg("--strict");
+    cmd.assert()
+        .stdout(predicate::eq(""))
+        .stderr(predicate::str::contains(
+            "can not set /list/5: index 5 is past the end of an array of length 3",
+        ))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg(doc)
+        .arg("/list/name/x")
+        .arg("9")
+        .arg("--strict");
+    cmd.assert()
+        .stdout(predicate::eq(""))
+        .stderr(predicate::str::contains(
+            r#"can not set /list/name/x: \"name\" is not an index of an array"#,
+        ))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg(doc).arg("list").arg("9").arg("--strict");
+    cmd.assert()
+        .stdout(predicate::eq(""))
+        .stderr(predicate::str::contains("has to start with /"))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg(doc)
+        .arg("/other/list/0")
+        .arg("9")
+        .arg("--strict");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"list\":[1,2,3],\"other\":{\"list\":{\"0\":9}}}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn set_pad() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("--pad")
+        .arg(r#"{"list":[1]}"#)
+        .arg("/list/3")
+        .arg("9");
+    cmd.assert()
+        .stdout(predicate::eq("{\"list\":[1,null,null,9]}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("--pad")
+        .arg("{}")
+        .arg("/list/2/name")
+        .arg(r#""x""#);
+    cmd.assert()
+        .stdout(predicate::eq("{\"list\":[null,null,{\"name\":\"x\"}]}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("--pad")
+        .arg(r#"{"list":[1,2]}"#)
+        .arg("/list/0")
+        .arg("9");
+    cmd.assert()
+        .stdout(predicate::eq("{\"list\":[9,2]}\n"))
+        .success();
+
+    // a huge index fails instead of running out of memory
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("--pad")
+        .arg(r#"{"a":[]}"#)
+        .arg("/a/99999999999")
+        .arg("1");
+    cmd.assert()
+        .stdout(predicate::eq(""))
+        .stderr(predicate::str::contains(
+            "--pad extends arrays up to index 1000000, not 99999999999",
+        ))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg(r#"{"list":[1]}"#)
+        .arg("/list/3")
+        .arg("9");
+    cmd.assert()
+        .stdout(predicate::eq("{\"list\":[1]}\n"))
+        .success();
+}
+
+#[test]
+fn delete_print_removed() {
+    let doc = r#"{"job": {"id": 1}, "name": "x", "items": [{"a": 1}, {"a": 2}, {}]}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("delete")
+        .arg(doc)
+        .arg("/job")
+        .arg("--print-removed");
+    cmd.assert().stdout(predicate::eq("{\"id\":1}\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("delete")
+        .arg(doc)
+        .arg("/name")
+        .arg("--print-removed")
+        .arg("--raw");
+    cmd.assert().stdout(predicate::eq("x\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("delete")
+        .arg(doc)
+        .arg("/items/*/a")
+        .arg("--wildcard")
+        .arg("--print-removed");
+    cmd.assert().stdout(predicate::eq("1\n2\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("delete")
+        .arg(doc)
+        .arg("/missing")
+        .arg("--print-removed");
+    cmd.assert().stdout(predicate::eq("")).code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("delete").arg(doc).arg("/name").arg("--raw");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "--raw only applies to --print-removed",
+        ))
+        .code(2);
+}
+
+#[test]
+fn file_delete_print_removed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"queue":["a","b"],"other":1}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("delete")
+        .arg("/queue/0")
+        .arg("--print-removed")
+        .arg("--raw");
+    cmd.assert().stdout(predicate::eq("a\n")).success();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\"queue\":[\"b\"],\"other\":1}\n"
+    );
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("delete")
+        .arg("/missing")
+        .arg("--print-removed");
+    cmd.assert().stdout(predicate::eq("")).code(1);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\"queue\":[\"b\"],\"other\":1}\n"
+    );
+}
+
+#[test]
+fn ndjson_get() {
+    let input = "{\"level\":\"info\",\"n\":1}\n\n{\"n\":2}\nnot json\n{\"level\":\"warn\"}\n";
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("ndjson")
+        .arg("get")
+        .arg("/level")
+        .write_stdin(input);
+    cmd.assert()
+        .stdout(predicate::eq("\"info\"\n\n\"warn\"\n"))
+        .stderr(predicate::str::starts_with("line 4: invalid json"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("ndjson")
+        .arg("get")
+        .arg("/level")
+        .arg("--skip-missing")
+        .write_stdin(input);
+    cmd.assert()
+        .stdout(predicate::eq("\"info\"\n\"warn\"\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("ndjson")
+        .arg("--strict")
+        .arg("get")
+        .arg("/level")
+        .write_stdin(input);
+    cmd.assert()
+        .stdout(predicate::eq("\"info\"\n\n"))
+        .stderr(predicate::str::contains("line 4: invalid json"))
+        .code(3);
+}
+
+#[test]
+fn ndjson_set() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("ndjson")
+        .arg("set")
+        .arg("/seen")
+        .arg("true")
+        .write_stdin("{\"n\": 1}\n{\"n\": 2, \"seen\": false}\n");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"n\":1,\"seen\":true}\n{\"n\":2,\"seen\":true}\n",
+        ))
+        .success();
+}
+
+#[test]
+fn exit_codes() {
+    let dir = tempfile::tempdir().unwrap();
+    let invalid = dir.path().join("invalid.json");
+    std::fs::write(&invalid, "{not json").unwrap();
+    let missing = dir.path().join("missing.json");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("compare").arg(r#"{"a": 1}"#).arg(r#"{"a": 1}"#);
+    cmd.assert().code(0);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("compare").arg(r#"{"a": 1}"#).arg(r#"{"a": 2}"#);
+    cmd.assert().code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("index-of").arg(r#"{"a": [1]}"#).arg("/a").arg("2");
+    cmd.assert().code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg("{}").arg("/a").arg("--unknown");
+    cmd.assert().code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("{}").arg("a").arg("1").arg("--strict");
+    cmd.assert().code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get")
+        .arg(format!("@{}", invalid.display()))
+        .arg("/a");
+    cmd.assert()
+        .stderr(predicate::str::contains("invalid json"))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get")
+        .arg(format!("@{}", missing.display()))
+        .arg("/a");
+    cmd.assert()
+        .stderr(predicate::str::contains("unable to read"))
+        .code(4);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file").arg(&missing).arg("get").arg("/a");
+    cmd.assert()
+        .stderr(predicate::str::contains("does not exist"))
+        .code(4);
+
+    // an environment variable that is not set is an input error for every command
+    for args in [
+        &["--strict-env", "get", "EXIT_CODES_UNSET", "/a"][..],
+        &["merge", "{}", "EXIT_CODES_UNSET", "--strict"],
+        &["gron", "EXIT_CODES_UNSET"],
+        &["ungron", "EXIT_CODES_UNSET"],
+        &["from-ini", "EXIT_CODES_UNSET"],
+        &["from-properties", "EXIT_CODES_UNSET"],
+        &["from-xml", "EXIT_CODES_UNSET"],
+        &["validate", "EXIT_CODES_UNSET"],
+        &["set", "{}", "/a", "env:EXIT_CODES_UNSET"],
+    ] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.env_remove("EXIT_CODES_UNSET")
+            .env("BASH_MAP_STRICT", "1")
+            .args(args);
+        cmd.assert()
+            .stderr(predicate::str::contains(
+                "environment variable EXIT_CODES_UNSET is not set",
+            ))
+            .code(3);
+    }
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--help");
+    cmd.assert()
+        .stdout(predicate::str::contains("Error codes:"))
+        .code(0);
+}
+
+#[test]
+fn verbose() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("-v")
+        .arg("-v")
+        .arg("get")
+        .arg(r#"{"a": {"b/c": [1, 2]}}"#)
+        .arg("/a/b~1c/1");
+    cmd.assert()
+        .stdout(predicate::eq("2\n"))
+        .stderr(predicate::str::contains("input is a json literal"))
+        .stderr(predicate::str::contains(r#"has tokens ["a", "b/c", "1"]"#))
+        .stderr(predicate::str::contains(r#"step 3: "1" in array"#))
+        .success();
+
+    // the same as -v -v
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("-vv").arg("get").arg(r#"{"a": [1]}"#).arg("/a/0");
+    cmd.assert()
+        .stdout(predicate::eq("1\n"))
+        .stderr(predicate::str::contains(r#"step 2: "0" in array"#))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--verbose")
+        .arg("get")
+        .arg("MAP")
+        .arg("/a/x")
+        .env("MAP", r#"{"a": []}"#);
+    cmd.assert()
+        .stdout(predicate::eq("\n"))
+        .stderr(predicate::str::contains("environment variable MAP"))
+        .stderr(predicate::str::contains(
+            r#"step 2: "x" not found in array"#,
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("-v").arg("set").arg("{}").arg("/a/b").arg("1");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":{\"b\":1}}\n"))
+        .stderr(predicate::str::contains(
+            r#"created an intermediate object in place of null to hold "b""#,
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("{}").arg("/a/b").arg("1");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":{\"b\":1}}\n"))
+        .stderr(predicate::str::is_empty())
+        .success();
+}
+
+#[test]
+fn quiet() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--quiet")
+        .arg("compare")
+        .arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 1.0}"#);
+    cmd.assert().stdout(predicate::str::is_empty()).code(0);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("-q")
+        .arg("compare")
+        .arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 2}"#);
+    cmd.assert().stdout(predicate::str::is_empty()).code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--quiet")
+        .arg("index-of")
+        .arg(r#"{"a": [1]}"#)
+        .arg("/a")
+        .arg("2");
+    cmd.assert().stdout(predicate::str::is_empty()).code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--quiet")
+        .arg("get")
+        .arg("@does-not-exist.json")
+        .arg("/a");
+    cmd.assert()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("unable to read"))
+        .code(4);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--quiet").arg("ndjson").arg("get").arg("/a");
+    cmd.write_stdin("{\"a\": 1}\n{\"a\": 2}\n");
+    cmd.assert().stdout(predicate::str::is_empty()).success();
+}
+
+#[test]
+fn dry_run() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg(r#"{"server": {"port": 8080}}"#)
+        .arg("/server/port")
+        .arg("9090")
+        .arg("--dry-run");
+    cmd.assert()
+        .stdout(predicate::eq("/server/port: 8080 -> 9090\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg(r#"{"a": 1}"#)
+        .arg("/a")
+        .arg("1")
+        .arg("--dry-run");
+    cmd.assert().stdout(predicate::str::is_empty()).code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("delete")
+        .arg(r#"{"a": 1, "b": {"c": true}}"#)
+        .arg("/b")
+        .arg("--dry-run");
+    cmd.assert()
+        .stdout(predicate::eq("/b: {\"c\":true} -> (missing)\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge")
+        .arg(r#"{"a": 1, "b": 2}"#)
+        .arg(r#"{"b": 3, "c": null}"#)
+        .arg("--dry-run");
+    cmd.assert()
+        .stdout(predicate::eq("/b: 2 -> 3\n/c: (missing) -> null\n"))
+        .success();
+}
+
+#[test]
+fn file_dry_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("map.json");
+    std::fs::write(&path, r#"{"a": 1}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("set")
+        .arg("/a")
+        .arg("2")
+        .arg("--dry-run");
+    cmd.assert().stdout(predicate::eq("/a: 1 -> 2\n")).success();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), r#"{"a": 1}"#);
+
+    let missing = dir.path().join("missing.json");
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&missing)
+        .arg("delete")
+        .arg("/a")
+        .arg("--dry-run");
+    cmd.assert().code(4);
+    assert!(!missing.exists());
+}
+
+#[test]
+fn escape_style_eval() {
+    let map = r#"{"a": "$HOME `whoami` !x 'q' \"d\" \\"}"#;
+
+    for pretty in [false, true] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        if pretty {
+            cmd.arg("--pretty");
+        }
+        let plain = cmd.arg("get").arg(map).arg("").output().unwrap().stdout;
+        let plain = String::from_utf8(plain).unwrap();
+
+        for style in ["bash-single", "bash-double"] {
+            let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+            if pretty {
+                cmd.arg("--pretty");
+            }
+            let escaped = cmd
+                .arg("--escape-style")
+                .arg(style)
+                .arg("get")
+                .arg(map)
+                .arg("")
+                .output()
+                .unwrap()
+                .stdout;
+            let escaped = String::from_utf8(escaped).unwrap();
+
+            let evaluated = std::process::Command::new("bash")
+                .arg("-c")
+                .arg(r#"eval "MAP=$1"; printf '%s\n' "$MAP""#)
+                .arg("bash")
+                .arg(escaped.trim_end_matches('\n'))
+                .output()
+                .unwrap()
+                .stdout;
+            assert_eq!(String::from_utf8(evaluated).unwrap(), plain, "{}", style);
+        }
+    }
+}
+
+#[test]
+fn escape_style() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--escape-style")
+        .arg("bash-single")
+        .arg("get")
+        .arg(r#"{"a": "it's"}"#)
+        .arg("/a");
+    cmd.assert()
+        .stdout(predicate::eq("'\"it'\\''s\"'\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--escaped")
+        .arg("get")
+        .arg(r#"{"a": "$x"}"#)
+        .arg("/a");
+    cmd.assert()
+        .stdout(predicate::eq("\"\\\"$x\\\"\"\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--escape-style").arg("tcsh").arg("init");
+    cmd.assert()
+        .stderr(predicate::str::contains("unknown escape style"))
+        .code(2);
+}
+
+#[test]
+fn escape_style_powershell() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--escape-style")
+        .arg("powershell")
+        .arg("get")
+        .arg(r#"{"a": "$env:PATH `t"}"#)
+        .arg(r#"\"\""#);
+    cmd.assert()
+        .stdout(predicate::eq("\"{\"\"a\"\":\"\"`$env:PATH ``t\"\"}\"\n"))
+        .success();
+
+    for pointer in [r"\'\'", r#"'""'"#, r#""''""#] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("get").arg(r#"{"a": 1}"#).arg(pointer);
+        cmd.assert().stdout(predicate::eq("{\"a\":1}\n")).success();
+    }
+}
+
+#[test]
+fn escape_style_fish() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--escape-style")
+        .arg("fish")
+        .arg("get")
+        .arg(r#"{"a": "it's \\ *"}"#)
+        .arg("/a");
+    cmd.assert()
+        .stdout(predicate::eq("'\"it\\'s \\\\\\\\ *\"'\n"))
+        .success();
+}
+
+#[test]
+fn escape_unicode() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--escape-unicode")
+        .arg("get")
+        .arg("{\"caf\u{e9}\": \"\u{1f600}\"}")
+        .arg("");
+    cmd.assert()
+        .stdout(predicate::eq("{\"caf\\u00e9\":\"\\ud83d\\ude00\"}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--escape-unicode")
+        .arg("get")
+        .arg(r#"{"a": "\u0001\u007f"}"#)
+        .arg("/a");
+    cmd.assert()
+        .stdout(predicate::eq("\"\\u0001\\u007f\"\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg(r#"{"a": "\u0001\u007f"}"#).arg("/a");
+    cmd.assert()
+        .stdout(predicate::eq("\"\\u0001\u{7f}\"\n"))
+        .success();
+}
+
+#[test]
+fn decode_unicode_keys() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--decode-unicode-keys")
+        .arg("get")
+        .arg(r#"{"caf\\u00e9": {"\\ud83d\\ude00": 1}}"#)
+        .arg("/caf\u{e9}/\u{1f600}");
+    cmd.assert().stdout(predicate::eq("1\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg(r#"{"caf\\u00e9": 1}"#).arg("/caf\u{e9}");
+    cmd.assert().stdout(predicate::eq("\n")).success();
+}
+
+#[test]
+fn numbers_keep_their_text() {
+    let map = r#"{"a": 1, "b": 1.0, "c": 1e3, "d": -0, "e": 0.10, "f": [2E-3, -1.5e+2]}"#;
+    let compact = r#"{"a":1,"b":1.0,"c":1e3,"d":-0,"e":0.10,"f":[2E-3,-1.5e+2]}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg(map).arg("");
+    cmd.assert()
+        .stdout(predicate::eq(format!("{}\n", compact)))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg(map).arg("/g").arg("true");
+    cmd.assert()
+        .stdout(predicate::eq(format!(
+            "{},\"g\":true}}\n",
+            compact.trim_end_matches('}')
+        )))
+        .success();
+
+    for value in ["1", "1.0", "1e3", "1E+3", "-0", "0.10"] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("set").arg("{}").arg("/x").arg("--").arg(value);
+        cmd.assert()
+            .stdout(predicate::eq(format!("{{\"x\":{}}}\n", value)))
+            .success();
+
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("get")
+            .arg(format!("{{\"x\": {}}}", value))
+            .arg("/x");
+        cmd.assert()
+            .stdout(predicate::eq(format!("{}\n", value)))
+            .success();
+    }
+}
+
+#[test]
+fn big_integers() {
+    let numbers = [
+        "18446744073709551614",
+        "18446744073709551615",
+        "18446744073709551616",
+        "-9223372036854775809",
+        "1234567890123456789012345678901234567890",
+    ];
+    for number in numbers {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("set").arg("{}").arg("/id").arg("--").arg(number);
+        let output = cmd.output().unwrap();
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("{{\"id\":{}}}\n", number)
+        );
+
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("get")
+            .arg(format!("{{\"id\": {}}}", number))
+            .arg("/id");
+        cmd.assert()
+            .stdout(predicate::eq(format!("{}\n", number)))
+            .success();
+
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("type").arg("--").arg(number);
+        cmd.assert().stdout(predicate::eq("number\n")).success();
+    }
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("compare")
+        .arg(r#"{"id": 18446744073709551616}"#)
+        .arg(r#"{"id": 18446744073709551617}"#);
+    cmd.assert().code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("compare")
+        .arg(r#"{"id": 1234567890123456789012345678901234567890}"#)
+        .arg(r#"{"id": 1234567890123456789012345678901234567890.0}"#);
+    cmd.assert().success();
+}
+
+#[test]
+fn front_matter_big_integer() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("post.md");
+    std::fs::write(&path, "---\ntitle: Hello\n---\nbody\n").unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("front-matter")
+        .arg("set")
+        .arg(format!("@{}", path.display()))
+        .arg("/id")
+        .arg("18446744073709551616");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "18446744073709551616 does not fit in a 64 bit integer",
+        ))
+        .failure();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("front-matter")
+        .arg("set")
+        .arg(format!("@{}", path.display()))
+        .arg("/id")
+        .arg("18446744073709551615");
+    cmd.assert()
+        .stdout(predicate::str::contains("id: 18446744073709551615\n"))
+        .success();
+}
+
+#[test]
+fn float_precision() {
+    let map = r#"{"a": 0.30000000000000004, "b": [2.675, -2.665, 1e-9, 7, 1.5e300, 1.23456e2]}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--float-precision")
+        .arg("2")
+        .arg("get")
+        .arg(map)
+        .arg("");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"a\":0.3,\"b\":[2.68,-2.67,0.0,7,1.5e300,123.46]}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--float-precision")
+        .arg("2")
+        .arg("--keep-trailing-zeros")
+        .arg("set")
+        .arg(r#"{"a": 0.1, "b": 12}"#)
+        .arg("/c")
+        .arg("9.999");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":0.10,\"b\":12,\"c\":10.00}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--float-precision")
+        .arg("0")
+        .arg("get")
+        .arg(map)
+        .arg("/a");
+    cmd.assert().stdout(predicate::eq("0\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--keep-trailing-zeros").arg("init");
+    cmd.assert().code(2);
+}
+
+#[test]
+fn strict_environment_variable() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg(r#"{"a": 1}"#).arg("/b");
+    cmd.assert().stdout(predicate::eq("\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg(r#"{"a": 1}"#).arg("/b").arg("--strict");
+    cmd.assert()
+        .stderr(predicate::str::contains("/b does not exist"))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("BASH_MAP_STRICT", "1")
+        .arg("get")
+        .arg(r#"{"a": 1}"#)
+        .arg("/b");
+    cmd.assert()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("/b does not exist"))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("BASH_MAP_STRICT", "1")
+        .arg("--no-strict")
+        .arg("get")
+        .arg(r#"{"a": 1}"#)
+        .arg("/b");
+    cmd.assert().stdout(predicate::eq("\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("BASH_MAP_STRICT", "1")
+        .env_remove("STRICT_MAP")
+        .arg("set")
+        .arg("STRICT_MAP")
+        .arg("/a")
+        .arg("1");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "environment variable STRICT_MAP is not set",
+        ))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("BASH_MAP_STRICT", "1")
+        .env("STRICT_MAP", "{not json")
+        .arg("get")
+        .arg("STRICT_MAP")
+        .arg("/a");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "environment variable STRICT_MAP contains invalid json",
+        ))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("BASH_MAP_STRICT", "1")
+        .arg("delete")
+        .arg(r#"{"a": 1}"#)
+        .arg("/b");
+    cmd.assert()
+        .stderr(predicate::str::contains("/b matches nothing"))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("BASH_MAP_STRICT", "0")
+        .arg("delete")
+        .arg(r#"{"a": 1}"#)
+        .arg("/b");
+    cmd.assert().stdout(predicate::eq("{\"a\":1}\n")).success();
+}
+
+#[test]
+fn quiet_get_strict() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--quiet")
+        .arg("get")
+        .arg(r#"{"a": 1}"#)
+        .arg("/b")
+        .arg("--strict");
+    cmd.assert().stdout(predicate::str::is_empty()).code(1);
+}
+
+#[test]
+fn array_and_scalar_literals() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg("[1, 2, 3]").arg("/0");
+    cmd.assert().stdout(predicate::eq("1\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg(r#""text""#).arg("");
+    cmd.assert().stdout(predicate::eq("\"text\"\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("[1, 2, 3]").arg("/1").arg("true");
+    cmd.assert().stdout(predicate::eq("[1,true,3]\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("[1]").arg("/1").arg("2").arg("--strict");
+    cmd.assert()
+        .stderr(predicate::str::contains("past the end"))
+        .code(1);
+
+    for (first, second, code) in [("[1, 2]", "[1.0, 2]", 0), ("[1, 2]", "[2, 1]", 1)] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("compare").arg(first).arg(second);
+        cmd.assert().code(code);
+
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("compare")
+            .arg("FIRST")
+            .arg(second)
+            .env("FIRST", first);
+        cmd.assert().code(code);
+    }
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg("{not json").arg("/a");
+    cmd.assert().stdout(predicate::eq("\n")).success();
+}
+
+#[test]
+fn strict_env() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env_remove("CONIFG")
+        .arg("get")
+        .arg("CONIFG")
+        .arg("/key");
+    cmd.assert().stdout(predicate::eq("\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env_remove("CONIFG")
+        .arg("--strict-env")
+        .arg("get")
+        .arg("CONIFG")
+        .arg("/key");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "environment variable CONIFG is not set",
+        ))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("CONFIG", "{\"key\"")
+        .arg("get")
+        .arg("CONFIG")
+        .arg("/key");
+    cmd.assert().stdout(predicate::eq("\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("CONFIG", "{\"key\"")
+        .arg("--strict-env")
+        .arg("get")
+        .arg("CONFIG")
+        .arg("/key");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "environment variable CONFIG contains invalid json",
+        ))
+        .stderr(predicate::str::contains("line 1 column 6"))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("CONFIG", "{\"key\": 1}")
+        .arg("--strict-env")
+        .arg("get")
+        .arg("CONFIG")
+        .arg("/missing");
+    cmd.assert().stdout(predicate::eq("\n")).success();
+}
+
+#[test]
+fn file_fallback() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("config.json"),
+        r#"{"server": {"port": 8080}}"#,
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("CONFIG"), r#"{"from": "file"}"#).unwrap();
+    std::fs::write(dir.path().join("[1]"), r#"{"from": "file"}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.current_dir(dir.path())
+        .arg("get")
+        .arg("config.json")
+        .arg("/server/port");
+    cmd.assert().stdout(predicate::eq("8080\n")).success();
+
+    // a set environment variable goes before the file with the same name
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.current_dir(dir.path())
+        .env("CONFIG", r#"{"from": "env"}"#)
+        .arg("get")
+        .arg("CONFIG")
+        .arg("/from");
+    cmd.assert().stdout(predicate::eq("\"env\"\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.current_dir(dir.path())
+        .env_remove("CONFIG")
+        .arg("get")
+        .arg("CONFIG")
+        .arg("/from");
+    cmd.assert().stdout(predicate::eq("\"file\"\n")).success();
+
+    // and valid json text before both
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.current_dir(dir.path()).arg("get").arg("[1]").arg("/0");
+    cmd.assert().stdout(predicate::eq("1\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.current_dir(dir.path())
+        .arg("--no-file-fallback")
+        .arg("get")
+        .arg("config.json")
+        .arg("/server/port");
+    cmd.assert().stdout(predicate::eq("\n")).success();
+}
+
+#[test]
+fn value_stdin() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg(r#"{"tls": {}}"#)
+        .arg("/tls/cert")
+        .arg("--value-stdin")
+        .write_stdin("{\"pem\": \"abc\", \"size\": 1.0}\n");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"tls\":{\"cert\":{\"pem\":\"abc\",\"size\":1.0}}}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("{}")
+        .arg("/cert")
+        .arg("--value-stdin")
+        .arg("--string")
+        .write_stdin("-----BEGIN-----\nMIIB\n-----END-----\n");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"cert\":\"-----BEGIN-----\\nMIIB\\n-----END-----\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("{}")
+        .arg("/cert")
+        .arg("--value-stdin")
+        .arg("--string")
+        .arg("--keep-trailing-newline")
+        .write_stdin("abc\n");
+    cmd.assert()
+        .stdout(predicate::eq("{\"cert\":\"abc\\n\"}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("{}")
+        .arg("/cert")
+        .arg("--value-stdin")
+        .write_stdin("not json");
+    cmd.assert()
+        .stderr(predicate::str::contains("invalid json"))
+        .code(3);
+}
+
+#[test]
+fn value_stdin_conflicts() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("/dev/stdin")
+        .arg("/a")
+        .arg("--value-stdin")
+        .write_stdin("1");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "the map and --value-stdin can not both read stdin",
+        ))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("{}")
+        .arg("/a")
+        .arg("1")
+        .arg("--value-stdin")
+        .write_stdin("2");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "only one of the value and --value-stdin can be given",
+        ))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("{}").arg("/a").arg("1").arg("--string");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "--string only applies to --value-stdin",
+        ))
+        .code(2);
+}
+
+#[test]
+fn value_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("value.json");
+    let items: Vec<String> = (0..20000)
+        .map(|i| {
+            format!(
+                r#"{{"id":{},"name":"item {}","tags":{{"a":[1,2.0,"x"]}}}}"#,
+                i, i
+            )
+        })
+        .collect();
+    let value = format!(r#"{{"nested":{{"items":[{}]}}}}"#, items.join(","));
+    assert!(value.len() > 1_000_000);
+    std::fs::write(&path, &value).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg(r#"{"config": {}}"#)
+        .arg("/config/big")
+        .arg(format!("@{}", path.display()));
+    let output = cmd.assert().success().get_output().stdout.clone();
+    // too long for a single command line argument
+    let map = dir.path().join("map.json");
+    std::fs::write(&map, output).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get")
+        .arg(format!("@{}", map.display()))
+        .arg("/config/big");
+    cmd.assert()
+        .stdout(predicate::eq(format!("{}\n", value)))
+        .success();
+}
+
+#[test]
+fn value_file_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("value.json");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("{}")
+        .arg("/a")
+        .arg(format!("@{}", path.display()));
+    cmd.assert()
+        .stderr(predicate::str::contains(format!(
+            "value file {} not found",
+            path.display()
+        )))
+        .code(4);
+
+    std::fs::write(&path, "{\n  \"a\": 1,\n}").unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set")
+        .arg("{}")
+        .arg("/a")
+        .arg(format!("@{}", path.display()));
+    cmd.assert()
+        .stderr(predicate::str::contains(format!(
+            "invalid json in value file {}: trailing comma at line 3 column 1",
+            path.display()
+        )))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("set").arg("{}").arg("/a").arg("@@x");
+    cmd.assert()
+        .stderr(predicate::str::contains("  @x\n  ^"))
+        .code(2);
+}
+
+#[test]
+fn value_env() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("BASH_MAP_DB", r#"{"host": "db", "port": 5432}"#)
+        .env("BASH_MAP_NAME", "web")
+        .arg("set")
+        .arg(r#"{"name": "app"}"#)
+        .arg("/db")
+        .arg("env:BASH_MAP_DB");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"name\":\"app\",\"db\":{\"host\":\"db\",\"port\":5432}}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("BASH_MAP_NAME", "web")
+        .arg("set")
+        .arg("{}")
+        .arg("/name")
+        .arg("env:BASH_MAP_NAME")
+        .arg("--string");
+    cmd.assert()
+        .stdout(predicate::eq("{\"name\":\"web\"}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("BASH_MAP_NAME", "web")
+        .arg("set")
+        .arg("{}")
+        .arg("/name")
+        .arg("env:BASH_MAP_NAME");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "environment variable BASH_MAP_NAME contains invalid json at byte 0",
+        ))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env_remove("BASH_MAP_UNSET")
+        .arg("set")
+        .arg("{}")
+        .arg("/a")
+        .arg("env:BASH_MAP_UNSET");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "environment variable BASH_MAP_UNSET is not set",
+        ))
+        .stdout(predicate::str::is_empty())
+        .code(3);
+}
+
+#[test]
+fn get_project() {
+    let doc = r#"{"a": 1, "b": {"c": [true], "a": 2}, "d": "x"}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get")
+        .arg(doc)
+        .arg("--project")
+        .arg("/a")
+        .arg("/b/c")
+        .arg("/d")
+        .arg("/missing");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":1,\"c\":[true],\"d\":\"x\"}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get")
+        .arg(doc)
+        .arg("--project")
+        .arg("--full-keys")
+        .arg("/a")
+        .arg("/b/a");
+    cmd.assert()
+        .stdout(predicate::eq("{\"/a\":1,\"/b/a\":2}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--pretty")
+        .arg("get")
+        .arg(doc)
+        .arg("--project")
+        .arg("/d");
+    cmd.assert()
+        .stdout(predicate::eq("{\n  \"d\": \"x\"\n}\n"))
+        .success();
+}
+
+#[test]
+fn get_project_errors() {
+    let doc = r#"{"a": 1, "b": {"a": 2}}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get")
+        .arg(doc)
+        .arg("--project")
+        .arg("/a")
+        .arg("/b/a");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            r#"more than one pointer gives the key \"a\", use --full-keys"#,
+        ))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get")
+        .arg(doc)
+        .arg("--project")
+        .arg("--strict")
+        .arg("/a")
+        .arg("/c");
+    cmd.assert()
+        .stderr(predicate::str::contains("/c does not exist"))
+        .stdout(predicate::str::is_empty())
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("get").arg(doc).arg("/a").arg("/b/a");
+    cmd.assert()
+        .stderr(predicate::str::contains("only apply to --project"))
+        .code(2);
+}
+
+#[test]
+fn jq() {
+    let doc = r#"{"items": [{"id": "a", "state": "running"}, {"id": "b", "state": "stopped"}]}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("jq")
+        .arg(doc)
+        .arg(r#".items[] | select(.state == "running") | {id}"#);
+    cmd.assert()
+        .stdout(predicate::eq("{\"id\":\"a\"}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("jq").arg(doc).arg(".items[].id");
+    cmd.assert()
+        .stdout(predicate::eq("\"a\"\n\"b\"\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("jq")
+        .arg(doc)
+        .arg(".items[] | select(.id == \"c\")");
+    cmd.assert().stdout(predicate::str::is_empty()).success();
+}
+
+#[test]
+fn file_jq() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"items":[1,2]}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file").arg(&path).arg("jq").arg(".items[]");
+    cmd.assert().stdout(predicate::eq("1\n2\n")).success();
+}
+
+#[test]
+fn jq_errors() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("jq").arg("{}").arg(".a | if");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "unknown or unsupported function `if` at character 6",
+        ))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("jq").arg("[1]").arg(".a");
+    cmd.assert()
+        .stderr(predicate::str::contains("can not index array with"))
+        .code(1);
+}
+
+#[cfg(feature = "jmespath")]
+#[test]
+fn jp() {
+    let doc = r#"{"items": [{"id": "i-1", "state": "running", "size": 12}, {"id": "i-2", "state": "stopped", "size": 3.5}]}"#;
+    let cases = [
+        ("items[*].id", r#"["i-1","i-2"]"#),
+        ("items[?state=='running'].id", r#"["i-1"]"#),
+        ("items[?state==`running`].id", r#"["i-1"]"#),
+        ("items[?size > `5`].id", r#"["i-1"]"#),
+        ("length(items)", "2"),
+        ("sort_by(items, &size)[0].id", r#""i-2""#),
+        ("items[0].{size: size, id: id}", r#"{"id":"i-1","size":12}"#),
+        ("max_by(items, &size).size", "12"),
+    ];
+    for (expression, output) in cases {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("jp").arg(doc).arg(expression);
+        cmd.assert()
+            .stdout(predicate::eq(format!("{}\n", output)))
+            .success();
+    }
+}
+
+#[cfg(feature = "jmespath")]
+#[test]
+fn file_jp() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"items":[{"id":"i-1"}]}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file").arg(&path).arg("jp").arg("items[*].id");
+    cmd.assert().stdout(predicate::eq("[\"i-1\"]\n")).success();
+}
+
+#[cfg(feature = "jmespath")]
+#[test]
+fn jp_errors() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("jp").arg("{}").arg("items[?state==");
+    cmd.assert()
+        .stderr(predicate::str::contains("(line 0, column 14)"))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("jp").arg(r#"{"items": []}"#).arg("items[*].id");
+    cmd.assert().stdout(predicate::eq("[]\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("jp")
+        .arg(r#"{"items": []}"#)
+        .arg("items[*].id")
+        .arg("--strict");
+    cmd.assert()
+        .stderr(predicate::str::contains("items[*].id found nothing"))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("jp").arg("{}").arg("missing").arg("--strict");
+    cmd.assert().code(1);
+}
+
+#[test]
+fn test_value() {
+    let doc = r#"{"status": "ready", "port": 80, "tags": {"a": 1, "b": [true]}}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("test").arg(doc).arg("/status").arg(r#""ready""#);
+    cmd.assert()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::is_empty())
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("test")
+        .arg(doc)
+        .arg("/tags")
+        .arg(r#"{"b": [true], "a": 1.0}"#);
+    cmd.assert().stdout(predicate::str::is_empty()).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("test").arg(doc).arg("/port").arg("8080");
+    cmd.assert()
+        .stderr(predicate::str::contains("/port is 80, expected 8080"))
+        .stdout(predicate::str::is_empty())
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("test")
+        .arg(doc)
+        .arg("/status")
+        .arg(r#""starting""#)
+        .arg("--not");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("test")
+        .arg(doc)
+        .arg("/status")
+        .arg(r#""ready""#)
+        .arg("--not");
+    cmd.assert()
+        .stderr(predicate::str::contains("expected anything else"))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("BASH_MAP_STATUS", "ready")
+        .arg("test")
+        .arg(doc)
+        .arg("/status")
+        .arg("env:BASH_MAP_STATUS")
+        .arg("--string");
+    cmd.assert().success();
+}
+
+#[test]
+fn file_test() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"status":"ready"}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("test")
+        .arg("/status")
+        .arg(r#""ready""#);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_missing_pointer() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("test").arg("{}").arg("/status").arg("null");
+    cmd.assert()
+        .stderr(predicate::str::contains("/status does not exist"))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("test")
+        .arg("{}")
+        .arg("/status")
+        .arg("null")
+        .arg("--not");
+    cmd.assert()
+        .stderr(predicate::str::contains("/status does not exist"))
+        .code(1);
+}
+
+#[test]
+fn apply() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("apply")
+        .arg(r#"{"name": "web", "port": 80}"#)
+        .arg("/name")
+        .arg("--string-result")
+        .arg("--")
+        .arg("tr")
+        .arg("a-z")
+        .arg("A-Z");
+    cmd.assert()
+        .stdout(predicate::eq("{\"name\":\"WEB\",\"port\":80}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("apply")
+        .arg(r#"{"list": [1, {"a": 2.0}]}"#)
+        .arg("/list")
+        .arg("--")
+        .arg("cat");
+    cmd.assert()
+        .stdout(predicate::eq("{\"list\":[1,{\"a\":2.0}]}\n"))
+        .success();
+
+    // the argument is not expanded by a shell
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("apply")
+        .arg(r#"{"a": "x"}"#)
+        .arg("/a")
+        .arg("--string-result")
+        .arg("--")
+        .arg("echo")
+        .arg("$HOME; rm -rf /");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":\"$HOME; rm -rf /\"}\n"))
+        .success();
+}
+
+#[test]
+fn apply_errors() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("apply")
+        .arg(r#"{"a": 1}"#)
+        .arg("/a")
+        .arg("--")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo 2; exit 7");
+    cmd.assert()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("sh failed"))
+        .code(7);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("apply")
+        .arg(r#"{"a": "x"}"#)
+        .arg("/a")
+        .arg("--")
+        .arg("tr")
+        .arg("x")
+        .arg("y");
+    cmd.assert()
+        .stderr(predicate::str::contains("the output of tr is invalid json"))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("apply")
+        .arg(r#"{"a": 1}"#)
+        .arg("/b")
+        .arg("--")
+        .arg("cat");
+    cmd.assert()
+        .stderr(predicate::str::contains("/b does not exist"))
+        .code(1);
+}
+
+#[test]
+fn file_apply() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"name":"web"}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("apply")
+        .arg("/name")
+        .arg("--")
+        .arg("sh")
+        .arg("-c")
+        .arg("exit 5");
+    cmd.assert().code(5);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), r#"{"name":"web"}"#);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("apply")
+        .arg("/name")
+        .arg("--string-result")
+        .arg("--")
+        .arg("tr")
+        .arg("a-z")
+        .arg("A-Z");
+    cmd.assert().success();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\"name\":\"WEB\"}\n"
+    );
+}
+
+#[test]
+fn env() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("ENV_TEST_B", "2")
+        .env("ENV_TEST_A", r#"{"x": [1]}"#)
+        .env("ENV_TEST_C", "text")
+        .arg("env")
+        .arg("--filter")
+        .arg("^ENV_TEST_");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"ENV_TEST_A\":\"{\\\"x\\\": [1]}\",\"ENV_TEST_B\":\"2\",\"ENV_TEST_C\":\"text\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("ENV_TEST_B", "2")
+        .env("ENV_TEST_A", r#"{"x": [1]}"#)
+        .env("ENV_TEST_C", "text")
+        .arg("env")
+        .arg("--filter")
+        .arg("^ENV_TEST_")
+        .arg("--parse");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"ENV_TEST_A\":{\"x\":[1]},\"ENV_TEST_B\":2,\"ENV_TEST_C\":\"text\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("env").arg("--filter").arg("(");
+    cmd.assert()
+        .stderr(predicate::str::contains("invalid filter"))
+        .code(2);
+}
+
+#[cfg(unix)]
+#[test]
+fn env_not_utf8() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.env("ENV_TEST_BYTES", OsStr::from_bytes(b"a\xffb"))
+        .arg("env")
+        .arg("--filter")
+        .arg("^ENV_TEST_");
+    cmd.assert()
+        .stdout(predicate::eq("{\"ENV_TEST_BYTES\":\"a\u{fffd}b\"}\n"))
+        .stderr(predicate::str::contains(
+            "warning: the value of ENV_TEST_BYTES is not valid utf-8",
+        ))
+        .success();
+}
+
+#[test]
+fn splice() {
+    let doc = r#"{"a": [1, 2, 3, 4, 5]}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("splice").arg(doc).arg("/a").arg("1").arg("3");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":[1,5]}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("splice")
+        .arg(doc)
+        .arg("/a")
+        .arg("2")
+        .arg("0")
+        .arg(r#"["x", {"y": 1}]"#);
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":[1,2,\"x\",{\"y\":1},3,4,5]}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("splice")
+        .arg(doc)
+        .arg("/a")
+        .arg("--")
+        .arg("-2")
+        .arg("9")
+        .arg(r#"["x"]"#);
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":[1,2,3,\"x\"]}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("splice")
+        .arg(doc)
+        .arg("/a")
+        .arg("10")
+        .arg("1")
+        .arg("[6]");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":[1,2,3,4,5,6]}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("splice")
+        .arg(doc)
+        .arg("/a")
+        .arg("1")
+        .arg("2")
+        .arg(r#"["x"]"#)
+        .arg("--print-removed");
+    cmd.assert().stdout(predicate::eq("[2,3]\n")).success();
+}
+
+#[test]
+fn splice_errors() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("splice")
+        .arg(r#"{"a": {}}"#)
+        .arg("/a")
+        .arg("0")
+        .arg("1");
+    cmd.assert()
+        .stderr(predicate::str::contains("/a is of type object, not array"))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("splice")
+        .arg(r#"{"a": []}"#)
+        .arg("/a")
+        .arg("0")
+        .arg("0")
+        .arg("1");
+    cmd.assert()
+        .stderr(predicate::str::contains("not array"))
+        .code(2);
+}
+
+#[test]
+fn file_splice() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"a":[1,2,3]}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("splice")
+        .arg("/a")
+        .arg("0")
+        .arg("1")
+        .arg("--print-removed");
+    cmd.assert().stdout(predicate::eq("[1]\n")).success();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), r#"{"a":[1,2,3]}"#);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("splice")
+        .arg("/a")
+        .arg("0")
+        .arg("1");
+    cmd.assert().success();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"a\":[2,3]}\n");
+}
+
+#[test]
+fn stringify() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("stringify")
+        .arg(r#"{"name": "p", "policy": {"Version": "2012", "n": 1.50, "s": ["*"]}}"#)
+        .arg("/policy");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"name\":\"p\",\"policy\":\"{\\\"Version\\\":\\\"2012\\\",\\\"n\\\":1.50,\\\"s\\\":[\\\"*\\\"]}\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("stringify")
+        .arg(r#"{"a": {"b": 1}}"#)
+        .arg("/a")
+        .arg("--pretty-inner");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":\"{\\n  \\\"b\\\": 1\\n}\"}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("stringify").arg(r#"{"a": "x"}"#).arg("/a");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":\"x\"}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("stringify")
+        .arg(r#"{"a": "x"}"#)
+        .arg("/a")
+        .arg("--strict");
+    cmd.assert()
+        .stderr(predicate::str::contains("/a is a string already"))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("stringify").arg(r#"{"a": "x"}"#).arg("/b");
+    cmd.assert()
+        .stderr(predicate::str::contains("/b does not exist"))
+        .code(1);
+}
+
+#[test]
+fn parse_embedded() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("parse-embedded")
+        .arg(r#"{"policy": "{\"Version\":\"2012\",\"s\":[1]}"}"#)
+        .arg("/policy");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"policy\":{\"Version\":\"2012\",\"s\":[1]}}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("parse-embedded")
+        .arg(r#"{"logs": [{"body": "{\"a\": \"[1]\"}"}, "2", "true story", "{"]}"#)
+        .arg("--recursive");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"logs\":[{\"body\":{\"a\":[1]}},\"2\",\"true story\",\"{\"]}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("parse-embedded")
+        .arg(r#"{"a": "[1]", "b": {"c": "[2]"}}"#)
+        .arg("/b")
+        .arg("--recursive");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":\"[1]\",\"b\":{\"c\":[2]}}\n"))
+        .success();
+}
+
+#[test]
+fn parse_embedded_errors() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("parse-embedded")
+        .arg(r#"{"p": "{\"a\":1"}"#)
+        .arg("/p");
+    cmd.assert()
+        .stderr(predicate::str::contains("the string at /p is invalid json"))
+        .code(3);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("parse-embedded").arg(r#"{"p": 1}"#).arg("/p");
+    cmd.assert()
+        .stderr(predicate::str::contains("/p is of type number, not string"))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("parse-embedded").arg(r#"{"p": 1}"#);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn stringify_round_trip() {
+    let doc =
+        r#"{"policy":{"b":1.50,"a":[{"z":null,"y":"é\"\n"}],"n":123456789012345678901234567890}}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("stringify").arg(doc).arg("/policy");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stringified = String::from_utf8(output).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("parse-embedded")
+        .arg(stringified.trim_end())
+        .arg("/policy");
+    cmd.assert()
+        .stdout(predicate::eq(format!("{}\n", doc)))
+        .success();
+}
+
+#[test]
+fn shell_quote() {
+    let values = [
+        "plain",
+        "it's a \"quote\"",
+        "line 1\nline 2\n",
+        "$HOME $(echo no) `echo no` \\ !x ✓",
+        "",
+    ];
+    for value in values {
+        let doc = serde_json::json!({ "v": value });
+        let mut cmd = Command::new("bash");
+        cmd.env("DOC", doc.to_string()).arg("-c").arg(format!(
+            r#"printf '%s' "$({} shell-quote DOC /v)" && eval "printf '%s' $({} shell-quote DOC /v)""#,
+            assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()).display(),
+            assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()).display()
+        ));
+        let output = cmd.assert().success().get_output().stdout.clone();
+        let output = String::from_utf8(output).unwrap();
+        let quoted = output.strip_suffix(value).unwrap();
+        assert!(quoted.starts_with('\'') && quoted.ends_with('\''));
+    }
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("shell-quote")
+        .arg(r#"{"v": [1, "it's"]}"#)
+        .arg("/v")
+        .arg("--shell")
+        .arg("fish");
+    cmd.assert()
+        .stdout(predicate::eq("'[1,\"it\\'s\"]'\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("shell-quote")
+        .arg(r#"{"v": "x"}"#)
+        .arg("/v")
+        .arg("--shell")
+        .arg("csh");
+    cmd.assert()
+        .stderr(predicate::str::contains("unknown shell"))
+        .code(2);
+}
+
+/// The json of the value encoded as a json string `times` times.
+fn encoded(value: &serde_json::Value, times: usize) -> String {
+    (0..times).fold(value.to_string(), |text, _| {
+        serde_json::Value::String(text).to_string()
+    })
+}
+
+#[test]
+fn fix_encoding() {
+    let payload = serde_json::json!({"user": {"id": 1, "tags": ["a"]}, "note": "true story"});
+    let doc = format!(
+        r#"{{"twice": {}, "thrice": {}, "count": "\"7\"", "text": "{{not json"}}"#,
+        encoded(&payload, 2),
+        encoded(&payload, 3)
+    );
+    let fixed = r#"{"user":{"id":1,"tags":["a"]},"note":"true story"}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("fix-encoding").arg(&doc);
+    cmd.assert()
+        .stdout(predicate::eq(format!(
+            "{{\"twice\":{},\"thrice\":{},\"count\":\"\\\"7\\\"\",\"text\":\"{{not json\"}}\n",
+            fixed, fixed
+        )))
+        .stderr(predicate::eq("5 fixed\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("fix-encoding").arg(&doc).arg("--aggressive");
+    cmd.assert()
+        .stdout(predicate::str::contains("\"count\":7,"))
+        .stderr(predicate::eq("7 fixed\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("fix-encoding").arg(&doc).arg("/thrice");
+    cmd.assert()
+        .stdout(predicate::str::contains(format!("\"thrice\":{}", fixed)))
+        .stdout(predicate::str::contains(format!(
+            "\"twice\":{}",
+            encoded(&payload, 2)
+        )))
+        .stderr(predicate::eq("3 fixed\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("fix-encoding").arg(encoded(&payload, 2));
+    cmd.assert()
+        .stdout(predicate::eq(format!("{}\n", fixed)))
+        .stderr(predicate::eq("2 fixed\n"))
+        .success();
+}
+
+#[test]
+fn natural_sorting() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("sort-by")
+        .arg(r#"{"a": [{"v": "v1.10.0"}, {"v": "v1.9.0"}, {"v": "v1.9.10"}]}"#)
+        .arg("/a")
+        .arg("/v")
+        .arg("--natural");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"a\":[{\"v\":\"v1.9.0\"},{\"v\":\"v1.9.10\"},{\"v\":\"v1.10.0\"}]}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--sort-keys")
+        .arg("--natural")
+        .arg("get")
+        .arg(r#"{"item10": 1, "item2": {"b10": 1, "b9": 2}, "item1": 3}"#)
+        .arg("");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"item1\":3,\"item2\":{\"b9\":2,\"b10\":1},\"item10\":1}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("leaves")
+        .arg(r#"{"a": [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]}"#)
+        .arg("/a")
+        .arg("--sort")
+        .arg("--natural");
+    cmd.assert()
+        .stdout(predicate::str::starts_with("/a/0=0\n/a/1=1\n/a/2=2\n"))
+        .stdout(predicate::str::ends_with("/a/9=9\n/a/10=10\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--natural").arg("init");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "--natural only applies to --sort-keys",
+        ))
+        .code(2);
+}
+
+#[test]
+fn key_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let spec = dir.path().join("order.json");
+    std::fs::write(
+        &spec,
+        r#"{"$order": ["name", "version"], "server": {"$order": ["host", "port"]}}"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--key-order")
+        .arg(format!("@{}", spec.display()))
+        .arg("set")
+        .arg(r#"{"version": "1.0", "server": {"port": 80}, "name": "app"}"#)
+        .arg("/server/host")
+        .arg(r#""localhost""#);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"name\":\"app\",\"version\":\"1.0\",\"server\":{\"host\":\"localhost\",\"port\":80}}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--key-order")
+        .arg(format!("@{}", spec.display()))
+        .arg("set")
+        .arg(r#"{"version": "1.0", "name": "app"}"#)
+        .arg("/author")
+        .arg(r#""me""#);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"name\":\"app\",\"version\":\"1.0\",\"author\":\"me\"}\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--key-order")
+        .arg(r#"{"$order": "name"}"#)
+        .arg("init");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "$order at \\\"\\\" is not an array",
+        ))
+        .code(2);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--key-order")
+        .arg("{}")
+        .arg("--sort-keys")
+        .arg("init");
+    cmd.assert().code(2);
+}
+
+fn golden(name: &str) -> String {
+    std::fs::read_to_string(format!("tests/golden/{}", name)).unwrap()
+}
+
+#[test]
+fn diff_golden() {
+    for (arguments, expected) in [
+        (vec!["--pretty"], "json-patch.json"),
+        (vec!["--format", "unified"], "unified.diff"),
+        (vec!["--format", "unified", "-U", "0"], "unified-0.diff"),
+    ] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        let (global, local) = arguments.split_at(usize::from(arguments[0] == "--pretty"));
+        cmd.args(global)
+            .arg("diff")
+            .arg("@tests/golden/old.json")
+            .arg("@tests/golden/new.json")
+            .args(local);
+        cmd.assert()
+            .stdout(predicate::eq(golden(expected)))
+            .success();
+    }
+}
+
+#[test]
+fn diff_unified_applies_with_patch() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("map.json");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.args([
+        "--pretty",
+        "--sort-keys",
+        "get",
+        "@tests/golden/old.json",
+        "",
+    ]);
+    let old = cmd.assert().success().get_output().stdout.clone();
+    std::fs::write(&path, old).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.args([
+        "--pretty",
+        "--sort-keys",
+        "get",
+        "@tests/golden/new.json",
+        "",
+    ]);
+    let new = cmd.assert().success().get_output().stdout.clone();
+
+    let applied = std::process::Command::new("patch")
+        .arg("--quiet")
+        .arg(&path)
+        .arg("tests/golden/unified.diff")
+        .status();
+    match applied {
+        Ok(status) => {
+            assert!(status.success());
+            assert_eq!(std::fs::read(&path).unwrap(), new);
+        }
+        Err(_) => eprintln!("patch is not installed, skipped"),
+    }
+}
+
+#[test]
+fn diff() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("diff")
+        .arg(r#"{"b": [1, 2], "a": 1}"#)
+        .arg(r#"{"a": 1, "b": [1, 2]}"#)
+        .arg("--format")
+        .arg("unified");
+    cmd.assert().stdout(predicate::eq("")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("diff").arg(r#"{"a": 1}"#).arg(r#"{"a": 1}"#);
+    cmd.assert().stdout(predicate::eq("[]\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("diff")
+        .arg("[1]")
+        .arg("2")
+        .arg("--format")
+        .arg("unified");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "--- old\n+++ new\n@@ -1,3 +1 @@\n-[\n-  1\n-]\n+2\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("diff")
+        .arg(r#"{"a": 1, "b": 2, "c": 3}"#)
+        .arg(r#"{"a": 1, "b": 4, "c": 3}"#)
+        .arg("--format")
+        .arg("unified")
+        .arg("-U0");
+    cmd.assert()
+        .stdout(predicate::eq(
+            "--- old\n+++ new\n@@ -3 +3 @@\n-  \"b\": 2,\n+  \"b\": 4,\n",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("diff")
+        .arg("{}")
+        .arg("{}")
+        .arg("--format")
+        .arg("side-by-side");
+    cmd.assert()
+        .stderr(predicate::str::contains("unknown diff format"))
+        .code(2);
+}
+
+#[test]
+fn patch_reverse() {
+    let old = r#"{"a": 1, "b": [1, 2], "c": {"d": "x"}}"#;
+    let new = r#"{"a": 2, "b": [1], "e": true}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("diff").arg(old).arg(new).arg("--invertible");
+    let patch = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("patch").arg(old).arg(&patch);
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":2,\"b\":[1],\"e\":true}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("patch").arg(new).arg(&patch).arg("--reverse");
+    cmd.assert()
+        .stdout(predicate::eq("{\"a\":1,\"b\":[1,2],\"c\":{\"d\":\"x\"}}\n"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("diff")
+        .arg("{}")
+        .arg("{}")
+        .arg("--invertible")
+        .arg("--format")
+        .arg("unified");
+    cmd.assert().code(2);
+}
+
+#[test]
+fn file_patch_reverse_fails_up_front() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"a":2,"list":[1,2,3]}"#).unwrap();
+    let patch = r#"[
+        {"op": "add", "path": "/list/-", "value": 3},
+        {"op": "remove", "path": "/b"},
+        {"op": "replace", "path": "/a", "value": 2}
+    ]"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("patch")
+        .arg(patch)
+        .arg("--reverse");
+    cmd.assert()
+        .stdout(predicate::eq(""))
+        .stderr(predicate::str::contains(
+            "without \\\"_prev\\\": 1 (remove /b), 2 (replace /a)",
+        ))
+        .code(1);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        r#"{"a":2,"list":[1,2,3]}"#
+    );
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("patch")
+        .arg(r#"[{"op": "add", "path": "/list/-", "value": 3}]"#)
+        .arg("--reverse");
+    cmd.assert().stdout(predicate::eq("")).success();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\"a\":2,\"list\":[1,2]}\n"
+    );
+}
+
+#[test]
+fn patch_errors() {
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("patch")
+        .arg(r#"{"a": 1}"#)
+        .arg(r#"[{"op": "remove", "path": "/a"}, {"op": "test", "path": "/a", "value": 1}]"#);
+    cmd.assert()
+        .stdout(predicate::eq(""))
+        .stderr(predicate::str::contains(
+            "operation 1 (test): /a does not exist",
+        ))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("patch")
+        .arg(r#"{"a": 1}"#)
+        .arg(r#"[{"op": "add", "value": 1}]"#);
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "operation 0: missing \\\"path\\\"",
+        ))
+        .code(1);
+}
+
+#[test]
+fn merge3() {
+    let dir = tempfile::tempdir().unwrap();
+    let write = |name: &str, text: &str| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, text).unwrap();
+        format!("@{}", path.display())
+    };
+    let base = write("base.json", r#"{"name": "app", "port": 80, "tags": ["a"]}"#);
+    let ours = write(
+        "ours.json",
+        r#"{"name": "app", "port": 8080, "tags": ["a"]}"#,
+    );
+    let theirs = write(
+        "theirs.json",
+        r#"{"name": "web", "port": 80, "tags": ["a", "b"]}"#,
+    );
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge3").arg(&base).arg(&ours).arg(&theirs);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "{\"name\":\"web\",\"port\":8080,\"tags\":[\"a\",\"b\"]}\n",
+        ))
+        .success();
+
+    let theirs = write("theirs.json", r#"{"port": 443, "tags": ["a"]}"#);
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge3").arg(&base).arg(&ours).arg(&theirs);
+    cmd.assert()
+        .stdout(predicate::eq(
+            "[{\"pointer\":\"/port\",\"base\":80,\"ours\":8080,\"theirs\":443}]\n",
+        ))
+        .code(1);
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge3")
+        .arg(&base)
+        .arg(&ours)
+        .arg(&theirs)
+        .arg("--union");
+    cmd.assert()
+        .stdout(predicate::eq("{\"port\":[8080,443],\"tags\":[\"a\"]}\n"))
+        .stderr(predicate::str::contains("conflict at \"/port\""))
+        .success();
+}
+
+#[test]
+fn merge3_annotate_round_trip() {
+    let base = r#"{"server": {"port": 80, "tls": {"cert": "a"}}, "name": "app"}"#;
+    let ours = r#"{"server": {"port": 8080, "tls": {"cert": "b"}}, "name": "app"}"#;
+    let theirs = r#"{"server": {"port": 443, "tls": {"cert": "c"}}, "name": "web"}"#;
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge3")
+        .arg(base)
+        .arg(ours)
+        .arg(theirs)
+        .arg("--annotate");
+    let annotated = String::from_utf8(cmd.assert().code(1).get_output().stdout.clone()).unwrap();
+    assert_eq!(
+        annotated,
+        "{\"server\":{\"port\":{\"<<<<<<<\":8080,\">>>>>>>\":443,\"|||||||\":80},\
+         \"tls\":{\"cert\":{\"<<<<<<<\":\"b\",\">>>>>>>\":\"c\",\"|||||||\":\"a\"}}},\"name\":\"web\"}\n"
+    );
+
+    for (side, expected) in [
+        (
+            "ours",
+            "{\"server\":{\"port\":8080,\"tls\":{\"cert\":\"b\"}},\"name\":\"web\"}\n",
+        ),
+        (
+            "theirs",
+            "{\"server\":{\"port\":443,\"tls\":{\"cert\":\"c\"}},\"name\":\"web\"}\n",
+        ),
+    ] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.arg("resolve-conflicts")
+            .arg(annotated.trim_end())
+            .arg("--take")
+            .arg(side);
+        cmd.assert().stdout(predicate::eq(expected)).success();
+    }
+
+    // a map with the default markers needs other ones
+    let theirs = r#"{"server": {"port": 443, "tls": {"cert": "c"}}, "name": {"<<<<<<<": 1}}"#;
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge3")
+        .arg(base)
+        .arg(ours)
+        .arg(theirs)
+        .arg("--annotate");
+    cmd.assert()
+        .stderr(predicate::str::contains(
+            "theirs has a marker key at \\\"/name/<<<<<<<\\\"",
+        ))
+        .code(2);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("merged.json");
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge3")
+        .arg(base)
+        .arg(ours)
+        .arg(theirs)
+        .args([
+            "--annotate",
+            "--marker-ours",
+            "OURS",
+            "--marker-theirs",
+            "THEIRS",
+        ])
+        .args(["--marker-base", "BASE"]);
+    let annotated = cmd.assert().code(1).get_output().stdout.clone();
+    std::fs::write(&path, annotated).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("resolve-conflicts")
+        .args([
+            "--take",
+            "theirs",
+            "--marker-ours",
+            "OURS",
+            "--marker-theirs",
+            "THEIRS",
+        ])
+        .args(["--marker-base", "BASE"]);
+    cmd.assert().stdout(predicate::eq("")).success();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\"server\":{\"port\":443,\"tls\":{\"cert\":\"c\"}},\"name\":{\"<<<<<<<\":1}}\n"
+    );
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("merge3")
+        .arg(base)
+        .arg(ours)
+        .arg(theirs)
+        .arg("--annotate")
+        .arg("--union");
+    cmd.assert().code(2);
+}
+
+#[test]
+fn file_backup() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    std::fs::write(&path, r#"{"a":1}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("--backup")
+        .args(["set", "/a", "2"]);
+    cmd.assert().success();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"a\":2}\n");
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("state.json~")).unwrap(),
+        r#"{"a":1}"#
+    );
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--backup=.bak")
+        .arg("--file")
+        .arg(&path)
+        .args(["set", "/a", "3"]);
+    cmd.assert().success();
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("state.json.bak")).unwrap(),
+        "{\"a\":2}\n"
+    );
+
+    // a failing command writes nothing
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&path)
+        .arg("--backup")
+        .args(["set", "/a", "{not json"]);
+    cmd.assert().failure();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"a\":3}\n");
+
+    let mut names: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|x| x.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "state.json",
+            "state.json.bak",
+            "state.json.lock",
+            "state.json~"
+        ]
+    );
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--backup=").arg("--file").arg(&path).arg("init");
+    cmd.assert().code(2);
+}
+
+#[cfg(unix)]
+#[test]
+fn file_symlink() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("target.json");
+    let link = dir.path().join("link.json");
+    std::fs::write(&target, r#"{"a":1}"#).unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file").arg(&link).args(["set", "/a", "2"]);
+    cmd.assert().success();
+    assert!(std::fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "{\"a\":2}\n");
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--file")
+        .arg(&link)
+        .arg("--no-follow-symlinks")
+        .args(["set", "/a", "3"]);
+    cmd.assert().success();
+    assert!(std::fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_file());
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "{\"a\":2}\n");
+    assert_eq!(std::fs::read_to_string(&link).unwrap(), "{\"a\":3}\n");
+}
+
+#[cfg(unix)]
+fn wait_for(mut what: impl FnMut() -> bool) -> bool {
+    let start = std::time::Instant::now();
+    while start.elapsed() < std::time::Duration::from_secs(10) {
+        if what() {
+            return true;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    false
+}
+
+#[cfg(unix)]
+#[test]
+fn serve_timeout_lifecycle() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    let socket = dir.path().join("bm.sock");
+    std::fs::write(&path, r#"{"a":1}"#).unwrap();
+
+    let mut server =
+        std::process::Command::new(assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()))
+            .arg("--file")
+            .arg(&path)
+            .args(["serve", "--timeout", "0.5", "--socket"])
+            .arg(&socket)
+            .spawn()
+            .unwrap();
+    assert!(wait_for(|| socket.exists()));
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--connect")
+        .arg(&socket)
+        .args(["set", "/b", "[1, 2]"]);
+    cmd.assert().stdout(predicate::eq("")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--connect").arg(&socket).args(["get", "/b/1"]);
+    cmd.assert().stdout(predicate::eq("2\n")).success();
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--connect")
+        .arg(&socket)
+        .args(["get", "/c", "--strict"]);
+    cmd.assert()
+        .stderr(predicate::str::contains("/c does not exist"))
+        .code(1);
+
+    // the output options are the ones of the server
+    for options in [
+        &["--sort-keys"][..],
+        &["--float-precision", "2"],
+        &["--output-format", "json5"],
+        &["--escape-unicode"],
+    ] {
+        let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+        cmd.args(options)
+            .arg("--connect")
+            .arg(&socket)
+            .args(["get", "/b"]);
+        cmd.assert()
+            .stderr(predicate::str::contains(format!(
+                "{} can not be combined with --connect",
+                options[0]
+            )))
+            .code(2);
+    }
+
+    // changes by others are picked up
+    std::fs::write(&path, r#"{"a":2}"#).unwrap();
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--connect").arg(&socket).args(["get", "/a"]);
+    cmd.assert().stdout(predicate::eq("2\n")).success();
+
+    // stops by itself once nothing is sent for --timeout
+    assert!(wait_for(|| server.try_wait().unwrap().is_some()));
+    assert!(server.wait().unwrap().success());
+    assert!(!socket.exists());
+
+    let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+    cmd.arg("--connect").arg(&socket).args(["get", "/a"]);
+    cmd.assert().code(4);
+}
+
+#[cfg(unix)]
+#[test]
+fn serve_is_faster_than_parsing() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("large.json");
+    let socket = dir.path().join("bm.sock");
+    // about 5 MB like in the deploy script, so parsing clearly dominates the start of a process
+    let items: Vec<String> = (0..50_000)
+        .map(|i| {
+            format!(
+                r#""key{0}": {{"name": "item {0}", "tags": ["a", "b", "c"], "size": {0}.5, "nested": {{"x": [1, 2, 3]}}}}"#,
+                i
+            )
+        })
+        .collect();
+    std::fs::write(&path, format!("{{{}}}", items.join(","))).unwrap();
+
+    let mut server =
+        std::process::Command::new(assert_cmd::cargo::cargo_bin(assert_cmd::pkg_name!()))
+            .arg("--file")
+            .arg(&path)
+            .args(["serve", "--timeout", "30", "--socket"])
+            .arg(&socket)
+            .spawn()
+            .unwrap();
+    assert!(wait_for(|| socket.exists()));
+
+    let time = |connect: bool| {
+        let start = std::time::Instant::now();
+        for i in 0..3 {
+            let mut cmd = Command::cargo_bin(assert_cmd::pkg_name!()).unwrap();
+            match connect {
+                true => cmd.arg("--connect").arg(&socket),
+                false => cmd.arg("--file").arg(&path),
+            };
+            cmd.arg("get").arg(format!("/key{}/size", i * 10_000));
+            cmd.assert()
+                .stdout(predicate::eq(format!("{}.5\n", i * 10_000)))
+                .success();
+        }
+        start.elapsed()
+    };
+    let parsing = time(false);
+    let served = time(true);
+    server.kill().unwrap();
+    server.wait().unwrap();
+    assert!(served * 2 < parsing);
+}